@@ -2,13 +2,25 @@
 
 use std::f32;
 use std::fmt;
-use std::ops::Index;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, Index, IndexMut};
+use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::{Point3, Vector3};
+use glam::{Mat4, Vec3A, Vec4};
 
 use crate::axis::Axis;
 
 /// AABB struct.
+///
+/// [`AABB`]s are closed, so they include their bounds. The bounds may coincide along one or
+/// more axes, which gives zero-thickness [`AABB`]s for planar quads, segments or points.
+/// These are valid, and are hit by every ray which crosses them, regardless of the sign of
+/// the ray direction along the flat axis.
+///
+/// [`AABB`]: struct.AABB.html
+///
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
 #[allow(clippy::upper_case_acronyms)]
@@ -60,6 +72,50 @@ pub trait Bounded {
     fn aabb(&self) -> AABB;
 }
 
+/// A trait implemented by volumes which can be tested for intersection with an [`AABB`].
+/// It is used to traverse a [`BVH`] with custom volumes, see [`BVH::query`].
+///
+/// [`AABB`]: struct.AABB.html
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::query`]: ../bvh/struct.BVH.html#method.query
+///
+pub trait IntersectsAabb {
+    /// Returns true if this volume intersects `aabb`. The test may be conservative, and
+    /// report [`AABB`]s which do not actually intersect the volume.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{IntersectsAabb, AABB};
+    /// use bvh::Point3;
+    ///
+    /// struct Sphere {
+    ///     center: Point3,
+    ///     radius: f32,
+    /// }
+    ///
+    /// impl IntersectsAabb for Sphere {
+    ///     fn intersects(&self, aabb: &AABB) -> bool {
+    ///         aabb.min_distance_squared(&self.center) <= self.radius * self.radius
+    ///     }
+    /// }
+    ///
+    /// let sphere = Sphere { center: Point3::new(0.0,0.0,0.0), radius: 1.0 };
+    /// let aabb = AABB::with_bounds(Point3::new(0.5,0.5,0.5), Point3::new(2.0,2.0,2.0));
+    ///
+    /// assert!(sphere.intersects(&aabb));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    fn intersects(&self, aabb: &AABB) -> bool;
+}
+
+impl IntersectsAabb for AABB {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        AABB::intersects(self, aabb)
+    }
+}
+
 impl AABB {
     /// Creates a new [`AABB`] with the given bounds.
     ///
@@ -139,6 +195,29 @@ impl AABB {
             && p.z <= self.max.z
     }
 
+    /// Returns true if the `other` [`AABB`] lies completely inside this [`AABB`]. Every
+    /// [`AABB`] contains the empty [`AABB`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    /// let inner = AABB::with_bounds(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.5, 0.5));
+    /// let crossing = AABB::with_bounds(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 0.5, 0.5));
+    ///
+    /// assert!(aabb.contains_aabb(&inner));
+    /// assert!(!aabb.contains_aabb(&crossing));
+    /// assert!(aabb.contains_aabb(&AABB::empty()));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn contains_aabb(&self, other: &AABB) -> bool {
+        other.is_empty() || (self.min.cmple(other.min).all() && other.max.cmple(self.max).all())
+    }
+
     /// Returns true if the [`Point3`] is approximately inside the [`AABB`]
     /// with respect to some `epsilon`.
     ///
@@ -219,7 +298,8 @@ impl AABB {
     }
 
     /// Returns a new minimal [`AABB`] which contains both this [`AABB`] and `other`.
-    /// The result is the convex hull of the both [`AABB`]s.
+    /// The result is the convex hull of the both [`AABB`]s. A NaN bound is ignored like by
+    /// `f32::min` and `f32::max`, with and without the `simd` feature.
     ///
     /// # Examples
     /// ```
@@ -250,6 +330,12 @@ impl AABB {
     /// [`AABB`]: struct.AABB.html
     ///
     pub fn join(&self, other: &AABB) -> AABB {
+        if cfg!(feature = "simd") {
+            let min = min_ignoring_nan(self.min.into(), other.min.into());
+            let max = max_ignoring_nan(self.max.into(), other.max.into());
+            return AABB::with_bounds(min.into(), max.into());
+        }
+
         AABB::with_bounds(
             Point3::new(
                 self.min.x.min(other.min.x),
@@ -264,6 +350,63 @@ impl AABB {
         )
     }
 
+    /// Returns the intersection of this [`AABB`] and the `other` [`AABB`], or `None` if
+    /// the two [`AABB`]s do not overlap.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(2.0,2.0,2.0));
+    /// let aabb2 = AABB::with_bounds(Point3::new(1.0,1.0,1.0), Point3::new(3.0,3.0,3.0));
+    /// let aabb3 = AABB::with_bounds(Point3::new(5.0,5.0,5.0), Point3::new(6.0,6.0,6.0));
+    ///
+    /// let intersection = aabb1.intersection(&aabb2).unwrap();
+    /// assert_eq!(intersection.min, Point3::new(1.0,1.0,1.0));
+    /// assert_eq!(intersection.max, Point3::new(2.0,2.0,2.0));
+    /// assert!(aabb1.intersection(&aabb3).is_none());
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn intersection(&self, other: &AABB) -> Option<AABB> {
+        if self.intersects(other) {
+            Some(AABB::with_bounds(
+                self.min.max(other.min),
+                self.max.min(other.max),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if this [`AABB`] and the `other` [`AABB`] overlap.
+    /// [`AABB`]s which only touch each other overlap as well.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(2.0,2.0,2.0));
+    /// let aabb2 = AABB::with_bounds(Point3::new(2.0,1.0,1.0), Point3::new(3.0,3.0,3.0));
+    /// let aabb3 = AABB::with_bounds(Point3::new(5.0,5.0,5.0), Point3::new(6.0,6.0,6.0));
+    ///
+    /// assert!(aabb1.intersects(&aabb2));
+    /// assert!(!aabb1.intersects(&aabb3));
+    /// assert!(!aabb1.intersects(&AABB::empty()));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn intersects(&self, other: &AABB) -> bool {
+        self.min.cmple(other.max).all()
+            && other.min.cmple(self.max).all()
+            && !self.is_empty()
+            && !other.is_empty()
+    }
+
     /// Mutable version of [`AABB::join`].
     ///
     /// # Examples
@@ -300,20 +443,12 @@ impl AABB {
     /// [`AABB::join`]: struct.AABB.html
     ///
     pub fn join_mut(&mut self, other: &AABB) {
-        self.min = Point3::new(
-            self.min.x.min(other.min.x),
-            self.min.y.min(other.min.y),
-            self.min.z.min(other.min.z),
-        );
-        self.max = Point3::new(
-            self.max.x.max(other.max.x),
-            self.max.y.max(other.max.y),
-            self.max.z.max(other.max.z),
-        );
+        *self = self.join(other);
     }
 
     /// Returns a new minimal [`AABB`] which contains both
-    /// this [`AABB`] and the [`Point3`] `other`.
+    /// this [`AABB`] and the [`Point3`] `other`. A NaN coordinate is ignored like by
+    /// `f32::min` and `f32::max`, with and without the `simd` feature.
     ///
     /// # Examples
     /// ```
@@ -339,6 +474,13 @@ impl AABB {
     /// [`Point3`]: glam::Vec3
     ///
     pub fn grow(&self, other: &Point3) -> AABB {
+        if cfg!(feature = "simd") {
+            let other = Vec3A::from(*other);
+            let min = min_ignoring_nan(self.min.into(), other);
+            let max = max_ignoring_nan(self.max.into(), other);
+            return AABB::with_bounds(min.into(), max.into());
+        }
+
         AABB::with_bounds(
             Point3::new(
                 self.min.x.min(other.x),
@@ -380,16 +522,61 @@ impl AABB {
     /// [`Point3`]: glam::Vec3
     ///
     pub fn grow_mut(&mut self, other: &Point3) {
-        self.min = Point3::new(
-            self.min.x.min(other.x),
-            self.min.y.min(other.y),
-            self.min.z.min(other.z),
-        );
-        self.max = Point3::new(
-            self.max.x.max(other.x),
-            self.max.y.max(other.y),
-            self.max.z.max(other.z),
-        );
+        *self = self.grow(other);
+    }
+
+    /// Returns a new [`AABB`] whose sides are moved outward by `margin` in all three
+    /// dimensions. A negative `margin` shrinks the [`AABB`]. Empty [`AABB`]s stay empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// // A flat AABB, which can be padded to give it some thickness.
+    /// let aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(4.0,2.0,0.0));
+    /// let grown = aabb.grown_by(0.5);
+    ///
+    /// assert_eq!(grown.min, Point3::new(-0.5,-0.5,-0.5));
+    /// assert_eq!(grown.max, Point3::new(4.5,2.5,0.5));
+    /// assert!(AABB::empty().grown_by(0.5).is_empty());
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn grown_by(&self, margin: f32) -> AABB {
+        if self.is_empty() {
+            return AABB::empty();
+        }
+
+        let margin = Vector3::new(margin, margin, margin);
+        AABB::with_bounds(self.min - margin, self.max + margin)
+    }
+
+    /// Returns a new [`AABB`] whose sides are moved outward by `factor` times the size of
+    /// the [`AABB`] along each axis. Empty [`AABB`]s stay empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(4.0,2.0,0.0));
+    /// let grown = aabb.grown_relative(0.25);
+    ///
+    /// assert_eq!(grown.min, Point3::new(-1.0,-0.5,0.0));
+    /// assert_eq!(grown.max, Point3::new(5.0,2.5,0.0));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn grown_relative(&self, factor: f32) -> AABB {
+        if self.is_empty() {
+            return AABB::empty();
+        }
+
+        let margin = self.size() * factor;
+        AABB::with_bounds(self.min - margin, self.max + margin)
     }
 
     /// Returns a new minimal [`AABB`] which contains both this [`AABB`] and the [`Bounded`]
@@ -511,6 +698,55 @@ impl AABB {
         2.0 * (size.x * size.y + size.x * size.z + size.y * size.z)
     }
 
+    /// Returns half of the surface area of this [`AABB`]. Cost functions like the surface
+    /// area heuristic only compare ratios of areas, for which this saves a multiplication.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,2.0,3.0));
+    /// assert_eq!(aabb.half_area(), 11.0);
+    /// assert_eq!(aabb.half_area() * 2.0, aabb.surface_area());
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn half_area(&self) -> f32 {
+        let size = self.size();
+        size.x * size.y + size.x * size.z + size.y * size.z
+    }
+
+    /// Returns the eight corners of the [`AABB`]. Bit `0` of the index of a corner selects
+    /// the maximum along the x-axis, bit `1` along the y-axis and bit `2` along the z-axis,
+    /// so the first corner is [`min`] and the last is [`max`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,2.0,3.0));
+    /// let corners = aabb.corners();
+    ///
+    /// assert_eq!(corners[0], aabb.min);
+    /// assert_eq!(corners[0b101], Point3::new(1.0,0.0,3.0));
+    /// assert_eq!(corners[7], aabb.max);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    /// [`max`]: #structfield.max
+    /// [`min`]: #structfield.min
+    ///
+    pub fn corners(&self) -> [Point3; 8] {
+        let mut corners = [self.min; 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            *corner = Point3::new(self[i & 1].x, self[(i >> 1) & 1].y, self[(i >> 2) & 1].z);
+        }
+        corners
+    }
+
     /// Returns the volume of this [`AABB`].
     ///
     /// # Examples
@@ -533,6 +769,120 @@ impl AABB {
         size.x * size.y * size.z
     }
 
+    /// Returns the ratio of the volume of the intersection of this [`AABB`] and `other` to
+    /// the volume of their union, between `0.0` for disjoint and `1.0` for equal [`AABB`]s.
+    /// Returns `0.0` if both [`AABB`]s have no volume.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(2.0,1.0,1.0));
+    /// let aabb2 = AABB::with_bounds(Point3::new(1.0,0.0,0.0), Point3::new(3.0,1.0,1.0));
+    /// let aabb3 = AABB::with_bounds(Point3::new(5.0,0.0,0.0), Point3::new(6.0,1.0,1.0));
+    ///
+    /// // The intersection has a volume of 1 and the union a volume of 2 + 2 - 1.
+    /// assert_eq!(aabb1.overlap(&aabb2), 1.0 / 3.0);
+    /// assert_eq!(aabb1.overlap(&aabb1), 1.0);
+    /// assert_eq!(aabb1.overlap(&aabb3), 0.0);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn overlap(&self, other: &AABB) -> f32 {
+        let intersection = match self.intersection(other) {
+            Some(intersection) => intersection.volume(),
+            None => return 0.0,
+        };
+        let union = self.volume() + other.volume() - intersection;
+        if union > 0.0 {
+            intersection / union
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the smallest [`AABB`] which contains this [`AABB`] after transforming it by
+    /// the affine `transform`. Empty [`AABB`]s stay empty.
+    ///
+    /// Like Arvo's method from Graphics Gems, this projects the half size onto the absolute
+    /// values of the matrix, instead of transforming all eight corners. The result is as
+    /// tight as the bounds of the transformed corners.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::{Point3, Vector3};
+    /// use glam::Mat4;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(-1.0,-1.0,-1.0), Point3::new(1.0,1.0,1.0));
+    /// let transform = Mat4::from_translation(Vector3::new(10.0,0.0,0.0))
+    ///     * Mat4::from_rotation_z(std::f32::consts::FRAC_PI_4);
+    ///
+    /// let transformed = aabb.transformed(&transform);
+    /// let half_diagonal = 2.0_f32.sqrt();
+    /// assert!((transformed.min.x - (10.0 - half_diagonal)).abs() < 1e-5);
+    /// assert!((transformed.max.y - half_diagonal).abs() < 1e-5);
+    /// assert!((transformed.max.z - 1.0).abs() < 1e-5);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn transformed(&self, transform: &Mat4) -> AABB {
+        if self.is_empty() {
+            return AABB::empty();
+        }
+
+        let center = transform.transform_point3(self.center());
+        let half_size = self.size() / 2.0;
+        let half_size = transform.x_axis.truncate().abs() * half_size.x
+            + transform.y_axis.truncate().abs() * half_size.y
+            + transform.z_axis.truncate().abs() * half_size.z;
+        AABB::with_bounds(center - half_size, center + half_size)
+    }
+
+    /// Returns the squared distance from `point` to the closest point of the [`AABB`].
+    /// Returns `0.0` if `point` lies inside of the [`AABB`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(-1.0,-1.0,-1.0), Point3::new(1.0,1.0,1.0));
+    ///
+    /// assert_eq!(aabb.min_distance_squared(&Point3::new(3.0,0.0,0.0)), 4.0);
+    /// assert_eq!(aabb.min_distance_squared(&Point3::new(0.5,0.0,0.0)), 0.0);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn min_distance_squared(&self, point: &Point3) -> f32 {
+        let closest = point.max(self.min).min(self.max);
+        (closest - *point).length_squared()
+    }
+
+    /// Returns the squared distance from `point` to the farthest point of the [`AABB`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(-1.0,-1.0,-1.0), Point3::new(1.0,1.0,1.0));
+    ///
+    /// assert_eq!(aabb.max_distance_squared(&Point3::new(3.0,0.0,0.0)), 18.0);
+    /// assert_eq!(aabb.max_distance_squared(&Point3::new(0.0,0.0,0.0)), 3.0);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn max_distance_squared(&self, point: &Point3) -> f32 {
+        let farthest = (*point - self.min).abs().max((self.max - *point).abs());
+        farthest.length_squared()
+    }
+
     /// Returns the axis along which the [`AABB`] is stretched the most.
     ///
     /// # Examples
@@ -561,6 +911,67 @@ impl AABB {
             Axis::Z
         }
     }
+
+    /// Returns the time of first overlap of this [`AABB`] with `other`, while this [`AABB`]
+    /// moves by `velocity` during a timestep from `0.0` to `1.0`. Returns `0.0` if the
+    /// [`AABB`]s overlap already, and `None` if they do not overlap during the timestep.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,1.0,1.0));
+    /// let wall = AABB::with_bounds(Point3::new(3.0,-5.0,-5.0), Point3::new(4.0,5.0,5.0));
+    ///
+    /// assert_eq!(aabb.sweep(&Vector3::new(4.0,0.0,0.0), &wall), Some(0.5));
+    /// assert_eq!(aabb.sweep(&Vector3::new(1.0,0.0,0.0), &wall), None);
+    /// assert_eq!(aabb.sweep(&Vector3::new(-4.0,0.0,0.0), &wall), None);
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn sweep(&self, velocity: &Vector3, other: &AABB) -> Option<f32> {
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+
+        let mut entry = 0.0f32;
+        let mut exit = 1.0f32;
+        for axis in 0..3 {
+            // The displacements along `axis` at which the `AABB`s overlap.
+            let lower = other.min[axis] - self.max[axis];
+            let upper = other.max[axis] - self.min[axis];
+            if velocity[axis] == 0.0 {
+                if lower > 0.0 || upper < 0.0 {
+                    return None;
+                }
+            } else {
+                let t_lower = lower / velocity[axis];
+                let t_upper = upper / velocity[axis];
+                entry = entry.max(t_lower.min(t_upper));
+                exit = exit.min(t_lower.max(t_upper));
+            }
+        }
+
+        if entry <= exit {
+            Some(entry)
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the lane-wise minimum of `a` and `b` like `f32::min`, which returns the other
+/// operand if one is NaN. The SIMD minimum returns `b` if either operand is NaN.
+fn min_ignoring_nan(a: Vec3A, b: Vec3A) -> Vec3A {
+    Vec3A::select(b.cmpne(b), a, a.min(b))
+}
+
+/// Returns the lane-wise maximum of `a` and `b` like `f32::max`, which returns the other
+/// operand if one is NaN. The SIMD maximum returns `b` if either operand is NaN.
+fn max_ignoring_nan(a: Vec3A, b: Vec3A) -> Vec3A {
+    Vec3A::select(b.cmpne(b), a, a.max(b))
 }
 
 /// Default instance for [`AABB`]s. Returns an [`AABB`] which is [`empty()`].
@@ -604,65 +1015,540 @@ impl Index<usize> for AABB {
     }
 }
 
-/// Implementation of [`Bounded`] for [`AABB`].
+/// Make [`AABB`]s mutably indexable. `aabb[0]` gives a mutable reference to the minimum
+/// bound. All other indices return a mutable reference to the maximum bound.
+/// Single components can be accessed by additionally indexing with an [`Axis`].
 ///
 /// # Examples
 /// ```
-/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::aabb::AABB;
+/// use bvh::axis::Axis;
 /// use bvh::Point3;
 ///
-/// let point_a = Point3::new(3.0,4.0,5.0);
-/// let point_b = Point3::new(17.0,18.0,19.0);
-/// let aabb = AABB::empty().grow(&point_a).grow(&point_b);
-///
-/// let aabb_aabb = aabb.aabb();
+/// let mut aabb = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,1.0,1.0));
+/// aabb[1][Axis::Y] = 5.0;
+/// aabb[0] = Point3::new(-1.0,-1.0,-1.0);
 ///
-/// assert_eq!(aabb_aabb.min, aabb.min);
-/// assert_eq!(aabb_aabb.max, aabb.max);
+/// assert_eq!(aabb.min, Point3::new(-1.0,-1.0,-1.0));
+/// assert_eq!(aabb.max, Point3::new(1.0,5.0,1.0));
+/// assert_eq!(aabb[1][Axis::Y], 5.0);
 /// ```
 ///
 /// [`AABB`]: struct.AABB.html
-/// [`Bounded`]: trait.Bounded.html
+/// [`Axis`]: ../axis/enum.Axis.html
 ///
-impl Bounded for AABB {
-    fn aabb(&self) -> AABB {
-        *self
+impl IndexMut<usize> for AABB {
+    fn index_mut(&mut self, index: usize) -> &mut Point3 {
+        if index == 0 {
+            &mut self.min
+        } else {
+            &mut self.max
+        }
     }
 }
 
-/// Implementation of [`Bounded`] for [`Point3`].
+/// Joins two [`AABB`]s. Same as [`AABB::join`].
 ///
 /// # Examples
 /// ```
-/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::aabb::AABB;
 /// use bvh::Point3;
 ///
-/// let point = Point3::new(3.0,4.0,5.0);
+/// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,1.0,1.0));
+/// let aabb2 = AABB::with_bounds(Point3::new(2.0,2.0,2.0), Point3::new(3.0,3.0,3.0));
 ///
-/// let aabb = point.aabb();
-/// assert!(aabb.contains(&point));
+/// let joint = aabb1 + aabb2;
+/// assert_eq!(joint.min, Point3::new(0.0,0.0,0.0));
+/// assert_eq!(joint.max, Point3::new(3.0,3.0,3.0));
 /// ```
 ///
-/// [`Bounded`]: trait.Bounded.html
-/// [`Point3`]: glam::Vec3
+/// [`AABB`]: struct.AABB.html
+/// [`AABB::join`]: struct.AABB.html#method.join
 ///
-impl Bounded for Point3 {
-    fn aabb(&self) -> AABB {
-        AABB::with_bounds(*self, *self)
+impl Add for AABB {
+    type Output = AABB;
+
+    fn add(self, other: AABB) -> AABB {
+        self.join(&other)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::aabb::{Bounded, AABB};
-    use crate::testbase::{tuple_to_point, tuple_to_vector, tuplevec_large_strategy, TupleVec};
-    use crate::EPSILON;
-    use crate::{Point3, Vector3};
-
-    use float_eq::assert_float_eq;
-    use proptest::prelude::*;
+/// Joins the `other` [`AABB`] into this one. Same as [`AABB::join_mut`].
+///
+/// [`AABB`]: struct.AABB.html
+/// [`AABB::join_mut`]: struct.AABB.html#method.join_mut
+///
+impl AddAssign for AABB {
+    fn add_assign(&mut self, other: AABB) {
+        self.join_mut(&other);
+    }
+}
 
-    proptest! {
+/// Joins all [`AABB`]s of an iterator. The sum of no [`AABB`]s is [`empty()`].
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::AABB;
+/// use bvh::Point3;
+///
+/// let points = vec![Point3::new(0.0,1.0,2.0), Point3::new(-1.0,4.0,0.0)];
+/// let aabb: AABB = points.iter().map(|point| AABB::with_bounds(*point, *point)).sum();
+///
+/// assert_eq!(aabb.min, Point3::new(-1.0,1.0,0.0));
+/// assert_eq!(aabb.max, Point3::new(0.0,4.0,2.0));
+/// ```
+///
+/// [`AABB`]: struct.AABB.html
+/// [`empty()`]: #method.empty
+///
+impl Sum for AABB {
+    fn sum<I: Iterator<Item = AABB>>(iter: I) -> AABB {
+        iter.fold(AABB::empty(), Add::add)
+    }
+}
+
+/// Intersects two [`AABB`]s. Same as [`AABB::intersection`], but returns an empty
+/// [`AABB`] if the two [`AABB`]s do not overlap.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::AABB;
+/// use bvh::Point3;
+///
+/// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(2.0,2.0,2.0));
+/// let aabb2 = AABB::with_bounds(Point3::new(1.0,1.0,1.0), Point3::new(3.0,3.0,3.0));
+///
+/// let intersection = aabb1 & aabb2;
+/// assert_eq!(intersection.min, Point3::new(1.0,1.0,1.0));
+/// assert_eq!(intersection.max, Point3::new(2.0,2.0,2.0));
+/// ```
+///
+/// [`AABB`]: struct.AABB.html
+/// [`AABB::intersection`]: struct.AABB.html#method.intersection
+///
+impl BitAnd for AABB {
+    type Output = AABB;
+
+    fn bitand(self, other: AABB) -> AABB {
+        self.intersection(&other).unwrap_or_else(AABB::empty)
+    }
+}
+
+/// Intersects this [`AABB`] with the `other` one. Becomes empty if the two [`AABB`]s do not
+/// overlap.
+///
+/// [`AABB`]: struct.AABB.html
+///
+impl BitAndAssign for AABB {
+    fn bitand_assign(&mut self, other: AABB) {
+        *self = *self & other;
+    }
+}
+
+/// Joins two [`AABB`]s. Same as [`AABB::join`].
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::AABB;
+/// use bvh::Point3;
+///
+/// let aabb1 = AABB::with_bounds(Point3::new(0.0,0.0,0.0), Point3::new(1.0,1.0,1.0));
+/// let aabb2 = AABB::with_bounds(Point3::new(2.0,2.0,2.0), Point3::new(3.0,3.0,3.0));
+///
+/// let union = aabb1 | aabb2;
+/// assert_eq!(union.min, Point3::new(0.0,0.0,0.0));
+/// assert_eq!(union.max, Point3::new(3.0,3.0,3.0));
+/// ```
+///
+/// [`AABB`]: struct.AABB.html
+/// [`AABB::join`]: struct.AABB.html#method.join
+///
+impl BitOr for AABB {
+    type Output = AABB;
+
+    fn bitor(self, other: AABB) -> AABB {
+        self.join(&other)
+    }
+}
+
+/// Joins the `other` [`AABB`] into this one. Same as [`AABB::join_mut`].
+///
+/// [`AABB`]: struct.AABB.html
+/// [`AABB::join_mut`]: struct.AABB.html#method.join_mut
+///
+impl BitOrAssign for AABB {
+    fn bitor_assign(&mut self, other: AABB) {
+        self.join_mut(&other);
+    }
+}
+
+/// Implementation of [`Bounded`] for [`AABB`].
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+///
+/// let point_a = Point3::new(3.0,4.0,5.0);
+/// let point_b = Point3::new(17.0,18.0,19.0);
+/// let aabb = AABB::empty().grow(&point_a).grow(&point_b);
+///
+/// let aabb_aabb = aabb.aabb();
+///
+/// assert_eq!(aabb_aabb.min, aabb.min);
+/// assert_eq!(aabb_aabb.max, aabb.max);
+/// ```
+///
+/// [`AABB`]: struct.AABB.html
+/// [`Bounded`]: trait.Bounded.html
+///
+impl Bounded for AABB {
+    fn aabb(&self) -> AABB {
+        *self
+    }
+}
+
+/// Implementation of [`Bounded`] for [`Point3`].
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+///
+/// let point = Point3::new(3.0,4.0,5.0);
+///
+/// let aabb = point.aabb();
+/// assert!(aabb.contains(&point));
+/// ```
+///
+/// [`Bounded`]: trait.Bounded.html
+/// [`Point3`]: glam::Vec3
+///
+impl Bounded for Point3 {
+    fn aabb(&self) -> AABB {
+        AABB::with_bounds(*self, *self)
+    }
+}
+
+/// Implementation of [`Bounded`] for the SIMD aligned [`glam::Vec3A`].
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+/// use glam::Vec3A;
+///
+/// let point = Vec3A::new(3.0,4.0,5.0);
+///
+/// let aabb = point.aabb();
+/// assert!(aabb.contains(&Point3::from(point)));
+/// ```
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl Bounded for Vec3A {
+    fn aabb(&self) -> AABB {
+        Point3::from(*self).aabb()
+    }
+}
+
+/// Implementation of [`Bounded`] for spheres, given by their center and radius.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+///
+/// let sphere = (Point3::new(3.0,4.0,5.0), 2.0);
+///
+/// let aabb = sphere.aabb();
+/// assert_eq!(aabb.min, Point3::new(1.0,2.0,3.0));
+/// assert_eq!(aabb.max, Point3::new(5.0,6.0,7.0));
+/// ```
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl Bounded for (Point3, f32) {
+    fn aabb(&self) -> AABB {
+        let (center, radius) = *self;
+        let half_size = Vector3::new(radius, radius, radius);
+        AABB::with_bounds(center - half_size, center + half_size)
+    }
+}
+
+/// Implementation of [`Bounded`] for slices. The [`AABB`] of a slice is the joint of the
+/// [`AABB`]s of its items, and [`empty()`] for an empty slice.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+///
+/// let points = vec![Point3::new(0.0,1.0,2.0), Point3::new(-1.0,4.0,0.0)];
+///
+/// let aabb = points[..].aabb();
+/// assert_eq!(aabb.min, Point3::new(-1.0,1.0,0.0));
+/// assert_eq!(aabb.max, Point3::new(0.0,4.0,2.0));
+/// ```
+///
+/// [`AABB`]: struct.AABB.html
+/// [`Bounded`]: trait.Bounded.html
+/// [`empty()`]: struct.AABB.html#method.empty
+///
+impl<T: Bounded> Bounded for [T] {
+    fn aabb(&self) -> AABB {
+        self.iter()
+            .fold(AABB::empty(), |aabb, item| aabb.join_bounded(item))
+    }
+}
+
+/// Implementation of [`Bounded`] for arrays, like for slices. This covers triangles given
+/// as `[Point3; 3]`.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+///
+/// let triangle = [
+///     Point3::new(0.0,0.0,0.0),
+///     Point3::new(1.0,0.0,-1.0),
+///     Point3::new(0.0,2.0,0.0),
+/// ];
+///
+/// let aabb = triangle.aabb();
+/// assert_eq!(aabb.min, Point3::new(0.0,0.0,-1.0));
+/// assert_eq!(aabb.max, Point3::new(1.0,2.0,0.0));
+/// ```
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl<T: Bounded, const N: usize> Bounded for [T; N] {
+    fn aabb(&self) -> AABB {
+        self[..].aabb()
+    }
+}
+
+/// Implementation of [`Bounded`] for references to [`Bounded`] types.
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl<T: Bounded + ?Sized> Bounded for &T {
+    fn aabb(&self) -> AABB {
+        (**self).aabb()
+    }
+}
+
+/// Implementation of [`Bounded`] for boxed [`Bounded`] types.
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl<T: Bounded + ?Sized> Bounded for Box<T> {
+    fn aabb(&self) -> AABB {
+        (**self).aabb()
+    }
+}
+
+/// Implementation of [`Bounded`] for reference counted [`Bounded`] types.
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl<T: Bounded + ?Sized> Bounded for Rc<T> {
+    fn aabb(&self) -> AABB {
+        (**self).aabb()
+    }
+}
+
+/// Implementation of [`Bounded`] for atomically reference counted [`Bounded`] types,
+/// which includes shared trait objects.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::Point3;
+/// use std::sync::Arc;
+///
+/// let shapes: Vec<Arc<dyn Bounded>> = vec![
+///     Arc::new(Point3::new(1.0,0.0,0.0)),
+///     Arc::new((Point3::new(0.0,0.0,0.0), 1.0)),
+/// ];
+///
+/// let aabb = shapes.as_slice().aabb();
+/// assert_eq!(aabb.min, Point3::new(-1.0,-1.0,-1.0));
+/// assert_eq!(aabb.max, Point3::new(1.0,1.0,1.0));
+/// ```
+///
+/// [`Bounded`]: trait.Bounded.html
+///
+impl<T: Bounded + ?Sized> Bounded for Arc<T> {
+    fn aabb(&self) -> AABB {
+        (**self).aabb()
+    }
+}
+
+/// Four [`AABB`]s in a structure of arrays layout, so that each coordinate of the four
+/// [`AABB`]s can be loaded into one SIMD register. See [`Ray::intersects_aabb4`].
+///
+/// [`AABB`]: struct.AABB.html
+/// [`Ray::intersects_aabb4`]: ../ray/struct.Ray.html#method.intersects_aabb4
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AABB4 {
+    /// The minimum x, y and z coordinates of the four [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub min: [Vec4; 3],
+
+    /// The maximum x, y and z coordinates of the four [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub max: [Vec4; 3],
+}
+
+impl AABB4 {
+    /// Creates four empty [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn empty() -> AABB4 {
+        AABB4 {
+            min: [Vec4::splat(f32::INFINITY); 3],
+            max: [Vec4::splat(f32::NEG_INFINITY); 3],
+        }
+    }
+
+    /// Returns the [`AABB`] in the given `slot`.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn get(&self, slot: usize) -> AABB {
+        let min = Point3::new(self.min[0][slot], self.min[1][slot], self.min[2][slot]);
+        let max = Point3::new(self.max[0][slot], self.max[1][slot], self.max[2][slot]);
+        AABB::with_bounds(min, max)
+    }
+
+    /// Replaces the [`AABB`] in the given `slot` by `aabb`.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn set(&mut self, slot: usize, aabb: &AABB) {
+        for axis in 0..3 {
+            self.min[axis][slot] = aabb.min[axis];
+            self.max[axis][slot] = aabb.max[axis];
+        }
+    }
+}
+
+impl Default for AABB4 {
+    fn default() -> AABB4 {
+        AABB4::empty()
+    }
+}
+
+impl From<[AABB; 4]> for AABB4 {
+    fn from(aabbs: [AABB; 4]) -> AABB4 {
+        let mut aabb4 = AABB4::empty();
+        for (slot, aabb) in aabbs.iter().enumerate() {
+            aabb4.set(slot, aabb);
+        }
+        aabb4
+    }
+}
+
+/// Eight [`AABB`]s in a structure of arrays layout, so that each coordinate of the eight
+/// [`AABB`]s can be loaded into one AVX register. See [`Ray::intersects_aabb8`].
+///
+/// [`AABB`]: struct.AABB.html
+/// [`Ray::intersects_aabb8`]: ../ray/struct.Ray.html#method.intersects_aabb8
+///
+#[repr(C, align(32))]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AABB8 {
+    /// The minimum x, y and z coordinates of the eight [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub min: [[f32; 8]; 3],
+
+    /// The maximum x, y and z coordinates of the eight [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub max: [[f32; 8]; 3],
+}
+
+impl AABB8 {
+    /// Creates eight empty [`AABB`]s.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn empty() -> AABB8 {
+        AABB8 {
+            min: [[f32::INFINITY; 8]; 3],
+            max: [[f32::NEG_INFINITY; 8]; 3],
+        }
+    }
+
+    /// Returns the [`AABB`] in the given `slot`.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn get(&self, slot: usize) -> AABB {
+        let min = Point3::new(self.min[0][slot], self.min[1][slot], self.min[2][slot]);
+        let max = Point3::new(self.max[0][slot], self.max[1][slot], self.max[2][slot]);
+        AABB::with_bounds(min, max)
+    }
+
+    /// Replaces the [`AABB`] in the given `slot` by `aabb`.
+    ///
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn set(&mut self, slot: usize, aabb: &AABB) {
+        for axis in 0..3 {
+            self.min[axis][slot] = aabb.min[axis];
+            self.max[axis][slot] = aabb.max[axis];
+        }
+    }
+}
+
+impl Default for AABB8 {
+    fn default() -> AABB8 {
+        AABB8::empty()
+    }
+}
+
+impl From<[AABB; 8]> for AABB8 {
+    fn from(aabbs: [AABB; 8]) -> AABB8 {
+        let mut aabb8 = AABB8::empty();
+        for (slot, aabb) in aabbs.iter().enumerate() {
+            aabb8.set(slot, aabb);
+        }
+        aabb8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bvh::BVH;
+    use crate::testbase::{
+        create_n_cubes, create_ray, default_bounds, tuple_to_point, tuple_to_vector,
+        tuplevec_large_strategy, tuplevec_small_strategy, TupleVec,
+    };
+    use crate::EPSILON;
+    use crate::{Point3, Vector3};
+
+    use float_eq::assert_float_eq;
+    use glam::{Mat4, Quat};
+    use proptest::prelude::*;
+
+    proptest! {
         // Test whether an empty `AABB` does not contains anything.
         #[test]
         fn test_empty_contains_nothing(tpl: TupleVec) {
@@ -703,6 +1589,63 @@ mod tests {
             assert!(aabb.contains(&aabb.center()));
         }
 
+        // Test whether the operators agree with `join` and `intersection`.
+        #[test]
+        fn test_operators_equal_methods(a: TupleVec, b: TupleVec, c: TupleVec, d: TupleVec) {
+            let aabb1 = AABB::empty().grow(&tuple_to_point(&a)).grow(&tuple_to_point(&b));
+            let aabb2 = AABB::empty().grow(&tuple_to_point(&c)).grow(&tuple_to_point(&d));
+
+            let union = aabb1.join(&aabb2);
+            let mut assigned = aabb1;
+            assigned |= aabb2;
+            for aabb in &[aabb1 | aabb2, assigned] {
+                assert_eq!((aabb.min, aabb.max), (union.min, union.max));
+            }
+
+            let mut assigned = aabb1;
+            assigned &= aabb2;
+            for aabb in &[aabb1 & aabb2, assigned] {
+                match aabb1.intersection(&aabb2) {
+                    Some(intersection) => {
+                        assert_eq!((aabb.min, aabb.max), (intersection.min, intersection.max));
+                    }
+                    None => assert!(aabb.is_empty()),
+                }
+            }
+        }
+
+        // Test whether a grown `AABB` contains the original `AABB` and keeps its center.
+        #[test]
+        fn test_grown_contains_original(a in tuplevec_small_strategy(),
+                                        b in tuplevec_small_strategy(),
+                                        margin in 0.0..1e10f32,
+                                        factor in 0.0..10.0f32) {
+            let aabb = AABB::empty()
+                .grow(&tuple_to_point(&a))
+                .grow(&tuple_to_point(&b));
+
+            for grown in &[aabb.grown_by(margin), aabb.grown_relative(factor)] {
+                assert!(grown.contains(&aabb.min) && grown.contains(&aabb.max));
+                let tolerance = 1e-5 * (grown.size().max_element() + grown.center().abs().max_element());
+                assert!((grown.center() - aabb.center()).abs().max_element() <= tolerance);
+            }
+        }
+
+        // Test whether the joint of two `AABB`s contains both of them, and whether their
+        // intersection is contained by both of them.
+        #[test]
+        fn test_contains_aabb_join_intersection(a: TupleVec, b: TupleVec, c: TupleVec, d: TupleVec) {
+            let aabb1 = AABB::empty().grow(&tuple_to_point(&a)).grow(&tuple_to_point(&b));
+            let aabb2 = AABB::empty().grow(&tuple_to_point(&c)).grow(&tuple_to_point(&d));
+
+            let joint = aabb1.join(&aabb2);
+            assert!(joint.contains_aabb(&aabb1) && joint.contains_aabb(&aabb2));
+            assert_eq!(aabb1.contains_aabb(&joint), aabb1.contains_aabb(&aabb2));
+
+            let intersection = aabb1 & aabb2;
+            assert!(aabb1.contains_aabb(&intersection) && aabb2.contains_aabb(&intersection));
+        }
+
         // Test whether the joint of two point-sets contains all the points.
         #[test]
         fn test_join_two_aabbs(a: (TupleVec, TupleVec, TupleVec, TupleVec, TupleVec),
@@ -766,6 +1709,36 @@ mod tests {
             assert!(!aabb.contains(&outside_mmm));
         }
 
+        // Test whether an `AABB` which is swept towards another `AABB` overlaps it at the
+        // reported time, and does not overlap it shortly before.
+        #[test]
+        fn test_sweep_overlaps_at_time(a in tuplevec_small_strategy(),
+                                       b in tuplevec_small_strategy(),
+                                       c in tuplevec_small_strategy(),
+                                       d in tuplevec_small_strategy(),
+                                       v in tuplevec_small_strategy()) {
+            let aabb = AABB::empty().grow(&tuple_to_point(&a)).grow(&tuple_to_point(&b));
+            let other = AABB::empty().grow(&tuple_to_point(&c)).grow(&tuple_to_point(&d));
+            let velocity = tuple_to_vector(&v);
+
+            if let Some(time) = aabb.sweep(&velocity, &other) {
+                assert!((0.0..=1.0).contains(&time));
+                let moved = AABB::with_bounds(aabb.min + velocity * time, aabb.max + velocity * time);
+                let scale = [velocity, aabb.min, aabb.max, other.min, other.max]
+                    .iter()
+                    .map(|v| v.abs().max_element())
+                    .fold(1.0, f32::max);
+                let tolerance = scale * 1e-5;
+                let grown = AABB::with_bounds(
+                    other.min - Vector3::new(tolerance, tolerance, tolerance),
+                    other.max + Vector3::new(tolerance, tolerance, tolerance),
+                );
+                assert!(moved.intersects(&grown));
+            } else {
+                assert!(!aabb.intersects(&other));
+            }
+        }
+
         // Test whether the surface of a nonempty AABB is always positive.
         #[test]
         fn test_surface_always_positive(a: TupleVec, b: TupleVec) {
@@ -812,6 +1785,23 @@ mod tests {
             assert_float_eq!(volume_a, volume_b, rmax <= EPSILON);
         }
 
+        // Test whether the overlap of two `AABB`s is symmetric and between 0 and 1.
+        #[test]
+        fn test_overlap_in_unit_interval(a in tuplevec_small_strategy(),
+                                         b in tuplevec_small_strategy(),
+                                         c in tuplevec_small_strategy(),
+                                         d in tuplevec_small_strategy()) {
+            let aabb1 = AABB::empty().grow(&tuple_to_point(&a)).grow(&tuple_to_point(&b));
+            let aabb2 = AABB::empty().grow(&tuple_to_point(&c)).grow(&tuple_to_point(&d));
+
+            let overlap = aabb1.overlap(&aabb2);
+            assert!((0.0..=1.0).contains(&overlap));
+            assert_eq!(overlap, aabb2.overlap(&aabb1));
+            if aabb1.volume() > 0.0 {
+                assert_float_eq!(aabb1.overlap(&aabb1), 1.0, abs <= EPSILON);
+            }
+        }
+
         // Test whether generating an `AABB` from the min and max bounds yields the same `AABB`.
         #[test]
         fn test_create_aabb_from_indexable(a: TupleVec, b: TupleVec, p: TupleVec) {
@@ -829,5 +1819,75 @@ mod tests {
             // The AABBs should be the same
             assert!(aabb.contains(&point) == aabb_by_index.contains(&point));
         }
+
+        // Test whether a transformed `AABB` equals the bounds of its transformed corners.
+        #[test]
+        fn test_transformed_bounds_corners(a in tuplevec_small_strategy(),
+                                           b in tuplevec_small_strategy(),
+                                           translation in tuplevec_small_strategy(),
+                                           axis in tuplevec_small_strategy(),
+                                           angle in -10.0..10.0f32,
+                                           scale in tuplevec_small_strategy()) {
+            let axis = tuple_to_vector(&axis);
+            let scale = tuple_to_vector(&scale) / 1e10;
+            prop_assume!(axis.length() > 0.1);
+
+            let aabb = AABB::empty()
+                .grow(&tuple_to_point(&a))
+                .grow(&tuple_to_point(&b));
+            let transform = Mat4::from_scale_rotation_translation(
+                scale,
+                Quat::from_axis_angle(axis.normalize(), angle),
+                tuple_to_vector(&translation),
+            );
+
+            let expected = aabb
+                .corners()
+                .iter()
+                .fold(AABB::empty(), |bounds, corner| bounds.grow(&transform.transform_point3(*corner)));
+            let transformed = aabb.transformed(&transform);
+
+            let tolerance = 1e-4 * (expected.size().max_element() + expected.center().abs().max_element());
+            assert!((transformed.min - expected.min).abs().max_element() <= tolerance);
+            assert!((transformed.max - expected.max).abs().max_element() <= tolerance);
+        }
+    }
+
+    #[test]
+    /// Tests whether a `BVH` can be traversed with shapes behind smart pointers.
+    fn test_traverse_boxed_shapes() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(10, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let boxed: Vec<Box<dyn Bounded>> = triangles
+            .iter()
+            .map(|triangle| Box::new(triangle.aabb()) as Box<dyn Bounded>)
+            .collect();
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let expected = bvh.traverse(&ray, &triangles);
+            let found = bvh.traverse(&ray, &boxed);
+            assert_eq!(found.len(), expected.len());
+        }
+    }
+
+    #[test]
+    /// Tests whether `AABB::join` and `AABB::grow` ignore NaN bounds like `f32::min` and
+    /// `f32::max`, whichever operand is NaN, so that the `simd` feature does not change
+    /// the result.
+    fn test_join_grow_ignore_nan() {
+        let nan = f32::NAN;
+        let aabb = AABB::with_bounds(Point3::new(0.0, nan, 0.0), Point3::new(1.0, nan, 1.0));
+        let other = AABB::with_bounds(Point3::new(nan, -1.0, 2.0), Point3::new(nan, 2.0, 3.0));
+        for joint in [aabb.join(&other), other.join(&aabb)] {
+            assert_eq!(joint.min, Point3::new(0.0, -1.0, 0.0));
+            assert_eq!(joint.max, Point3::new(1.0, 2.0, 3.0));
+        }
+
+        let grown = aabb.grow(&Point3::new(nan, 0.5, 2.0));
+        assert_eq!(grown.min, Point3::new(0.0, 0.5, 0.0));
+        assert_eq!(grown.max, Point3::new(1.0, 0.5, 2.0));
     }
 }
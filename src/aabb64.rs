@@ -0,0 +1,359 @@
+//! This module defines [`AABB64`], a double precision variant of the [`AABB`] for scenes
+//! which are too large for the precision of `f32`.
+//!
+//! A [`BVH`] stays in single precision, because its [`AABB`]s only need to be conservative.
+//! Shapes with double precision coordinates compute their [`AABB64`] and convert it with
+//! [`AABB64::to_aabb`], which rounds the bounds outwards, so that the [`AABB`] contains the
+//! [`AABB64`]. A [`Ray64`] then traverses the [`BVH`] through [`BVH::query`] and tests the
+//! shapes in double precision. No coordinates have to be rescaled into a local frame.
+//!
+//! # Examples
+//! ```
+//! use bvh::aabb::{Bounded, AABB};
+//! use bvh::aabb64::{AABB64, Bounded64};
+//! use bvh::bounding_hierarchy::BHShape;
+//! use bvh::bvh::BVH;
+//! use bvh::ray64::{DPoint3, DVector3, Ray64};
+//!
+//! struct Sphere64 {
+//!     center: DPoint3,
+//!     radius: f64,
+//!     node_index: usize,
+//! }
+//!
+//! impl Bounded64 for Sphere64 {
+//!     fn aabb64(&self) -> AABB64 {
+//!         let half_size = DVector3::splat(self.radius);
+//!         AABB64::with_bounds(self.center - half_size, self.center + half_size)
+//!     }
+//! }
+//!
+//! impl Bounded for Sphere64 {
+//!     fn aabb(&self) -> AABB {
+//!         self.aabb64().to_aabb()
+//!     }
+//! }
+//!
+//! impl BHShape for Sphere64 {
+//!     fn set_bh_node_index(&mut self, index: usize) {
+//!         self.node_index = index;
+//!     }
+//!
+//!     fn bh_node_index(&self) -> usize {
+//!         self.node_index
+//!     }
+//! }
+//!
+//! // Spheres one millimeter apart, at a distance where `f32` has a precision of 64 meters.
+//! let mut spheres: Vec<Sphere64> = (0..10)
+//!     .map(|i| Sphere64 {
+//!         center: DPoint3::new(1.0e9 + i as f64 * 0.001, 0.0, 0.0),
+//!         radius: 0.0001,
+//!         node_index: 0,
+//!     })
+//!     .collect();
+//! let bvh = BVH::build(&mut spheres);
+//!
+//! let ray = Ray64::new(DPoint3::new(1.0e9 + 0.003, 0.0, -1.0), DVector3::new(0.0, 0.0, 1.0));
+//! let hits: Vec<&Sphere64> = bvh
+//!     .query(&ray, &spheres)
+//!     .into_iter()
+//!     .filter(|sphere| ray.intersects_aabb64(&sphere.aabb64()))
+//!     .collect();
+//! assert_eq!(hits.len(), 1);
+//! assert_eq!(hits[0].center.x, 1.0e9 + 0.003);
+//! ```
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`AABB64`]: struct.AABB64.html
+//! [`AABB64::to_aabb`]: struct.AABB64.html#method.to_aabb
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`BVH::query`]: ../bvh/struct.BVH.html#method.query
+//! [`Ray64`]: ../ray64/struct.Ray64.html
+//!
+
+use std::fmt;
+
+use crate::aabb::AABB;
+use crate::ray64::{DPoint3, DVector3};
+use crate::Point3;
+
+/// A double precision [`AABB`].
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub struct AABB64 {
+    /// Minimum coordinates
+    pub min: DPoint3,
+
+    /// Maximum coordinates
+    pub max: DPoint3,
+}
+
+impl fmt::Display for AABB64 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Min bound: {}; Max bound: {}", self.min, self.max)
+    }
+}
+
+/// A trait implemented by things which can be bounded by an [`AABB64`].
+///
+/// [`AABB64`]: struct.AABB64.html
+///
+pub trait Bounded64 {
+    /// Returns the geometric bounds of this object in the form of an [`AABB64`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    fn aabb64(&self) -> AABB64;
+}
+
+impl AABB64 {
+    /// Creates a new [`AABB64`] with the given bounds.
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn with_bounds(min: DPoint3, max: DPoint3) -> AABB64 {
+        AABB64 { min, max }
+    }
+
+    /// Creates a new empty [`AABB64`], like [`AABB::empty`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    /// [`AABB::empty`]: ../aabb/struct.AABB.html#method.empty
+    ///
+    pub fn empty() -> AABB64 {
+        AABB64 {
+            min: DPoint3::splat(f64::INFINITY),
+            max: DPoint3::splat(f64::NEG_INFINITY),
+        }
+    }
+
+    /// Returns true if the [`DPoint3`] is inside the [`AABB64`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    /// [`DPoint3`]: ../ray64/type.DPoint3.html
+    ///
+    pub fn contains(&self, p: &DPoint3) -> bool {
+        self.min.cmple(*p).all() && p.cmple(self.max).all()
+    }
+
+    /// Returns true if `other` is inside the [`AABB64`]. An empty `other` is inside of
+    /// every [`AABB64`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn contains_aabb(&self, other: &AABB64) -> bool {
+        other.is_empty() || (self.min.cmple(other.min).all() && other.max.cmple(self.max).all())
+    }
+
+    /// Returns a new minimal [`AABB64`] which contains both this [`AABB64`] and `other`.
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn join(&self, other: &AABB64) -> AABB64 {
+        AABB64::with_bounds(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Mutable version of [`AABB64::join`].
+    ///
+    /// [`AABB64::join`]: struct.AABB64.html#method.join
+    ///
+    pub fn join_mut(&mut self, other: &AABB64) {
+        *self = self.join(other);
+    }
+
+    /// Returns a new minimal [`AABB64`] which contains both this [`AABB64`] and the
+    /// [`DPoint3`] `other`.
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    /// [`DPoint3`]: ../ray64/type.DPoint3.html
+    ///
+    pub fn grow(&self, other: &DPoint3) -> AABB64 {
+        AABB64::with_bounds(self.min.min(*other), self.max.max(*other))
+    }
+
+    /// Mutable version of [`AABB64::grow`].
+    ///
+    /// [`AABB64::grow`]: struct.AABB64.html#method.grow
+    ///
+    pub fn grow_mut(&mut self, other: &DPoint3) {
+        *self = self.grow(other);
+    }
+
+    /// Returns the size of this [`AABB64`] in all three dimensions.
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn size(&self) -> DVector3 {
+        self.max - self.min
+    }
+
+    /// Returns the center [`DPoint3`] of the [`AABB64`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    /// [`DPoint3`]: ../ray64/type.DPoint3.html
+    ///
+    pub fn center(&self) -> DPoint3 {
+        self.min + (self.size() / 2.0)
+    }
+
+    /// Returns true if the [`AABB64`] is empty.
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Returns the total surface area of this [`AABB64`].
+    ///
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    pub fn surface_area(&self) -> f64 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.x * size.z + size.y * size.z)
+    }
+
+    /// Converts the [`AABB64`] into the smallest single precision [`AABB`] which
+    /// contains it. The bounds are rounded outwards, so every [`Ray64`] which hits the
+    /// [`AABB64`] also hits the [`AABB`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb64::AABB64;
+    /// use bvh::ray64::DPoint3;
+    ///
+    /// let aabb64 = AABB64::with_bounds(DPoint3::new(0.1, 0.1, 0.1), DPoint3::new(0.2, 0.2, 0.2));
+    /// let aabb = aabb64.to_aabb();
+    ///
+    /// assert!((aabb.min.x as f64) <= 0.1);
+    /// assert!((aabb.max.x as f64) >= 0.2);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`AABB64`]: struct.AABB64.html
+    /// [`Ray64`]: ../ray64/struct.Ray64.html
+    ///
+    pub fn to_aabb(&self) -> AABB {
+        let min = self.min.to_array().map(round_down);
+        let max = self.max.to_array().map(round_up);
+        AABB::with_bounds(Point3::from(min), Point3::from(max))
+    }
+}
+
+/// Rounds `x` to the largest `f32` which is not greater than `x`.
+fn round_down(x: f64) -> f32 {
+    let rounded = x as f32;
+    if rounded as f64 > x {
+        next_after(rounded, false)
+    } else {
+        rounded
+    }
+}
+
+/// Rounds `x` to the smallest `f32` which is not less than `x`.
+fn round_up(x: f64) -> f32 {
+    let rounded = x as f32;
+    if (rounded as f64) < x {
+        next_after(rounded, true)
+    } else {
+        rounded
+    }
+}
+
+/// Returns the neighbour of the finite or infinite `x` above it if `up` is true, and below
+/// it otherwise.
+fn next_after(x: f32, up: bool) -> f32 {
+    if x == 0.0 {
+        let smallest = f32::from_bits(1);
+        return if up { smallest } else { -smallest };
+    }
+    // The bits of a float grow with its magnitude.
+    let bits = x.to_bits();
+    if (x > 0.0) == up {
+        f32::from_bits(bits + 1)
+    } else {
+        f32::from_bits(bits - 1)
+    }
+}
+
+impl From<AABB> for AABB64 {
+    /// Widens an [`AABB`] exactly into an [`AABB64`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`AABB64`]: struct.AABB64.html
+    ///
+    fn from(aabb: AABB) -> AABB64 {
+        AABB64::with_bounds(aabb.min.as_dvec3(), aabb.max.as_dvec3())
+    }
+}
+
+impl Default for AABB64 {
+    fn default() -> AABB64 {
+        AABB64::empty()
+    }
+}
+
+impl Bounded64 for AABB64 {
+    fn aabb64(&self) -> AABB64 {
+        *self
+    }
+}
+
+impl Bounded64 for DPoint3 {
+    fn aabb64(&self) -> AABB64 {
+        AABB64::with_bounds(*self, *self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb64::{next_after, AABB64};
+    use crate::ray64::DPoint3;
+    use proptest::prelude::*;
+
+    #[test]
+    /// Tests whether the conversion of an empty `AABB64` is empty.
+    fn test_empty_to_aabb() {
+        assert!(AABB64::empty().to_aabb().is_empty());
+    }
+
+    #[test]
+    /// Tests whether bounds beyond the range of `f32` are rounded to infinity or to the
+    /// largest finite `f32`, respectively.
+    fn test_to_aabb_out_of_range() {
+        let aabb64 = AABB64::with_bounds(DPoint3::splat(-1.0e300), DPoint3::splat(1.0e300));
+        let aabb = aabb64.to_aabb();
+        assert_eq!(aabb.min.x, f32::NEG_INFINITY);
+        assert_eq!(aabb.max.x, f32::INFINITY);
+
+        let aabb64 = AABB64::with_bounds(DPoint3::splat(1.0e300), DPoint3::splat(1.0e301));
+        assert_eq!(aabb64.to_aabb().min.x, f32::MAX);
+    }
+
+    proptest! {
+        // Test whether the `AABB` of an `AABB64` contains it, and whether the next `f32`
+        // inwards of each bound would not.
+        #[test]
+        fn test_to_aabb_is_tight(a: (f64, f64, f64), b: (f64, f64, f64)) {
+            let aabb64 = AABB64::empty()
+                .grow(&DPoint3::new(a.0, a.1, a.2))
+                .grow(&DPoint3::new(b.0, b.1, b.2));
+            let aabb = aabb64.to_aabb();
+            assert!(AABB64::from(aabb).contains_aabb(&aabb64));
+
+            for axis in 0..3 {
+                if aabb.min[axis].is_finite() {
+                    assert!(next_after(aabb.min[axis], true) as f64 > aabb64.min[axis]);
+                }
+                if aabb.max[axis].is_finite() {
+                    assert!((next_after(aabb.max[axis], false) as f64) < aabb64.max[axis]);
+                }
+            }
+        }
+    }
+}
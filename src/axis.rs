@@ -36,6 +36,7 @@ struct MyType<T>(T);
 /// # }
 /// ```
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
 pub enum Axis {
     /// Index of the X axis.
     X = 0,
@@ -0,0 +1,331 @@
+//! This module exports a harness to benchmark acceleration structures against each other.
+//!
+//! Other acceleration structures, like bindings to other ray tracing
+//! libraries, can be measured on the same scenes as the [`BoundingHierarchy`]s of this crate.
+//! Implement [`BenchmarkableBh`] for a structure and pass it to [`measure`].
+//!
+//! # Examples
+//!
+//! ```
+//! use bvh::benchmark::{measure, verify, BenchmarkableBh, BhAdapter, Scene};
+//! use bvh::bvh::BVH;
+//!
+//! let scene = Scene::cubes(100);
+//! let rays = scene.rays(1000, 0);
+//! let measurement = measure::<BhAdapter<BVH>>(&scene, &rays);
+//! assert!(measurement.hits > 0);
+//!
+//! let bh = BhAdapter::<BVH>::build(&scene);
+//! assert!(verify(&bh, &scene, &rays));
+//! ```
+//!
+//! [`BenchmarkableBh`]: trait.BenchmarkableBh.html
+//! [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+//! [`measure`]: fn.measure.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bounding_hierarchy::{BHShape, IndexedBoundingHierarchy};
+use crate::ray::Ray;
+use crate::{Point3, Vector3};
+
+use std::time::{Duration, Instant};
+
+/// A deterministic scene of triangles.
+#[derive(Debug, Clone)]
+pub struct Scene {
+    /// The vertices of the triangles of the scene.
+    pub triangles: Vec<[Point3; 3]>,
+
+    /// The [`AABB`] which contains the scene.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub bounds: AABB,
+}
+
+impl Scene {
+    /// Creates a [`Scene`] of `n` unit size cubes at deterministic random positions
+    /// inside of the cube from `-100_000` to `100_000` on every axis. The scene consists
+    /// of `12 * n` triangles.
+    ///
+    /// [`Scene`]: struct.Scene.html
+    ///
+    pub fn cubes(n: usize) -> Scene {
+        let bounds = default_bounds();
+        let mut triangles = Vec::with_capacity(12 * n);
+        let mut seed = 0;
+        for _ in 0..n {
+            triangles.extend_from_slice(&cube_triangles(next_point3(&mut seed, &bounds)));
+        }
+        Scene { triangles, bounds }
+    }
+
+    /// Creates `n` deterministic random rays from `seed`. The rays start inside of the
+    /// bounds of the scene and point towards another point inside of the bounds.
+    pub fn rays(&self, n: usize, mut seed: u64) -> Vec<Ray> {
+        (0..n)
+            .map(|_| {
+                let origin = next_point3(&mut seed, &self.bounds);
+                let direction = next_point3(&mut seed, &self.bounds);
+                Ray::new(origin, direction)
+            })
+            .collect()
+    }
+}
+
+/// An acceleration structure which can be measured on a [`Scene`].
+///
+/// [`Scene`]: struct.Scene.html
+///
+pub trait BenchmarkableBh: Sized {
+    /// Builds the acceleration structure over the triangles of `scene`.
+    fn build(scene: &Scene) -> Self;
+
+    /// Returns the indices of the triangles which may be hit by `ray`. Every triangle whose
+    /// [`AABB`] is hit must be returned, see [`verify`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`verify`]: fn.verify.html
+    ///
+    fn traverse(&self, ray: &Ray) -> Vec<usize>;
+}
+
+/// A triangle of a [`Scene`], which is given to the [`BoundingHierarchy`] by [`BhAdapter`].
+///
+/// [`BhAdapter`]: struct.BhAdapter.html
+/// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+/// [`Scene`]: struct.Scene.html
+///
+struct SceneTriangle {
+    aabb: AABB,
+    node_index: usize,
+}
+
+impl Bounded for SceneTriangle {
+    fn aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl BHShape for SceneTriangle {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+/// Adapts an [`IndexedBoundingHierarchy`] of this crate to [`BenchmarkableBh`].
+///
+/// [`BenchmarkableBh`]: trait.BenchmarkableBh.html
+/// [`IndexedBoundingHierarchy`]: ../bounding_hierarchy/trait.IndexedBoundingHierarchy.html
+///
+pub struct BhAdapter<BH: IndexedBoundingHierarchy> {
+    bh: BH,
+}
+
+impl<BH: IndexedBoundingHierarchy> BenchmarkableBh for BhAdapter<BH> {
+    fn build(scene: &Scene) -> BhAdapter<BH> {
+        let mut shapes: Vec<_> = scene
+            .triangles
+            .iter()
+            .map(|triangle| SceneTriangle {
+                aabb: triangle.iter().fold(AABB::empty(), |aabb, v| aabb.grow(v)),
+                node_index: 0,
+            })
+            .collect();
+        BhAdapter {
+            bh: BH::build(&mut shapes),
+        }
+    }
+
+    fn traverse(&self, ray: &Ray) -> Vec<usize> {
+        self.bh.traverse_indices(ray)
+    }
+}
+
+/// The result of [`measure`].
+///
+/// [`measure`]: fn.measure.html
+///
+#[derive(Debug, Copy, Clone)]
+pub struct Measurement {
+    /// The time it took to build the acceleration structure.
+    pub build_time: Duration,
+
+    /// The time it took to traverse the acceleration structure with all rays.
+    pub traverse_time: Duration,
+
+    /// The total number of triangles returned by all traversals.
+    pub hits: usize,
+}
+
+/// Builds `B` over `scene` and traverses it with all `rays`, measuring the time of both.
+pub fn measure<B: BenchmarkableBh>(scene: &Scene, rays: &[Ray]) -> Measurement {
+    let start = Instant::now();
+    let bh = B::build(scene);
+    let build_time = start.elapsed();
+
+    let start = Instant::now();
+    let hits = rays.iter().map(|ray| bh.traverse(ray).len()).sum();
+    let traverse_time = start.elapsed();
+
+    Measurement {
+        build_time,
+        traverse_time,
+        hits,
+    }
+}
+
+/// Checks whether `bh` returns every triangle of `scene` whose [`AABB`] is hit by one of the
+/// `rays`, so that a faster structure does not simply skip work.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+pub fn verify<B: BenchmarkableBh>(bh: &B, scene: &Scene, rays: &[Ray]) -> bool {
+    let aabbs: Vec<AABB> = scene
+        .triangles
+        .iter()
+        .map(|triangle| triangle.iter().fold(AABB::empty(), |aabb, v| aabb.grow(v)))
+        .collect();
+    rays.iter().all(|ray| {
+        let mut found = vec![false; aabbs.len()];
+        for index in bh.traverse(ray) {
+            if let Some(found) = found.get_mut(index) {
+                *found = true;
+            }
+        }
+        aabbs
+            .iter()
+            .zip(found)
+            .all(|(aabb, found)| found || !ray.intersects_aabb(aabb))
+    })
+}
+
+/// Returns the 12 triangles of the surface of a unit size cube centered at `pos`.
+pub(crate) fn cube_triangles(pos: Point3) -> [[Point3; 3]; 12] {
+    let top_front_right = pos + Vector3::new(0.5, 0.5, -0.5);
+    let top_back_right = pos + Vector3::new(0.5, 0.5, 0.5);
+    let top_back_left = pos + Vector3::new(-0.5, 0.5, 0.5);
+    let top_front_left = pos + Vector3::new(-0.5, 0.5, -0.5);
+    let bottom_front_right = pos + Vector3::new(0.5, -0.5, -0.5);
+    let bottom_back_right = pos + Vector3::new(0.5, -0.5, 0.5);
+    let bottom_back_left = pos + Vector3::new(-0.5, -0.5, 0.5);
+    let bottom_front_left = pos + Vector3::new(-0.5, -0.5, -0.5);
+
+    [
+        [top_back_right, top_front_right, top_front_left],
+        [top_front_left, top_back_left, top_back_right],
+        [bottom_front_left, bottom_front_right, bottom_back_right],
+        [bottom_back_right, bottom_back_left, bottom_front_left],
+        [top_back_left, top_front_left, bottom_front_left],
+        [bottom_front_left, bottom_back_left, top_back_left],
+        [bottom_front_right, top_front_right, top_back_right],
+        [top_back_right, bottom_back_right, bottom_front_right],
+        [top_front_left, top_front_right, bottom_front_right],
+        [bottom_front_right, bottom_front_left, top_front_left],
+        [bottom_back_right, top_back_right, top_back_left],
+        [top_back_left, bottom_back_left, bottom_back_right],
+    ]
+}
+
+/// Implementation of splitmix64.
+/// For reference see: http://xoroshiro.di.unimi.it/splitmix64.c
+fn splitmix64(x: &mut u64) -> u64 {
+    *x = x.wrapping_add(0x9E3779B97F4A7C15u64);
+    let mut z = *x;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9u64);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EBu64);
+    z ^ (z >> 31)
+}
+
+/// Generates a new `i32` triple. Mutates the seed.
+fn next_point3_raw(seed: &mut u64) -> (i32, i32, i32) {
+    let u = splitmix64(seed);
+    let a = ((u >> 32) & 0xFFFFFFFF) as i64 - 0x80000000;
+    let b = (u & 0xFFFFFFFF) as i64 - 0x80000000;
+    let c = a ^ b.rotate_left(6);
+    (a as i32, b as i32, c as i32)
+}
+
+/// Generates a new `Point3`, which will lie inside the given `aabb`. Mutates the seed.
+pub(crate) fn next_point3(seed: &mut u64, aabb: &AABB) -> Point3 {
+    let (a, b, c) = next_point3_raw(seed);
+    let float_vector = Vector3::new(
+        (a as f32 / i32::MAX as f32) + 1.0,
+        (b as f32 / i32::MAX as f32) + 1.0,
+        (c as f32 / i32::MAX as f32) + 1.0,
+    ) * 0.5;
+
+    assert!(float_vector.x >= 0.0 && float_vector.x <= 1.0);
+    assert!(float_vector.y >= 0.0 && float_vector.y <= 1.0);
+    assert!(float_vector.z >= 0.0 && float_vector.z <= 1.0);
+
+    let size = aabb.size();
+    let offset = Vector3::new(
+        float_vector.x * size.x,
+        float_vector.y * size.y,
+        float_vector.z * size.z,
+    );
+    aabb.min + offset
+}
+
+/// Returns the [`AABB`] of the space in which the scenes are generated.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+pub(crate) fn default_bounds() -> AABB {
+    AABB::with_bounds(
+        Point3::new(-100_000.0, -100_000.0, -100_000.0),
+        Point3::new(100_000.0, 100_000.0, 100_000.0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::benchmark::{verify, BenchmarkableBh, BhAdapter, Scene};
+    use crate::bvh::BVH;
+    use crate::flat_bvh::FlatBVH;
+    use crate::ray::Ray;
+
+    /// Returns all triangles, which is trivially correct.
+    struct AllTriangles(usize);
+
+    impl BenchmarkableBh for AllTriangles {
+        fn build(scene: &Scene) -> AllTriangles {
+            AllTriangles(scene.triangles.len())
+        }
+
+        fn traverse(&self, _: &Ray) -> Vec<usize> {
+            (0..self.0).collect()
+        }
+    }
+
+    /// Returns no triangles, which is wrong.
+    struct NoTriangles;
+
+    impl BenchmarkableBh for NoTriangles {
+        fn build(_: &Scene) -> NoTriangles {
+            NoTriangles
+        }
+
+        fn traverse(&self, _: &Ray) -> Vec<usize> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    /// Tests whether `verify` accepts the adapters and catches a wrong structure.
+    fn test_verify() {
+        let scene = Scene::cubes(100);
+        let rays = scene.rays(100, 0);
+
+        assert!(verify(&BhAdapter::<BVH>::build(&scene), &scene, &rays));
+        assert!(verify(&BhAdapter::<FlatBVH>::build(&scene), &scene, &rays));
+        assert!(verify(&AllTriangles::build(&scene), &scene, &rays));
+        assert!(!verify(&NoTriangles::build(&scene), &scene, &rays));
+    }
+}
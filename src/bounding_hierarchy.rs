@@ -163,7 +163,7 @@ pub trait BoundingHierarchy {
     /// [`BoundingHierarchy`]: trait.BoundingHierarchy.html
     /// [`AABB`]: ../aabb/struct.AABB.html
     ///
-    fn traverse<'a, Shape: BHShape>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&Shape>;
+    fn traverse<'a, Shape: BHShape>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape>;
 
     /// Prints the [`BoundingHierarchy`] in a tree-like visualization.
     ///
@@ -171,3 +171,98 @@ pub trait BoundingHierarchy {
     ///
     fn pretty_print(&self) {}
 }
+
+/// A [`BoundingHierarchy`] which can return the indices of the hit shapes without access to
+/// the shapes themselves.
+///
+/// [`BoundingHierarchy`]: trait.BoundingHierarchy.html
+///
+pub trait IndexedBoundingHierarchy: BoundingHierarchy {
+    /// Traverses the [`IndexedBoundingHierarchy`].
+    /// Returns the indices of the shapes whose [`AABB`]s were hit by `ray`, e.g. to look up
+    /// attributes of the shapes which are stored in parallel arrays.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::IndexedBoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// use bvh::ray::Ray;
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bvh() -> (BVH, Vec<UnitBox>) {
+    /// #     let mut shapes: Vec<_> = (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect();
+    /// #     let bvh = BVH::build(&mut shapes);
+    /// #     (bvh, shapes)
+    /// # }
+    ///
+    /// let (bvh, shapes) = create_bvh();
+    /// let materials: Vec<u32> = (0..shapes.len() as u32).collect();
+    ///
+    /// let origin = Point3::new(-10.0, 0.0, 0.0);
+    /// let direction = Vector3::new(1.0, 0.0, 0.0);
+    /// let ray = Ray::new(origin, direction);
+    /// for index in bvh.traverse_indices(&ray) {
+    ///     let _material = materials[index];
+    /// }
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`IndexedBoundingHierarchy`]: trait.IndexedBoundingHierarchy.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize>;
+
+    /// Returns the shapes at the indices returned by [`traverse_indices`] whose [`AABB`]s
+    /// are hit by `ray`. Implementations of [`BoundingHierarchy::traverse`] can delegate to
+    /// this method.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BoundingHierarchy::traverse`]: trait.BoundingHierarchy.html#tymethod.traverse
+    /// [`traverse_indices`]: trait.IndexedBoundingHierarchy.html#tymethod.traverse_indices
+    ///
+    fn traverse_shapes<'a, Shape: Bounded>(
+        &'a self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.traverse_indices(ray)
+            .into_iter()
+            .map(|index| &shapes[index])
+            .filter(|shape| ray.intersects_aabb(&shape.aabb()))
+            .collect()
+    }
+}
+
+/// Reports the memory used by a hierarchy, e.g. to budget the acceleration structures of
+/// many meshes.
+pub trait MemoryUsage {
+    /// Returns the number of bytes used by the hierarchy, including its heap buffers. The
+    /// whole capacity of the buffers is counted, not only their length.
+    fn memory_usage(&self) -> usize;
+}
@@ -0,0 +1,294 @@
+//! This module defines the traversal of a [`BVH`] with many [`Ray`]s at once.
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`Ray`]: ../ray/struct.Ray.html
+//!
+
+use crate::aabb::Bounded;
+use crate::bvh::{BVHNode, BVH};
+use crate::packet::RayPacket;
+use crate::ray::Ray;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// The number of rays which [`BVH::par_traverse_batch`] traverses together on one thread.
+///
+/// [`BVH::par_traverse_batch`]: struct.BVH.html#method.par_traverse_batch
+///
+#[cfg(feature = "rayon")]
+pub const PARALLEL_BATCH_SIZE: usize = 256;
+
+impl BVH {
+    /// Traverses the [`BVH`] with all `rays` at once. Returns the hits of each ray, in the
+    /// same order as [`BVH::traverse`] would return them for that ray.
+    ///
+    /// Each node is visited once for all rays which hit its [`AABB`], instead of once per
+    /// ray. This works best for coherent rays, like the primary rays of a camera, which
+    /// share most of the nodes they visit.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::traverse`]: struct.BVH.html#method.traverse
+    ///
+    pub fn traverse_batch<'a, Shape: Bounded>(
+        &'a self,
+        rays: &[Ray],
+        shapes: &'a [Shape],
+    ) -> Vec<Vec<&'a Shape>> {
+        let mut hits = vec![Vec::new(); rays.len()];
+        self.traverse_batch_indices(rays, &mut |ray_index, shape_index| {
+            hits[ray_index].push(&shapes[shape_index]);
+        });
+        hits
+    }
+
+    /// Parallel variant of [`BVH::traverse_batch`]. The rays are split into chunks of
+    /// [`PARALLEL_BATCH_SIZE`], which are traversed with [`BVH::traverse_batch`] on the
+    /// threads of the global `rayon` thread pool. Returns the same hits in the same order.
+    ///
+    /// Only available with the `rayon` feature.
+    ///
+    /// [`BVH::traverse_batch`]: struct.BVH.html#method.traverse_batch
+    /// [`PARALLEL_BATCH_SIZE`]: constant.PARALLEL_BATCH_SIZE.html
+    ///
+    #[cfg(feature = "rayon")]
+    pub fn par_traverse_batch<'a, Shape: Bounded + Sync>(
+        &'a self,
+        rays: &[Ray],
+        shapes: &'a [Shape],
+    ) -> Vec<Vec<&'a Shape>> {
+        rays.par_chunks(PARALLEL_BATCH_SIZE)
+            .flat_map_iter(|chunk| self.traverse_batch(chunk, shapes))
+            .collect()
+    }
+
+    /// Traverses the [`BVH`] with a [`RayPacket`]. Returns the hits of each lane, in the
+    /// same order as [`BVH::traverse`] would return them for the ray of that lane.
+    ///
+    /// The children of a node are tested against all lanes at once, and the traversal
+    /// keeps track of the mask of the lanes which are still active in each subtree.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BHShape;
+    /// use bvh::bvh::BVH;
+    /// use bvh::packet::RayPacket4;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # struct UnitBox {
+    /// #     pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    ///
+    /// // A row of boxes along the x-axis.
+    /// let mut shapes: Vec<UnitBox> = (0..100)
+    ///     .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    ///     .collect();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let along = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// let across = Ray::new(Point3::new(50.0, -10.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    /// let above = Ray::new(Point3::new(-10.0, 5.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// let packet = RayPacket4::new([&along, &across, &above, &along]);
+    /// let hits = bvh.traverse_packet(&packet, &shapes);
+    ///
+    /// assert_eq!(hits[0].len(), 100);
+    /// assert_eq!(hits[1].len(), 1);
+    /// assert!(hits[2].is_empty());
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::traverse`]: struct.BVH.html#method.traverse
+    /// [`RayPacket`]: ../packet/struct.RayPacket.html
+    ///
+    pub fn traverse_packet<'a, Shape: Bounded, const N: usize>(
+        &'a self,
+        packet: &RayPacket<N>,
+        shapes: &'a [Shape],
+    ) -> Vec<Vec<&'a Shape>> {
+        let mut hits = vec![Vec::new(); N];
+        if self.nodes.is_empty() {
+            return hits;
+        }
+
+        let mut stack = vec![(0, RayPacket::<N>::ALL_LANES)];
+        while let Some((node_index, mask)) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    // Push the right child first, so that the left child is visited first.
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        let child_mask = packet.intersects_aabb(child_aabb) & mask;
+                        if child_mask != 0 {
+                            stack.push((child_index as usize, child_mask));
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    for (lane, lane_hits) in hits.iter_mut().enumerate() {
+                        if mask & (1 << lane) != 0 {
+                            lane_hits.push(&shapes[shape_index as usize]);
+                        }
+                    }
+                }
+            }
+        }
+
+        hits
+    }
+
+    /// Traverses the [`BVH`] with all `rays` at once, and calls `visit` with the index of
+    /// the ray and the index of the shape for every hit [`AABB`].
+    ///
+    /// The indices of the rays which are still active are kept in a single buffer. The
+    /// entries on the stack refer to ranges of that buffer, and a node appends the rays
+    /// which hit each of its children to the end of the buffer.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn traverse_batch_indices<F>(&self, rays: &[Ray], visit: &mut F)
+    where
+        F: FnMut(usize, usize),
+    {
+        if self.nodes.is_empty() || rays.is_empty() {
+            return;
+        }
+
+        let mut active: Vec<usize> = (0..rays.len()).collect();
+        let mut stack = vec![(0, 0, rays.len())];
+        while let Some((node_index, start, end)) = stack.pop() {
+            // Entries pushed after this one have already been popped, so the rest of the
+            // buffer is no longer needed.
+            active.truncate(end);
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    // Push the right child first, so that the left child is visited first.
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        let child_start = active.len();
+                        for i in start..end {
+                            let ray_index = active[i];
+                            if rays[ray_index].intersects_aabb(child_aabb) {
+                                active.push(ray_index);
+                            }
+                        }
+                        if active.len() > child_start {
+                            stack.push((child_index as usize, child_start, active.len()));
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    for &ray_index in &active[start..end] {
+                        visit(ray_index, shape_index as usize);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::BVH;
+    use crate::packet::RayPacket8;
+    use crate::ray::Ray;
+    use crate::testbase::{assert_same_shapes, create_n_cubes, create_ray, default_bounds};
+
+    #[test]
+    /// Tests whether `traverse_batch` returns the same hits as `traverse` for every ray.
+    fn test_traverse_batch_equals_traverse() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for &n in &[0, 1, 10, 1000] {
+            let rays: Vec<Ray> = (0..n).map(|_| create_ray(&mut seed, &bounds)).collect();
+            let hits = bvh.traverse_batch(&rays, &triangles);
+
+            assert_eq!(hits.len(), rays.len());
+            for (ray, found) in rays.iter().zip(hits.iter()) {
+                let expected = bvh.traverse(ray, &triangles);
+                assert_same_shapes(found, &expected);
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_packet` returns the same hits as `traverse` for every lane.
+    fn test_traverse_packet_equals_traverse() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let rays: Vec<Ray> = (0..8).map(|_| create_ray(&mut seed, &bounds)).collect();
+            let packet = RayPacket8::new([
+                &rays[0], &rays[1], &rays[2], &rays[3], &rays[4], &rays[5], &rays[6], &rays[7],
+            ]);
+            let hits = bvh.traverse_packet(&packet, &triangles);
+
+            assert_eq!(hits.len(), rays.len());
+            for (ray, found) in rays.iter().zip(hits.iter()) {
+                let expected = bvh.traverse(ray, &triangles);
+                assert_same_shapes(found, &expected);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    /// Tests whether `par_traverse_batch` returns the same hits as `traverse_batch`.
+    fn test_par_traverse_batch_equals_traverse_batch() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for &n in &[0, 1, 1000, 10000] {
+            let rays: Vec<Ray> = (0..n).map(|_| create_ray(&mut seed, &bounds)).collect();
+            let expected = bvh.traverse_batch(&rays, &triangles);
+            let hits = bvh.par_traverse_batch(&rays, &triangles);
+
+            assert_eq!(hits.len(), expected.len());
+            for (found, expected) in hits.iter().zip(expected.iter()) {
+                assert_same_shapes(found, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! This module defines the best-first traversal of a [`BVH`], which always expands the node
+//! with the smallest key next. It is the engine behind the ordered and nearest queries.
+//!
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHNode, BVH};
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// An entry of a [`BinaryHeap`], which is ordered by its `priority`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct HeapEntry {
+    pub(crate) priority: f32,
+    pub(crate) index: usize,
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &HeapEntry) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &HeapEntry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &HeapEntry) -> Ordering {
+        self.priority
+            .partial_cmp(&other.priority)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// A leaf which is reached by a [`BestFirst`] traversal.
+///
+/// [`BestFirst`]: struct.BestFirst.html
+///
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct BestFirstLeaf {
+    /// The index of the leaf node.
+    pub(crate) node_index: usize,
+    /// The index of the shape of the leaf.
+    pub(crate) shape_index: usize,
+    /// The key of the leaf. The root has no stored [`AABB`], so its key is negative infinity.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub(crate) key: f32,
+}
+
+/// The state of a best-first traversal of a [`BVH`]. The nodes are expanded in order of
+/// increasing key, which is computed from their [`AABB`]. As the key of a node must be
+/// a lower bound of the keys of its descendants, the leaves are reached in order as well.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: struct.BVH.html
+///
+#[derive(Debug, Clone)]
+pub(crate) struct BestFirst {
+    /// The nodes which still have to be expanded, smallest key first.
+    queue: BinaryHeap<Reverse<HeapEntry>>,
+}
+
+impl BestFirst {
+    /// Starts a best-first traversal at the root of `bvh`.
+    pub(crate) fn new(bvh: &BVH) -> BestFirst {
+        let mut queue = BinaryHeap::new();
+        if !bvh.nodes.is_empty() {
+            // The `AABB` of the root is not stored, so it is always expanded first.
+            queue.push(Reverse(HeapEntry {
+                priority: f32::NEG_INFINITY,
+                index: 0,
+            }));
+        }
+        BestFirst { queue }
+    }
+
+    /// Expands nodes until the next leaf is reached and returns it. `key` returns the key
+    /// of an [`AABB`], or `None` if the node should be skipped. Nodes whose key exceeds
+    /// `cutoff` are pruned, so the traversal ends once no node below `cutoff` remains.
+    /// `cutoff` must not increase between calls.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub(crate) fn next_leaf<F>(&mut self, bvh: &BVH, key: F, cutoff: f32) -> Option<BestFirstLeaf>
+    where
+        F: Fn(&AABB) -> Option<f32>,
+    {
+        while let Some(Reverse(HeapEntry { priority, index })) = self.queue.pop() {
+            if priority > cutoff {
+                self.queue.clear();
+                return None;
+            }
+
+            match bvh.nodes[index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_l_aabb, child_l_index), (child_r_aabb, child_r_index)]
+                    {
+                        match key(child_aabb) {
+                            Some(child_key) if child_key <= cutoff => {
+                                self.queue.push(Reverse(HeapEntry {
+                                    priority: child_key,
+                                    index: child_index as usize,
+                                }));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    return Some(BestFirstLeaf {
+                        node_index: index,
+                        shape_index: shape_index as usize,
+                        key: priority,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::best_first::BestFirst;
+    use crate::bvh::BVH;
+    use crate::testbase::{create_n_cubes, create_ray, default_bounds};
+
+    #[test]
+    /// Tests whether the leaves hit by a ray are reached in order of their entry distance.
+    fn test_best_first_entry_distance() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let key = |aabb: &_| ray.intersection_slab(aabb).map(|(entry, _)| entry);
+
+            let mut search = BestFirst::new(&bvh);
+            let mut keys = Vec::new();
+            let mut indices = Vec::new();
+            while let Some(leaf) = search.next_leaf(&bvh, key, f32::INFINITY) {
+                keys.push(leaf.key);
+                indices.push(leaf.shape_index);
+            }
+            assert!(keys.windows(2).all(|pair| pair[0] <= pair[1]));
+
+            let mut expected = bvh.traverse_indices(&ray);
+            expected.sort_unstable();
+            indices.sort_unstable();
+            assert_eq!(indices, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether no leaf beyond the cutoff is reached.
+    fn test_best_first_cutoff() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let key = |aabb: &_| ray.intersection_slab(aabb).map(|(entry, _)| entry);
+            let cutoff = 10.0;
+
+            let mut search = BestFirst::new(&bvh);
+            while let Some(leaf) = search.next_leaf(&bvh, key, cutoff) {
+                assert!(leaf.key <= cutoff);
+            }
+        }
+    }
+}
@@ -0,0 +1,422 @@
+//! This module defines a compact binary format of the [`BVH`], which is written by
+//! [`BVH::write_to`] and read by [`BVH::read_from`].
+//!
+//! The format starts with the magic bytes `BVH\0`, followed by the version of the format
+//! and the number of nodes. Then the nodes follow in the order of [`BVH::nodes`]. A node
+//! starts with a tag byte, which is `0` for an inner node and `1` for a leaf. An inner node
+//! continues with the index and the [`AABB`] of its left child and then of its right child,
+//! while a leaf continues with the index of its shape. All numbers are stored in
+//! little-endian byte order, indices as `u32` and the bounds of an [`AABB`] as `f32`.
+//!
+//! The parent indices and depths of the nodes are not stored, but restored when reading.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: struct.BVH.html
+//! [`BVH::nodes`]: struct.BVH.html#structfield.nodes
+//! [`BVH::read_from`]: struct.BVH.html#method.read_from
+//! [`BVH::write_to`]: struct.BVH.html#method.write_to
+//!
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHNode, BVH};
+use crate::Point3;
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+/// The magic bytes at the start of the binary format.
+const MAGIC: [u8; 4] = *b"BVH\0";
+
+/// The version of the binary format which is written by [`BVH::write_to`].
+///
+/// [`BVH::write_to`]: struct.BVH.html#method.write_to
+///
+const VERSION: u32 = 1;
+
+/// The tag byte of an inner node.
+const NODE_TAG: u8 = 0;
+
+/// The tag byte of a leaf.
+const LEAF_TAG: u8 = 1;
+
+impl BVH {
+    /// Writes the [`BVH`] to `writer` in the compact binary format described in the
+    /// [module documentation](index.html). A leaf takes 5 bytes and an inner node 57
+    /// bytes. `writer` should be buffered, as the nodes are written field by field.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..100)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let mut bytes = Vec::new();
+    /// bvh.write_to(&mut bytes).unwrap();
+    /// assert_eq!(bytes.len(), 12 + 100 * 5 + 99 * 57);
+    ///
+    /// let loaded = BVH::read_from(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!(loaded.nodes, bvh.nodes);
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_u32(writer, self.nodes.len())?;
+        for node in &self.nodes {
+            match *node {
+                BVHNode::Node {
+                    child_l_index,
+                    ref child_l_aabb,
+                    child_r_index,
+                    ref child_r_aabb,
+                    ..
+                } => {
+                    writer.write_all(&[NODE_TAG])?;
+                    writer.write_all(&child_l_index.to_le_bytes())?;
+                    write_aabb(writer, child_l_aabb)?;
+                    writer.write_all(&child_r_index.to_le_bytes())?;
+                    write_aabb(writer, child_r_aabb)?;
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    writer.write_all(&[LEAF_TAG])?;
+                    writer.write_all(&shape_index.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a [`BVH`] which was written by [`BVH::write_to`] from `reader`. `reader`
+    /// should be buffered, as the nodes are read field by field.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`InvalidData`] if the data does not start with the magic
+    /// bytes, has an unknown version, contains an unknown tag or a node index which is out
+    /// of range, or if the nodes do not form a tree, in which every node is reached from the
+    /// root exactly once. Errors of `reader` are passed through, which includes an error of kind
+    /// [`UnexpectedEof`] if the data ends early.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::write_to`]: struct.BVH.html#method.write_to
+    /// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+    /// [`UnexpectedEof`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.UnexpectedEof
+    ///
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<BVH> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("the data is not a BVH".to_string()));
+        }
+        let version = read_u32(reader)?;
+        if version != VERSION {
+            return Err(invalid_data(format!(
+                "version {} of the BVH format is not supported",
+                version
+            )));
+        }
+
+        let node_count = read_u32(reader)?;
+        // The capacity is limited, so that a corrupt count cannot exhaust the memory.
+        let mut nodes = Vec::with_capacity(node_count.min(1 << 20) as usize);
+        let check_index = |index: u32| {
+            if index < node_count {
+                Ok(index)
+            } else {
+                Err(invalid_data(format!(
+                    "node index {} is out of range for {} nodes",
+                    index, node_count
+                )))
+            }
+        };
+        for _ in 0..node_count {
+            let mut tag = [0; 1];
+            reader.read_exact(&mut tag)?;
+            let node = match tag[0] {
+                NODE_TAG => BVHNode::Node {
+                    parent_index: 0,
+                    depth: 0,
+                    child_l_index: check_index(read_u32(reader)?)?,
+                    child_l_aabb: read_aabb(reader)?,
+                    child_r_index: check_index(read_u32(reader)?)?,
+                    child_r_aabb: read_aabb(reader)?,
+                },
+                LEAF_TAG => BVHNode::Leaf {
+                    parent_index: 0,
+                    depth: 0,
+                    shape_index: read_u32(reader)?,
+                },
+                tag => return Err(invalid_data(format!("unknown node tag {}", tag))),
+            };
+            nodes.push(node);
+        }
+        link_nodes(&mut nodes)?;
+        Ok(BVH { nodes })
+    }
+}
+
+/// Restores the parent indices and depths of `nodes`, starting at the root. Fails unless
+/// every node is reached exactly once, which rules out children pointing to themselves or
+/// to their ancestors. Children are not required to follow their parents, as
+/// [`BVH::optimize`] may place them anywhere.
+///
+/// [`BVH::optimize`]: struct.BVH.html#method.optimize
+///
+fn link_nodes(nodes: &mut [BVHNode]) -> io::Result<()> {
+    if nodes.is_empty() {
+        return Ok(());
+    }
+
+    let mut reached = vec![false; nodes.len()];
+    reached[0] = true;
+    let mut reached_count = 1;
+    let mut stack = vec![0];
+    while let Some(index) = stack.pop() {
+        let (child_l_index, child_r_index, depth) = match nodes[index] {
+            BVHNode::Node {
+                child_l_index,
+                child_r_index,
+                depth,
+                ..
+            } => (child_l_index, child_r_index, depth),
+            BVHNode::Leaf { .. } => continue,
+        };
+        for &child_index in &[child_l_index, child_r_index] {
+            let child_index = child_index as usize;
+            if reached[child_index] {
+                return Err(invalid_data(format!(
+                    "node {} is reached more than once",
+                    child_index
+                )));
+            }
+            reached[child_index] = true;
+            reached_count += 1;
+            match nodes[child_index] {
+                BVHNode::Node {
+                    ref mut parent_index,
+                    depth: ref mut child_depth,
+                    ..
+                }
+                | BVHNode::Leaf {
+                    ref mut parent_index,
+                    depth: ref mut child_depth,
+                    ..
+                } => {
+                    *parent_index = index as u32;
+                    *child_depth = depth + 1;
+                }
+            }
+            stack.push(child_index);
+        }
+    }
+
+    if reached_count != nodes.len() {
+        return Err(invalid_data(format!(
+            "{} nodes are not reached from the root",
+            nodes.len() - reached_count
+        )));
+    }
+    Ok(())
+}
+
+/// Returns an [`io::Error`] of kind [`InvalidData`] with the given `message`.
+///
+/// [`InvalidData`]: https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.InvalidData
+/// [`io::Error`]: https://doc.rust-lang.org/std/io/struct.Error.html
+///
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes `value` as a little-endian `u32`.
+fn write_u32<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    let value =
+        u32::try_from(value).map_err(|_| invalid_data(format!("{} is too large", value)))?;
+    writer.write_all(&value.to_le_bytes())
+}
+
+/// Reads a little-endian `u32`.
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Writes the bounds of `aabb` as little-endian `f32`s, `min` first.
+fn write_aabb<W: Write>(writer: &mut W, aabb: &AABB) -> io::Result<()> {
+    let mut bytes = [0; 24];
+    let (min, max) = (aabb.min.to_array(), aabb.max.to_array());
+    let values = min.iter().chain(&max);
+    for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    writer.write_all(&bytes)
+}
+
+/// Reads the bounds of an [`AABB`] written by `write_aabb`.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+fn read_aabb<R: Read>(reader: &mut R) -> io::Result<AABB> {
+    let mut bytes = [0; 24];
+    reader.read_exact(&mut bytes)?;
+    let mut values = [0.0; 6];
+    for (value, chunk) in values.iter_mut().zip(bytes.chunks_exact(4)) {
+        *value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    Ok(AABB::with_bounds(
+        Point3::new(values[0], values[1], values[2]),
+        Point3::new(values[3], values[4], values[5]),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::bvh::{BVHNode, BVH};
+    use crate::testbase::{create_n_cubes, default_bounds};
+
+    use std::io::ErrorKind;
+
+    #[test]
+    /// Tests whether a `BVH` survives a round trip through the binary format, and whether
+    /// corrupt data is rejected.
+    fn test_binary_round_trip() {
+        let mut triangles = create_n_cubes(100, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let mut bytes = Vec::new();
+        bvh.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"BVH\0\x01\0\0\0");
+        let loaded = BVH::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(loaded.nodes, bvh.nodes);
+        loaded.assert_consistent(&triangles);
+
+        let error_kind = |bytes: &[u8]| BVH::read_from(&mut &bytes[..]).unwrap_err().kind();
+        assert_eq!(
+            error_kind(&bytes[..bytes.len() - 1]),
+            ErrorKind::UnexpectedEof
+        );
+
+        let mut corrupt = bytes.clone();
+        corrupt[0] = b'X';
+        assert_eq!(error_kind(&corrupt), ErrorKind::InvalidData);
+
+        let mut corrupt = bytes.clone();
+        corrupt[4] = 2;
+        assert_eq!(error_kind(&corrupt), ErrorKind::InvalidData);
+
+        // The left child index of the root.
+        let mut corrupt = bytes;
+        corrupt[13..17].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(error_kind(&corrupt), ErrorKind::InvalidData);
+    }
+
+    /// Writes a `BVH` with the given nodes, which need not form a valid tree.
+    fn write_nodes(nodes: Vec<BVHNode>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        BVH { nodes }.write_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Creates an inner node with the given children.
+    fn inner_node(child_l_index: u32, child_r_index: u32) -> BVHNode {
+        BVHNode::Node {
+            parent_index: 0,
+            depth: 0,
+            child_l_index,
+            child_l_aabb: AABB::empty(),
+            child_r_index,
+            child_r_aabb: AABB::empty(),
+        }
+    }
+
+    /// Creates a leaf for the given shape.
+    fn leaf(shape_index: u32) -> BVHNode {
+        BVHNode::Leaf {
+            parent_index: 0,
+            depth: 0,
+            shape_index,
+        }
+    }
+
+    #[test]
+    /// Tests whether nodes which do not form a tree are rejected, and whether the parent
+    /// indices and depths are restored for a tree with children before their parents.
+    fn test_binary_structure() {
+        let error_kind = |nodes| {
+            let bytes = write_nodes(nodes);
+            BVH::read_from(&mut bytes.as_slice()).unwrap_err().kind()
+        };
+        // A child which points to its parent.
+        assert_eq!(
+            error_kind(vec![inner_node(1, 0), leaf(0)]),
+            ErrorKind::InvalidData
+        );
+        // A child which points to itself.
+        assert_eq!(
+            error_kind(vec![inner_node(1, 2), inner_node(1, 3), leaf(0), leaf(1)]),
+            ErrorKind::InvalidData
+        );
+        // A child which points to an ancestor.
+        assert_eq!(
+            error_kind(vec![inner_node(1, 2), inner_node(3, 0), leaf(0), leaf(1)]),
+            ErrorKind::InvalidData
+        );
+        // A node which is shared by two parents.
+        assert_eq!(
+            error_kind(vec![inner_node(1, 2), inner_node(3, 2), leaf(0), leaf(1)]),
+            ErrorKind::InvalidData
+        );
+        // A node which is not reached from the root.
+        assert_eq!(
+            error_kind(vec![inner_node(1, 2), leaf(0), leaf(1), leaf(2)]),
+            ErrorKind::InvalidData
+        );
+
+        let bytes = write_nodes(vec![
+            inner_node(3, 1),
+            leaf(0),
+            leaf(1),
+            inner_node(4, 2),
+            leaf(2),
+        ]);
+        let loaded = BVH::read_from(&mut bytes.as_slice()).unwrap();
+        let parents: Vec<usize> = loaded.nodes.iter().map(|node| node.parent()).collect();
+        let depths: Vec<u32> = loaded.nodes.iter().map(|node| node.depth()).collect();
+        assert_eq!(parents, vec![0, 0, 3, 0, 3]);
+        assert_eq!(depths, vec![0, 1, 2, 1, 2]);
+    }
+}
@@ -5,22 +5,36 @@
 //!
 
 use crate::aabb::{Bounded, AABB};
-use crate::bounding_hierarchy::{BHShape, BoundingHierarchy};
-use crate::bvh::iter::BVHTraverseIterator;
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
+use crate::bvh::iter::{BVHOrderedIterator, BVHTraverseIterator, MAX_TRAVERSAL_DEPTH};
+use crate::bvh::{BuildArena, BuildScratch};
 use crate::ray::Ray;
 use crate::utils::{concatenate_vectors, joint_aabb_of_shapes, Bucket};
 use crate::Point3;
 use crate::EPSILON;
 use std::f32;
+use std::ops::ControlFlow;
+
+/// The maximum number of shapes in a [`BVH`]. The indices in a [`BVHNode`] are `u32`,
+/// and a [`BVH`] of `n` shapes has `2 * n - 1` nodes.
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVHNode`]: enum.BVHNode.html
+///
+pub const MAX_SHAPES: usize = 1 << 31;
 
 /// The [`BVHNode`] enum that describes a node in a [`BVH`].
 /// It's either a leaf node and references a shape (by holding its index)
 /// or a regular node that has two child nodes.
 /// The non-leaf node stores the [`AABB`]s of its children.
+/// All indices are stored as `u32`, see [`MAX_SHAPES`].
 ///
 /// [`AABB`]: ../aabb/struct.AABB.html
 /// [`BVH`]: struct.BVH.html
 /// [`BVH`]: struct.BVHNode.html
+/// [`MAX_SHAPES`]: constant.MAX_SHAPES.html
 ///
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
@@ -29,30 +43,30 @@ pub enum BVHNode {
     /// Leaf node.
     Leaf {
         /// The node's parent.
-        parent_index: usize,
+        parent_index: u32,
 
         /// The node's depth.
         depth: u32,
 
         /// The shape contained in this leaf.
-        shape_index: usize,
+        shape_index: u32,
     },
     /// Inner node.
     Node {
         /// The node's parent.
-        parent_index: usize,
+        parent_index: u32,
 
         /// The node's depth.
         depth: u32,
 
         /// Index of the left subtree's root node.
-        child_l_index: usize,
+        child_l_index: u32,
 
         /// The convex hull of the shapes' `AABB`s in child_l.
         child_l_aabb: AABB,
 
         /// Index of the right subtree's root node.
-        child_r_index: usize,
+        child_r_index: u32,
 
         /// The convex hull of the shapes' `AABB`s in child_r.
         child_r_aabb: AABB,
@@ -109,12 +123,14 @@ impl BVHNode {
     /// Returns the index of the parent node.
     pub fn parent(&self) -> usize {
         match *self {
-            BVHNode::Node { parent_index, .. } | BVHNode::Leaf { parent_index, .. } => parent_index,
+            BVHNode::Node { parent_index, .. } | BVHNode::Leaf { parent_index, .. } => {
+                parent_index as usize
+            }
         }
     }
 
     /// Returns a mutable reference to the parent node index.
-    pub fn parent_mut(&mut self) -> &mut usize {
+    pub fn parent_mut(&mut self) -> &mut u32 {
         match *self {
             BVHNode::Node {
                 ref mut parent_index,
@@ -130,7 +146,7 @@ impl BVHNode {
     /// Returns the index of the left child node.
     pub fn child_l(&self) -> usize {
         match *self {
-            BVHNode::Node { child_l_index, .. } => child_l_index,
+            BVHNode::Node { child_l_index, .. } => child_l_index as usize,
             _ => panic!("Tried to get the left child of a leaf node."),
         }
     }
@@ -157,7 +173,7 @@ impl BVHNode {
     /// Returns the index of the right child node.
     pub fn child_r(&self) -> usize {
         match *self {
-            BVHNode::Node { child_r_index, .. } => child_r_index,
+            BVHNode::Node { child_r_index, .. } => child_r_index as usize,
             _ => panic!("Tried to get the right child of a leaf node."),
         }
     }
@@ -198,7 +214,7 @@ impl BVHNode {
                 child_r_aabb,
                 ..
             } => child_l_aabb.join(&child_r_aabb),
-            BVHNode::Leaf { shape_index, .. } => shapes[shape_index].aabb(),
+            BVHNode::Leaf { shape_index, .. } => shapes[shape_index as usize].aabb(),
         }
     }
 
@@ -206,14 +222,14 @@ impl BVHNode {
     /// or `None` if it is an interior node.
     pub fn shape_index(&self) -> Option<usize> {
         match *self {
-            BVHNode::Leaf { shape_index, .. } => Some(shape_index),
+            BVHNode::Leaf { shape_index, .. } => Some(shape_index as usize),
             _ => None,
         }
     }
 
     /// The build function sometimes needs to add nodes while their data is not available yet.
     /// A dummy cerated by this function serves the purpose of being changed later on.
-    fn create_dummy() -> BVHNode {
+    pub(crate) fn create_dummy() -> BVHNode {
         BVHNode::Leaf {
             parent_index: 0,
             depth: 0,
@@ -233,31 +249,39 @@ impl BVHNode {
         parent_index: usize,
         depth: u32,
     ) -> usize {
-        // Helper function to accumulate the AABB joint and the centroids AABB
-        fn grow_convex_hull(convex_hull: (AABB, AABB), shape_aabb: &AABB) -> (AABB, AABB) {
-            let center = &shape_aabb.center();
-            let convex_hull_aabbs = &convex_hull.0;
-            let convex_hull_centroids = &convex_hull.1;
-            (
-                convex_hull_aabbs.join(shape_aabb),
-                convex_hull_centroids.grow(center),
-            )
-        }
+        let mut pool = BuildScratch::new();
+        BVHNode::build_pooled(shapes, indices, nodes, parent_index, depth, &mut pool)
+    }
 
-        let mut convex_hull = Default::default();
+    /// Like [`BVHNode::build`], but takes the index vectors of the partitions from the
+    /// [`BuildArena`] `pool` and returns them to it when they are no longer needed, so that
+    /// they are allocated only once per build, or once for many builds.
+    ///
+    /// [`BVHNode::build`]: enum.BVHNode.html#method.build
+    /// [`BuildArena`]: trait.BuildArena.html
+    ///
+    pub(crate) fn build_pooled<T: BHShape, A: BuildArena + ?Sized>(
+        shapes: &mut [T],
+        indices: &[usize],
+        nodes: &mut Vec<BVHNode>,
+        parent_index: usize,
+        depth: u32,
+        pool: &mut A,
+    ) -> usize {
+        // Accumulate the `AABB` of the centroids of the shapes.
+        let mut centroid_bounds = AABB::empty();
         for index in indices {
-            convex_hull = grow_convex_hull(convex_hull, &shapes[*index].aabb());
+            centroid_bounds.grow_mut(&shapes[*index].aabb().center());
         }
-        let (aabb_bounds, centroid_bounds) = convex_hull;
 
         // If there is only one element left, don't split anymore
         if indices.len() == 1 {
             let shape_index = indices[0];
             let node_index = nodes.len();
             nodes.push(BVHNode::Leaf {
-                parent_index,
+                parent_index: parent_index as u32,
                 depth,
-                shape_index,
+                shape_index: shape_index as u32,
             });
             // Let the shape know the index of the node that represents it.
             shapes[shape_index].set_bh_node_index(node_index);
@@ -285,15 +309,18 @@ impl BVHNode {
 
             // Proceed recursively.
             let child_l_index =
-                BVHNode::build(shapes, child_l_indices, nodes, node_index, depth + 1);
+                BVHNode::build_pooled(shapes, child_l_indices, nodes, node_index, depth + 1, pool);
             let child_r_index =
-                BVHNode::build(shapes, child_r_indices, nodes, node_index, depth + 1);
+                BVHNode::build_pooled(shapes, child_r_indices, nodes, node_index, depth + 1, pool);
             (child_l_index, child_l_aabb, child_r_index, child_r_aabb)
         } else {
             // Create six `Bucket`s, and six index assignment vector.
             const NUM_BUCKETS: usize = 6;
             let mut buckets = [Bucket::empty(); NUM_BUCKETS];
             let mut bucket_assignments: [Vec<usize>; NUM_BUCKETS] = Default::default();
+            for assignments in bucket_assignments.iter_mut() {
+                *assignments = pool.alloc_indices();
+            }
 
             // In this branch the `split_axis_size` is large enough to perform meaningful splits.
             // We start by assigning the shapes to `Bucket`s.
@@ -324,9 +351,15 @@ impl BVHNode {
                 let child_l = l_buckets.iter().fold(Bucket::empty(), Bucket::join_bucket);
                 let child_r = r_buckets.iter().fold(Bucket::empty(), Bucket::join_bucket);
 
-                let cost = (child_l.size as f32 * child_l.aabb.surface_area()
-                    + child_r.size as f32 * child_r.aabb.surface_area())
-                    / aabb_bounds.surface_area();
+                // Empty children have an infinite surface area, which would make the cost NaN.
+                if child_l.size == 0 || child_r.size == 0 {
+                    continue;
+                }
+
+                // The cost is not normalized by the surface area of the parent node, because
+                // that is the same for all splits, and zero for zero-thickness shapes.
+                let cost = child_l.size as f32 * child_l.aabb.surface_area()
+                    + child_r.size as f32 * child_r.aabb.surface_area();
                 if cost < min_cost {
                     min_bucket = i;
                     min_cost = cost;
@@ -337,14 +370,20 @@ impl BVHNode {
 
             // Join together all index buckets.
             let (l_assignments, r_assignments) = bucket_assignments.split_at_mut(min_bucket + 1);
-            let child_l_indices = concatenate_vectors(l_assignments);
-            let child_r_indices = concatenate_vectors(r_assignments);
+            let child_l_indices = concatenate_vectors(l_assignments, pool.alloc_indices());
+            let child_r_indices = concatenate_vectors(r_assignments, pool.alloc_indices());
+            for assignments in bucket_assignments {
+                pool.free_indices(assignments);
+            }
 
             // Proceed recursively.
             let child_l_index =
-                BVHNode::build(shapes, &child_l_indices, nodes, node_index, depth + 1);
+                BVHNode::build_pooled(shapes, &child_l_indices, nodes, node_index, depth + 1, pool);
             let child_r_index =
-                BVHNode::build(shapes, &child_r_indices, nodes, node_index, depth + 1);
+                BVHNode::build_pooled(shapes, &child_r_indices, nodes, node_index, depth + 1, pool);
+            for child_indices in [child_l_indices, child_r_indices] {
+                pool.free_indices(child_indices);
+            }
             (child_l_index, child_l_aabb, child_r_index, child_r_aabb)
         };
 
@@ -352,12 +391,12 @@ impl BVHNode {
         assert!(!child_l_aabb.is_empty());
         assert!(!child_r_aabb.is_empty());
         nodes[node_index] = BVHNode::Node {
-            parent_index,
+            parent_index: parent_index as u32,
             depth,
             child_l_aabb,
-            child_l_index,
+            child_l_index: child_l_index as u32,
             child_r_aabb,
-            child_r_index,
+            child_r_index: child_r_index as u32,
         };
 
         node_index
@@ -385,16 +424,102 @@ impl BVHNode {
                 ..
             } => {
                 if ray.intersects_aabb(child_l_aabb) {
-                    BVHNode::traverse_recursive(nodes, child_l_index, ray, indices);
+                    BVHNode::traverse_recursive(nodes, child_l_index as usize, ray, indices);
                 }
                 if ray.intersects_aabb(child_r_aabb) {
-                    BVHNode::traverse_recursive(nodes, child_r_index, ray, indices);
+                    BVHNode::traverse_recursive(nodes, child_r_index as usize, ray, indices);
                 }
             }
             BVHNode::Leaf { shape_index, .. } => {
-                indices.push(shape_index);
+                indices.push(shape_index as usize);
+            }
+        }
+    }
+
+    /// Traverses the [`BVH`] iteratively, starting at `node_index`, and returns all shapes
+    /// whose [`AABB`] is intersected by the given [`Ray`]. The shapes are returned in the
+    /// same order as by [`BVHNode::traverse_recursive`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVHNode::traverse_recursive`]: enum.BVHNode.html#method.traverse_recursive
+    /// [`Ray`]: ../ray/struct.Ray.html
+    ///
+    pub fn traverse_iterative(
+        nodes: &[BVHNode],
+        node_index: usize,
+        ray: &Ray,
+        indices: &mut Vec<usize>,
+    ) {
+        let _ = BVHNode::traverse_iterative_with(nodes, node_index, ray, &mut |shape_index| {
+            indices.push(shape_index);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+
+    /// Traverses the [`BVH`] iteratively, starting at `node_index`, and calls `visit` with
+    /// the index of every shape whose [`AABB`] is intersected by the given [`Ray`].
+    /// The traversal stops as soon as `visit` returns [`ControlFlow::Break`].
+    ///
+    /// Pending nodes are kept on a fixed-size stack of [`MAX_TRAVERSAL_DEPTH`] entries.
+    /// Subtrees which do not fit onto the stack are traversed with a fresh stack.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`ControlFlow::Break`]: https://doc.rust-lang.org/std/ops/enum.ControlFlow.html
+    /// [`MAX_TRAVERSAL_DEPTH`]: constant.MAX_TRAVERSAL_DEPTH.html
+    /// [`Ray`]: ../ray/struct.Ray.html
+    ///
+    fn traverse_iterative_with<B, F>(
+        nodes: &[BVHNode],
+        node_index: usize,
+        ray: &Ray,
+        visit: &mut F,
+    ) -> ControlFlow<B>
+    where
+        F: FnMut(usize) -> ControlFlow<B>,
+    {
+        let mut stack = [0; MAX_TRAVERSAL_DEPTH];
+        let mut stack_size = 1;
+        stack[0] = node_index;
+
+        while stack_size > 0 {
+            stack_size -= 1;
+            match nodes[stack[stack_size]] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    // Push the right child first, so that the left child is visited first.
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        if !ray.intersects_aabb(child_aabb) {
+                            continue;
+                        }
+                        if stack_size < MAX_TRAVERSAL_DEPTH {
+                            stack[stack_size] = child_index as usize;
+                            stack_size += 1;
+                        } else {
+                            BVHNode::traverse_iterative_with(
+                                nodes,
+                                child_index as usize,
+                                ray,
+                                visit,
+                            )?;
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    visit(shape_index as usize)?;
+                }
             }
         }
+
+        ControlFlow::Continue(())
     }
 }
 
@@ -416,14 +541,14 @@ pub struct BVH {
 impl BVH {
     /// Creates a new [`BVH`] from the `shapes` slice.
     ///
+    /// # Panics
+    /// Panics if there are more than [`MAX_SHAPES`] shapes.
+    ///
     /// [`BVH`]: struct.BVH.html
+    /// [`MAX_SHAPES`]: constant.MAX_SHAPES.html
     ///
     pub fn build<Shape: BHShape>(shapes: &mut [Shape]) -> BVH {
-        let indices = (0..shapes.len()).collect::<Vec<usize>>();
-        let expected_node_count = shapes.len() * 2;
-        let mut nodes = Vec::with_capacity(expected_node_count);
-        BVHNode::build(shapes, &indices, &mut nodes, 0, 0);
-        BVH { nodes }
+        BVH::build_in(shapes, &mut BuildScratch::new())
     }
 
     /// Traverses the [`BVH`].
@@ -432,29 +557,141 @@ impl BVH {
     /// [`BVH`]: struct.BVH.html
     /// [`AABB`]: ../aabb/struct.AABB.html
     ///
-    pub fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&Shape> {
-        let mut indices = Vec::new();
-        BVHNode::traverse_recursive(&self.nodes, 0, ray, &mut indices);
-        indices
+    pub fn traverse<'a, Shape: Bounded>(
+        &'a self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.traverse_indices(ray)
             .iter()
             .map(|index| &shapes[*index])
             .collect::<Vec<_>>()
     }
 
+    /// Traverses the [`BVH`].
+    /// Returns the indices of the shapes whose [`AABB`]s were hit by `ray`.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut indices = Vec::new();
+        if !self.nodes.is_empty() {
+            BVHNode::traverse_iterative(&self.nodes, 0, ray, &mut indices);
+        }
+        indices
+    }
+
+    /// Traverses the [`BVH`] and calls `visit` with the index of every shape whose [`AABB`]
+    /// is hit by `ray`. The traversal stops as soon as `visit` returns
+    /// [`ControlFlow::Break`], whose value is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// use std::ops::ControlFlow;
+    /// # use bvh::aabb::Bounded;
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # struct UnitBox {
+    /// #     pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..100)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    ///
+    /// // Stop at the first visited shape beyond x = 50.
+    /// let found = bvh.traverse_with(&ray, |shape_index| {
+    ///     if shapes[shape_index].aabb().min.x > 50.0 {
+    ///         ControlFlow::Break(shape_index)
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// });
+    /// if let ControlFlow::Break(shape_index) = found {
+    ///     assert!(shapes[shape_index].pos.x > 50.0);
+    /// } else {
+    ///     panic!("no shape found");
+    /// }
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`ControlFlow::Break`]: https://doc.rust-lang.org/std/ops/enum.ControlFlow.html
+    ///
+    pub fn traverse_with<B, F>(&self, ray: &Ray, mut visit: F) -> ControlFlow<B>
+    where
+        F: FnMut(usize) -> ControlFlow<B>,
+    {
+        if self.nodes.is_empty() {
+            return ControlFlow::Continue(());
+        }
+        BVHNode::traverse_iterative_with(&self.nodes, 0, ray, &mut visit)
+    }
+
     /// Creates a [`BVHTraverseIterator`] to traverse the [`BVH`].
     /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements were hit by `ray`.
     ///
+    /// The traversal is lazy and keeps its stack in the iterator, so it does not allocate on
+    /// the heap, unless the depth of the [`BVH`] exceeds [`MAX_TRAVERSAL_DEPTH`].
+    ///
     /// [`BVH`]: struct.BVH.html
+    /// [`BVHTraverseIterator`]: struct.BVHTraverseIterator.html
     /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`MAX_TRAVERSAL_DEPTH`]: constant.MAX_TRAVERSAL_DEPTH.html
     ///
     pub fn traverse_iterator<'a, Shape: Bounded>(
         &'a self,
         ray: &'a Ray,
         shapes: &'a [Shape],
-    ) -> BVHTraverseIterator<Shape> {
+    ) -> BVHTraverseIterator<'a, Shape> {
         BVHTraverseIterator::new(self, ray, shapes)
     }
 
+    /// Creates a [`BVHOrderedIterator`] to traverse the [`BVH`] near to far.
+    /// Returns the subset of `shapes`, in which the [`AABB`]s of the elements were hit by
+    /// `ray`, sorted by the distance at which `ray` enters them, together with that distance.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVHOrderedIterator`]: struct.BVHOrderedIterator.html
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn traverse_ordered<'a, Shape: Bounded>(
+        &'a self,
+        ray: &'a Ray,
+        shapes: &'a [Shape],
+    ) -> BVHOrderedIterator<'a, Shape> {
+        BVHOrderedIterator::new(self, ray, shapes)
+    }
+
     /// Prints the [`BVH`] in a tree-like visualization.
     ///
     /// [`BVH`]: struct.BVH.html
@@ -473,9 +710,9 @@ impl BVH {
                 } => {
                     let padding: String = " ".repeat(depth as usize);
                     println!("{}child_l {}", padding, child_l_aabb);
-                    print_node(nodes, child_l_index);
+                    print_node(nodes, child_l_index as usize);
                     println!("{}child_r {}", padding, child_r_aabb);
-                    print_node(nodes, child_r_index);
+                    print_node(nodes, child_r_index as usize);
                 }
                 BVHNode::Leaf {
                     shape_index, depth, ..
@@ -510,14 +747,14 @@ impl BVH {
                 child_r_index,
                 child_r_aabb,
             } => {
-                let correct_parent_index = expected_parent_index == parent_index;
+                let correct_parent_index = expected_parent_index == parent_index as usize;
                 let correct_depth = expected_depth == depth;
                 let left_aabb_in_parent =
                     expected_outer_aabb.approx_contains_aabb_eps(&child_l_aabb, EPSILON);
                 let right_aabb_in_parent =
                     expected_outer_aabb.approx_contains_aabb_eps(&child_r_aabb, EPSILON);
                 let left_subtree_consistent = self.is_consistent_subtree(
-                    child_l_index,
+                    child_l_index as usize,
                     node_index,
                     &child_l_aabb,
                     expected_depth + 1,
@@ -525,7 +762,7 @@ impl BVH {
                     shapes,
                 );
                 let right_subtree_consistent = self.is_consistent_subtree(
-                    child_r_index,
+                    child_r_index as usize,
                     node_index,
                     &child_r_aabb,
                     expected_depth + 1,
@@ -545,9 +782,9 @@ impl BVH {
                 depth,
                 shape_index,
             } => {
-                let correct_parent_index = expected_parent_index == parent_index;
+                let correct_parent_index = expected_parent_index == parent_index as usize;
                 let correct_depth = expected_depth == depth;
-                let shape_aabb = shapes[shape_index].aabb();
+                let shape_aabb = shapes[shape_index as usize].aabb();
                 let shape_aabb_in_parent =
                     expected_outer_aabb.approx_contains_aabb_eps(&shape_aabb, EPSILON);
 
@@ -627,7 +864,7 @@ impl BVH {
                     child_r_aabb
                 );
                 self.assert_consistent_subtree(
-                    child_l_index,
+                    child_l_index as usize,
                     node_index,
                     &child_l_aabb,
                     expected_depth + 1,
@@ -635,7 +872,7 @@ impl BVH {
                     shapes,
                 );
                 self.assert_consistent_subtree(
-                    child_r_index,
+                    child_r_index as usize,
                     node_index,
                     &child_r_aabb,
                     expected_depth + 1,
@@ -644,7 +881,7 @@ impl BVH {
                 );
             }
             BVHNode::Leaf { shape_index, .. } => {
-                let shape_aabb = shapes[shape_index].aabb();
+                let shape_aabb = shapes[shape_index as usize].aabb();
                 assert!(
                     expected_outer_aabb.approx_contains_aabb_eps(&shape_aabb, EPSILON),
                     "Shape's AABB lies outside the expected bounds.\n\tBounds: {}\n\tShape: {}",
@@ -675,6 +912,7 @@ impl BVH {
     /// Check that the `AABB`s in the `BVH` are tight, which means, that parent `AABB`s are not
     /// larger than they should be. This function checks, whether the children of node `node_index`
     /// lie inside `outer_aabb`.
+    #[allow(clippy::only_used_in_recursion)]
     pub fn assert_tight_subtree<Shape: BHShape>(
         &self,
         node_index: usize,
@@ -691,8 +929,8 @@ impl BVH {
         {
             let joint_aabb = child_l_aabb.join(&child_r_aabb);
             assert!(joint_aabb.relative_eq(outer_aabb, EPSILON));
-            self.assert_tight_subtree(child_l_index, &child_l_aabb, shapes);
-            self.assert_tight_subtree(child_r_index, &child_r_aabb, shapes);
+            self.assert_tight_subtree(child_l_index as usize, &child_l_aabb, shapes);
+            self.assert_tight_subtree(child_r_index as usize, &child_r_aabb, shapes);
         }
     }
 
@@ -713,12 +951,18 @@ impl BVH {
     }
 }
 
+impl MemoryUsage for BVH {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.nodes.capacity() * std::mem::size_of::<BVHNode>()
+    }
+}
+
 impl BoundingHierarchy for BVH {
     fn build<Shape: BHShape>(shapes: &mut [Shape]) -> BVH {
         BVH::build(shapes)
     }
 
-    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&Shape> {
+    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape> {
         self.traverse(ray, shapes)
     }
 
@@ -727,10 +971,21 @@ impl BoundingHierarchy for BVH {
     }
 }
 
+impl IndexedBoundingHierarchy for BVH {
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        self.traverse_indices(ray)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::bvh::{BVHNode, BVH};
-    use crate::testbase::{build_some_bh, traverse_some_bh};
+    use crate::ray::Ray;
+    use crate::testbase::{
+        build_some_bh, create_n_cubes, create_ray, default_bounds, traverse_some_bh, Triangle,
+    };
+    use crate::{Point3, Vector3};
+    use std::ops::ControlFlow;
 
     #[test]
     /// Tests whether the building procedure succeeds in not failing.
@@ -771,6 +1026,114 @@ mod tests {
 
         assert_eq!(expected_shapes, found_shapes);
     }
+
+    #[test]
+    /// Tests whether the iterative traversal returns the same shapes in the same order
+    /// as the recursive traversal.
+    fn test_traverse_iterative_equals_recursive() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let mut recursive = Vec::new();
+            let mut iterative = Vec::new();
+            BVHNode::traverse_recursive(&bvh.nodes, 0, &ray, &mut recursive);
+            BVHNode::traverse_iterative(&bvh.nodes, 0, &ray, &mut iterative);
+            assert_eq!(recursive, iterative);
+        }
+    }
+
+    #[test]
+    /// Tests whether a `BVH` can be built from zero-thickness shapes, and whether all
+    /// of them are found by rays crossing them.
+    fn test_build_bvh_zero_thickness() {
+        // Points on a line, which have a joint `AABB` with zero surface area.
+        let mut points: Vec<_> = (0..20)
+            .map(|i| {
+                let point = Point3::new(i as f32, 0.0, 0.0);
+                Triangle::new(point, point, point)
+            })
+            .collect();
+        let bvh = BVH::build(&mut points);
+        bvh.assert_consistent(&points);
+        let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.traverse(&ray, &points).len(), 20);
+
+        // Parallel quads in the planes z = i.
+        let mut quads: Vec<_> = (0..20)
+            .map(|i| {
+                let z = i as f32;
+                Triangle::new(
+                    Point3::new(-1.0, -1.0, z),
+                    Point3::new(1.0, -1.0, z),
+                    Point3::new(1.0, 1.0, z),
+                )
+            })
+            .collect();
+        let bvh = BVH::build(&mut quads);
+        bvh.assert_consistent(&quads);
+        for &direction in &[Vector3::new(-0.0, 0.0, 1.0), Vector3::new(0.0, -0.0, -1.0)] {
+            let ray = Ray::new(Point3::new(0.5, 0.0, 10.0) - direction * 20.0, direction);
+            assert_eq!(bvh.traverse(&ray, &quads).len(), 20);
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_with` visits the same shapes as `traverse`, and whether
+    /// it stops as soon as the visitor breaks.
+    fn test_traverse_with() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let mut expected = Vec::new();
+            BVHNode::traverse_recursive(&bvh.nodes, 0, &ray, &mut expected);
+
+            let mut visited = Vec::new();
+            let result = bvh.traverse_with(&ray, |shape_index| {
+                visited.push(shape_index);
+                ControlFlow::<()>::Continue(())
+            });
+            assert_eq!(result, ControlFlow::Continue(()));
+            assert_eq!(visited, expected);
+
+            let mut visited = 0;
+            let result = bvh.traverse_with(&ray, |shape_index| {
+                visited += 1;
+                if visited == 3 {
+                    ControlFlow::Break(shape_index)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+            if expected.len() >= 3 {
+                assert_eq!(result, ControlFlow::Break(expected[2]));
+                assert_eq!(visited, 3);
+            } else {
+                assert_eq!(result, ControlFlow::Continue(()));
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether a `BVH` without shapes has no nodes and can be traversed.
+    fn test_empty_bvh() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        assert!(bvh.nodes.is_empty());
+
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+        assert!(bvh.traverse(&ray, &triangles).is_empty());
+        assert!(bvh.traverse_indices(&ray).is_empty());
+        assert_eq!(bvh.traverse_iterator(&ray, &triangles).count(), 0);
+    }
 }
 
 #[cfg(all(feature = "bench", test))]
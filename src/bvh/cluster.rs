@@ -0,0 +1,246 @@
+//! This module defines functions to cut a [`BVH`] into clusters of shapes, e.g. to produce
+//! meshlets or draw-call batches from the same hierarchy which is used for culling.
+//!
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::AABB;
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{BVHNode, BVH};
+
+/// A set of shapes which belong to the same subtree of a [`BVH`].
+///
+/// [`BVH`]: struct.BVH.html
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cluster {
+    /// The indices of the shapes in this [`Cluster`].
+    ///
+    /// [`Cluster`]: struct.Cluster.html
+    ///
+    pub shape_indices: Vec<usize>,
+
+    /// The joint [`AABB`] of all shapes in this [`Cluster`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Cluster`]: struct.Cluster.html
+    ///
+    pub aabb: AABB,
+}
+
+impl BVH {
+    /// Cuts the [`BVH`] at `depth` and returns one [`Cluster`] per subtree below the cut.
+    /// Leaves above the cut form clusters of their own.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`Cluster`]: struct.Cluster.html
+    ///
+    pub fn clusters_at_depth<Shape: BHShape>(&self, depth: u32, shapes: &[Shape]) -> Vec<Cluster> {
+        self.clusters(shapes, |_, node_depth, _| node_depth >= depth)
+    }
+
+    /// Cuts the [`BVH`] at the highest nodes whose SAH cost, the surface area of the node
+    /// multiplied by the number of shapes below it, does not exceed `max_cost`.
+    /// Returns one [`Cluster`] per subtree below the cut.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`Cluster`]: struct.Cluster.html
+    ///
+    pub fn clusters_by_cost<Shape: BHShape>(
+        &self,
+        max_cost: f32,
+        shapes: &[Shape],
+    ) -> Vec<Cluster> {
+        self.clusters(shapes, |aabb, _, shape_count| {
+            aabb.surface_area() * shape_count as f32 <= max_cost
+        })
+    }
+
+    /// Cuts the [`BVH`] at the highest nodes for which `cut` returns `true`.
+    /// `cut` is given the [`AABB`], the depth and the number of shapes of a node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn clusters<Shape, F>(&self, shapes: &[Shape], cut: F) -> Vec<Cluster>
+    where
+        Shape: BHShape,
+        F: Fn(&AABB, u32, usize) -> bool,
+    {
+        let mut clusters = Vec::new();
+        if !self.nodes.is_empty() {
+            let root_aabb = self.nodes[0].get_node_aabb(shapes);
+            let shape_counts = self.shape_counts();
+            self.clusters_recursive(0, root_aabb, &shape_counts, &cut, &mut clusters);
+        }
+        clusters
+    }
+
+    /// Recursive part of `clusters`. The shapes are only collected below the cut, so that
+    /// each node is visited at most twice.
+    fn clusters_recursive<F>(
+        &self,
+        node_index: usize,
+        aabb: AABB,
+        shape_counts: &[usize],
+        cut: &F,
+        clusters: &mut Vec<Cluster>,
+    ) where
+        F: Fn(&AABB, u32, usize) -> bool,
+    {
+        match self.nodes[node_index] {
+            BVHNode::Node {
+                child_l_aabb,
+                child_l_index,
+                child_r_aabb,
+                child_r_index,
+                depth,
+                ..
+            } if !cut(&aabb, depth, shape_counts[node_index]) => {
+                let (child_l_index, child_r_index) =
+                    (child_l_index as usize, child_r_index as usize);
+                self.clusters_recursive(child_l_index, child_l_aabb, shape_counts, cut, clusters);
+                self.clusters_recursive(child_r_index, child_r_aabb, shape_counts, cut, clusters);
+            }
+            _ => {
+                let mut shape_indices = Vec::with_capacity(shape_counts[node_index]);
+                BVHNode::traverse_subtree(&self.nodes, node_index, &mut shape_indices);
+                clusters.push(Cluster {
+                    shape_indices,
+                    aabb,
+                });
+            }
+        }
+    }
+
+    /// Returns the number of shapes below each node, in the order of [`BVH::nodes`].
+    /// The counts are computed bottom-up in a single pass.
+    ///
+    /// [`BVH::nodes`]: struct.BVH.html#structfield.nodes
+    ///
+    pub(crate) fn shape_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.nodes.len()];
+        if !self.nodes.is_empty() {
+            BVHNode::count_shapes(&self.nodes, 0, &mut counts);
+        }
+        counts
+    }
+}
+
+impl BVHNode {
+    /// Stores the number of shapes below each node of the subtree below `node_index` in
+    /// `counts`, and returns the number of shapes below `node_index`.
+    fn count_shapes(nodes: &[BVHNode], node_index: usize, counts: &mut [usize]) -> usize {
+        let count = match nodes[node_index] {
+            BVHNode::Node {
+                child_l_index,
+                child_r_index,
+                ..
+            } => {
+                BVHNode::count_shapes(nodes, child_l_index as usize, counts)
+                    + BVHNode::count_shapes(nodes, child_r_index as usize, counts)
+            }
+            BVHNode::Leaf { .. } => 1,
+        };
+        counts[node_index] = count;
+        count
+    }
+
+    /// Collects the indices of all shapes in the subtree below `node_index`.
+    fn traverse_subtree(nodes: &[BVHNode], node_index: usize, indices: &mut Vec<usize>) {
+        match nodes[node_index] {
+            BVHNode::Node {
+                child_l_index,
+                child_r_index,
+                ..
+            } => {
+                BVHNode::traverse_subtree(nodes, child_l_index as usize, indices);
+                BVHNode::traverse_subtree(nodes, child_r_index as usize, indices);
+            }
+            BVHNode::Leaf { shape_index, .. } => {
+                indices.push(shape_index as usize);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bvh::{Cluster, BVH};
+    use crate::testbase::{build_some_bh, UnitBox};
+
+    /// Checks whether every shape is contained in exactly one of the `clusters`,
+    /// and whether the `AABB`s of the `clusters` are tight.
+    fn assert_partition(clusters: &[Cluster], shapes: &[UnitBox]) {
+        let mut indices: Vec<usize> = clusters
+            .iter()
+            .flat_map(|cluster| cluster.shape_indices.iter().cloned())
+            .collect();
+        indices.sort_unstable();
+        assert_eq!(indices, (0..shapes.len()).collect::<Vec<_>>());
+
+        for cluster in clusters {
+            let aabb: AABB = cluster
+                .shape_indices
+                .iter()
+                .map(|&index| shapes[index].aabb())
+                .sum();
+            assert!(aabb.relative_eq(&cluster.aabb, crate::EPSILON));
+        }
+    }
+
+    #[test]
+    /// Tests whether cutting at a depth partitions the shapes.
+    fn test_clusters_at_depth() {
+        let (shapes, bvh) = build_some_bh::<BVH>();
+
+        let clusters = bvh.clusters_at_depth(0, &shapes);
+        assert_eq!(clusters.len(), 1);
+        assert_partition(&clusters, &shapes);
+
+        let clusters = bvh.clusters_at_depth(2, &shapes);
+        assert!(clusters.len() <= 4);
+        assert_partition(&clusters, &shapes);
+
+        let clusters = bvh.clusters_at_depth(100, &shapes);
+        assert_eq!(clusters.len(), shapes.len());
+        assert_partition(&clusters, &shapes);
+    }
+
+    #[test]
+    /// Tests whether cutting by SAH cost partitions the shapes and respects the cost.
+    fn test_clusters_by_cost() {
+        let (shapes, bvh) = build_some_bh::<BVH>();
+
+        let max_cost = 100.0;
+        let clusters = bvh.clusters_by_cost(max_cost, &shapes);
+        assert_partition(&clusters, &shapes);
+        for cluster in &clusters {
+            let cost = cluster.aabb.surface_area() * cluster.shape_indices.len() as f32;
+            assert!(cluster.shape_indices.len() == 1 || cost <= max_cost);
+        }
+
+        let clusters = bvh.clusters_by_cost(f32::INFINITY, &shapes);
+        assert_eq!(clusters.len(), 1);
+    }
+
+    #[test]
+    /// Tests whether the shape count of every node is the sum of the counts of its children.
+    fn test_shape_counts() {
+        let (shapes, bvh) = build_some_bh::<BVH>();
+        let counts = bvh.shape_counts();
+        assert_eq!(counts[0], shapes.len());
+        for (index, node) in bvh.nodes.iter().enumerate() {
+            match node.shape_index() {
+                Some(_) => assert_eq!(counts[index], 1),
+                None => assert_eq!(
+                    counts[index],
+                    counts[node.child_l()] + counts[node.child_r()]
+                ),
+            }
+        }
+        assert!(BVH { nodes: Vec::new() }.shape_counts().is_empty());
+    }
+}
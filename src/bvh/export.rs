@@ -0,0 +1,502 @@
+//! This module defines exports of a [`BVH`] to external formats, which help to debug the
+//! quality of a tree with other tools.
+//!
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHNode, BVH};
+
+use std::io::{self, Write};
+
+impl BVH {
+    /// Returns the index, the depth and the [`AABB`] of every node whose [`AABB`] is stored,
+    /// which are all nodes except for a root leaf, in the order of [`BVH::nodes`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::nodes`]: struct.BVH.html#structfield.nodes
+    ///
+    fn stored_node_aabbs(&self) -> Vec<(usize, u32, AABB)> {
+        let mut aabbs = Vec::with_capacity(self.nodes.len());
+        if let Some(BVHNode::Node {
+            child_l_aabb,
+            child_r_aabb,
+            ..
+        }) = self.nodes.first()
+        {
+            aabbs.push((0, 0, child_l_aabb.join(child_r_aabb)));
+        }
+        for node in &self.nodes {
+            if let BVHNode::Node {
+                depth,
+                child_l_index,
+                child_l_aabb,
+                child_r_index,
+                child_r_aabb,
+                ..
+            } = *node
+            {
+                aabbs.push((child_l_index as usize, depth + 1, child_l_aabb));
+                aabbs.push((child_r_index as usize, depth + 1, child_r_aabb));
+            }
+        }
+        aabbs.sort_by_key(|&(index, _, _)| index);
+        aabbs
+    }
+
+    /// Writes the [`AABB`]s of all nodes up to `max_depth` to `writer` as the edges of
+    /// boxes in the Wavefront OBJ format, which can be imported into e.g. Blender. Each box
+    /// is an object named after the index of its node, and belongs to a group per depth,
+    /// so that the levels of the tree can be shown one by one. The root has depth `0`.
+    ///
+    /// The [`AABB`] of a root leaf is not stored in the [`BVH`], so a [`BVH`] of a single
+    /// shape writes no boxes.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..8)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let mut obj = Vec::new();
+    /// bvh.export_wireframe(&mut obj, 1).unwrap();
+    /// let obj = String::from_utf8(obj).unwrap();
+    ///
+    /// // The root and its two children.
+    /// assert_eq!(obj.lines().filter(|line| line.starts_with("o ")).count(), 3);
+    /// assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 3 * 8);
+    /// assert_eq!(obj.lines().filter(|line| line.starts_with("l ")).count(), 3 * 12);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn export_wireframe<W: Write>(&self, writer: &mut W, max_depth: u32) -> io::Result<()> {
+        // The corners are indexed by three bits, which select the maximum along x, y and z,
+        // as in `AABB::corners`. Each edge connects two corners which differ in one bit.
+        const EDGES: [(usize, usize); 12] = [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ];
+
+        writeln!(writer, "# BVH of {} nodes", self.nodes.len())?;
+        let mut vertex_count = 0;
+        for (index, depth, aabb) in self.stored_node_aabbs() {
+            if depth > max_depth {
+                continue;
+            }
+            writeln!(writer, "o node_{}", index)?;
+            writeln!(writer, "g depth_{}", depth)?;
+            for corner in aabb.corners().iter() {
+                writeln!(writer, "v {} {} {}", corner.x, corner.y, corner.z)?;
+            }
+            // The vertices of OBJ are numbered from 1.
+            for &(a, b) in EDGES.iter() {
+                writeln!(
+                    writer,
+                    "l {} {}",
+                    vertex_count + a + 1,
+                    vertex_count + b + 1
+                )?;
+            }
+            vertex_count += 8;
+        }
+        Ok(())
+    }
+
+    /// Writes the [`BVH`] to `writer` as a directed graph in the DOT language of Graphviz,
+    /// e.g. to be rendered with `dot -Tsvg`. Each node is labeled with its index, its depth,
+    /// the number of leaves below it and the surface area of its [`AABB`]. Leaves are drawn
+    /// as ellipses and are labeled with the index of their shape instead of a leaf count.
+    ///
+    /// The [`AABB`] of a root leaf is not stored in the [`BVH`], so it is labeled without a
+    /// surface area.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..8)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let mut dot = Vec::new();
+    /// bvh.to_dot(&mut dot).unwrap();
+    /// let dot = String::from_utf8(dot).unwrap();
+    ///
+    /// assert!(dot.starts_with("digraph BVH {"));
+    /// assert!(dot.contains("8 leaves"));
+    /// assert_eq!(dot.matches(" -> ").count(), bvh.nodes.len() - 1);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn to_dot<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut areas = vec![None; self.nodes.len()];
+        for (index, _, aabb) in self.stored_node_aabbs() {
+            areas[index] = Some(aabb.surface_area());
+        }
+        let leaf_counts = self.shape_counts();
+
+        writeln!(writer, "digraph BVH {{")?;
+        writeln!(writer, "    node [shape=box];")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = format!("node {}\\ndepth {}", index, node.depth());
+            match node.shape_index() {
+                Some(shape_index) => label.push_str(&format!("\\nshape {}", shape_index)),
+                None => label.push_str(&format!("\\n{} leaves", leaf_counts[index])),
+            }
+            if let Some(area) = areas[index] {
+                label.push_str(&format!("\\narea {}", area));
+            }
+            let shape = if node.shape_index().is_some() {
+                ", shape=ellipse"
+            } else {
+                ""
+            };
+            writeln!(writer, "    n{} [label=\"{}\"{}];", index, label, shape)?;
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if node.shape_index().is_none() {
+                writeln!(writer, "    n{} -> n{};", index, node.child_l())?;
+                writeln!(writer, "    n{} -> n{};", index, node.child_r())?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+
+    /// Writes the [`BVH`] to `writer` as a JSON object for debugging, with the total SAH
+    /// cost of the tree in `"sah_cost"` and the nodes in `"nodes"`, in the order of
+    /// [`BVH::nodes`]. Each node has its `"index"`, `"parent"` and `"depth"`, the bounds of
+    /// its [`AABB`] in `"min"` and `"max"`, and its contribution to the SAH cost in
+    /// `"sah_cost"`. An inner node lists its `"children"`, while a leaf has its
+    /// `"shape_index"`.
+    ///
+    /// The SAH cost of a node is the surface area of its [`AABB`] relative to the surface
+    /// area of the root, which is the probability that a random ray which hits the root also
+    /// hits the node, assuming unit costs for the traversal of a node and for the
+    /// intersection of a shape. The SAH cost of the tree is the sum over all nodes. The
+    /// [`AABB`] of a root leaf is not stored in the [`BVH`], so its bounds are `null`.
+    /// Bounds and costs which are not finite are written as `null` as well.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..8)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let mut json = Vec::new();
+    /// bvh.dump_json(&mut json).unwrap();
+    /// let json = String::from_utf8(json).unwrap();
+    ///
+    /// assert!(json.starts_with("{\"sah_cost\":"));
+    /// assert_eq!(json.matches("\"shape_index\":").count(), shapes.len());
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::nodes`]: struct.BVH.html#structfield.nodes
+    ///
+    pub fn dump_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut aabbs = vec![None; self.nodes.len()];
+        for (index, _, aabb) in self.stored_node_aabbs() {
+            aabbs[index] = Some(aabb);
+        }
+        let root_area = aabbs
+            .first()
+            .and_then(|aabb| aabb.map(|aabb| aabb.surface_area()));
+        let costs: Vec<f32> = aabbs
+            .iter()
+            .map(|aabb| match (aabb, root_area) {
+                (Some(aabb), Some(root_area)) => aabb.surface_area() / root_area,
+                // The only node without a stored `AABB` is a root leaf.
+                _ => 1.0,
+            })
+            .collect();
+
+        write!(writer, "{{\"sah_cost\":")?;
+        write_json_number(writer, costs.iter().sum())?;
+        write!(writer, ",\"nodes\":[")?;
+        for (index, node) in self.nodes.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "\n{{\"index\":{},\"parent\":{},\"depth\":{}",
+                index,
+                node.parent(),
+                node.depth()
+            )?;
+            match aabbs[index] {
+                Some(aabb) => {
+                    write!(writer, ",\"min\":")?;
+                    write_json_point(writer, aabb.min.to_array())?;
+                    write!(writer, ",\"max\":")?;
+                    write_json_point(writer, aabb.max.to_array())?;
+                }
+                None => write!(writer, ",\"min\":null,\"max\":null")?,
+            }
+            write!(writer, ",\"sah_cost\":")?;
+            write_json_number(writer, costs[index])?;
+            match node.shape_index() {
+                Some(shape_index) => write!(writer, ",\"shape_index\":{}}}", shape_index)?,
+                None => write!(
+                    writer,
+                    ",\"children\":[{},{}]}}",
+                    node.child_l(),
+                    node.child_r()
+                )?,
+            }
+        }
+        writeln!(writer, "\n]}}")
+    }
+}
+
+/// Writes `value` as a JSON number, or as `null` if it is not finite, which JSON cannot
+/// represent.
+fn write_json_number<W: Write>(writer: &mut W, value: f32) -> io::Result<()> {
+    if value.is_finite() {
+        write!(writer, "{}", value)
+    } else {
+        write!(writer, "null")
+    }
+}
+
+/// Writes the coordinates of a point as a JSON array of numbers.
+fn write_json_point<W: Write>(writer: &mut W, point: [f32; 3]) -> io::Result<()> {
+    write!(writer, "[")?;
+    for (axis, &value) in point.iter().enumerate() {
+        if axis > 0 {
+            write!(writer, ",")?;
+        }
+        write_json_number(writer, value)?;
+    }
+    write!(writer, "]")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::BVH;
+    use crate::testbase::{create_n_cubes, default_bounds};
+
+    #[test]
+    /// Tests whether the wireframe of a `BVH` contains a box for each node up to the given
+    /// depth, and whether all lines refer to existing vertices.
+    fn test_export_wireframe() {
+        let mut triangles = create_n_cubes(10, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let max_depth = bvh.nodes.iter().map(|node| node.depth()).max().unwrap();
+
+        for depth in 0..=max_depth {
+            let mut obj = Vec::new();
+            bvh.export_wireframe(&mut obj, depth).unwrap();
+            let obj = String::from_utf8(obj).unwrap();
+
+            let boxes = bvh
+                .nodes
+                .iter()
+                .filter(|node| node.depth() <= depth)
+                .count();
+            let vertices = obj.lines().filter(|line| line.starts_with("v ")).count();
+            assert_eq!(vertices, boxes * 8);
+            for line in obj.lines().filter(|line| line.starts_with("l ")) {
+                for vertex in line[2..].split(' ') {
+                    let vertex: usize = vertex.parse().unwrap();
+                    assert!(vertex >= 1 && vertex <= vertices);
+                }
+            }
+        }
+
+        let mut single = create_n_cubes(1, &default_bounds());
+        single.truncate(1);
+        let bvh = BVH::build(&mut single);
+        let mut obj = Vec::new();
+        bvh.export_wireframe(&mut obj, 10).unwrap();
+        assert!(!String::from_utf8(obj).unwrap().contains("\nv "));
+    }
+
+    #[test]
+    /// Tests whether the DOT graph of a `BVH` contains every node and edge, and whether the
+    /// root is labeled with the number of all shapes.
+    fn test_to_dot() {
+        let mut triangles = create_n_cubes(10, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let mut dot = Vec::new();
+        bvh.to_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph BVH {\n"));
+        assert!(dot.ends_with("}\n"));
+        let root = format!("n0 [label=\"node 0\\ndepth 0\\n{} leaves", triangles.len());
+        assert!(dot.contains(&root));
+        for (index, node) in bvh.nodes.iter().enumerate() {
+            assert!(dot.contains(&format!("    n{} [label=", index)));
+            if node.shape_index().is_none() {
+                assert!(dot.contains(&format!("    n{} -> n{};", index, node.child_l())));
+                assert!(dot.contains(&format!("    n{} -> n{};", index, node.child_r())));
+            }
+        }
+        assert_eq!(dot.matches(" -> ").count(), bvh.nodes.len() - 1);
+        assert_eq!(dot.matches("shape=ellipse").count(), triangles.len());
+    }
+
+    #[test]
+    /// Tests whether the JSON dump of a `BVH` contains every node, and whether the SAH cost
+    /// of the tree is the sum of the costs of the nodes.
+    fn test_dump_json() {
+        let mut triangles = create_n_cubes(10, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let mut json = Vec::new();
+        bvh.dump_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+
+        let lines: Vec<&str> = json.lines().collect();
+        assert_eq!(lines.len(), bvh.nodes.len() + 2);
+        assert_eq!(lines[lines.len() - 1], "]}");
+        assert!(!json.contains("null"));
+        assert_eq!(json.matches('{').count(), json.matches('}').count());
+        assert_eq!(json.matches('[').count(), json.matches(']').count());
+
+        // Extracts the number after `key` in `line`.
+        let number = |line: &str, key: &str| -> f32 {
+            let start = line.find(key).unwrap() + key.len();
+            let end = start + line[start..].find(&[',', '}'][..]).unwrap();
+            line[start..end].parse().unwrap()
+        };
+        let mut cost_sum = 0.0;
+        for (index, node) in bvh.nodes.iter().enumerate() {
+            let line = lines[index + 1];
+            assert!(line.starts_with(&format!("{{\"index\":{},", index)));
+            let cost = number(line, "\"sah_cost\":");
+            assert!(cost > 0.0 && cost <= 1.0);
+            cost_sum += cost;
+            match node.shape_index() {
+                Some(shape_index) => {
+                    assert_eq!(number(line, "\"shape_index\":"), shape_index as f32)
+                }
+                None => assert!(line.contains(&format!(
+                    "\"children\":[{},{}]",
+                    node.child_l(),
+                    node.child_r()
+                ))),
+            }
+        }
+        assert_eq!(number(lines[1], "\"sah_cost\":"), 1.0);
+        let total = number(lines[0], "{\"sah_cost\":");
+        assert!((total - cost_sum).abs() < 1e-3 * total);
+
+        let mut single = create_n_cubes(1, &default_bounds());
+        single.truncate(1);
+        let bvh = BVH::build(&mut single);
+        let mut json = Vec::new();
+        bvh.dump_json(&mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.contains("\"min\":null,\"max\":null,\"sah_cost\":1,"));
+    }
+}
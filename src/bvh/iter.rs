@@ -1,7 +1,16 @@
 use crate::aabb::Bounded;
+use crate::bvh::best_first::BestFirst;
 use crate::bvh::{BVHNode, BVH};
 use crate::ray::Ray;
 
+/// The maximum depth of a [`BVH`] which can be traversed by a [`BVHTraverseIterator`]
+/// without heap allocations. Deeper nodes are kept on the heap.
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVHTraverseIterator`]: struct.BVHTraverseIterator.html
+///
+pub const MAX_TRAVERSAL_DEPTH: usize = 64;
+
 /// Iterator to traverse a [`BVH`] without memory allocations
 #[allow(clippy::upper_case_acronyms)]
 pub struct BVHTraverseIterator<'a, Shape: Bounded> {
@@ -11,8 +20,18 @@ pub struct BVHTraverseIterator<'a, Shape: Bounded> {
     ray: &'a Ray,
     /// Reference to the input shapes array
     shapes: &'a [Shape],
-    /// Traversal stack. 4 billion items seems enough?
-    stack: [usize; 32],
+    /// Traversal stack. Holds the path from the root to the current node, up to a depth of
+    /// [`MAX_TRAVERSAL_DEPTH`].
+    ///
+    /// [`MAX_TRAVERSAL_DEPTH`]: constant.MAX_TRAVERSAL_DEPTH.html
+    ///
+    stack: [usize; MAX_TRAVERSAL_DEPTH],
+    /// The rest of the path, if the traversed [`BVH`] is deeper than [`MAX_TRAVERSAL_DEPTH`].
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`MAX_TRAVERSAL_DEPTH`]: constant.MAX_TRAVERSAL_DEPTH.html
+    ///
+    overflow: Vec<usize>,
     /// Position of the iterator in bvh.nodes
     node_index: usize,
     /// Size of the traversal stack
@@ -28,10 +47,12 @@ impl<'a, Shape: Bounded> BVHTraverseIterator<'a, Shape> {
             bvh,
             ray,
             shapes,
-            stack: [0; 32],
+            stack: [0; MAX_TRAVERSAL_DEPTH],
+            overflow: Vec::new(),
             node_index: 0,
             stack_size: 0,
-            has_node: true,
+            // A `BVH` without shapes has no root node.
+            has_node: !bvh.nodes.is_empty(),
         }
     }
 
@@ -40,13 +61,14 @@ impl<'a, Shape: Bounded> BVHTraverseIterator<'a, Shape> {
         self.stack_size == 0
     }
 
-    /// Push node onto stack.
-    ///
-    /// # Panics
-    ///
-    /// Panics if `stack[stack_size]` is out of bounds.
+    /// Push node onto stack. Once the fixed-size stack is full, the node is pushed onto
+    /// the heap.
     fn stack_push(&mut self, node: usize) {
-        self.stack[self.stack_size] = node;
+        if self.stack_size < MAX_TRAVERSAL_DEPTH {
+            self.stack[self.stack_size] = node;
+        } else {
+            self.overflow.push(node);
+        }
         self.stack_size += 1;
     }
 
@@ -57,7 +79,11 @@ impl<'a, Shape: Bounded> BVHTraverseIterator<'a, Shape> {
     /// Panics if `stack_size` underflows.
     fn stack_pop(&mut self) -> usize {
         self.stack_size -= 1;
-        self.stack[self.stack_size]
+        if self.stack_size < MAX_TRAVERSAL_DEPTH {
+            self.stack[self.stack_size]
+        } else {
+            self.overflow.pop().unwrap()
+        }
     }
 
     /// Attempt to move to the left node child of the current node.
@@ -70,7 +96,7 @@ impl<'a, Shape: Bounded> BVHTraverseIterator<'a, Shape> {
                 ..
             } => {
                 if self.ray.intersects_aabb(child_l_aabb) {
-                    self.node_index = child_l_index;
+                    self.node_index = child_l_index as usize;
                     self.has_node = true;
                 } else {
                     self.has_node = false;
@@ -92,7 +118,7 @@ impl<'a, Shape: Bounded> BVHTraverseIterator<'a, Shape> {
                 ..
             } => {
                 if self.ray.intersects_aabb(child_r_aabb) {
-                    self.node_index = child_r_index;
+                    self.node_index = child_r_index as usize;
                     self.has_node = true;
                 } else {
                     self.has_node = false;
@@ -130,7 +156,7 @@ impl<'a, Shape: Bounded> Iterator for BVHTraverseIterator<'a, Shape> {
                         // We previously pushed a leaf node. This is the "visit" of the in-order traverse.
                         // Next time we call `next()` we try to pop the stack again.
                         self.has_node = false;
-                        return Some(&self.shapes[shape_index]);
+                        return Some(&self.shapes[shape_index as usize]);
                     }
                 }
             }
@@ -139,11 +165,69 @@ impl<'a, Shape: Bounded> Iterator for BVHTraverseIterator<'a, Shape> {
     }
 }
 
+/// Iterator to traverse a [`BVH`] in the order in which `ray` enters the [`AABB`]s of the
+/// shapes, near to far. Yields the shapes together with the distance at which `ray` enters
+/// their [`AABB`]. The distance is smaller than `ray.t_min` if the ray starts inside of the
+/// [`AABB`] of a shape.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: struct.BVH.html
+///
+#[allow(clippy::upper_case_acronyms)]
+pub struct BVHOrderedIterator<'a, Shape: Bounded> {
+    /// Reference to the BVH to traverse
+    bvh: &'a BVH,
+    /// Reference to the input ray
+    ray: &'a Ray,
+    /// Reference to the input shapes array
+    shapes: &'a [Shape],
+    /// The best-first traversal by entry distance
+    search: BestFirst,
+}
+
+impl<'a, Shape: Bounded> BVHOrderedIterator<'a, Shape> {
+    /// Creates a new `BVHOrderedIterator`
+    pub fn new(bvh: &'a BVH, ray: &'a Ray, shapes: &'a [Shape]) -> Self {
+        BVHOrderedIterator {
+            bvh,
+            ray,
+            shapes,
+            search: BestFirst::new(bvh),
+        }
+    }
+}
+
+impl<'a, Shape: Bounded> Iterator for BVHOrderedIterator<'a, Shape> {
+    type Item = (&'a Shape, f32);
+
+    fn next(&mut self) -> Option<(&'a Shape, f32)> {
+        let ray = self.ray;
+        let key = |aabb: &_| {
+            ray.intersection_slab(aabb)
+                .map(|(entry_distance, _)| entry_distance)
+        };
+        while let Some(leaf) = self.search.next_leaf(self.bvh, key, f32::INFINITY) {
+            let shape = &self.shapes[leaf.shape_index];
+            if leaf.node_index != 0 {
+                return Some((shape, leaf.key));
+            }
+
+            // A root leaf has not been tested yet.
+            if let Some((entry_distance, _)) = ray.intersection_slab(&shape.aabb()) {
+                return Some((shape, entry_distance));
+            }
+        }
+        None
+    }
+}
+
 // Copy of part of the BH testing in testbase.
 // TODO: Once iterators are part of the BoundingHierarchy trait we can move all this to testbase.
 #[cfg(test)]
 mod tests {
-    use crate::bvh::BVH;
+    use crate::aabb::{Bounded, AABB};
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{BVHNode, BVH, MAX_TRAVERSAL_DEPTH};
     use crate::ray::Ray;
     use crate::testbase::{generate_aligned_boxes, UnitBox};
     use crate::{Point3, Vector3};
@@ -250,6 +334,119 @@ mod tests {
     fn test_traverse_bvh() {
         traverse_some_bvh();
     }
+
+    /// Creates a degenerate `BVH` in which every node has a leaf as its left child,
+    /// so that the depth of the `BVH` equals the number of shapes minus one.
+    fn build_chain_bvh(shapes: &mut [UnitBox]) -> BVH {
+        let mut nodes = Vec::new();
+        for (i, shape) in shapes.iter_mut().enumerate() {
+            let depth = i as u32;
+            let index = nodes.len() as u32;
+            let parent_index = if i == 0 { 0 } else { index - 2 };
+            shape.set_bh_node_index(index as usize + 1);
+            nodes.push(BVHNode::Node {
+                parent_index,
+                depth,
+                child_l_aabb: shape.aabb(),
+                child_l_index: index + 1,
+                child_r_aabb: AABB::empty(),
+                child_r_index: index + 2,
+            });
+            nodes.push(BVHNode::Leaf {
+                parent_index: index,
+                depth: depth + 1,
+                shape_index: i as u32,
+            });
+        }
+
+        // Replace the last inner node by a leaf and fix the right child `AABB`s bottom-up.
+        let last = shapes.len() - 1;
+        let last_index = nodes.len() - 2;
+        nodes.truncate(last_index);
+        nodes.push(BVHNode::Leaf {
+            parent_index: last_index as u32 - 2,
+            depth: last as u32,
+            shape_index: last as u32,
+        });
+        shapes[last].set_bh_node_index(last_index);
+
+        let mut aabb = shapes[last].aabb();
+        for i in (0..last).rev() {
+            *nodes[2 * i].child_r_aabb_mut() = aabb;
+            aabb.join_mut(&shapes[i].aabb());
+        }
+        BVH { nodes }
+    }
+
+    #[test]
+    /// Tests whether the ordered iterator yields the same shapes as `traverse`,
+    /// sorted by their entry distance.
+    fn test_traverse_ordered() {
+        let (shapes, bvh) = build_some_bvh();
+
+        let ray = Ray::new(Point3::new(-1000.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let ids: Vec<i32> = bvh
+            .traverse_ordered(&ray, &shapes)
+            .map(|(shape, _)| shape.id)
+            .collect();
+        assert_eq!(ids, (-10..11).collect::<Vec<_>>());
+
+        let ray = Ray::new(Point3::new(1000.0, 0.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        let mut distances = bvh.traverse_ordered(&ray, &shapes).map(|(_, t)| t);
+        let mut previous = distances.next().unwrap();
+        assert_eq!(previous, 989.5);
+        for distance in distances {
+            assert!(distance >= previous);
+            previous = distance;
+        }
+
+        // Take only the first two surfaces along the ray.
+        let ids: Vec<i32> = bvh
+            .traverse_ordered(&ray, &shapes)
+            .take(2)
+            .map(|(shape, _)| shape.id)
+            .collect();
+        assert_eq!(ids, vec![10, 9]);
+    }
+
+    #[test]
+    /// Tests whether the iterator can traverse a `BVH` deeper than `MAX_TRAVERSAL_DEPTH`.
+    fn test_traverse_deep_bvh() {
+        let mut shapes: Vec<_> = (0..200)
+            .map(|i| UnitBox::new(i, Point3::new(i as f32, 0.0, 0.0)))
+            .collect();
+        let bvh = build_chain_bvh(&mut shapes);
+        bvh.assert_consistent(&shapes);
+
+        let ray = Ray::new(Point3::new(-1000.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(bvh
+            .nodes
+            .iter()
+            .any(|node| node.depth() as usize > MAX_TRAVERSAL_DEPTH));
+        assert_eq!(bvh.traverse_iterator(&ray, &shapes).count(), 200);
+        assert_eq!(bvh.traverse(&ray, &shapes).len(), 200);
+
+        // Mirror the chain, so that the iterator has to keep every inner node on its stack.
+        let mut bvh = bvh;
+        for node in bvh.nodes.iter_mut() {
+            if let BVHNode::Node {
+                child_l_index,
+                child_l_aabb,
+                child_r_index,
+                child_r_aabb,
+                ..
+            } = node
+            {
+                std::mem::swap(child_l_index, child_r_index);
+                std::mem::swap(child_l_aabb, child_r_aabb);
+            }
+        }
+        bvh.assert_consistent(&shapes);
+        let mut ids: Vec<i32> = bvh.traverse_iterator(&ray, &shapes).map(|s| s.id).collect();
+        assert_eq!(ids.len(), 200);
+        ids.sort_unstable();
+        assert_eq!(ids, (0..200).collect::<Vec<_>>());
+    }
 }
 
 #[cfg(all(feature = "bench", test))]
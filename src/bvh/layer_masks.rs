@@ -0,0 +1,243 @@
+//! This module defines [`LayerMasks`], which filter the shapes of a [`BVH`] by collision
+//! layers during traversal and skip subtrees without any shape on the requested layers.
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`LayerMasks`]: struct.LayerMasks.html
+//!
+
+use crate::aabb::{Bounded, IntersectsAabb};
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{BVHNode, BVH};
+
+/// Shapes which belong to one or more layers, given as a bitmask.
+pub trait MaskedShape: BHShape {
+    /// Returns the bitmask of the layers of this shape.
+    fn mask(&self) -> u32;
+}
+
+/// The layer bitmask of every node of a [`BVH`]. The mask of a leaf is the mask of its
+/// shape, and the mask of an interior node is the union of the masks of its children.
+///
+/// The masks follow the structure of the [`BVH`]. They stay valid when the [`BVH`] is only
+/// refitted, but must be rebuilt when it is rebuilt or optimized.
+///
+/// [`BVH`]: struct.BVH.html
+///
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayerMasks {
+    /// The mask of each node, indexed like the nodes of the [`BVH`].
+    ///
+    /// [`BVH`]: struct.BVH.html
+    ///
+    masks: Vec<u32>,
+}
+
+impl LayerMasks {
+    /// Computes the [`LayerMasks`] of all nodes of `bvh` from the masks of `shapes`.
+    ///
+    /// [`LayerMasks`]: struct.LayerMasks.html
+    ///
+    pub fn new<Shape: MaskedShape>(bvh: &BVH, shapes: &[Shape]) -> LayerMasks {
+        let mut layer_masks = LayerMasks {
+            masks: vec![0; bvh.nodes.len()],
+        };
+        if !bvh.nodes.is_empty() {
+            layer_masks.compute(bvh, 0, shapes);
+        }
+        layer_masks
+    }
+
+    /// Computes the masks of the subtree at `node_index` and returns the mask of the node.
+    fn compute<Shape: MaskedShape>(
+        &mut self,
+        bvh: &BVH,
+        node_index: usize,
+        shapes: &[Shape],
+    ) -> u32 {
+        let mask = match bvh.nodes[node_index] {
+            BVHNode::Node {
+                child_l_index,
+                child_r_index,
+                ..
+            } => {
+                self.compute(bvh, child_l_index as usize, shapes)
+                    | self.compute(bvh, child_r_index as usize, shapes)
+            }
+            BVHNode::Leaf { shape_index, .. } => shapes[shape_index as usize].mask(),
+        };
+        self.masks[node_index] = mask;
+        mask
+    }
+
+    /// Returns the mask of the node with index `node_index`.
+    pub fn node_mask(&self, node_index: usize) -> u32 {
+        self.masks[node_index]
+    }
+
+    /// Updates the masks after the mask of `shape` has changed. Only the ancestors of the
+    /// leaf of `shape` are visited.
+    pub fn update<Shape: MaskedShape>(&mut self, bvh: &BVH, shape: &Shape) {
+        let mut node_index = shape.bh_node_index();
+        self.masks[node_index] = shape.mask();
+        while node_index != 0 {
+            node_index = bvh.nodes[node_index].parent();
+            let mask = self.masks[bvh.nodes[node_index].child_l()]
+                | self.masks[bvh.nodes[node_index].child_r()];
+            if self.masks[node_index] == mask {
+                break;
+            }
+            self.masks[node_index] = mask;
+        }
+    }
+}
+
+impl BVH {
+    /// Traverses the [`BVH`] with `query`, skipping all shapes whose mask does not share a
+    /// layer with `mask`. Subtrees without any shape on one of these layers are skipped
+    /// without testing their [`AABB`]s.
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements intersect `query`
+    /// and whose masks share a layer with `mask`.
+    ///
+    /// As [`Ray`] implements [`IntersectsAabb`], this is also the masked ray traversal.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`IntersectsAabb`]: ../aabb/trait.IntersectsAabb.html
+    /// [`Ray`]: ../ray/struct.Ray.html
+    ///
+    pub fn query_masked<'a, Query: IntersectsAabb, Shape: Bounded>(
+        &'a self,
+        query: &Query,
+        mask: u32,
+        layer_masks: &LayerMasks,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() || layer_masks.node_mask(0) & mask == 0 {
+            return found;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        if layer_masks.node_mask(child_index as usize) & mask != 0
+                            && query.intersects(child_aabb)
+                        {
+                            stack.push(child_index as usize);
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    let shape = &shapes[shape_index as usize];
+                    // A root leaf has no stored `AABB`, so it has not been tested yet.
+                    if node_index != 0 || query.intersects(&shape.aabb()) {
+                        found.push(shape);
+                    }
+                }
+            }
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{LayerMasks, MaskedShape, BVH};
+    use crate::ray::Ray;
+    use crate::testbase::{generate_aligned_boxes, UnitBox};
+    use crate::{Point3, Vector3};
+
+    /// A `UnitBox` with a layer mask.
+    struct MaskedBox(UnitBox, u32);
+
+    impl Bounded for MaskedBox {
+        fn aabb(&self) -> AABB {
+            self.0.aabb()
+        }
+    }
+
+    impl BHShape for MaskedBox {
+        fn set_bh_node_index(&mut self, index: usize) {
+            self.0.set_bh_node_index(index);
+        }
+
+        fn bh_node_index(&self) -> usize {
+            self.0.bh_node_index()
+        }
+    }
+
+    impl MaskedShape for MaskedBox {
+        fn mask(&self) -> u32 {
+            self.1
+        }
+    }
+
+    /// Creates the aligned boxes with the layers `1` for even and `2` for odd ids.
+    fn create_masked_boxes() -> Vec<MaskedBox> {
+        generate_aligned_boxes()
+            .into_iter()
+            .map(|unit_box| {
+                let mask = 1 << (unit_box.id & 1);
+                MaskedBox(unit_box, mask)
+            })
+            .collect()
+    }
+
+    #[test]
+    /// Tests whether `query_masked` only returns the shapes on the requested layers.
+    fn test_query_masked() {
+        let mut shapes = create_masked_boxes();
+        let bvh = BVH::build(&mut shapes);
+        let layer_masks = LayerMasks::new(&bvh, &shapes);
+        let ray = Ray::new(Point3::new(-1000.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+
+        for &mask in &[0, 1, 2, 3] {
+            let mut expected: Vec<i32> = bvh
+                .traverse(&ray, &shapes)
+                .into_iter()
+                .filter(|shape| shape.mask() & mask != 0)
+                .map(|shape| shape.0.id)
+                .collect();
+            let mut found: Vec<i32> = bvh
+                .query_masked(&ray, mask, &layer_masks, &shapes)
+                .into_iter()
+                .map(|shape| shape.0.id)
+                .collect();
+            expected.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `update` propagates a changed mask to the ancestors of the shape.
+    fn test_update_mask() {
+        let mut shapes = create_masked_boxes();
+        let bvh = BVH::build(&mut shapes);
+        let mut layer_masks = LayerMasks::new(&bvh, &shapes);
+        assert_eq!(layer_masks.node_mask(0), 3);
+
+        shapes[0].1 = 4;
+        layer_masks.update(&bvh, &shapes[0]);
+        assert_eq!(layer_masks.node_mask(0), 7);
+
+        let ray = Ray::new(Point3::new(-1000.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let found = bvh.query_masked(&ray, 4, &layer_masks, &shapes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0.id, shapes[0].0.id);
+    }
+}
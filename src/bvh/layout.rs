@@ -0,0 +1,320 @@
+//! This module defines [`BVH::relayout`], which reorders the nodes of a [`BVH`] in memory
+//! to reduce the cache misses of traversals.
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`BVH::relayout`]: struct.BVH.html#method.relayout
+//!
+
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{BVHNode, BVH};
+
+/// The order in which [`BVH::relayout`] stores the nodes of a [`BVH`]. The root is always
+/// the first node.
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVH::relayout`]: struct.BVH.html#method.relayout
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayoutStrategy {
+    /// Every node is followed by the subtree of its left child, and then by the subtree of
+    /// its right child. This is the order of [`BVH::build`].
+    ///
+    /// [`BVH::build`]: struct.BVH.html#method.build
+    ///
+    DepthFirst,
+
+    /// The nodes are stored level by level, starting with the root.
+    BreadthFirst,
+
+    /// The van Emde Boas order. The tree is cut at half of its height, the tree above the
+    /// cut is stored first, followed by each of the subtrees below the cut, all of them in
+    /// the same order recursively. A traversal then misses the cache about `log_B(n)`
+    /// times on the way down, for any cache line size `B`, without knowing `B`.
+    VanEmdeBoas,
+
+    /// The tree is cut into treelets of the given number of levels, which are stored in
+    /// breadth-first order each, so that the nodes of a treelet are close together. The
+    /// treelets are stored in depth-first order. A height of `0` is treated as `1`.
+    Clustered(u32),
+}
+
+impl BVH {
+    /// Reorders the nodes of the [`BVH`] in memory according to `strategy`, and updates the
+    /// node indices of the `shapes`. The tree itself, and thus the results of all queries,
+    /// stay the same. Nodes which cannot be reached from the root are dropped, and the
+    /// shapes whose leaves were dropped get the node index `usize::MAX`.
+    ///
+    /// The order of [`BVH::build`] is good for coherent rays, which visit neighbouring
+    /// subtrees, while [`LayoutStrategy::VanEmdeBoas`] and [`LayoutStrategy::Clustered`]
+    /// keep the nodes on the path from the root to each leaf close together, which helps
+    /// incoherent queries.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::build`]: struct.BVH.html#method.build
+    /// [`LayoutStrategy::Clustered`]: enum.LayoutStrategy.html#variant.Clustered
+    /// [`LayoutStrategy::VanEmdeBoas`]: enum.LayoutStrategy.html#variant.VanEmdeBoas
+    ///
+    pub fn relayout<Shape: BHShape>(&mut self, strategy: LayoutStrategy, shapes: &mut [Shape]) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut order = Vec::with_capacity(self.nodes.len());
+        match strategy {
+            LayoutStrategy::DepthFirst => self.depth_first_order(0, &mut order),
+            LayoutStrategy::BreadthFirst => self.clustered_order(0, u32::MAX, &mut order),
+            LayoutStrategy::VanEmdeBoas => {
+                let height = self.nodes.iter().map(BVHNode::depth).max().unwrap() + 1;
+                self.van_emde_boas_order(0, height, &mut order);
+            }
+            LayoutStrategy::Clustered(height) => self.clustered_order(0, height.max(1), &mut order),
+        }
+
+        let mut new_index = vec![0; self.nodes.len()];
+        for (index, &old_index) in order.iter().enumerate() {
+            new_index[old_index] = index as u32;
+        }
+
+        let nodes = order
+            .iter()
+            .map(|&old_index| match self.nodes[old_index] {
+                BVHNode::Leaf {
+                    parent_index,
+                    depth,
+                    shape_index,
+                } => BVHNode::Leaf {
+                    parent_index: new_index[parent_index as usize],
+                    depth,
+                    shape_index,
+                },
+                BVHNode::Node {
+                    parent_index,
+                    depth,
+                    child_l_index,
+                    child_l_aabb,
+                    child_r_index,
+                    child_r_aabb,
+                } => BVHNode::Node {
+                    parent_index: new_index[parent_index as usize],
+                    depth,
+                    child_l_index: new_index[child_l_index as usize],
+                    child_l_aabb,
+                    child_r_index: new_index[child_r_index as usize],
+                    child_r_aabb,
+                },
+            })
+            .collect();
+        // The shapes of dropped leaves are no longer in the tree.
+        for node in &self.nodes {
+            if let Some(shape_index) = node.shape_index() {
+                shapes[shape_index].set_bh_node_index(usize::MAX);
+            }
+        }
+        self.nodes = nodes;
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(shape_index) = node.shape_index() {
+                shapes[shape_index].set_bh_node_index(index);
+            }
+        }
+    }
+
+    /// Drops the nodes of the [`BVH`] which cannot be reached from the root, e.g. after
+    /// subtrees were detached by hand, renumbers the remaining nodes in depth-first order,
+    /// updates the node indices of the `shapes`, and shrinks the node buffer to fit. The
+    /// shapes whose leaves were dropped get the node index `usize::MAX`.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn compact<Shape: BHShape>(&mut self, shapes: &mut [Shape]) {
+        self.relayout(LayoutStrategy::DepthFirst, shapes);
+        self.nodes.shrink_to_fit();
+    }
+
+    /// Appends the indices of the subtree at `node_index` to `order` in depth-first order.
+    fn depth_first_order(&self, node_index: usize, order: &mut Vec<usize>) {
+        order.push(node_index);
+        if let BVHNode::Node { .. } = self.nodes[node_index] {
+            self.depth_first_order(self.nodes[node_index].child_l(), order);
+            self.depth_first_order(self.nodes[node_index].child_r(), order);
+        }
+    }
+
+    /// Appends the indices of the top `height` levels of the subtree at `node_index` to
+    /// `order` in van Emde Boas order.
+    fn van_emde_boas_order(&self, node_index: usize, height: u32, order: &mut Vec<usize>) {
+        if height <= 1 {
+            order.push(node_index);
+            return;
+        }
+
+        let top_height = height / 2;
+        self.van_emde_boas_order(node_index, top_height, order);
+        for root in self.nodes_below(node_index, top_height) {
+            self.van_emde_boas_order(root, height - top_height, order);
+        }
+    }
+
+    /// Appends the indices of the subtree at `node_index` to `order`, in treelets of
+    /// `height` levels.
+    fn clustered_order(&self, node_index: usize, height: u32, order: &mut Vec<usize>) {
+        let mut level = vec![node_index];
+        for _ in 0..height {
+            if level.is_empty() {
+                return;
+            }
+            order.extend_from_slice(&level);
+            level = level
+                .iter()
+                .flat_map(|&index| self.nodes_below(index, 1))
+                .collect();
+        }
+        for root in level {
+            self.clustered_order(root, height, order);
+        }
+    }
+
+    /// Returns the indices of the nodes `levels` levels below the node at `node_index`,
+    /// from left to right.
+    fn nodes_below(&self, node_index: usize, levels: u32) -> Vec<usize> {
+        let mut nodes = vec![node_index];
+        for _ in 0..levels {
+            nodes = nodes
+                .iter()
+                .filter(|&&index| matches!(self.nodes[index], BVHNode::Node { .. }))
+                .flat_map(|&index| {
+                    let node = &self.nodes[index];
+                    vec![node.child_l(), node.child_r()]
+                })
+                .collect();
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{BVHNode, LayoutStrategy, BVH};
+    use crate::testbase::{
+        create_n_cubes, create_ray, default_bounds, generate_aligned_boxes, UnitBox,
+    };
+    use crate::Point3;
+
+    #[test]
+    /// Tests whether every `LayoutStrategy` keeps the `BVH` consistent, updates the node
+    /// indices of the shapes, and keeps the results of traversals.
+    fn test_relayout_keeps_bvh() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let strategies = [
+            LayoutStrategy::BreadthFirst,
+            LayoutStrategy::VanEmdeBoas,
+            LayoutStrategy::Clustered(0),
+            LayoutStrategy::Clustered(3),
+            LayoutStrategy::DepthFirst,
+        ];
+        for &strategy in &strategies {
+            let mut shapes = create_n_cubes(100, &bounds);
+            let mut relaid = BVH::build(&mut shapes);
+            relaid.relayout(strategy, &mut shapes);
+            relaid.assert_consistent(&shapes);
+            for shape in &shapes {
+                let node = &relaid.nodes[shape.bh_node_index()];
+                assert!(std::ptr::eq(&shapes[node.shape_index().unwrap()], shape));
+            }
+
+            let mut seed = 0;
+            for _ in 0..100 {
+                let ray = create_ray(&mut seed, &bounds);
+                assert_eq!(relaid.traverse_indices(&ray), bvh.traverse_indices(&ray));
+            }
+        }
+    }
+
+    #[test]
+    /// Tests the van Emde Boas order of a complete tree of four levels.
+    fn test_relayout_van_emde_boas() {
+        let mut shapes: Vec<UnitBox> = (0..8)
+            .map(|i| UnitBox::new(i, Point3::new(i as f32 * 2.0, 0.0, 0.0)))
+            .collect();
+        let mut bvh = BVH::build(&mut shapes);
+        assert!(bvh
+            .nodes
+            .iter()
+            .all(|node| node.shape_index().is_none() || node.depth() == 3));
+
+        bvh.relayout(LayoutStrategy::VanEmdeBoas, &mut shapes);
+        // The top tree of the root and its children, then four trees of three nodes.
+        assert_eq!((bvh.nodes[0].child_l(), bvh.nodes[0].child_r()), (1, 2));
+        for (subtree, &root) in [3, 6, 9, 12].iter().enumerate() {
+            assert_eq!(bvh.nodes[root].parent(), 1 + subtree / 2);
+            assert_eq!(bvh.nodes[root].child_l(), root + 1);
+            assert_eq!(bvh.nodes[root].child_r(), root + 2);
+        }
+    }
+
+    #[test]
+    /// Tests whether `compact` drops unreachable nodes and unused capacity, and keeps the
+    /// results of traversals.
+    fn test_compact() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut compacted = bvh.clone();
+        let detached = compacted.nodes[10];
+        compacted.nodes.extend_from_slice(&[detached; 10]);
+        compacted.nodes.reserve(1000);
+        compacted.compact(&mut triangles);
+
+        assert_eq!(compacted.nodes.len(), bvh.nodes.len());
+        assert_eq!(compacted.nodes.capacity(), compacted.nodes.len());
+        compacted.assert_consistent(&triangles);
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            assert_eq!(compacted.traverse_indices(&ray), bvh.traverse_indices(&ray));
+        }
+    }
+
+    #[test]
+    /// Tests whether `compact` gives the shapes of a detached subtree the node index
+    /// `usize::MAX`, and the other shapes the indices of their new leaves.
+    fn test_compact_detached_shapes() {
+        let mut shapes = generate_aligned_boxes();
+        let mut bvh = BVH::build(&mut shapes);
+
+        // Replace the left child of the root by its own left child.
+        let child_index = bvh.nodes[0].child_l();
+        let grandchild_index = bvh.nodes[child_index].child_l();
+        let grandchild_aabb = bvh.nodes[child_index].child_l_aabb();
+        if let BVHNode::Node {
+            ref mut child_l_index,
+            ref mut child_l_aabb,
+            ..
+        } = bvh.nodes[0]
+        {
+            *child_l_index = grandchild_index as u32;
+            *child_l_aabb = grandchild_aabb;
+        }
+        *bvh.nodes[grandchild_index].parent_mut() = 0;
+        bvh.compact(&mut shapes);
+
+        let mut kept = vec![false; shapes.len()];
+        for (index, node) in bvh.nodes.iter().enumerate() {
+            if let Some(shape_index) = node.shape_index() {
+                assert_eq!(shapes[shape_index].bh_node_index(), index);
+                kept[shape_index] = true;
+            }
+        }
+        let dropped: Vec<usize> = (0..shapes.len()).filter(|&i| !kept[i]).collect();
+        assert!(!dropped.is_empty());
+        for &shape_index in &dropped {
+            assert_eq!(shapes[shape_index].bh_node_index(), usize::MAX);
+        }
+    }
+}
@@ -3,10 +3,36 @@
 //! [`BVH`]: struct.BVH.html
 //!
 
+mod batch;
+mod best_first;
+mod binary;
 mod bvh_impl;
+mod cluster;
+mod export;
 mod iter;
+mod layer_masks;
+mod layout;
 mod optimization;
+mod overlap;
+mod proximity;
+mod proxy;
+mod ray_query;
+mod refine;
+mod refit;
+mod scratch;
+mod stats;
+mod transform_groups;
+mod volume_query;
 
 pub use self::bvh_impl::*;
+pub use self::cluster::*;
 pub use self::iter::*;
-pub use self::optimization::*;
+pub use self::layer_masks::*;
+pub use self::layout::*;
+pub use self::proximity::*;
+pub use self::proxy::*;
+pub use self::refine::*;
+pub use self::scratch::*;
+pub use self::stats::*;
+pub use self::transform_groups::*;
+pub use self::volume_query::*;
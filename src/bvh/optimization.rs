@@ -52,11 +52,11 @@ impl BVHNode {
                 ..
             } => Some((
                 NodeData {
-                    index: child_l_index,
+                    index: child_l_index as usize,
                     aabb: child_l_aabb,
                 },
                 NodeData {
-                    index: child_r_index,
+                    index: child_r_index as usize,
                     aabb: child_r_aabb,
                 },
             )),
@@ -150,6 +150,243 @@ impl BVH {
         }
     }
 
+    /// Updates the [`AABB`] of the shape with index `shape_index` to `new_aabb`.
+    ///
+    /// The parent of a leaf stores the [`AABB`] of the shape fattened by `margin` on all
+    /// sides, from the last time the leaf was inserted. If `new_aabb` lies within that
+    /// stored [`AABB`], nothing changes. Otherwise the leaf is removed from the tree and
+    /// reinserted next to the node whose [`AABB`] grows the least, like in incremental
+    /// builders, with `new_aabb` fattened by `margin`. The [`AABB`]s of the ancestors are
+    /// refitted, which stops as soon as an ancestor's [`AABB`] does not change anymore.
+    /// Since a [`BVH`] is built with tight [`AABB`]s, the first update of a shape which
+    /// moved at all reinserts it. In a [`BVH`] of two shapes, the leaf is refitted in place.
+    ///
+    /// This is a lot cheaper than rebuilding the [`BVH`] or calling [`BVH::optimize`] when
+    /// only a few shapes moved. A shape which drifts in many small steps is reinserted as
+    /// soon as it leaves its fattened [`AABB`], so the tree keeps its quality.
+    ///
+    /// `shapes` is only used to look up the leaf node of the shape, the shape itself does not
+    /// need to report `new_aabb` yet. The node indices of the shapes do not change.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::optimize`]: struct.BVH.html#method.optimize
+    ///
+    pub fn update_shape<Shape: BHShape>(
+        &mut self,
+        shape_index: usize,
+        new_aabb: &AABB,
+        margin: f32,
+        shapes: &[Shape],
+    ) {
+        let node_index = shapes[shape_index].bh_node_index();
+        // The root node has no parent which stores its `AABB`.
+        if node_index == 0 || self.stored_aabb(node_index).contains_aabb(new_aabb) {
+            return;
+        }
+
+        let fat_aabb = new_aabb.grown_by(margin);
+        match self.remove_leaf(node_index) {
+            Some(free_index) => self.insert_leaf(node_index, free_index, &fat_aabb),
+            None => self.refit_leaf(node_index, &fat_aabb),
+        }
+    }
+
+    /// Stores `aabb` as the [`AABB`] of the shape with index `shape_index`, without changing
+    /// the structure of the [`BVH`], and refits the [`AABB`]s of its ancestors.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub(crate) fn refit_shape<Shape: BHShape>(
+        &mut self,
+        shape_index: usize,
+        aabb: &AABB,
+        shapes: &[Shape],
+    ) {
+        let node_index = shapes[shape_index].bh_node_index();
+        // The root node has no parent which stores its `AABB`.
+        if node_index != 0 {
+            self.refit_leaf(node_index, aabb);
+        }
+    }
+
+    /// Stores `aabb` as the [`AABB`] of the node at `node_index`, which must not be the
+    /// root, and refits the [`AABB`]s of its ancestors.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn refit_leaf(&mut self, node_index: usize, aabb: &AABB) {
+        if self.set_stored_aabb(node_index, aabb) {
+            self.refit_ancestors(self.nodes[node_index].parent());
+        }
+    }
+
+    /// Stores `aabb` as the [`AABB`] of the node at `node_index` in its parent. Returns
+    /// whether the stored [`AABB`] changed.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn set_stored_aabb(&mut self, node_index: usize, aabb: &AABB) -> bool {
+        let is_left_child = self.node_is_left_child(node_index);
+        let parent_index = self.nodes[node_index].parent();
+        let parent = &mut self.nodes[parent_index];
+        let child_aabb = if is_left_child {
+            parent.child_l_aabb_mut()
+        } else {
+            parent.child_r_aabb_mut()
+        };
+        let changed = child_aabb.min != aabb.min || child_aabb.max != aabb.max;
+        *child_aabb = *aabb;
+        changed
+    }
+
+    /// Sets the left or right child of the inner node at `parent_index` to `child_index`.
+    fn replace_child(&mut self, parent_index: usize, left_child: bool, child_index: usize) {
+        if let BVHNode::Node {
+            ref mut child_l_index,
+            ref mut child_r_index,
+            ..
+        } = self.nodes[parent_index]
+        {
+            if left_child {
+                *child_l_index = child_index as u32;
+            } else {
+                *child_r_index = child_index as u32;
+            }
+        } else {
+            unreachable!();
+        }
+    }
+
+    /// Refits the [`AABB`]s of the inner node at `node_index` and of its ancestors to
+    /// their children. Refitting stops as soon as an [`AABB`] does not change anymore,
+    /// because all ancestors are correct as well then.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn refit_ancestors(&mut self, mut node_index: usize) {
+        // The root node has no parent which stores its `AABB`.
+        while node_index != 0 {
+            let node = &self.nodes[node_index];
+            let aabb = node.child_l_aabb().join(&node.child_r_aabb());
+            if !self.set_stored_aabb(node_index, &aabb) {
+                return;
+            }
+            node_index = self.nodes[node_index].parent();
+        }
+    }
+
+    /// Removes the leaf at `node_index`, which must not be the root, by putting its sibling
+    /// in the place of its parent. Returns the index of the node which was freed, which is
+    /// the parent, or the old index of the sibling if the sibling moved to the root. The
+    /// leaf and the freed node are left unconnected. Returns `None` and changes nothing if
+    /// the sibling is the only other leaf, because the root has to stay an inner node.
+    fn remove_leaf(&mut self, node_index: usize) -> Option<usize> {
+        let parent_index = self.nodes[node_index].parent();
+        let parent = &self.nodes[parent_index];
+        let (sibling_index, sibling_aabb) = if parent.child_l() == node_index {
+            (parent.child_r(), parent.child_r_aabb())
+        } else {
+            (parent.child_l(), parent.child_l_aabb())
+        };
+
+        if parent_index == 0 {
+            // The root has to stay at index 0, so the sibling moves there.
+            let (child_l_index, child_r_index) = match self.nodes[sibling_index] {
+                BVHNode::Node {
+                    child_l_index,
+                    child_r_index,
+                    ..
+                } => (child_l_index as usize, child_r_index as usize),
+                BVHNode::Leaf { .. } => return None,
+            };
+            self.nodes[0] = self.nodes[sibling_index];
+            *self.nodes[0].parent_mut() = 0;
+            *self.nodes[child_l_index].parent_mut() = 0;
+            *self.nodes[child_r_index].parent_mut() = 0;
+            self.update_depth_recursively(0, 0);
+            return Some(sibling_index);
+        }
+
+        let parent_depth = parent.depth();
+        let grandparent_index = parent.parent();
+        let parent_is_left_child = self.node_is_left_child(parent_index);
+        self.replace_child(grandparent_index, parent_is_left_child, sibling_index);
+        *self.nodes[sibling_index].parent_mut() = grandparent_index as u32;
+        self.set_stored_aabb(sibling_index, &sibling_aabb);
+        self.update_depth_recursively(sibling_index, parent_depth);
+        self.refit_ancestors(grandparent_index);
+        Some(parent_index)
+    }
+
+    /// Inserts the unconnected leaf at `node_index` with the given `aabb` into the tree,
+    /// using the unconnected node at `free_index` as its new parent. The sibling of the
+    /// leaf is chosen by descending from the root towards the smallest increase of surface
+    /// area, but is never the root itself.
+    fn insert_leaf(&mut self, node_index: usize, free_index: usize, aabb: &AABB) {
+        // The cost of a node as the sibling is the surface area of the new parent, plus the
+        // growth of the `AABB`s of all ancestors which is inherited from the new leaf.
+        let mut current_index = 0;
+        let mut current_aabb = self.nodes[0]
+            .child_l_aabb()
+            .join(&self.nodes[0].child_r_aabb());
+        let sibling_index = loop {
+            let current = &self.nodes[current_index];
+            let joined_area = current_aabb.join(aabb).surface_area();
+            let inherited_cost = 2.0 * (joined_area - current_aabb.surface_area());
+            let child_cost = |child_index: usize, child_aabb: &AABB| {
+                let joined_area = child_aabb.join(aabb).surface_area();
+                match self.nodes[child_index] {
+                    BVHNode::Leaf { .. } => joined_area + inherited_cost,
+                    BVHNode::Node { .. } => {
+                        joined_area - child_aabb.surface_area() + inherited_cost
+                    }
+                }
+            };
+            let (child_l_index, child_l_aabb) = (current.child_l(), current.child_l_aabb());
+            let (child_r_index, child_r_aabb) = (current.child_r(), current.child_r_aabb());
+            let cost_l = child_cost(child_l_index, &child_l_aabb);
+            let cost_r = child_cost(child_r_index, &child_r_aabb);
+
+            if current_index != 0 && 2.0 * joined_area < cost_l.min(cost_r) {
+                break current_index;
+            }
+            let (child_index, child_aabb) = if cost_l <= cost_r {
+                (child_l_index, child_l_aabb)
+            } else {
+                (child_r_index, child_r_aabb)
+            };
+            if let BVHNode::Leaf { .. } = self.nodes[child_index] {
+                break child_index;
+            }
+            current_index = child_index;
+            current_aabb = child_aabb;
+        };
+
+        // Put the new parent in the place of the sibling.
+        let sibling_aabb = self.stored_aabb(sibling_index);
+        let sibling_parent_index = self.nodes[sibling_index].parent();
+        let sibling_is_left_child = self.node_is_left_child(sibling_index);
+        let depth = self.nodes[sibling_index].depth();
+        self.nodes[free_index] = BVHNode::Node {
+            parent_index: sibling_parent_index as u32,
+            depth,
+            child_l_index: sibling_index as u32,
+            child_l_aabb: sibling_aabb,
+            child_r_index: node_index as u32,
+            child_r_aabb: *aabb,
+        };
+        self.replace_child(sibling_parent_index, sibling_is_left_child, free_index);
+        *self.nodes[sibling_index].parent_mut() = free_index as u32;
+        *self.nodes[node_index].parent_mut() = free_index as u32;
+        self.update_depth_recursively(sibling_index, depth + 1);
+        self.update_depth_recursively(node_index, depth + 1);
+
+        self.set_stored_aabb(free_index, &sibling_aabb.join(aabb));
+        self.refit_ancestors(sibling_parent_index);
+    }
+
     /// This method is called for each node which has been modified and needs to be updated.
     /// If the specified node is a grandparent, then try to optimize the `BVH` by rotating its
     /// children.
@@ -170,9 +407,9 @@ impl BVH {
                 info!(
                     "Leaf node. Queueing parent ({}). {}.",
                     parent_index,
-                    shapes[shape_index].aabb()
+                    shapes[shape_index as usize].aabb()
                 );
-                Some(OptimizationIndex::Refit(parent_index))
+                Some(OptimizationIndex::Refit(parent_index as usize))
             }
             BVHNode::Node {
                 parent_index,
@@ -190,8 +427,10 @@ impl BVH {
                         shape_index: shape_r_index,
                         ..
                     },
-                ) = (&self.nodes[child_l_index], &self.nodes[child_r_index])
-                {
+                ) = (
+                    &self.nodes[child_l_index as usize],
+                    &self.nodes[child_r_index as usize],
+                ) {
                     // The current node is a final parent. Update its `AABB`s, because at least
                     // one of its children was updated and queue its parent for refitting.
                     if let BVHNode::Node {
@@ -200,11 +439,11 @@ impl BVH {
                         ..
                     } = self.nodes[node_index]
                     {
-                        *child_l_aabb = shapes[shape_l_index].aabb();
-                        *child_r_aabb = shapes[shape_r_index].aabb();
+                        *child_l_aabb = shapes[shape_l_index as usize].aabb();
+                        *child_r_aabb = shapes[shape_r_index as usize].aabb();
                         info!("Setting {} from {}", child_l_aabb, child_l_index);
                         info!("\tand {} from {}.", child_r_aabb, child_r_index);
-                        return Some(OptimizationIndex::Refit(parent_index));
+                        return Some(OptimizationIndex::Refit(parent_index as usize));
                     }
                     unreachable!();
                 }
@@ -297,7 +536,11 @@ impl BVH {
             ..
         } = self.nodes[node_index]
         {
-            (parent_index, child_l_index, child_r_index)
+            (
+                parent_index as usize,
+                child_l_index as usize,
+                child_r_index as usize,
+            )
         } else {
             unreachable!()
         };
@@ -358,7 +601,7 @@ impl BVH {
             ..
         } = self.nodes[node_index]
         {
-            (child_l_index, child_r_index)
+            (child_l_index as usize, child_r_index as usize)
         } else {
             unreachable!()
         };
@@ -387,12 +630,12 @@ impl BVH {
                 ..
             } => {
                 *self.nodes[node_index].child_l_aabb_mut() =
-                    self.nodes[child_l_index].get_node_aabb(shapes);
+                    self.nodes[child_l_index as usize].get_node_aabb(shapes);
                 *self.nodes[node_index].child_r_aabb_mut() =
-                    self.nodes[child_r_index].get_node_aabb(shapes);
+                    self.nodes[child_r_index as usize].get_node_aabb(shapes);
 
                 if node_index > 0 {
-                    Some(OptimizationIndex::FixAABBs(parent_index))
+                    Some(OptimizationIndex::FixAABBs(parent_index as usize))
                 } else {
                     None
                 }
@@ -447,7 +690,7 @@ impl BVH {
                     ..
                 } => {
                     *depth = new_depth;
-                    Some((child_l_index, child_r_index))
+                    Some((child_l_index as usize, child_r_index as usize))
                 }
                 BVHNode::Leaf { ref mut depth, .. } => {
                     *depth = new_depth;
@@ -490,10 +733,10 @@ impl BVH {
                     ..
                 } => {
                     if left_child {
-                        *child_l_index = child_index;
+                        *child_l_index = child_index as u32;
                         *child_l_aabb = child_aabb;
                     } else {
-                        *child_r_index = child_index;
+                        *child_r_index = child_index as u32;
                         *child_r_aabb = child_aabb;
                     }
                     info!("\t  {}'s new {}", parent_index, child_aabb);
@@ -505,7 +748,7 @@ impl BVH {
         };
 
         // Set child's parent.
-        *self.nodes[child_index].parent_mut() = parent_index;
+        *self.nodes[child_index].parent_mut() = parent_index as u32;
 
         // Update the node's and the node's descendants' depth values.
         self.update_depth_recursively(child_index, parent_depth + 1);
@@ -517,11 +760,13 @@ mod tests {
     use crate::aabb::Bounded;
     use crate::bounding_hierarchy::BHShape;
     use crate::bvh::{BVHNode, BVH};
+    use crate::ray::Ray;
     use crate::testbase::{
-        build_some_bh, create_n_cubes, default_bounds, randomly_transform_scene, UnitBox,
+        build_some_bh, create_n_cubes, create_ray, default_bounds, next_point3,
+        randomly_transform_scene, UnitBox,
     };
-    use crate::Point3;
     use crate::EPSILON;
+    use crate::{Point3, Vector3};
     use std::collections::HashSet;
 
     #[test]
@@ -626,6 +871,177 @@ mod tests {
         }
     }
 
+    #[test]
+    /// Tests whether updating single shapes keeps the `BVH` consistent and tight, whether
+    /// the shapes are fattened or not.
+    fn test_update_shape() {
+        for &margin in &[1.0, 0.0] {
+            let (mut shapes, mut bvh) = build_some_bh::<BVH>();
+
+            shapes[0].pos = Point3::new(100.0, 10.0, -5.0);
+            shapes[7].pos = Point3::new(0.5, 0.0, 0.0);
+            for &index in &[0, 7] {
+                let new_aabb = shapes[index].aabb();
+                bvh.update_shape(index, &new_aabb, margin, &shapes);
+            }
+
+            bvh.assert_consistent(&shapes);
+            bvh.assert_tight(&shapes);
+        }
+    }
+
+    #[test]
+    /// Tests whether a shape which escaped its fattened bound is reinserted next to its new
+    /// neighbours, and whether a shape within its fattened bound keeps its place.
+    fn test_update_shape_reinserts() {
+        let (mut shapes, mut bvh) = build_some_bh::<BVH>();
+        let node_index = shapes[0].bh_node_index();
+
+        // The tight `AABB` of the build does not contain the moved shape.
+        shapes[0].pos.x += 0.5;
+        let new_aabb = shapes[0].aabb();
+        bvh.update_shape(0, &new_aabb, 1.0, &shapes);
+        let fat_aabb = bvh.stored_aabb(node_index);
+        assert!(fat_aabb.relative_eq(&new_aabb.grown_by(1.0), EPSILON));
+
+        // Within the margin, nothing changes.
+        let parent_index = bvh.nodes[node_index].parent();
+        shapes[0].pos.x += 0.5;
+        let new_aabb = shapes[0].aabb();
+        bvh.update_shape(0, &new_aabb, 1.0, &shapes);
+        assert_eq!(bvh.nodes[node_index].parent(), parent_index);
+        assert!(bvh.stored_aabb(node_index).relative_eq(&fat_aabb, EPSILON));
+
+        // The first box moves from the left end of the row to the right end. Without a
+        // margin, its new sibling is the box at the right end.
+        shapes[0].pos = Point3::new(11.0, 0.0, 0.0);
+        let new_aabb = shapes[0].aabb();
+        bvh.update_shape(0, &new_aabb, 0.0, &shapes);
+        bvh.assert_consistent(&shapes);
+        bvh.assert_tight(&shapes);
+        assert_eq!(shapes[0].bh_node_index(), node_index);
+        let parent = &bvh.nodes[bvh.nodes[node_index].parent()];
+        let sibling_index = if parent.child_l() == node_index {
+            parent.child_r()
+        } else {
+            parent.child_l()
+        };
+        let sibling_shape = bvh.nodes[sibling_index].shape_index().unwrap();
+        assert_eq!(shapes[sibling_shape].id, 10);
+
+        let ray = Ray::new(Point3::new(11.0, 0.0, -100.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(bvh.traverse_indices(&ray), vec![0]);
+    }
+
+    #[test]
+    /// Tests whether a shape which drifts in steps smaller than the margin is reinserted
+    /// whenever it leaves its fattened bound, so that its stored `AABB` follows it.
+    fn test_update_shape_drift() {
+        let (mut shapes, mut bvh) = build_some_bh::<BVH>();
+        let node_index = shapes[0].bh_node_index();
+        let margin = 1.0;
+        let mut reinsertions = 0;
+        for _ in 0..80 {
+            shapes[0].pos.x += 0.25;
+            let new_aabb = shapes[0].aabb();
+            let old_aabb = bvh.stored_aabb(node_index);
+            bvh.update_shape(0, &new_aabb, margin, &shapes);
+            let stored_aabb = bvh.stored_aabb(node_index);
+            if !stored_aabb.relative_eq(&old_aabb, EPSILON) {
+                assert!(stored_aabb.relative_eq(&new_aabb.grown_by(margin), EPSILON));
+                reinsertions += 1;
+            }
+            assert!(stored_aabb.contains_aabb(&new_aabb));
+            assert!(new_aabb.grown_by(2.0 * margin).contains_aabb(&stored_aabb));
+            bvh.assert_consistent(&shapes);
+            bvh.assert_tight(&shapes);
+        }
+        assert!(reinsertions >= 80 / 8);
+    }
+
+    #[test]
+    /// Tests whether a leaf directly below the root is reinserted, and whether the leaves of
+    /// a `BVH` of two shapes are refitted in place.
+    fn test_update_shape_below_root() {
+        let mut shapes = vec![
+            UnitBox::new(0, Point3::new(0.0, 0.0, 0.0)),
+            UnitBox::new(1, Point3::new(2.0, 0.0, 0.0)),
+            UnitBox::new(2, Point3::new(20.0, 0.0, 0.0)),
+        ];
+        let mut bvh = BVH::build(&mut shapes);
+        let node_index = shapes[2].bh_node_index();
+        assert_eq!(bvh.nodes[node_index].parent(), 0);
+
+        shapes[2].pos = Point3::new(3.0, 0.0, 0.0);
+        let new_aabb = shapes[2].aabb();
+        bvh.update_shape(2, &new_aabb, 0.0, &shapes);
+        bvh.assert_consistent(&shapes);
+        bvh.assert_tight(&shapes);
+        assert_ne!(bvh.nodes[node_index].parent(), 0);
+        let ray = Ray::new(Point3::new(3.0, 0.0, -100.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(bvh.traverse_indices(&ray), vec![2]);
+
+        let mut shapes = vec![
+            UnitBox::new(0, Point3::new(0.0, 0.0, 0.0)),
+            UnitBox::new(1, Point3::new(2.0, 0.0, 0.0)),
+        ];
+        let mut bvh = BVH::build(&mut shapes);
+        shapes[1].pos = Point3::new(5.0, 0.0, 0.0);
+        let new_aabb = shapes[1].aabb();
+        bvh.update_shape(1, &new_aabb, 1.0, &shapes);
+        bvh.assert_consistent(&shapes);
+        let node_index = shapes[1].bh_node_index();
+        assert_eq!(bvh.nodes[node_index].parent(), 0);
+        assert!(bvh
+            .stored_aabb(node_index)
+            .relative_eq(&new_aabb.grown_by(1.0), EPSILON));
+    }
+    #[test]
+    /// Tests whether many random updates with reinsertion keep the `BVH` consistent, tight
+    /// and equivalent to a rebuilt `BVH`.
+    fn test_update_shape_random() {
+        let bounds = default_bounds();
+        let (mut shapes, mut bvh) = build_some_bh::<BVH>();
+        let mut seed = 0;
+        for i in 0..200 {
+            let index = i % shapes.len();
+            shapes[index].pos = next_point3(&mut seed, &bounds);
+            let new_aabb = shapes[index].aabb();
+            bvh.update_shape(index, &new_aabb, 0.1, &shapes);
+            bvh.assert_consistent(&shapes);
+            bvh.assert_tight(&shapes);
+        }
+
+        let mut rebuilt_shapes: Vec<UnitBox> = shapes
+            .iter()
+            .map(|shape| UnitBox::new(shape.id, shape.pos))
+            .collect();
+        let rebuilt = BVH::build(&mut rebuilt_shapes);
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            let mut hits = bvh.traverse_indices(&ray);
+            let mut expected = rebuilt.traverse_indices(&ray);
+            hits.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(hits, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether a shape which was moved via `update_shape` can be found by traversal.
+    fn test_traverse_after_update_shape() {
+        let (mut shapes, mut bvh) = build_some_bh::<BVH>();
+
+        shapes[3].pos = Point3::new(0.0, 50.0, 0.0);
+        let new_aabb = shapes[3].aabb();
+        bvh.update_shape(3, &new_aabb, 0.0, &shapes);
+
+        let ray = Ray::new(Point3::new(0.0, 50.0, -100.0), Vector3::new(0.0, 0.0, 1.0));
+        let hits = bvh.traverse(&ray, &shapes);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, shapes[3].id);
+    }
+
     /// Creates a small `BVH` with 4 shapes and 7 nodes.
     fn create_predictable_bvh() -> (Vec<UnitBox>, BVH) {
         let shapes = vec![
@@ -996,7 +1412,7 @@ mod bench {
     /// `BVH`. Iterate this procedure `iterations` times. Afterwards benchmark the performance
     /// of intersecting this scene/`BVH`.
     fn intersect_scene_after_optimize(
-        triangles: &mut Vec<Triangle>,
+        triangles: &mut [Triangle],
         bounds: &AABB,
         percent: f32,
         max_offset: Option<f32>,
@@ -1049,7 +1465,7 @@ mod bench {
     /// scene/`BVH`. Used to compare optimizing with rebuilding. For reference see
     /// `intersect_scene_after_optimize`.
     fn intersect_scene_with_rebuild(
-        triangles: &mut Vec<Triangle>,
+        triangles: &mut [Triangle],
         bounds: &AABB,
         percent: f32,
         max_offset: Option<f32>,
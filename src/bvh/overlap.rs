@@ -0,0 +1,238 @@
+//! This module defines overlap queries between two [`BVH`]s, or a [`BVH`] and itself, which
+//! descend both hierarchies simultaneously and report the pairs of shapes whose [`AABB`]s
+//! overlap.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bvh::{BVHNode, BVH};
+
+impl BVH {
+    /// Descends this [`BVH`] and `other` simultaneously and returns all pairs of shapes
+    /// from `shapes` and `other_shapes` whose [`AABB`]s overlap.
+    /// The pairs are returned in no particular order.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn intersect_bvh<'a, 'b, Shape: Bounded, OtherShape: Bounded>(
+        &self,
+        other: &BVH,
+        shapes: &'a [Shape],
+        other_shapes: &'b [OtherShape],
+    ) -> Vec<(&'a Shape, &'b OtherShape)> {
+        let mut pairs = Vec::new();
+        if self.nodes.is_empty() || other.nodes.is_empty() {
+            return pairs;
+        }
+
+        BVH::overlapping_leaves(
+            (self, 0, self.root_aabb(shapes)),
+            (other, 0, other.root_aabb(other_shapes)),
+            &mut |shape_index, other_shape_index| {
+                pairs.push((&shapes[shape_index], &other_shapes[other_shape_index]));
+            },
+        );
+        pairs
+    }
+
+    /// Returns all pairs of distinct shapes whose [`AABB`]s overlap. Each pair is returned
+    /// once, in no particular order.
+    ///
+    /// The two subtrees of every node are descended simultaneously, so that subtrees whose
+    /// siblings do not overlap them are skipped entirely.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn overlapping_pairs<'a, Shape: Bounded>(
+        &self,
+        shapes: &'a [Shape],
+    ) -> Vec<(&'a Shape, &'a Shape)> {
+        let mut pairs = Vec::new();
+        // Two leaves can only be paired below their lowest common ancestor.
+        for node in &self.nodes {
+            if let Some([(l_index, l_aabb), (r_index, r_aabb)]) = node.children() {
+                BVH::overlapping_leaves(
+                    (self, l_index, l_aabb),
+                    (self, r_index, r_aabb),
+                    &mut |shape_index, other_shape_index| {
+                        pairs.push((&shapes[shape_index], &shapes[other_shape_index]));
+                    },
+                );
+            }
+        }
+        pairs
+    }
+
+    /// Returns the [`AABB`] of the root of the [`BVH`], which is not stored in the nodes.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn root_aabb<Shape: Bounded>(&self, shapes: &[Shape]) -> AABB {
+        match self.nodes.first() {
+            Some(BVHNode::Node {
+                child_l_aabb,
+                child_r_aabb,
+                ..
+            }) => child_l_aabb.join(child_r_aabb),
+            Some(BVHNode::Leaf { shape_index, .. }) => shapes[*shape_index as usize].aabb(),
+            None => AABB::empty(),
+        }
+    }
+
+    /// Descends the subtrees `a` and `b`, each given by its [`BVH`], node index and
+    /// [`AABB`], and calls `visit` with the shape indices of every pair of overlapping leaves.
+    /// The subtree with the larger [`AABB`] is split first.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn overlapping_leaves<F>(a: (&BVH, usize, AABB), b: (&BVH, usize, AABB), visit: &mut F)
+    where
+        F: FnMut(usize, usize),
+    {
+        let (bvh_a, bvh_b) = (a.0, b.0);
+        let mut stack = vec![(a.1, a.2, b.1, b.2)];
+        while let Some((a_index, a_aabb, b_index, b_aabb)) = stack.pop() {
+            if !a_aabb.intersects(&b_aabb) {
+                continue;
+            }
+
+            let a_node = &bvh_a.nodes[a_index];
+            let b_node = &bvh_b.nodes[b_index];
+            match (a_node.children(), b_node.children()) {
+                (None, None) => visit(a_node.shape_index().unwrap(), b_node.shape_index().unwrap()),
+                (Some(a_children), None) => {
+                    for &(child_index, child_aabb) in a_children.iter().rev() {
+                        stack.push((child_index, child_aabb, b_index, b_aabb));
+                    }
+                }
+                (Some(a_children), Some(_)) if a_aabb.surface_area() >= b_aabb.surface_area() => {
+                    for &(child_index, child_aabb) in a_children.iter().rev() {
+                        stack.push((child_index, child_aabb, b_index, b_aabb));
+                    }
+                }
+                (_, Some(b_children)) => {
+                    for &(child_index, child_aabb) in b_children.iter().rev() {
+                        stack.push((a_index, a_aabb, child_index, child_aabb));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl BVHNode {
+    /// Returns the indices and [`AABB`]s of the left and right child of the node,
+    /// or `None` if it is a leaf.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn children(&self) -> Option<[(usize, AABB); 2]> {
+        match *self {
+            BVHNode::Node {
+                child_l_aabb,
+                child_l_index,
+                child_r_aabb,
+                child_r_index,
+                ..
+            } => Some([
+                (child_l_index as usize, child_l_aabb),
+                (child_r_index as usize, child_r_aabb),
+            ]),
+            BVHNode::Leaf { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bvh::BVH;
+    use crate::testbase::{next_point3, UnitBox};
+    use crate::{Point3, Vector3};
+
+    /// Creates `n` boxes at deterministic random positions inside of a small space,
+    /// so that many of them overlap.
+    fn create_crowded_boxes(n: usize, seed: &mut u64) -> Vec<UnitBox> {
+        let bounds = AABB::with_bounds(Point3::new(0.0, 0.0, 0.0), Point3::new(10.0, 10.0, 10.0));
+        (0..n)
+            .map(|id| UnitBox::new(id as i32, next_point3(seed, &bounds)))
+            .collect()
+    }
+
+    #[test]
+    /// Tests whether `intersect_bvh` finds the same pairs as a linear search.
+    fn test_intersect_bvh_equals_linear_search() {
+        let mut seed = 0;
+        for &(n, m) in &[(1, 1), (1, 100), (100, 1), (200, 300)] {
+            let mut boxes_a = create_crowded_boxes(n, &mut seed);
+            let mut boxes_b = create_crowded_boxes(m, &mut seed);
+            let bvh_a = BVH::build(&mut boxes_a);
+            let bvh_b = BVH::build(&mut boxes_b);
+
+            let mut expected = Vec::new();
+            for a in &boxes_a {
+                for b in &boxes_b {
+                    if a.aabb().intersects(&b.aabb()) {
+                        expected.push((a.id, b.id));
+                    }
+                }
+            }
+
+            let mut found: Vec<(i32, i32)> = bvh_a
+                .intersect_bvh(&bvh_b, &boxes_a, &boxes_b)
+                .into_iter()
+                .map(|(a, b)| (a.id, b.id))
+                .collect();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `intersect_bvh` finds no pairs between disjoint scenes.
+    fn test_intersect_bvh_disjoint() {
+        let mut seed = 0;
+        let mut boxes = create_crowded_boxes(100, &mut seed);
+        let mut moved: Vec<UnitBox> = boxes
+            .iter()
+            .map(|unit_box| UnitBox::new(unit_box.id, unit_box.pos + Vector3::new(20.0, 0.0, 0.0)))
+            .collect();
+        let bvh = BVH::build(&mut boxes);
+        let moved_bvh = BVH::build(&mut moved);
+
+        assert!(bvh.intersect_bvh(&moved_bvh, &boxes, &moved).is_empty());
+        assert!(!bvh.intersect_bvh(&bvh, &boxes, &boxes).is_empty());
+    }
+
+    #[test]
+    /// Tests whether `overlapping_pairs` finds the same pairs as a linear search.
+    fn test_overlapping_pairs_equals_linear_search() {
+        let mut seed = 0;
+        for &n in &[1, 2, 10, 300] {
+            let mut boxes = create_crowded_boxes(n, &mut seed);
+            let bvh = BVH::build(&mut boxes);
+
+            let mut expected = Vec::new();
+            for (i, a) in boxes.iter().enumerate() {
+                for b in &boxes[i + 1..] {
+                    if a.aabb().intersects(&b.aabb()) {
+                        expected.push((a.id, b.id));
+                    }
+                }
+            }
+
+            let mut found: Vec<(i32, i32)> = bvh
+                .overlapping_pairs(&boxes)
+                .into_iter()
+                .map(|(a, b)| (a.id.min(b.id), a.id.max(b.id)))
+                .collect();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! This module defines proximity queries on the [`BVH`], which search for shapes by their
+//! distance to a point. The queries are answered by a best-first traversal, which always
+//! expands the node with the most promising bound next.
+//!
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bvh::best_first::{BestFirst, HeapEntry};
+use crate::bvh::BVH;
+use crate::Point3;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// A shape which knows its exact distance to a point, see [`BVH::nearest`].
+///
+/// [`BVH::nearest`]: struct.BVH.html#method.nearest
+///
+pub trait PointDistance {
+    /// Returns the squared distance from `point` to the closest point of the shape.
+    /// It must not be smaller than the squared distance from `point` to the [`AABB`]
+    /// of the shape.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn distance_squared(&self, point: &Point3) -> f32;
+}
+
+impl PointDistance for AABB {
+    fn distance_squared(&self, point: &Point3) -> f32 {
+        self.min_distance_squared(point)
+    }
+}
+
+impl BVH {
+    /// Finds the `k` leaves with the highest `score` by expanding the node with the highest
+    /// `bound` first. `bound` must return an upper bound of the `score` of all shapes
+    /// inside of the given [`AABB`]. `score` is given the index of the shape and the `bound`
+    /// of its leaf. Returns the indices of the leaf nodes and their scores, sorted by
+    /// decreasing score.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn k_best<BoundFn, ScoreFn>(
+        &self,
+        k: usize,
+        bound: BoundFn,
+        score: ScoreFn,
+    ) -> Vec<(usize, f32)>
+    where
+        BoundFn: Fn(&AABB) -> f32,
+        ScoreFn: Fn(usize, f32) -> f32,
+    {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        // The best shapes found so far. The worst of them is on top of the heap.
+        let mut results: BinaryHeap<Reverse<HeapEntry>> = BinaryHeap::with_capacity(k + 1);
+
+        // The search expands the smallest key first, so the bounds are negated.
+        let mut search = BestFirst::new(self);
+        let mut cutoff = f32::INFINITY;
+        while let Some(leaf) = search.next_leaf(self, |aabb| Some(-bound(aabb)), cutoff) {
+            results.push(Reverse(HeapEntry {
+                priority: score(leaf.shape_index, -leaf.key),
+                index: leaf.node_index,
+            }));
+            if results.len() > k {
+                results.pop();
+            }
+
+            // No node with a lower bound can contain a better shape than the ones we have.
+            if results.len() == k {
+                cutoff = -results.peek().unwrap().0.priority;
+            }
+        }
+
+        results
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(entry)| (entry.index, entry.priority))
+            .collect()
+    }
+
+    /// Returns up to `k` shapes whose [`AABB`]s reach farthest away from `point`, together
+    /// with the distance from `point` to the farthest point of their [`AABB`].
+    /// The shapes are sorted by decreasing distance.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn k_farthest<'a, Shape: Bounded>(
+        &self,
+        point: &Point3,
+        k: usize,
+        shapes: &'a [Shape],
+    ) -> Vec<(&'a Shape, f32)> {
+        self.k_best(
+            k,
+            |aabb| aabb.max_distance_squared(point),
+            |shape_index, _| shapes[shape_index].aabb().max_distance_squared(point),
+        )
+        .into_iter()
+        .map(|(node_index, distance_squared)| {
+            (
+                &shapes[self.shape_index_of(node_index)],
+                distance_squared.sqrt(),
+            )
+        })
+        .collect()
+    }
+
+    /// Returns the indices of up to `k` shapes whose [`AABB`]s are closest to `point`,
+    /// together with the distance from `point` to their [`AABB`], and the joint [`AABB`]
+    /// of these shapes. The shapes are sorted by increasing distance.
+    ///
+    /// Only the [`AABB`]s stored in the [`BVH`] are used, the shapes are never accessed.
+    /// As the [`BVH`] does not store the [`AABB`] of its root, a [`BVH`] which consists of a
+    /// single shape reports that shape at distance `0.0` with an empty [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn k_nearest_leaves(&self, point: &Point3, k: usize) -> (Vec<(usize, f32)>, AABB) {
+        let leaves = self.k_best(
+            k,
+            |aabb| -aabb.min_distance_squared(point),
+            |_, bound| bound,
+        );
+
+        let mut joint_aabb = AABB::empty();
+        let nearest = leaves
+            .into_iter()
+            .map(|(node_index, negative_distance_squared)| {
+                if node_index == 0 {
+                    return (self.shape_index_of(node_index), 0.0);
+                }
+                joint_aabb.join_mut(&self.stored_aabb(node_index));
+                (
+                    self.shape_index_of(node_index),
+                    (-negative_distance_squared).sqrt(),
+                )
+            })
+            .collect();
+        (nearest, joint_aabb)
+    }
+
+    /// Returns the shape which is closest to `point`, together with its distance to `point`,
+    /// or `None` if the [`BVH`] is empty.
+    ///
+    /// The distance of a shape is given by [`PointDistance`], while the distance to the
+    /// [`AABB`]s of the nodes is used to skip all subtrees which cannot contain a closer shape.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`PointDistance`]: trait.PointDistance.html
+    ///
+    pub fn nearest<'a, Shape: PointDistance>(
+        &self,
+        point: &Point3,
+        shapes: &'a [Shape],
+    ) -> Option<(&'a Shape, f32)> {
+        let mut best: Option<&'a Shape> = None;
+        let mut best_distance_squared = f32::INFINITY;
+
+        let key = |aabb: &AABB| Some(aabb.min_distance_squared(point));
+        let mut search = BestFirst::new(self);
+        while let Some(leaf) = search.next_leaf(self, key, best_distance_squared) {
+            let shape = &shapes[leaf.shape_index];
+            let distance_squared = shape.distance_squared(point);
+            if best.is_none() || distance_squared < best_distance_squared {
+                best_distance_squared = distance_squared;
+                best = Some(shape);
+            }
+        }
+
+        best.map(|shape| (shape, best_distance_squared.sqrt()))
+    }
+
+    /// Returns the index of the shape of the leaf with index `node_index`.
+    fn shape_index_of(&self, node_index: usize) -> usize {
+        self.nodes[node_index]
+            .shape_index()
+            .expect("the node is not a leaf")
+    }
+
+    /// Returns the [`AABB`] of the node with index `node_index`, which is stored in its
+    /// parent. Must not be called for the root node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub(crate) fn stored_aabb(&self, node_index: usize) -> AABB {
+        let parent = &self.nodes[self.nodes[node_index].parent()];
+        if parent.child_l() == node_index {
+            parent.child_l_aabb()
+        } else {
+            parent.child_r_aabb()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bvh::{PointDistance, BVH};
+    use crate::testbase::{create_n_cubes, default_bounds, next_point3, UnitBox};
+    use crate::{Point3, EPSILON};
+
+    /// The exact distance of a `UnitBox` is the distance to its center, which is never
+    /// closer than its `AABB`.
+    impl PointDistance for UnitBox {
+        fn distance_squared(&self, point: &Point3) -> f32 {
+            self.pos.distance_squared(*point)
+        }
+    }
+
+    #[test]
+    /// Tests whether `k_farthest` finds the same distances as a linear search.
+    fn test_k_farthest_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(50, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for &k in &[0, 1, 5, 100, 1000] {
+            let point = next_point3(&mut seed, &bounds);
+            let mut expected: Vec<f32> = triangles
+                .iter()
+                .map(|triangle| triangle.aabb().max_distance_squared(&point).sqrt())
+                .collect();
+            expected.sort_by(|a, b| b.partial_cmp(a).unwrap());
+            expected.truncate(k);
+
+            let found: Vec<f32> = bvh
+                .k_farthest(&point, k, &triangles)
+                .into_iter()
+                .map(|(_, distance)| distance)
+                .collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `k_nearest_leaves` finds the same distances as a linear search, and
+    /// whether the joint `AABB` contains exactly the found shapes.
+    fn test_k_nearest_leaves_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(50, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for &k in &[0, 1, 5, 100, 1000] {
+            let point = next_point3(&mut seed, &bounds);
+            let mut expected: Vec<f32> = triangles
+                .iter()
+                .map(|triangle| triangle.aabb().min_distance_squared(&point).sqrt())
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            expected.truncate(k);
+
+            let (nearest, joint_aabb) = bvh.k_nearest_leaves(&point, k);
+            let found: Vec<f32> = nearest.iter().map(|&(_, distance)| distance).collect();
+            assert_eq!(found, expected);
+
+            let expected_aabb: AABB = nearest
+                .iter()
+                .map(|&(index, _)| triangles[index].aabb())
+                .sum();
+            assert!(joint_aabb.relative_eq(&expected_aabb, EPSILON) || k == 0);
+        }
+    }
+
+    #[test]
+    /// Tests whether `nearest` finds the same distance as a linear search.
+    fn test_nearest_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut seed = 0;
+        let mut boxes: Vec<UnitBox> = (0..1000)
+            .map(|id| UnitBox::new(id, next_point3(&mut seed, &bounds)))
+            .collect();
+        let bvh = BVH::build(&mut boxes);
+
+        for _ in 0..100 {
+            let point = next_point3(&mut seed, &bounds);
+            let expected = boxes
+                .iter()
+                .map(|unit_box| unit_box.distance_squared(&point))
+                .fold(f32::INFINITY, f32::min)
+                .sqrt();
+
+            let (nearest, distance) = bvh.nearest(&point, &boxes).unwrap();
+            assert_eq!(distance, expected);
+            assert_eq!(nearest.distance_squared(&point).sqrt(), distance);
+        }
+    }
+
+    #[test]
+    /// Tests whether `nearest` handles a `BVH` with a single shape.
+    fn test_nearest_single_shape() {
+        let point = Point3::new(3.0, 4.0, 0.0);
+        let mut boxes = vec![UnitBox::new(0, Point3::new(0.0, 0.0, 0.0))];
+        let bvh = BVH::build(&mut boxes);
+        let (nearest, distance) = bvh.nearest(&point, &boxes).unwrap();
+        assert_eq!(nearest.id, 0);
+        assert_eq!(distance, 5.0);
+    }
+}
@@ -0,0 +1,211 @@
+//! This module defines cheap proxy shapes which can be used in place of the exact
+//! shapes when traversing a [`BVH`] with a coarse level of detail.
+//!
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bvh::BVH;
+use crate::ray::Ray;
+use crate::shapes::sphere_interval;
+use crate::Point3;
+
+/// A cheap stand-in for the exact geometry of a shape.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum Proxy {
+    /// The [`AABB`] of the shape itself. This proxy is free, because it is already
+    /// stored in the parent of the leaf.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Aabb,
+
+    /// A bounding sphere.
+    Sphere {
+        /// The center of the sphere.
+        center: Point3,
+        /// The radius of the sphere.
+        radius: f32,
+    },
+}
+
+impl Proxy {
+    /// Tests whether `ray` hits this [`Proxy`]. `aabb` is the [`AABB`] of the shape.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Proxy`]: enum.Proxy.html
+    ///
+    fn intersects(&self, ray: &Ray, aabb: &AABB) -> bool {
+        match *self {
+            Proxy::Aabb => ray.intersects_aabb(aabb),
+            // The interval of the ray only has to overlap the sphere, it may also lie inside
+            // of it.
+            Proxy::Sphere { center, radius } => match sphere_interval(ray, &center, radius) {
+                Some((entry, exit)) => entry <= ray.t_max && exit >= ray.t_min,
+                None => false,
+            },
+        }
+    }
+}
+
+/// Shapes which can register a [`Proxy`] for coarse queries.
+///
+/// [`Proxy`]: enum.Proxy.html
+///
+pub trait ProxyShape: Bounded {
+    /// Returns the [`Proxy`] of this shape. Defaults to the [`AABB`] of the shape.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Proxy`]: enum.Proxy.html
+    ///
+    fn proxy(&self) -> Proxy {
+        Proxy::Aabb
+    }
+}
+
+/// The level of detail of a query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum Detail {
+    /// Return all shapes whose [`AABB`] is hit, to be tested against the exact shapes
+    /// by the caller.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Exact,
+
+    /// Return only the shapes whose [`Proxy`] is hit.
+    ///
+    /// [`Proxy`]: enum.Proxy.html
+    ///
+    Coarse,
+}
+
+impl BVH {
+    /// Traverses the [`BVH`] with the given level of detail.
+    /// With [`Detail::Exact`] this is the same as [`BVH::traverse`]. With [`Detail::Coarse`]
+    /// the shapes whose [`AABB`] is hit are additionally tested against their [`Proxy`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::traverse`]: struct.BVH.html#method.traverse
+    /// [`Detail::Coarse`]: enum.Detail.html#variant.Coarse
+    /// [`Detail::Exact`]: enum.Detail.html#variant.Exact
+    /// [`Proxy`]: enum.Proxy.html
+    ///
+    pub fn traverse_detail<'a, Shape: ProxyShape>(
+        &'a self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+        detail: Detail,
+    ) -> Vec<&'a Shape> {
+        self.traverse_indices(ray)
+            .iter()
+            .map(|index| &shapes[*index])
+            .filter(|shape| match detail {
+                Detail::Exact => true,
+                Detail::Coarse => match shape.proxy() {
+                    // The `AABB` of the shape has already been hit during traversal.
+                    Proxy::Aabb => true,
+                    proxy => proxy.intersects(ray, &shape.aabb()),
+                },
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{Detail, Proxy, ProxyShape, BVH};
+    use crate::ray::Ray;
+    use crate::testbase::{generate_aligned_boxes, UnitBox};
+    use crate::{Point3, Vector3};
+
+    /// A `UnitBox` with a small bounding sphere as its proxy.
+    struct SphereProxyBox(UnitBox);
+
+    impl Bounded for SphereProxyBox {
+        fn aabb(&self) -> AABB {
+            self.0.aabb()
+        }
+    }
+
+    impl BHShape for SphereProxyBox {
+        fn set_bh_node_index(&mut self, index: usize) {
+            self.0.set_bh_node_index(index);
+        }
+
+        fn bh_node_index(&self) -> usize {
+            self.0.bh_node_index()
+        }
+    }
+
+    impl ProxyShape for SphereProxyBox {
+        fn proxy(&self) -> Proxy {
+            Proxy::Sphere {
+                center: self.0.pos,
+                radius: 0.25,
+            }
+        }
+    }
+
+    impl ProxyShape for UnitBox {}
+
+    #[test]
+    /// Tests whether coarse traversal only returns shapes whose proxy is hit.
+    fn test_traverse_coarse_sphere() {
+        let mut shapes: Vec<_> = generate_aligned_boxes()
+            .into_iter()
+            .map(SphereProxyBox)
+            .collect();
+        let bvh = BVH::build(&mut shapes);
+
+        // This ray hits the `AABB`s of all boxes, but none of the spheres.
+        let ray = Ray::new(Point3::new(-1000.0, 0.4, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.traverse_detail(&ray, &shapes, Detail::Exact).len(), 21);
+        assert!(bvh
+            .traverse_detail(&ray, &shapes, Detail::Coarse)
+            .is_empty());
+
+        // This ray hits all spheres.
+        let ray = Ray::new(Point3::new(-1000.0, 0.1, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(bvh.traverse_detail(&ray, &shapes, Detail::Coarse).len(), 21);
+    }
+
+    #[test]
+    /// Tests whether coarse traversal with `AABB` proxies equals exact traversal.
+    fn test_traverse_coarse_aabb() {
+        let mut shapes = generate_aligned_boxes();
+        let bvh = BVH::build(&mut shapes);
+
+        let ray = Ray::new(Point3::new(6.0, 0.5, 0.0), Vector3::new(-2.0, -1.0, 0.0));
+        let exact = bvh.traverse_detail(&ray, &shapes, Detail::Exact);
+        let coarse = bvh.traverse_detail(&ray, &shapes, Detail::Coarse);
+        assert_eq!(exact.len(), 3);
+        assert_eq!(coarse.len(), 3);
+    }
+
+    #[test]
+    /// Tests whether coarse traversal returns a shape whose sphere proxy contains the whole
+    /// interval of the ray.
+    fn test_traverse_coarse_sphere_inside() {
+        let mut shapes: Vec<_> = generate_aligned_boxes()
+            .into_iter()
+            .map(SphereProxyBox)
+            .collect();
+        let bvh = BVH::build(&mut shapes);
+
+        let ray = Ray::with_interval(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            0.0,
+            0.1,
+        );
+        let coarse = bvh.traverse_detail(&ray, &shapes, Detail::Coarse);
+        assert_eq!(coarse.len(), 1);
+        assert_eq!(coarse[0].0.id, 0);
+    }
+}
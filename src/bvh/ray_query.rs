@@ -0,0 +1,281 @@
+//! This module defines ray queries on the [`BVH`] which intersect the actual shapes
+//! instead of only their [`AABB`]s.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::AABB;
+use crate::bvh::best_first::BestFirst;
+use crate::bvh::{BVHNode, BVH};
+use crate::ray::{Intersectable, Intersection, Ray};
+
+impl BVH {
+    /// Returns the shape which is hit first by `ray`, together with its [`Intersection`],
+    /// or `None` if no shape is hit.
+    ///
+    /// The nodes are visited in order of the distance at which the ray enters them, and the
+    /// traversal stops once the ray enters all remaining nodes behind the closest
+    /// intersection found so far.
+    ///
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn nearest_hit<'a, Shape: Intersectable>(
+        &self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+    ) -> Option<(&'a Shape, Intersection)> {
+        self.nearest_hit_with(ray, |shape_index| shapes[shape_index].intersect(ray))
+            .map(|(shape_index, intersection)| (&shapes[shape_index], intersection))
+    }
+
+    /// Like [`BVH::nearest_hit`], but intersects the shapes with `intersect`, which is given
+    /// the index of a shape. Returns the index of the shape which is hit first.
+    ///
+    /// [`BVH::nearest_hit`]: struct.BVH.html#method.nearest_hit
+    ///
+    pub(crate) fn nearest_hit_with<F>(
+        &self,
+        ray: &Ray,
+        mut intersect: F,
+    ) -> Option<(usize, Intersection)>
+    where
+        F: FnMut(usize) -> Option<Intersection>,
+    {
+        let mut best: Option<(usize, Intersection)> = None;
+        let mut best_distance = f32::INFINITY;
+
+        let key = |aabb: &_| {
+            ray.intersection_slab(aabb)
+                .map(|(entry_distance, _)| entry_distance)
+        };
+        let mut search = BestFirst::new(self);
+        while let Some(leaf) = search.next_leaf(self, key, best_distance) {
+            if let Some(intersection) = intersect(leaf.shape_index) {
+                if intersection.distance < best_distance {
+                    best_distance = intersection.distance;
+                    best = Some((leaf.shape_index, intersection));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Returns `true` if `ray` hits any shape closer than `max_distance`.
+    /// Stops traversing the [`BVH`] as soon as the first hit is found, which makes this
+    /// a lot cheaper than [`BVH::nearest_hit`] for shadow rays.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::nearest_hit`]: struct.BVH.html#method.nearest_hit
+    ///
+    pub fn is_occluded<Shape: Intersectable>(
+        &self,
+        ray: &Ray,
+        shapes: &[Shape],
+        max_distance: f32,
+    ) -> bool {
+        self.any_hit(ray, shapes, max_distance).is_some()
+    }
+
+    /// Returns any shape which is hit by `ray` closer than `max_distance`, together with
+    /// its [`Intersection`], or `None` if no shape is hit. Like [`BVH::is_occluded`], the
+    /// traversal stops at the first hit, which is not necessarily the closest one.
+    ///
+    /// [`BVH::is_occluded`]: struct.BVH.html#method.is_occluded
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn any_hit<'a, Shape: Intersectable>(
+        &self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+        max_distance: f32,
+    ) -> Option<(&'a Shape, Intersection)> {
+        let mut hit = None;
+        self.find_occluder(ray, max_distance, |shape_index| {
+            let shape = &shapes[shape_index];
+            match shape.intersect(ray) {
+                Some(intersection) if intersection.distance < max_distance => {
+                    hit = Some((shape, intersection));
+                    true
+                }
+                _ => false,
+            }
+        });
+        hit
+    }
+
+    /// Conservative variant of [`BVH::is_occluded`], which returns `true` if `ray` enters
+    /// the [`AABB`] of any shape closer than `max_distance`. The shapes are never tested.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::is_occluded`]: struct.BVH.html#method.is_occluded
+    ///
+    pub fn is_occluded_conservative(&self, ray: &Ray, max_distance: f32) -> bool {
+        self.find_occluder(ray, max_distance, |_| true)
+    }
+
+    /// Traverses all leaves whose [`AABB`] is entered by `ray` closer than `max_distance`,
+    /// until `is_hit` returns `true` for one of their shape indices.
+    ///
+    /// The root is always visited, because the [`BVH`] does not store its [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn find_occluder<F>(&self, ray: &Ray, max_distance: f32, mut is_hit: F) -> bool
+    where
+        F: FnMut(usize) -> bool,
+    {
+        if self.nodes.is_empty() {
+            return false;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    // Push the farther child first, so that the nearer child is visited first.
+                    let [near, far] = BVH::order_children(
+                        ray,
+                        (child_l_aabb, child_l_index as usize),
+                        (child_r_aabb, child_r_index as usize),
+                    );
+                    for &(child_aabb, child_index) in &[far, near] {
+                        match ray.intersection_slab(child_aabb) {
+                            Some((entry_distance, _)) if entry_distance < max_distance => {
+                                stack.push(child_index);
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    if is_hit(shape_index as usize) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Orders two children of a node front to back along `ray`, using only the signs of its
+    /// direction. The children are compared along the axis on which their centers differ
+    /// most, which is usually the axis along which their parent was split.
+    fn order_children<'a>(
+        ray: &Ray,
+        left: (&'a AABB, usize),
+        right: (&'a AABB, usize),
+    ) -> [(&'a AABB, usize); 2] {
+        let offset = right.0.center() - left.0.center();
+        let distance = offset.abs();
+        let axis = if distance.x > distance.y && distance.x > distance.z {
+            0
+        } else if distance.y > distance.z {
+            1
+        } else {
+            2
+        };
+        if (offset[axis] < 0.0) != ray.is_negative(axis) {
+            [right, left]
+        } else {
+            [left, right]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::bvh::BVH;
+    use crate::ray::{Intersectable, Ray};
+    use crate::testbase::{create_n_cubes, create_ray, default_bounds};
+    use crate::Point3;
+
+    #[test]
+    /// Tests whether `nearest_hit` finds the same closest intersection as a linear search.
+    fn test_nearest_hit_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let ray = create_ray(&mut seed, &bounds);
+            let expected = triangles
+                .iter()
+                .filter_map(|triangle| triangle.intersect(&ray))
+                .map(|intersection| intersection.distance)
+                .fold(f32::INFINITY, f32::min);
+
+            match bvh.nearest_hit(&ray, &triangles) {
+                Some((triangle, intersection)) => {
+                    assert_eq!(intersection.distance, expected);
+                    assert_eq!(
+                        triangle.intersect(&ray).unwrap().distance,
+                        intersection.distance
+                    );
+                }
+                None => assert_eq!(expected, f32::INFINITY),
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `is_occluded` agrees with `nearest_hit`, whether `any_hit` returns a
+    /// hit in front of `max_distance`, and whether the conservative variant never misses
+    /// an occluder.
+    fn test_is_occluded_agrees_with_nearest_hit() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let ray = create_ray(&mut seed, &bounds);
+            for &max_distance in &[1.0, 10.0, 100.0, f32::INFINITY] {
+                let expected = bvh
+                    .nearest_hit(&ray, &triangles)
+                    .is_some_and(|(_, hit)| hit.distance < max_distance);
+                assert_eq!(bvh.is_occluded(&ray, &triangles, max_distance), expected);
+                if let Some((triangle, hit)) = bvh.any_hit(&ray, &triangles, max_distance) {
+                    assert!(hit.distance < max_distance);
+                    assert_eq!(triangle.intersect(&ray).unwrap().distance, hit.distance);
+                }
+                if expected {
+                    assert!(bvh.is_occluded_conservative(&ray, max_distance));
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `order_children` puts the child first which the ray reaches first.
+    fn test_order_children() {
+        let left = AABB::with_bounds(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 5.0, 5.0));
+        let right = AABB::with_bounds(Point3::new(2.0, 0.0, 0.0), Point3::new(3.0, 5.0, 5.0));
+
+        // The rays start outside of both children and point through both of them.
+        for &(origin, target, first) in &[
+            (Point3::new(-5.0, 2.5, 2.5), Point3::new(10.0, 2.0, 3.0), 0),
+            (Point3::new(10.0, 2.5, 2.5), Point3::new(-5.0, 2.0, 3.0), 1),
+            (Point3::new(-5.0, 4.0, 4.0), Point3::new(10.0, 1.0, 1.0), 0),
+            (Point3::new(10.0, 1.0, 1.0), Point3::new(-5.0, 4.0, 4.0), 1),
+        ] {
+            let ray = Ray::new(origin, target - origin);
+            let [near, far] = BVH::order_children(&ray, (&left, 0), (&right, 1));
+            assert_eq!((near.1, far.1), (first, 1 - first));
+            assert!(
+                ray.intersection_slab(near.0).unwrap().0 < ray.intersection_slab(far.0).unwrap().0
+            );
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! This module defines [`RefinableShape`], which lets curved or elongated shapes provide
+//! tighter bounds of their parts, and [`BVH::build_refined`], which builds a [`BVH`] over
+//! these parts instead of over one loose [`AABB`] per shape.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: struct.BVH.html
+//! [`BVH::build_refined`]: struct.BVH.html#method.build_refined
+//! [`RefinableShape`]: trait.RefinableShape.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{BVHNode, BVH};
+
+/// Shapes which can be split into parts with tighter [`AABB`]s on demand, e.g. a Bézier
+/// patch or a hair curve which is split into sub-patches or sub-segments.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+pub trait RefinableShape: BHShape {
+    /// A part of the shape, e.g. an interval of the parameter of a curve.
+    type Part;
+
+    /// Returns the part which is the whole shape.
+    fn whole(&self) -> Self::Part;
+
+    /// Returns the [`AABB`] of `part`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn part_aabb(&self, part: &Self::Part) -> AABB;
+
+    /// Splits `part` into smaller parts which together cover it, or returns an empty
+    /// `Vec` if `part` cannot be split any further.
+    fn split(&self, part: &Self::Part) -> Vec<Self::Part>;
+}
+
+/// A part of a shape while a [`BVH`] is built by [`BVH::build_refined`].
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVH::build_refined`]: struct.BVH.html#method.build_refined
+///
+struct RefinedPart {
+    /// The index of the shape.
+    shape_index: usize,
+
+    /// The [`AABB`] of the part.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    aabb: AABB,
+
+    /// The index of the node of the part.
+    node_index: usize,
+}
+
+impl Bounded for RefinedPart {
+    fn aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl BHShape for RefinedPart {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl BVH {
+    /// Creates a new [`BVH`] over the parts of the `shapes`. Each shape is split
+    /// recursively by [`RefinableShape::split`], up to `max_depth` times, as long as the
+    /// [`AABB`]s of the smaller parts have less surface area together than the [`AABB`] of
+    /// the part they were split from. Every part becomes a leaf of its own, which stores the
+    /// index of its shape.
+    ///
+    /// A shape can thus be found in several leaves, and a traversal may return it more than
+    /// once. The node index of a shape is the index of its first leaf. A refined [`BVH`]
+    /// should only be queried, and not be updated or optimized, which expect one leaf per
+    /// shape.
+    ///
+    /// # Panics
+    /// Panics if `shapes` is empty.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`RefinableShape::split`]: trait.RefinableShape.html#tymethod.split
+    ///
+    pub fn build_refined<Shape: RefinableShape>(shapes: &mut [Shape], max_depth: u32) -> BVH {
+        let mut parts = Vec::with_capacity(shapes.len());
+        for (shape_index, shape) in shapes.iter().enumerate() {
+            let whole = shape.whole();
+            let aabb = shape.part_aabb(&whole);
+            refine(shape, shape_index, whole, aabb, max_depth, &mut parts);
+        }
+
+        let mut bvh = BVH::build(&mut parts);
+        for shape in shapes.iter_mut() {
+            shape.set_bh_node_index(usize::MAX);
+        }
+        for node_index in 0..bvh.nodes.len() {
+            if let BVHNode::Leaf {
+                ref mut shape_index,
+                ..
+            } = bvh.nodes[node_index]
+            {
+                let part_shape_index = parts[*shape_index as usize].shape_index;
+                *shape_index = part_shape_index as u32;
+                let shape = &mut shapes[part_shape_index];
+                if shape.bh_node_index() == usize::MAX {
+                    shape.set_bh_node_index(node_index);
+                }
+            }
+        }
+        bvh
+    }
+}
+
+/// Appends the refined parts of `part` of the shape at `shape_index` to `parts`.
+fn refine<Shape: RefinableShape>(
+    shape: &Shape,
+    shape_index: usize,
+    part: Shape::Part,
+    aabb: AABB,
+    max_depth: u32,
+    parts: &mut Vec<RefinedPart>,
+) {
+    if max_depth > 0 {
+        let children: Vec<(Shape::Part, AABB)> = shape
+            .split(&part)
+            .into_iter()
+            .map(|child| {
+                let child_aabb = shape.part_aabb(&child);
+                (child, child_aabb)
+            })
+            .collect();
+        let child_area: f32 = children.iter().map(|(_, aabb)| aabb.surface_area()).sum();
+        if !children.is_empty() && child_area < aabb.surface_area() {
+            for (child, child_aabb) in children {
+                refine(shape, shape_index, child, child_aabb, max_depth - 1, parts);
+            }
+            return;
+        }
+    }
+
+    parts.push(RefinedPart {
+        shape_index,
+        aabb,
+        node_index: 0,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{RefinableShape, BVH};
+    use crate::ray::Ray;
+    use crate::shapes::Segment;
+    use crate::{Point3, Vector3};
+
+    /// Returns the total surface area of the `AABB`s of the leaves of `bvh`.
+    fn leaf_area(bvh: &BVH) -> f32 {
+        bvh.nodes
+            .iter()
+            .filter(|node| node.shape_index().is_none())
+            .map(|node| {
+                let (l, r) = (node.child_l(), node.child_r());
+                let mut area = 0.0;
+                if bvh.nodes[l].shape_index().is_some() {
+                    area += node.child_l_aabb().surface_area();
+                }
+                if bvh.nodes[r].shape_index().is_some() {
+                    area += node.child_r_aabb().surface_area();
+                }
+                area
+            })
+            .sum()
+    }
+
+    #[test]
+    /// Tests whether diagonal `Segment`s are split into parts with tighter `AABB`s, and
+    /// whether a `Ray` which only hits their loose `AABB`s misses the refined `BVH`.
+    fn test_build_refined() {
+        let mut segments: Vec<Segment> = (0..10)
+            .map(|i| {
+                let offset = Vector3::new(i as f32 * 20.0, 0.0, 0.0);
+                Segment::new(
+                    Point3::new(0.0, 0.0, 0.0) + offset,
+                    Point3::new(10.0, 10.0, 10.0) + offset,
+                )
+            })
+            .collect();
+        let loose = BVH::build(&mut segments);
+        let refined = BVH::build_refined(&mut segments, 3);
+        assert_eq!(refined.nodes.len(), 2 * 10 * 8 - 1);
+        assert!(leaf_area(&refined) < leaf_area(&loose) / 4.0);
+        for (index, segment) in segments.iter().enumerate() {
+            let node = &refined.nodes[segment.bh_node_index()];
+            assert_eq!(node.shape_index(), Some(index));
+        }
+
+        // A ray through the corner of the `AABB` of the first segment.
+        let ray = Ray::new(Point3::new(9.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(loose.traverse_indices(&ray), vec![0]);
+        assert!(refined.traverse_indices(&ray).is_empty());
+
+        // A ray through the first segment hits some of its parts.
+        let ray = Ray::new(Point3::new(5.0, 5.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let hits = refined.traverse_indices(&ray);
+        assert!(!hits.is_empty() && hits.iter().all(|&index| index == 0));
+    }
+
+    #[test]
+    /// Tests whether parts are not split when that does not make their `AABB`s tighter.
+    fn test_build_refined_keeps_tight_parts() {
+        // The padding of the `AABB`s is the same for all parts, as it depends on `z`.
+        let mut segments = vec![Segment::new(
+            Point3::new(0.0, -10.0, 20.0),
+            Point3::new(0.0, 10.0, 20.0),
+        )];
+        let refined = BVH::build_refined(&mut segments, 5);
+        assert_eq!(refined.nodes.len(), 1);
+        let whole = segments[0].part_aabb(&segments[0].whole());
+        let aabb: AABB = segments[0].aabb();
+        assert_eq!((whole.min, whole.max), (aabb.min, aabb.max));
+    }
+}
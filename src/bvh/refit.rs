@@ -0,0 +1,170 @@
+//! This module defines functions to refit all [`AABB`]s of a [`BVH`] to moved shapes.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: struct.BVH.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bvh::{BVHNode, BVH};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+impl BVH {
+    /// Recomputes the [`AABB`]s of all nodes bottom-up from the current [`AABB`]s of the
+    /// `shapes`, e.g. after the vertices of a deformable mesh moved. The structure of the
+    /// tree is not changed. To update a single shape, use
+    /// [`BVH::update_shape`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::update_shape`]: struct.BVH.html#method.update_shape
+    ///
+    pub fn refit<Shape: Bounded>(&mut self, shapes: &[Shape]) {
+        if !self.nodes.is_empty() {
+            self.refit_subtree(0, shapes);
+        }
+    }
+
+    /// Refits the subtree at `node_index` and returns its [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn refit_subtree<Shape: Bounded>(&mut self, node_index: usize, shapes: &[Shape]) -> AABB {
+        match self.nodes[node_index] {
+            BVHNode::Leaf { shape_index, .. } => shapes[shape_index as usize].aabb(),
+            BVHNode::Node {
+                child_l_index,
+                child_r_index,
+                ..
+            } => {
+                let child_l_aabb = self.refit_subtree(child_l_index as usize, shapes);
+                let child_r_aabb = self.refit_subtree(child_r_index as usize, shapes);
+                *self.nodes[node_index].child_l_aabb_mut() = child_l_aabb;
+                *self.nodes[node_index].child_r_aabb_mut() = child_r_aabb;
+                child_l_aabb.join(&child_r_aabb)
+            }
+        }
+    }
+
+    /// Parallel variant of [`BVH::refit`]. The nodes are grouped by their depth, and the
+    /// [`AABB`]s of each level are computed on the threads of the global `rayon` thread
+    /// pool, starting with the deepest level. Produces the same [`AABB`]s as [`BVH::refit`].
+    ///
+    /// Only available with the `rayon` feature.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::refit`]: struct.BVH.html#method.refit
+    ///
+    #[cfg(feature = "rayon")]
+    pub fn refit_parallel<Shape: Bounded + Sync>(&mut self, shapes: &[Shape]) {
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            let depth = node.depth() as usize;
+            if levels.len() <= depth {
+                levels.resize(depth + 1, Vec::new());
+            }
+            levels[depth].push(index);
+        }
+
+        // The `AABB` of every node, which its parent stores as the `AABB` of its child.
+        let mut aabbs = vec![AABB::empty(); self.nodes.len()];
+        for level in levels.iter().rev() {
+            let nodes = &self.nodes;
+            let children = &aabbs;
+            let level_aabbs: Vec<AABB> = level
+                .par_iter()
+                .map(|&index| match nodes[index] {
+                    BVHNode::Leaf { shape_index, .. } => shapes[shape_index as usize].aabb(),
+                    BVHNode::Node {
+                        child_l_index,
+                        child_r_index,
+                        ..
+                    } => children[child_l_index as usize].join(&children[child_r_index as usize]),
+                })
+                .collect();
+            for (&index, aabb) in level.iter().zip(level_aabbs) {
+                aabbs[index] = aabb;
+            }
+        }
+
+        self.nodes.par_iter_mut().for_each(|node| {
+            if let BVHNode::Node {
+                child_l_index,
+                child_l_aabb,
+                child_r_index,
+                child_r_aabb,
+                ..
+            } = node
+            {
+                *child_l_aabb = aabbs[*child_l_index as usize];
+                *child_r_aabb = aabbs[*child_r_index as usize];
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::Bounded;
+    use crate::bvh::{BVHNode, BVH};
+    use crate::testbase::{generate_aligned_boxes, UnitBox};
+    use crate::Vector3;
+
+    /// Builds a `BVH` of aligned boxes, and moves the boxes afterwards.
+    fn build_moved_bvh() -> (Vec<UnitBox>, BVH) {
+        let mut shapes = generate_aligned_boxes();
+        let bvh = BVH::build(&mut shapes);
+        for shape in shapes.iter_mut() {
+            shape.pos += Vector3::new(0.0, shape.id as f32 * shape.id as f32, 1.0);
+        }
+        (shapes, bvh)
+    }
+
+    /// Asserts that the `AABB` which a parent stores for each leaf is the `AABB` of its
+    /// shape, and that the `BVH` is consistent and tight.
+    fn assert_refitted(bvh: &BVH, shapes: &[UnitBox]) {
+        for (index, node) in bvh.nodes.iter().enumerate().skip(1) {
+            if let BVHNode::Leaf { shape_index, .. } = *node {
+                let parent = &bvh.nodes[node.parent()];
+                let stored = if parent.child_l() == index {
+                    parent.child_l_aabb()
+                } else {
+                    parent.child_r_aabb()
+                };
+                let aabb = shapes[shape_index as usize].aabb();
+                assert_eq!((stored.min, stored.max), (aabb.min, aabb.max));
+            }
+        }
+        bvh.assert_consistent(shapes);
+        bvh.assert_tight(shapes);
+    }
+
+    #[test]
+    /// Tests whether `refit` fits all `AABB`s to the moved shapes.
+    fn test_refit() {
+        let (shapes, mut bvh) = build_moved_bvh();
+        bvh.refit(&shapes);
+        assert_refitted(&bvh, &shapes);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    /// Tests whether `refit_parallel` computes the same `AABB`s as `refit`.
+    fn test_refit_parallel_equals_refit() {
+        let (shapes, mut bvh) = build_moved_bvh();
+        let mut expected = bvh.clone();
+        expected.refit(&shapes);
+        bvh.refit_parallel(&shapes);
+        assert_refitted(&bvh, &shapes);
+
+        for (node, expected) in bvh.nodes.iter().zip(expected.nodes.iter()) {
+            if let BVHNode::Node { .. } = node {
+                let (l, r) = (node.child_l_aabb(), node.child_r_aabb());
+                let (el, er) = (expected.child_l_aabb(), expected.child_r_aabb());
+                assert_eq!(
+                    (l.min, l.max, r.min, r.max),
+                    (el.min, el.max, er.min, er.max)
+                );
+            }
+        }
+    }
+}
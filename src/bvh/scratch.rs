@@ -0,0 +1,285 @@
+//! This module defines [`BuildArena`], a handle to the memory from which a [`BVH`] is built,
+//! and [`BuildScratch`], which recycles the memory of successive builds.
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`BuildArena`]: trait.BuildArena.html
+//! [`BuildScratch`]: struct.BuildScratch.html
+//!
+
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{BVHNode, BVH, MAX_SHAPES};
+
+/// A handle to the memory pool from which [`BVH::build_in`] takes its node and index buffers,
+/// and to which it returns the index buffers when it is done. The node buffer ends up in the
+/// [`BVH`], and can be returned with [`free_nodes`] once the [`BVH`] is no longer needed.
+///
+/// Implementing this trait for a pool owned by an engine keeps the buffers of all builds in
+/// that pool, instead of allocating and freeing them per build. The buffers are still `Vec`s
+/// of the global allocator, because custom allocators for `Vec` are not stable yet, so an
+/// arena can reuse buffers but not place them in its own memory.
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVH::build_in`]: struct.BVH.html#method.build_in
+/// [`free_nodes`]: trait.BuildArena.html#tymethod.free_nodes
+///
+pub trait BuildArena {
+    /// Returns an empty node buffer.
+    fn alloc_nodes(&mut self) -> Vec<BVHNode>;
+
+    /// Takes back a node buffer which is no longer needed. The buffer may contain nodes.
+    fn free_nodes(&mut self, nodes: Vec<BVHNode>);
+
+    /// Returns an empty index buffer.
+    fn alloc_indices(&mut self) -> Vec<usize>;
+
+    /// Takes back an index buffer which is no longer needed. The buffer may contain indices.
+    fn free_indices(&mut self, indices: Vec<usize>);
+}
+
+/// The temporary buffers of [`BVH::build_with_scratch`] and the node buffers of recycled
+/// [`BVH`]s. Passing the same [`BuildScratch`] to successive builds, even of different
+/// meshes, allocates the buffers only once instead of once per build.
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVH::build_with_scratch`]: struct.BVH.html#method.build_with_scratch
+/// [`BuildScratch`]: struct.BuildScratch.html
+///
+#[derive(Debug, Default)]
+pub struct BuildScratch {
+    /// The empty index buffers of the shapes and of the partitions.
+    indices: Vec<Vec<usize>>,
+
+    /// The empty node buffers of recycled [`BVH`]s.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    ///
+    nodes: Vec<Vec<BVHNode>>,
+}
+
+impl BuildScratch {
+    /// Creates an empty [`BuildScratch`].
+    ///
+    /// [`BuildScratch`]: struct.BuildScratch.html
+    ///
+    pub fn new() -> BuildScratch {
+        BuildScratch::default()
+    }
+
+    /// Keeps the node buffer of `bvh`, which is no longer needed, for a later build.
+    pub fn recycle(&mut self, bvh: BVH) {
+        self.free_nodes(bvh.nodes);
+    }
+}
+
+impl BuildArena for BuildScratch {
+    fn alloc_nodes(&mut self) -> Vec<BVHNode> {
+        self.nodes.pop().unwrap_or_default()
+    }
+
+    fn free_nodes(&mut self, mut nodes: Vec<BVHNode>) {
+        nodes.clear();
+        self.nodes.push(nodes);
+    }
+
+    fn alloc_indices(&mut self) -> Vec<usize> {
+        self.indices.pop().unwrap_or_default()
+    }
+
+    fn free_indices(&mut self, mut indices: Vec<usize>) {
+        indices.clear();
+        self.indices.push(indices);
+    }
+}
+
+impl BVH {
+    /// Creates a new [`BVH`] from the `shapes` slice like [`BVH::build`], but takes its
+    /// temporary buffers, and the node buffer if one was recycled, from `scratch`.
+    ///
+    /// # Panics
+    /// Panics if there are more than [`MAX_SHAPES`] shapes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::{BuildScratch, BVH};
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes(n: usize) -> Vec<UnitBox> {
+    /// #     (0..n)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut scratch = BuildScratch::new();
+    /// for n in 1..100 {
+    ///     let mut shapes = create_bhshapes(n);
+    ///     let bvh = BVH::build_with_scratch(&mut shapes, &mut scratch);
+    ///     assert_eq!(bvh.nodes.len(), 2 * n - 1);
+    ///     scratch.recycle(bvh);
+    /// }
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::build`]: struct.BVH.html#method.build
+    /// [`MAX_SHAPES`]: constant.MAX_SHAPES.html
+    ///
+    pub fn build_with_scratch<Shape: BHShape>(
+        shapes: &mut [Shape],
+        scratch: &mut BuildScratch,
+    ) -> BVH {
+        BVH::build_in(shapes, scratch)
+    }
+
+    /// Creates a new [`BVH`] from the `shapes` slice like [`BVH::build`], but takes its node
+    /// and index buffers from `arena`, and returns the index buffers to it.
+    ///
+    /// # Panics
+    /// Panics if there are more than [`MAX_SHAPES`] shapes.
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::build`]: struct.BVH.html#method.build
+    /// [`MAX_SHAPES`]: constant.MAX_SHAPES.html
+    ///
+    pub fn build_in<Shape: BHShape, A: BuildArena + ?Sized>(
+        shapes: &mut [Shape],
+        arena: &mut A,
+    ) -> BVH {
+        assert!(
+            shapes.len() <= MAX_SHAPES,
+            "cannot build a BVH of {} shapes, the maximum is {}",
+            shapes.len(),
+            MAX_SHAPES
+        );
+        let mut indices = arena.alloc_indices();
+        indices.extend(0..shapes.len());
+        let mut nodes = arena.alloc_nodes();
+        nodes.reserve(shapes.len() * 2);
+        // A `BVH` without shapes has no nodes.
+        if !shapes.is_empty() {
+            BVHNode::build_pooled(shapes, &indices, &mut nodes, 0, 0, arena);
+        }
+        arena.free_indices(indices);
+        BVH { nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::{BVHNode, BuildArena, BuildScratch, BVH};
+    use crate::testbase::{create_n_cubes, create_ray, default_bounds};
+
+    /// A `BuildArena` which counts the buffers that it had to allocate, and the index
+    /// buffers which are not returned yet.
+    #[derive(Default)]
+    struct CountingArena {
+        scratch: BuildScratch,
+        fresh_buffers: usize,
+        lent_indices: usize,
+    }
+
+    impl BuildArena for CountingArena {
+        fn alloc_nodes(&mut self) -> Vec<BVHNode> {
+            if self.scratch.nodes.is_empty() {
+                self.fresh_buffers += 1;
+            }
+            self.scratch.alloc_nodes()
+        }
+
+        fn free_nodes(&mut self, nodes: Vec<BVHNode>) {
+            self.scratch.free_nodes(nodes);
+        }
+
+        fn alloc_indices(&mut self) -> Vec<usize> {
+            if self.scratch.indices.is_empty() {
+                self.fresh_buffers += 1;
+            }
+            self.lent_indices += 1;
+            self.scratch.alloc_indices()
+        }
+
+        fn free_indices(&mut self, indices: Vec<usize>) {
+            self.lent_indices -= 1;
+            self.scratch.free_indices(indices);
+        }
+    }
+
+    #[test]
+    /// Tests whether building with a reused `BuildScratch` yields the same `BVH`s as
+    /// `BVH::build`, and whether the node buffers are recycled.
+    fn test_build_with_scratch_equals_build() {
+        let bounds = default_bounds();
+        let mut scratch = BuildScratch::new();
+        for &n in &[100, 10, 1, 50] {
+            let mut triangles = create_n_cubes(n, &bounds);
+            let expected = BVH::build(&mut triangles);
+            let bvh = BVH::build_with_scratch(&mut triangles, &mut scratch);
+            assert_eq!(bvh.nodes, expected.nodes);
+            bvh.assert_consistent(&triangles);
+
+            let mut seed = 0;
+            for _ in 0..100 {
+                let ray = create_ray(&mut seed, &bounds);
+                assert_eq!(bvh.traverse_indices(&ray), expected.traverse_indices(&ray));
+            }
+
+            let capacity = bvh.nodes.capacity();
+            scratch.recycle(bvh);
+            assert_eq!(scratch.nodes.len(), 1);
+            assert_eq!(scratch.nodes[0].capacity(), capacity);
+        }
+    }
+
+    #[test]
+    /// Tests whether building a `BVH` without shapes with a `BuildScratch` yields no nodes.
+    fn test_build_with_scratch_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let mut scratch = BuildScratch::new();
+        let bvh = BVH::build_with_scratch(&mut triangles, &mut scratch);
+        assert!(bvh.nodes.is_empty());
+    }
+
+    #[test]
+    /// Tests whether `BVH::build_in` yields the same `BVH` as `BVH::build`, returns every
+    /// index buffer to the arena, and takes all buffers of a repeated build from the arena.
+    fn test_build_in_reuses_arena() {
+        let mut triangles = create_n_cubes(100, &default_bounds());
+        let expected = BVH::build(&mut triangles);
+        let mut arena = CountingArena::default();
+
+        let bvh = BVH::build_in(&mut triangles, &mut arena);
+        assert_eq!(bvh.nodes, expected.nodes);
+        assert_eq!(arena.lent_indices, 0);
+        assert!(arena.fresh_buffers > 0);
+        arena.free_nodes(bvh.nodes);
+
+        arena.fresh_buffers = 0;
+        let bvh = BVH::build_in(&mut triangles, &mut arena);
+        assert_eq!(bvh.nodes, expected.nodes);
+        assert_eq!(arena.lent_indices, 0);
+        assert_eq!(arena.fresh_buffers, 0);
+    }
+}
@@ -0,0 +1,180 @@
+//! This module defines [`TraversalStats`], which count the work done by a traversal of a
+//! [`BVH`]. They are meant for comparing builders and finding expensive parts of a scene.
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`TraversalStats`]: struct.TraversalStats.html
+//!
+
+use crate::aabb::Bounded;
+use crate::bvh::{BVHNode, BVH};
+use crate::ray::Ray;
+use std::ops::{Add, AddAssign};
+
+/// Counters for the work done by one or more traversals of a [`BVH`].
+///
+/// [`BVH`]: struct.BVH.html
+///
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraversalStats {
+    /// The number of nodes which were visited, including leaves.
+    pub nodes_visited: usize,
+
+    /// The number of [`AABB`]s which were tested against the query.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub aabb_tests: usize,
+
+    /// The number of leaves which were visited.
+    pub leaves_visited: usize,
+}
+
+impl Add for TraversalStats {
+    type Output = TraversalStats;
+
+    fn add(self, other: TraversalStats) -> TraversalStats {
+        TraversalStats {
+            nodes_visited: self.nodes_visited + other.nodes_visited,
+            aabb_tests: self.aabb_tests + other.aabb_tests,
+            leaves_visited: self.leaves_visited + other.leaves_visited,
+        }
+    }
+}
+
+impl AddAssign for TraversalStats {
+    fn add_assign(&mut self, other: TraversalStats) {
+        *self = *self + other;
+    }
+}
+
+impl BVH {
+    /// Traverses the [`BVH`] like [`BVH::traverse`], and additionally returns the
+    /// [`TraversalStats`] of the traversal. The shapes are returned in the same order.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BHShape;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// # struct Sphere {
+    /// #     position: Point3,
+    /// #     radius: f32,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for Sphere {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let half_size = Vector3::new(self.radius, self.radius, self.radius);
+    /// #         let min = self.position - half_size;
+    /// #         let max = self.position + half_size;
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for Sphere {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<Sphere> {
+    /// #     let mut spheres = Vec::new();
+    /// #     for i in 0..1000u32 {
+    /// #         let position = Point3::new(i as f32, i as f32, i as f32);
+    /// #         let radius = (i % 10) as f32 + 1.0;
+    /// #         spheres.push(Sphere {
+    /// #             position: position,
+    /// #             radius: radius,
+    /// #             node_index: 0,
+    /// #         });
+    /// #     }
+    /// #     spheres
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let ray = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// let (hit_shapes, stats) = bvh.traverse_instrumented(&ray, &shapes);
+    ///
+    /// assert!(stats.leaves_visited >= hit_shapes.len());
+    /// assert!(stats.nodes_visited <= bvh.nodes.len());
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::traverse`]: struct.BVH.html#method.traverse
+    /// [`TraversalStats`]: struct.TraversalStats.html
+    ///
+    pub fn traverse_instrumented<'a, Shape: Bounded>(
+        &'a self,
+        ray: &Ray,
+        shapes: &'a [Shape],
+    ) -> (Vec<&'a Shape>, TraversalStats) {
+        let mut found = Vec::new();
+        let mut stats = TraversalStats::default();
+        if self.nodes.is_empty() {
+            return (found, stats);
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            stats.nodes_visited += 1;
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        stats.aabb_tests += 1;
+                        if ray.intersects_aabb(child_aabb) {
+                            stack.push(child_index as usize);
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    stats.leaves_visited += 1;
+                    found.push(&shapes[shape_index as usize]);
+                }
+            }
+        }
+
+        (found, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testbase::{build_cubes_bvh, check_traverse_equals_bvh};
+
+    #[test]
+    /// Tests whether `traverse_instrumented` returns the same shapes as `traverse`, and
+    /// whether its counters are consistent with each other.
+    fn test_traverse_instrumented_equals_traverse() {
+        let (triangles, bvh) = build_cubes_bvh();
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| {
+            let (found, stats) = bvh.traverse_instrumented(ray, &triangles);
+            assert_eq!(stats.leaves_visited, found.len());
+            // Every visited node but the root passed the test of its `AABB`.
+            assert!(stats.nodes_visited <= stats.aabb_tests + 1);
+            // Every visited interior node tests both of its children.
+            assert_eq!(
+                stats.aabb_tests,
+                2 * (stats.nodes_visited - stats.leaves_visited)
+            );
+            found
+        });
+    }
+}
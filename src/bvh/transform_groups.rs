@@ -0,0 +1,201 @@
+//! This module defines [`TransformGroups`], which move groups of shapes sharing a transform,
+//! like the bones of a skinned mesh or attachments, without updating every shape by hand.
+//!
+//! [`TransformGroups`]: struct.TransformGroups.html
+//!
+
+use crate::aabb::AABB;
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::BVH;
+
+use glam::Mat4;
+
+/// Shapes which belong to a group of [`TransformGroups`].
+///
+/// [`TransformGroups`]: struct.TransformGroups.html
+///
+pub trait GroupedShape: BHShape {
+    /// Returns the index of the group of this shape.
+    fn group(&self) -> usize;
+
+    /// Returns the [`AABB`] of this shape in the local space of its group.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn local_aabb(&self) -> AABB;
+}
+
+/// A set of transforms, one per group of shapes. Changing the transform of a group is O(1).
+/// The world [`AABB`]s of its shapes are only computed when the [`BVH`] is refitted.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: struct.BVH.html
+///
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransformGroups {
+    /// The transform of each group, from its local space to world space.
+    transforms: Vec<Mat4>,
+
+    /// The indices of the shapes of each group.
+    members: Vec<Vec<usize>>,
+
+    /// The groups whose transform changed since the last refit.
+    dirty_groups: Vec<usize>,
+
+    /// Whether a group is contained in `dirty_groups`.
+    is_dirty: Vec<bool>,
+}
+
+impl TransformGroups {
+    /// Creates [`TransformGroups`] with the given initial `transforms` and registers the
+    /// `shapes` with their groups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a shape refers to a group without a transform.
+    ///
+    /// [`TransformGroups`]: struct.TransformGroups.html
+    ///
+    pub fn new<Shape: GroupedShape>(transforms: Vec<Mat4>, shapes: &[Shape]) -> TransformGroups {
+        let mut members = vec![Vec::new(); transforms.len()];
+        for (shape_index, shape) in shapes.iter().enumerate() {
+            members[shape.group()].push(shape_index);
+        }
+        let group_count = transforms.len();
+        TransformGroups {
+            transforms,
+            members,
+            dirty_groups: Vec::new(),
+            is_dirty: vec![false; group_count],
+        }
+    }
+
+    /// Returns the current transform of `group`.
+    pub fn transform(&self, group: usize) -> &Mat4 {
+        &self.transforms[group]
+    }
+
+    /// Sets the transform of `group`. The [`BVH`] is updated on the next call
+    /// to [`TransformGroups::refit`].
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`TransformGroups::refit`]: struct.TransformGroups.html#method.refit
+    ///
+    pub fn set_transform(&mut self, group: usize, transform: Mat4) {
+        self.transforms[group] = transform;
+        if !self.is_dirty[group] {
+            self.is_dirty[group] = true;
+            self.dirty_groups.push(group);
+        }
+    }
+
+    /// Returns the world [`AABB`] of `shape` under the current transform of its group.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn world_aabb<Shape: GroupedShape>(&self, shape: &Shape) -> AABB {
+        shape
+            .local_aabb()
+            .transformed(&self.transforms[shape.group()])
+    }
+
+    /// Refits `bvh` to the shapes of all groups whose transform changed since the last refit.
+    /// The structure of the [`BVH`] is not changed, see [`BVH::update_shape`].
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH::update_shape`]: struct.BVH.html#method.update_shape
+    ///
+    pub fn refit<Shape: GroupedShape>(&mut self, bvh: &mut BVH, shapes: &[Shape]) {
+        for group in self.dirty_groups.drain(..) {
+            self.is_dirty[group] = false;
+            for &shape_index in &self.members[group] {
+                let shape = &shapes[shape_index];
+                let world_aabb = shape.local_aabb().transformed(&self.transforms[group]);
+                bvh.refit_shape(shape_index, &world_aabb, shapes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::bounding_hierarchy::BHShape;
+    use crate::bvh::{GroupedShape, TransformGroups, BVH};
+    use crate::ray::Ray;
+    use crate::testbase::{generate_aligned_boxes, UnitBox};
+    use crate::{Point3, Vector3};
+
+    use glam::Mat4;
+
+    /// A `UnitBox` in group `0` or `1`, depending on the sign of its position.
+    struct GroupedBox(UnitBox);
+
+    impl Bounded for GroupedBox {
+        fn aabb(&self) -> AABB {
+            self.0.aabb()
+        }
+    }
+
+    impl BHShape for GroupedBox {
+        fn set_bh_node_index(&mut self, index: usize) {
+            self.0.set_bh_node_index(index);
+        }
+
+        fn bh_node_index(&self) -> usize {
+            self.0.bh_node_index()
+        }
+    }
+
+    impl GroupedShape for GroupedBox {
+        fn group(&self) -> usize {
+            (self.0.pos.x >= 0.0) as usize
+        }
+
+        fn local_aabb(&self) -> AABB {
+            self.0.aabb()
+        }
+    }
+
+    #[test]
+    /// Tests whether moving a group refits the `BVH` to the moved shapes.
+    fn test_refit_moved_group() {
+        let mut shapes: Vec<_> = generate_aligned_boxes()
+            .into_iter()
+            .map(GroupedBox)
+            .collect();
+        let mut bvh = BVH::build(&mut shapes);
+        let mut groups = TransformGroups::new(vec![Mat4::IDENTITY; 2], &shapes);
+
+        let offset = Vector3::new(0.0, 100.0, 0.0);
+        groups.set_transform(1, Mat4::from_translation(offset));
+        groups.refit(&mut bvh, &shapes);
+
+        // The boxes with positive x have moved up by 100.
+        let ray = Ray::new(
+            Point3::new(-1000.0, 100.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+        );
+        let hits = bvh.traverse(&ray, &shapes);
+        assert_eq!(hits.len(), 11);
+        assert!(hits.iter().all(|shape| shape.group() == 1));
+
+        let ray = Ray::new(Point3::new(-1000.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let hits = bvh.traverse(&ray, &shapes);
+        assert_eq!(hits.len(), 10);
+        assert!(hits.iter().all(|shape| shape.group() == 0));
+
+        // Only the shapes of the moved group have moved.
+        for shape in &shapes {
+            let aabb = shape.aabb();
+            let expected = if shape.group() == 1 {
+                AABB::with_bounds(aabb.min + offset, aabb.max + offset)
+            } else {
+                aabb
+            };
+            assert!(groups
+                .world_aabb(shape)
+                .relative_eq(&expected, crate::EPSILON));
+        }
+    }
+}
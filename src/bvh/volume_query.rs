@@ -0,0 +1,706 @@
+//! This module defines queries on the [`BVH`] which search for shapes inside of a volume
+//! or along a finite segment instead of along a [`Ray`].
+//!
+//! [`BVH`]: struct.BVH.html
+//! [`Ray`]: ../ray/struct.Ray.html
+//!
+
+use crate::aabb::{Bounded, IntersectsAabb, AABB};
+use crate::bvh::{BVHNode, BVH};
+use crate::cone::Cone;
+use crate::obb::OBB;
+use crate::plane::{Plane, PlaneSide};
+use crate::ray::Segment;
+use crate::Vector3;
+
+/// The shapes of a [`BVH`] partitioned by a [`Plane`], see [`BVH::partition_by_plane`].
+///
+/// [`BVH`]: struct.BVH.html
+/// [`BVH::partition_by_plane`]: struct.BVH.html#method.partition_by_plane
+/// [`Plane`]: ../plane/struct.Plane.html
+///
+#[derive(Debug)]
+pub struct PlanePartition<'a, Shape> {
+    /// The shapes whose [`AABB`] lies completely in front of the plane.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub front: Vec<&'a Shape>,
+
+    /// The shapes whose [`AABB`] lies completely behind the plane.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub back: Vec<&'a Shape>,
+
+    /// The shapes whose [`AABB`] intersects the plane.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub straddling: Vec<&'a Shape>,
+}
+
+impl BVH {
+    /// Traverses the [`BVH`] with a custom volume.
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements intersect `query`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{Bounded, IntersectsAabb, AABB};
+    /// use bvh::bounding_hierarchy::BHShape;
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// # struct UnitBox {
+    /// #     pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// struct Sphere {
+    ///     center: Point3,
+    ///     radius: f32,
+    /// }
+    ///
+    /// impl IntersectsAabb for Sphere {
+    ///     fn intersects(&self, aabb: &AABB) -> bool {
+    ///         aabb.min_distance_squared(&self.center) <= self.radius * self.radius
+    ///     }
+    /// }
+    ///
+    /// let mut shapes: Vec<UnitBox> = (0..100)
+    ///     .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    ///     .collect();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// let sphere = Sphere { center: Point3::new(50.0, 0.0, 0.0), radius: 2.0 };
+    /// assert_eq!(bvh.query(&sphere, &shapes).len(), 5);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn query<'a, Query: IntersectsAabb, Shape: Bounded>(
+        &'a self,
+        query: &Query,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        let mut found = Vec::new();
+        self.traverse_overlapping(
+            |aabb| query.intersects(aabb),
+            |shape_index| {
+                let shape = &shapes[shape_index];
+                // A root leaf has no stored `AABB`, so it has not been tested yet.
+                if self.nodes.len() > 1 || query.intersects(&shape.aabb()) {
+                    found.push(shape);
+                }
+            },
+        );
+        found
+    }
+
+    /// Traverses the [`BVH`].
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements overlap `query`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn traverse_aabb<'a, Shape: Bounded>(
+        &'a self,
+        query: &AABB,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.query(query, shapes)
+    }
+
+    /// Traverses the [`BVH`].
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements intersect `cone`.
+    /// The result is conservative, see [`Cone::intersects_aabb`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`Cone::intersects_aabb`]: ../cone/struct.Cone.html#method.intersects_aabb
+    ///
+    pub fn traverse_cone<'a, Shape: Bounded>(
+        &'a self,
+        cone: &Cone,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.query(cone, shapes)
+    }
+
+    /// Traverses the [`BVH`].
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements intersect `obb`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn traverse_obb<'a, Shape: Bounded>(
+        &'a self,
+        obb: &OBB,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.query(obb, shapes)
+    }
+
+    /// Traverses the [`BVH`] with `query`, which moves by `velocity` during a timestep.
+    /// Returns the subset of `shapes`, in which the [`AABB`]s of the elements are hit by the
+    /// moving `query`, together with the time of first overlap between `0.0` and `1.0`.
+    /// The shapes are sorted by increasing time, see [`AABB::sweep`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`AABB::sweep`]: ../aabb/struct.AABB.html#method.sweep
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn traverse_swept_aabb<'a, Shape: Bounded>(
+        &'a self,
+        query: &AABB,
+        velocity: &Vector3,
+        shapes: &'a [Shape],
+    ) -> Vec<(&'a Shape, f32)> {
+        let mut found = Vec::new();
+        self.traverse_overlapping(
+            |aabb| query.sweep(velocity, aabb).is_some(),
+            |shape_index| {
+                let shape = &shapes[shape_index];
+                if let Some(time) = query.sweep(velocity, &shape.aabb()) {
+                    found.push((shape, time));
+                }
+            },
+        );
+        found.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+        found
+    }
+
+    /// Traverses the [`BVH`].
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements are intersected
+    /// by `segment`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn traverse_segment<'a, Shape: Bounded>(
+        &'a self,
+        segment: &Segment,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        match segment.to_ray() {
+            Some(ray) => self.traverse(&ray, shapes),
+            None => self.traverse_aabb(&AABB::with_bounds(segment.start, segment.start), shapes),
+        }
+    }
+
+    /// Partitions `shapes` by the [`PlaneSide`] of `plane` on which their [`AABB`]s lie.
+    /// Subtrees which lie completely on one side of `plane` are assigned to that side
+    /// without classifying their shapes individually.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`PlaneSide`]: ../plane/enum.PlaneSide.html
+    ///
+    pub fn partition_by_plane<'a, Shape: Bounded>(
+        &'a self,
+        plane: &Plane,
+        shapes: &'a [Shape],
+    ) -> PlanePartition<'a, Shape> {
+        let mut partition = PlanePartition {
+            front: Vec::new(),
+            back: Vec::new(),
+            straddling: Vec::new(),
+        };
+        if self.nodes.is_empty() {
+            return partition;
+        }
+
+        // The stack only contains nodes which straddle the plane, except for the root.
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        match plane.classify_aabb(child_aabb) {
+                            PlaneSide::Front => self
+                                .visit_subtree(child_index as usize, |shape_index| {
+                                    partition.front.push(&shapes[shape_index])
+                                }),
+                            PlaneSide::Back => self
+                                .visit_subtree(child_index as usize, |shape_index| {
+                                    partition.back.push(&shapes[shape_index])
+                                }),
+                            PlaneSide::Straddling => stack.push(child_index as usize),
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    let shape = &shapes[shape_index as usize];
+                    // A root leaf has no stored `AABB`, so it has not been classified yet.
+                    let side = if node_index == 0 {
+                        plane.classify_aabb(&shape.aabb())
+                    } else {
+                        PlaneSide::Straddling
+                    };
+                    match side {
+                        PlaneSide::Front => partition.front.push(shape),
+                        PlaneSide::Back => partition.back.push(shape),
+                        PlaneSide::Straddling => partition.straddling.push(shape),
+                    }
+                }
+            }
+        }
+
+        partition
+    }
+
+    /// Traverses the [`BVH`].
+    /// Returns a subset of `shapes`, in which the [`AABB`]s of the elements intersect the
+    /// convex volume bounded by `planes`, whose normals point to the inside.
+    ///
+    /// Subtrees which lie completely inside of the volume are returned without further
+    /// tests. The result is conservative, see [`Plane::classify_aabb_convex`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{Bounded, AABB};
+    /// use bvh::bounding_hierarchy::BHShape;
+    /// use bvh::bvh::BVH;
+    /// use bvh::plane::Plane;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// # struct UnitBox {
+    /// #     pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// let mut shapes: Vec<UnitBox> = (0..100)
+    ///     .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    ///     .collect();
+    /// let bvh = BVH::build(&mut shapes);
+    ///
+    /// // The slab between x = 10 and x = 20.
+    /// let planes = [
+    ///     Plane::new(Vector3::new(1.0, 0.0, 0.0), 10.0),
+    ///     Plane::new(Vector3::new(-1.0, 0.0, 0.0), -20.0),
+    /// ];
+    /// let found = bvh.traverse_convex(&planes, &shapes);
+    /// assert_eq!(found.len(), 11);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    /// [`Plane::classify_aabb_convex`]: ../plane/struct.Plane.html#method.classify_aabb_convex
+    ///
+    pub fn traverse_convex<'a, Shape: Bounded>(
+        &'a self,
+        planes: &[Plane],
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        // The stack only contains nodes which straddle the volume, except for the root.
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        match Plane::classify_aabb_convex(planes, child_aabb) {
+                            PlaneSide::Front => self
+                                .visit_subtree(child_index as usize, |shape_index| {
+                                    found.push(&shapes[shape_index])
+                                }),
+                            PlaneSide::Back => {}
+                            PlaneSide::Straddling => stack.push(child_index as usize),
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    let shape = &shapes[shape_index as usize];
+                    // A root leaf has no stored `AABB`, so it has not been classified yet.
+                    if node_index != 0
+                        || Plane::classify_aabb_convex(planes, &shape.aabb()) != PlaneSide::Back
+                    {
+                        found.push(shape);
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Calls `visit` with the shape index of every leaf in the subtree at `node_index`.
+    fn visit_subtree<V>(&self, node_index: usize, mut visit: V)
+    where
+        V: FnMut(usize),
+    {
+        let mut stack = vec![node_index];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    child_l_index,
+                    child_r_index,
+                    ..
+                } => {
+                    stack.push(child_r_index as usize);
+                    stack.push(child_l_index as usize);
+                }
+                BVHNode::Leaf { shape_index, .. } => visit(shape_index as usize),
+            }
+        }
+    }
+
+    /// Traverses all nodes whose [`AABB`] passes `overlaps`, and calls `visit` with the
+    /// shape index of every leaf reached.
+    ///
+    /// The root is always visited, because the [`BVH`] does not store its [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    fn traverse_overlapping<F, V>(&self, overlaps: F, mut visit: V)
+    where
+        F: Fn(&AABB) -> bool,
+        V: FnMut(usize),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    ref child_l_aabb,
+                    child_l_index,
+                    ref child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    for &(child_aabb, child_index) in
+                        &[(child_r_aabb, child_r_index), (child_l_aabb, child_l_index)]
+                    {
+                        if overlaps(child_aabb) {
+                            stack.push(child_index as usize);
+                        }
+                    }
+                }
+                BVHNode::Leaf { shape_index, .. } => visit(shape_index as usize),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, IntersectsAabb, AABB};
+    use crate::bvh::BVH;
+    use crate::cone::Cone;
+    use crate::obb::OBB;
+    use crate::plane::{Plane, PlaneSide};
+    use crate::ray::Segment;
+    use crate::testbase::{create_n_cubes, default_bounds, next_point3, UnitBox};
+    use crate::{Point3, Vector3};
+    use glam::Quat;
+
+    /// Returns the index of every shape in `found`, sorted.
+    fn indices_of<Shape>(found: &[&Shape], shapes: &[Shape]) -> Vec<usize> {
+        let mut indices: Vec<usize> = found
+            .iter()
+            .map(|&found| {
+                shapes
+                    .iter()
+                    .position(|shape| std::ptr::eq(shape, found))
+                    .unwrap()
+            })
+            .collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    #[test]
+    /// Tests whether `traverse_aabb` finds the same shapes as a linear search.
+    fn test_traverse_aabb_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let query = AABB::empty()
+                .grow(&next_point3(&mut seed, &bounds))
+                .grow(&next_point3(&mut seed, &bounds));
+            let expected: Vec<usize> = (0..triangles.len())
+                .filter(|&index| triangles[index].aabb().intersects(&query))
+                .collect();
+
+            let found = bvh.traverse_aabb(&query, &triangles);
+            assert_eq!(indices_of(&found, &triangles), expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_aabb` tests the shape of a `BVH` with a single leaf.
+    fn test_traverse_aabb_single_shape() {
+        let mut shapes = vec![UnitBox::new(0, Point3::new(0.0, 0.0, 0.0))];
+        let bvh = BVH::build(&mut shapes);
+
+        let inside = AABB::with_bounds(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        let outside = AABB::with_bounds(Point3::new(2.0, 2.0, 2.0), Point3::new(3.0, 3.0, 3.0));
+        assert_eq!(bvh.traverse_aabb(&inside, &shapes).len(), 1);
+        assert!(bvh.traverse_aabb(&outside, &shapes).is_empty());
+    }
+
+    #[test]
+    /// Tests whether `partition_by_plane` classifies the shapes like a linear search.
+    fn test_partition_by_plane_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let plane = Plane::from_point_normal(
+                next_point3(&mut seed, &bounds),
+                next_point3(&mut seed, &bounds),
+            );
+            let partition = bvh.partition_by_plane(&plane, &triangles);
+
+            for (found, side) in &[
+                (&partition.front, PlaneSide::Front),
+                (&partition.back, PlaneSide::Back),
+                (&partition.straddling, PlaneSide::Straddling),
+            ] {
+                let expected: Vec<usize> = (0..triangles.len())
+                    .filter(|&index| plane.classify_aabb(&triangles[index].aabb()) == *side)
+                    .collect();
+                assert_eq!(indices_of(found, &triangles), expected);
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_convex` finds the same shapes as a linear search.
+    fn test_traverse_convex_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for &plane_count in &[0, 1, 4, 6] {
+            for _ in 0..20 {
+                // Planes through a common point, so that the volume is not empty.
+                let center = next_point3(&mut seed, &bounds);
+                let planes: Vec<Plane> = (0..plane_count)
+                    .map(|_| {
+                        let normal = next_point3(&mut seed, &bounds) - center;
+                        Plane::from_point_normal(center - normal * 0.1, normal)
+                    })
+                    .collect();
+
+                let expected: Vec<usize> = (0..triangles.len())
+                    .filter(|&index| {
+                        Plane::classify_aabb_convex(&planes, &triangles[index].aabb())
+                            != PlaneSide::Back
+                    })
+                    .collect();
+                let found = bvh.traverse_convex(&planes, &triangles);
+                assert_eq!(indices_of(&found, &triangles), expected);
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_segment` finds the same shapes as a linear search, and
+    /// whether it ignores shapes behind the end of the segment.
+    fn test_traverse_segment_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let start = next_point3(&mut seed, &bounds);
+            let end = next_point3(&mut seed, &bounds);
+            for segment in &[Segment::new(start, end), Segment::new(start, start)] {
+                let expected: Vec<usize> = (0..triangles.len())
+                    .filter(|&index| segment.intersects_aabb(&triangles[index].aabb()))
+                    .collect();
+                let found = bvh.traverse_segment(segment, &triangles);
+                assert_eq!(indices_of(&found, &triangles), expected);
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_swept_aabb` finds the same shapes and times as a linear
+    /// search, sorted by time.
+    fn test_traverse_swept_aabb_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let center = next_point3(&mut seed, &bounds);
+            let query = AABB::with_bounds(
+                center - Vector3::new(1000.0, 1000.0, 1000.0),
+                center + Vector3::new(1000.0, 1000.0, 1000.0),
+            );
+            let velocity = next_point3(&mut seed, &bounds) - center;
+
+            let mut expected: Vec<(usize, f32)> = (0..triangles.len())
+                .filter_map(|index| {
+                    query
+                        .sweep(&velocity, &triangles[index].aabb())
+                        .map(|time| (index, time))
+                })
+                .collect();
+
+            let found = bvh.traverse_swept_aabb(&query, &velocity, &triangles);
+            assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+            let shapes: Vec<_> = found.iter().map(|&(shape, _)| shape).collect();
+            let mut found: Vec<(usize, f32)> = indices_of(&shapes, &triangles)
+                .into_iter()
+                .map(|index| {
+                    (
+                        index,
+                        query.sweep(&velocity, &triangles[index].aabb()).unwrap(),
+                    )
+                })
+                .collect();
+            found.sort_by_key(|&(index, _)| index);
+            expected.sort_by_key(|&(index, _)| index);
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_obb` finds the same shapes as a linear search.
+    fn test_traverse_obb_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let center = next_point3(&mut seed, &bounds);
+            let half_extents = (next_point3(&mut seed, &bounds) - bounds.min) / 8.0;
+            let axis = next_point3(&mut seed, &bounds).normalize();
+            let rotation = Quat::from_axis_angle(axis, center.x);
+            let obb = OBB::new(center, half_extents, rotation);
+
+            let expected: Vec<usize> = (0..triangles.len())
+                .filter(|&index| obb.intersects_aabb(&triangles[index].aabb()))
+                .collect();
+            let found = bvh.traverse_obb(&obb, &triangles);
+            assert_eq!(indices_of(&found, &triangles), expected);
+        }
+    }
+
+    #[test]
+    /// Tests whether `traverse_cone` finds the same shapes as a linear search.
+    fn test_traverse_cone_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let apex = next_point3(&mut seed, &bounds);
+            let direction = next_point3(&mut seed, &bounds) - apex;
+            let cone = Cone::new(apex, direction, 0.5, direction.length());
+
+            let expected: Vec<usize> = (0..triangles.len())
+                .filter(|&index| cone.intersects_aabb(&triangles[index].aabb()))
+                .collect();
+            let found = bvh.traverse_cone(&cone, &triangles);
+            assert_eq!(indices_of(&found, &triangles), expected);
+        }
+    }
+
+    /// A sphere, which is not known to the crate.
+    struct Sphere {
+        center: Point3,
+        radius: f32,
+    }
+
+    impl IntersectsAabb for Sphere {
+        fn intersects(&self, aabb: &AABB) -> bool {
+            aabb.min_distance_squared(&self.center) <= self.radius * self.radius
+        }
+    }
+
+    #[test]
+    /// Tests whether `query` with a custom volume finds the same shapes as a linear search.
+    fn test_query_equals_linear_search() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..100 {
+            let center = next_point3(&mut seed, &bounds);
+            let radius = (next_point3(&mut seed, &bounds) - center).length() / 4.0;
+            let sphere = Sphere { center, radius };
+
+            let expected: Vec<usize> = (0..triangles.len())
+                .filter(|&index| sphere.intersects(&triangles[index].aabb()))
+                .collect();
+            let found = bvh.query(&sphere, &triangles);
+            assert_eq!(indices_of(&found, &triangles), expected);
+        }
+    }
+}
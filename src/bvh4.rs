@@ -0,0 +1,296 @@
+//! This module defines [`BVH4`], a [`BVH`] with up to four children per node, which is
+//! created by collapsing the nodes of a binary [`BVH`].
+//!
+//! Wide nodes halve the depth of the tree, and the four child [`AABB`]s of a node can be
+//! tested against a ray at once with SIMD instructions or by the threads of a GPU. The
+//! child [`AABB`]s are stored as an [`AABB4`], which [`Ray::intersects_aabb4`] tests with
+//! one SIMD lane per child.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`AABB4`]: ../aabb/struct.AABB4.html
+//! [`Ray::intersects_aabb4`]: ../ray/struct.Ray.html#method.intersects_aabb4
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`BVH4`]: struct.BVH4.html
+//!
+
+use crate::aabb::{Bounded, AABB, AABB4};
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
+use crate::bvh::BVH;
+use crate::ray::Ray;
+use crate::wide::{self, WideNode};
+
+/// The bit of a child reference which marks a leaf. The remaining bits of the reference
+/// are the index of the shape for leaves, and the index of the node otherwise.
+pub const BVH4_LEAF_BIT: u32 = wide::LEAF_BIT;
+
+/// The child reference of an unused child slot.
+pub const BVH4_EMPTY: u32 = wide::EMPTY;
+
+/// A node of a [`BVH4`] with up to four children. The used child slots come first.
+///
+/// [`BVH4`]: struct.BVH4.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BVH4Node {
+    /// The [`AABB`]s of the children. The [`AABB`]s of unused slots are empty.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub child_aabbs: AABB4,
+
+    /// The references to the children. A reference is [`BVH4_EMPTY`] for an unused slot,
+    /// the index of the shape with the [`BVH4_LEAF_BIT`] set for a leaf, and the index of
+    /// the node otherwise.
+    ///
+    /// [`BVH4_EMPTY`]: constant.BVH4_EMPTY.html
+    /// [`BVH4_LEAF_BIT`]: constant.BVH4_LEAF_BIT.html
+    ///
+    pub children: [u32; 4],
+}
+
+/// A [`BVH`] with up to four children per node, which is created by [`BVH::collapse_bvh4`].
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::collapse_bvh4`]: ../bvh/struct.BVH.html#method.collapse_bvh4
+///
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BVH4 {
+    /// The nodes. The root is the first node, unless the [`BVH4`] is a single leaf.
+    ///
+    /// [`BVH4`]: struct.BVH4.html
+    ///
+    pub nodes: Vec<BVH4Node>,
+
+    /// The reference to the root. See [`BVH4Node::children`].
+    ///
+    /// [`BVH4Node::children`]: struct.BVH4Node.html#structfield.children
+    ///
+    pub root: u32,
+}
+
+impl BVH {
+    /// Collapses the binary [`BVH`] into a [`BVH4`]. The children of every node are
+    /// replaced by their own children, starting with the child of the largest surface area,
+    /// until the node has four children or only leaves are left. The order of the shapes
+    /// in the tree is kept.
+    ///
+    /// # Panics
+    /// Panics if the [`BVH`] has `2^31` shapes or nodes, or more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1024)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let bvh4 = bvh.collapse_bvh4();
+    /// assert!(bvh4.nodes.len() < bvh.nodes.len() / 4);
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(bvh4.traverse(&ray, &shapes).len(), 1024);
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH4`]: ../bvh4/struct.BVH4.html
+    ///
+    pub fn collapse_bvh4(&self) -> BVH4 {
+        let (nodes, root) = wide::collapse(self);
+        BVH4 { nodes, root }
+    }
+}
+
+impl WideNode for BVH4Node {
+    const WIDTH: usize = 4;
+
+    /// `glam` already selects the SIMD instructions of [`Ray::intersects_aabb4`] at compile
+    /// time.
+    ///
+    /// [`Ray::intersects_aabb4`]: ../ray/struct.Ray.html#method.intersects_aabb4
+    ///
+    type Kernel = ();
+
+    fn empty() -> BVH4Node {
+        BVH4Node {
+            child_aabbs: AABB4::empty(),
+            children: [BVH4_EMPTY; 4],
+        }
+    }
+
+    fn children(&self) -> &[u32] {
+        &self.children
+    }
+
+    #[cfg(test)]
+    fn child_aabb(&self, slot: usize) -> AABB {
+        self.child_aabbs.get(slot)
+    }
+
+    fn set_child(&mut self, slot: usize, aabb: &AABB, child: u32) {
+        self.child_aabbs.set(slot, aabb);
+        self.children[slot] = child;
+    }
+
+    fn detect_kernel() {}
+
+    fn intersects(&self, ray: &Ray, _kernel: ()) -> u32 {
+        ray.intersects_aabb4(&self.child_aabbs).0
+    }
+}
+
+impl MemoryUsage for BVH4 {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.nodes.capacity() * std::mem::size_of::<BVH4Node>()
+    }
+}
+
+impl BoundingHierarchy for BVH4 {
+    /// A [`BVH4`] is built from a regular [`BVH`] using the [`collapse_bvh4`] method.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVH4`]: struct.BVH4.html
+    /// [`collapse_bvh4`]: ../bvh/struct.BVH.html#method.collapse_bvh4
+    ///
+    fn build<Shape: BHShape>(shapes: &mut [Shape]) -> BVH4 {
+        let bvh = BVH::build(shapes);
+        bvh.collapse_bvh4()
+    }
+
+    /// Traverses a [`BVH4`] with a stack. The shapes are returned in the same order as by
+    /// [`BVH::traverse`].
+    ///
+    /// [`BVH::traverse`]: ../bvh/struct.BVH.html#method.traverse
+    /// [`BVH4`]: struct.BVH4.html
+    ///
+    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`BVH4`].
+    ///
+    /// [`BVH4`]: struct.BVH4.html
+    ///
+    fn pretty_print(&self) {
+        wide::pretty_print(&self.nodes);
+    }
+}
+
+impl IndexedBoundingHierarchy for BVH4 {
+    /// Traverses a [`BVH4`] and returns the indices of the shapes whose [`AABB`]s were hit
+    /// by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH4`]: struct.BVH4.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        wide::traverse_indices(&self.nodes, self.root, ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::bounding_hierarchy::BoundingHierarchy;
+    use crate::bvh::BVH;
+    use crate::bvh4::{BVH4, BVH4_EMPTY, BVH4_LEAF_BIT};
+    use crate::testbase::{
+        build_cubes_bvh, check_traverse_equals_bvh, create_n_cubes, create_ray, default_bounds,
+        traverse_some_bh,
+    };
+    use crate::wide::assert_collapsed;
+    use crate::Point3;
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `BVH4`.
+    fn test_traverse_bvh4() {
+        traverse_some_bh::<BVH4>();
+    }
+
+    #[test]
+    /// Tests whether every node of a `BVH4` has at least two children, whose `AABB`s
+    /// contain the `AABB`s of their own children, and whether a `BVH4` finds the same
+    /// shapes as the `BVH`.
+    fn test_bvh4_equals_bvh() {
+        let (triangles, bvh) = build_cubes_bvh();
+        let bvh4 = bvh.collapse_bvh4();
+        assert_collapsed(&bvh4.nodes, triangles.len());
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| bvh4.traverse(ray, &triangles));
+    }
+
+    #[test]
+    /// Tests whether collapsing a `BVH` with a NaN child `AABB` keeps all shapes instead of
+    /// panicking.
+    fn test_collapse_bvh4_nan_aabb() {
+        let mut triangles = create_n_cubes(10, &default_bounds());
+        let mut bvh = BVH::build(&mut triangles);
+        *bvh.nodes[0].child_l_aabb_mut() = AABB::with_bounds(
+            Point3::new(f32::NAN, 0.0, 0.0),
+            Point3::new(1.0, f32::NAN, 1.0),
+        );
+        let bvh4 = bvh.collapse_bvh4();
+
+        let mut shapes: Vec<u32> = bvh4
+            .nodes
+            .iter()
+            .flat_map(|node| node.children.iter())
+            .filter(|&&child| child != BVH4_EMPTY && child & BVH4_LEAF_BIT != 0)
+            .map(|&child| child & !BVH4_LEAF_BIT)
+            .collect();
+        shapes.sort_unstable();
+        assert_eq!(shapes, (0..triangles.len() as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    /// Tests whether collapsing a `BVH` without shapes yields a `BVH4` with an empty root,
+    /// which is never hit.
+    fn test_collapse_bvh4_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let bvh4 = bvh.collapse_bvh4();
+        assert!(bvh4.nodes.is_empty());
+        assert_eq!(bvh4.root, BVH4_EMPTY);
+
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+        assert!(bvh4.traverse(&ray, &triangles).is_empty());
+    }
+}
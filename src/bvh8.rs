@@ -0,0 +1,268 @@
+//! This module defines [`BVH8`], a [`BVH`] with up to eight children per node, which is
+//! created by collapsing the nodes of a binary [`BVH`] like a [`BVH4`].
+//!
+//! The eight child [`AABB`]s of a node are stored as an [`AABB8`], which
+//! [`Ray::intersects_aabb8`] tests with one AVX lane per child if the CPU supports AVX, and
+//! with NEON on AArch64. The CPU features are detected once per traversal.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`AABB8`]: ../aabb/struct.AABB8.html
+//! [`Ray::intersects_aabb8`]: ../ray/struct.Ray.html#method.intersects_aabb8
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`BVH4`]: ../bvh4/struct.BVH4.html
+//! [`BVH8`]: struct.BVH8.html
+//!
+
+use crate::aabb::{Bounded, AABB, AABB8};
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
+use crate::bvh::BVH;
+use crate::ray::{AABB8Kernel, Ray};
+use crate::wide::{self, WideNode};
+
+/// The bit of a child reference which marks a leaf. The remaining bits of the reference
+/// are the index of the shape for leaves, and the index of the node otherwise.
+pub const BVH8_LEAF_BIT: u32 = wide::LEAF_BIT;
+
+/// The child reference of an unused child slot.
+pub const BVH8_EMPTY: u32 = wide::EMPTY;
+
+/// A node of a [`BVH8`] with up to eight children. The used child slots come first.
+///
+/// [`BVH8`]: struct.BVH8.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BVH8Node {
+    /// The [`AABB`]s of the children. The [`AABB`]s of unused slots are empty.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub child_aabbs: AABB8,
+
+    /// The references to the children. A reference is [`BVH8_EMPTY`] for an unused slot,
+    /// the index of the shape with the [`BVH8_LEAF_BIT`] set for a leaf, and the index of
+    /// the node otherwise.
+    ///
+    /// [`BVH8_EMPTY`]: constant.BVH8_EMPTY.html
+    /// [`BVH8_LEAF_BIT`]: constant.BVH8_LEAF_BIT.html
+    ///
+    pub children: [u32; 8],
+}
+
+/// A [`BVH`] with up to eight children per node, which is created by [`BVH::collapse_bvh8`].
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::collapse_bvh8`]: ../bvh/struct.BVH.html#method.collapse_bvh8
+///
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BVH8 {
+    /// The nodes. The root is the first node, unless the [`BVH8`] is a single leaf.
+    ///
+    /// [`BVH8`]: struct.BVH8.html
+    ///
+    pub nodes: Vec<BVH8Node>,
+
+    /// The reference to the root. See [`BVH8Node::children`].
+    ///
+    /// [`BVH8Node::children`]: struct.BVH8Node.html#structfield.children
+    ///
+    pub root: u32,
+}
+
+impl BVH {
+    /// Collapses the binary [`BVH`] into a [`BVH8`]. The children of every node are
+    /// replaced by their own children, starting with the child of the largest surface area,
+    /// until the node has eight children or only leaves are left. The order of the shapes
+    /// in the tree is kept.
+    ///
+    /// # Panics
+    /// Panics if the [`BVH`] has `2^31` shapes or nodes, or more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..512)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let bvh8 = bvh.collapse_bvh8();
+    /// assert!(bvh8.nodes.len() < bvh.nodes.len() / 8);
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(bvh8.traverse(&ray, &shapes).len(), 512);
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`BVH8`]: ../bvh8/struct.BVH8.html
+    ///
+    pub fn collapse_bvh8(&self) -> BVH8 {
+        let (nodes, root) = wide::collapse(self);
+        BVH8 { nodes, root }
+    }
+}
+
+impl WideNode for BVH8Node {
+    const WIDTH: usize = 8;
+
+    type Kernel = AABB8Kernel;
+
+    fn empty() -> BVH8Node {
+        BVH8Node {
+            child_aabbs: AABB8::empty(),
+            children: [BVH8_EMPTY; 8],
+        }
+    }
+
+    fn children(&self) -> &[u32] {
+        &self.children
+    }
+
+    #[cfg(test)]
+    fn child_aabb(&self, slot: usize) -> AABB {
+        self.child_aabbs.get(slot)
+    }
+
+    fn set_child(&mut self, slot: usize, aabb: &AABB, child: u32) {
+        self.child_aabbs.set(slot, aabb);
+        self.children[slot] = child;
+    }
+
+    fn detect_kernel() -> AABB8Kernel {
+        AABB8Kernel::detect()
+    }
+
+    fn intersects(&self, ray: &Ray, kernel: AABB8Kernel) -> u32 {
+        ray.intersects_aabb8_with(&self.child_aabbs, kernel).0
+    }
+}
+
+impl MemoryUsage for BVH8 {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.nodes.capacity() * std::mem::size_of::<BVH8Node>()
+    }
+}
+
+impl BoundingHierarchy for BVH8 {
+    /// A [`BVH8`] is built from a regular [`BVH`] using the [`collapse_bvh8`] method.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVH8`]: struct.BVH8.html
+    /// [`collapse_bvh8`]: ../bvh/struct.BVH.html#method.collapse_bvh8
+    ///
+    fn build<Shape: BHShape>(shapes: &mut [Shape]) -> BVH8 {
+        let bvh = BVH::build(shapes);
+        bvh.collapse_bvh8()
+    }
+
+    /// Traverses a [`BVH8`] with a stack. The shapes are returned in the same order as by
+    /// [`BVH::traverse`].
+    ///
+    /// [`BVH::traverse`]: ../bvh/struct.BVH.html#method.traverse
+    /// [`BVH8`]: struct.BVH8.html
+    ///
+    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`BVH8`].
+    ///
+    /// [`BVH8`]: struct.BVH8.html
+    ///
+    fn pretty_print(&self) {
+        wide::pretty_print(&self.nodes);
+    }
+}
+
+impl IndexedBoundingHierarchy for BVH8 {
+    /// Traverses a [`BVH8`] and returns the indices of the shapes whose [`AABB`]s were hit
+    /// by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH8`]: struct.BVH8.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        wide::traverse_indices(&self.nodes, self.root, ray)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounding_hierarchy::BoundingHierarchy;
+    use crate::bvh::BVH;
+    use crate::bvh8::{BVH8, BVH8_EMPTY};
+    use crate::testbase::{
+        build_cubes_bvh, check_traverse_equals_bvh, create_n_cubes, create_ray, default_bounds,
+        traverse_some_bh,
+    };
+    use crate::wide::assert_collapsed;
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `BVH8`.
+    fn test_traverse_bvh8() {
+        traverse_some_bh::<BVH8>();
+    }
+
+    #[test]
+    /// Tests whether every node of a `BVH8` has at least two children, whose `AABB`s
+    /// contain the `AABB`s of their own children, and whether a `BVH8` finds the same
+    /// shapes as the `BVH`.
+    fn test_bvh8_equals_bvh() {
+        let (triangles, bvh) = build_cubes_bvh();
+        let bvh8 = bvh.collapse_bvh8();
+        assert_collapsed(&bvh8.nodes, triangles.len());
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| bvh8.traverse(ray, &triangles));
+    }
+
+    #[test]
+    /// Tests whether collapsing a `BVH` without shapes yields a `BVH8` with an empty root,
+    /// which is never hit.
+    fn test_collapse_bvh8_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let bvh8 = bvh.collapse_bvh8();
+        assert!(bvh8.nodes.is_empty());
+        assert_eq!(bvh8.root, BVH8_EMPTY);
+
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+        assert!(bvh8.traverse(&ray, &triangles).is_empty());
+    }
+}
@@ -0,0 +1,557 @@
+//! This module defines a compressed [`BVH`], whose nodes store the [`AABB`]s of their
+//! children quantized relative to their own [`AABB`].
+//!
+//! A node of a [`CompressedBVH`] takes 20 bytes with 8 bit offsets and 32 bytes with 16 bit
+//! offsets, instead of the 36 bytes of a [`FlatNode`]. The [`AABB`] of a node is only known
+//! from its parent, so the traversal keeps the decoded [`AABB`] of every node on its stack.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`CompressedBVH`]: struct.CompressedBVH.html
+//! [`FlatNode`]: ../flat_bvh/struct.FlatNode.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
+use crate::bvh::{BVHNode, BVH};
+use crate::ray::Ray;
+use crate::Point3;
+
+/// The bit of a child reference which marks a leaf. The remaining bits of the reference
+/// are the index of the shape for leaves, and the index of the node otherwise.
+const LEAF_BIT: u32 = 1 << 31;
+
+/// An unsigned integer type in which a [`CompressedNode`] stores its offsets. An offset of
+/// `0` stands for the lower bound of the [`AABB`] of the node and an offset of `STEPS` for
+/// its upper bound. Implemented for `u8` and `u16`.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`CompressedNode`]: struct.CompressedNode.html
+///
+pub trait QuantizedOffset: Copy {
+    /// The largest offset.
+    const STEPS: u32;
+
+    /// Converts an offset between `0` and `STEPS` to this type.
+    fn from_steps(steps: u32) -> Self;
+
+    /// Converts this offset back to a `u32`.
+    fn steps(self) -> u32;
+}
+
+impl QuantizedOffset for u8 {
+    const STEPS: u32 = u8::MAX as u32;
+
+    fn from_steps(steps: u32) -> u8 {
+        steps as u8
+    }
+
+    fn steps(self) -> u32 {
+        self as u32
+    }
+}
+
+impl QuantizedOffset for u16 {
+    const STEPS: u32 = u16::MAX as u32;
+
+    fn from_steps(steps: u32) -> u16 {
+        steps as u16
+    }
+
+    fn steps(self) -> u32 {
+        self as u32
+    }
+}
+
+/// An inner node of a [`CompressedBVH`]. It stores the [`AABB`]s of its two children as
+/// offsets relative to its own [`AABB`], which are rounded outwards, so a decoded [`AABB`]
+/// always contains the exact one.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`CompressedBVH`]: struct.CompressedBVH.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedNode<T: QuantizedOffset> {
+    /// The offsets of the lower bounds of the left and the right child.
+    child_min: [[T; 3]; 2],
+
+    /// The offsets of the upper bounds of the left and the right child.
+    child_max: [[T; 3]; 2],
+
+    /// The references to the left and the right child. See [`LEAF_BIT`].
+    ///
+    /// [`LEAF_BIT`]: constant.LEAF_BIT.html
+    ///
+    children: [u32; 2],
+}
+
+impl<T: QuantizedOffset> CompressedNode<T> {
+    /// Returns the bound at `steps` offsets between `min` and `max`. The bounds themselves
+    /// are returned exactly, even if they are not finite.
+    fn decode(min: f32, max: f32, steps: u32) -> f32 {
+        if steps == 0 {
+            min
+        } else if steps == T::STEPS {
+            max
+        } else {
+            min + (max - min) * (steps as f32 / T::STEPS as f32)
+        }
+    }
+
+    /// Quantizes `child` relative to `parent`, which must contain `child`. Returns the
+    /// offsets of the lower and the upper bound. Along axes on which the extent of `parent`
+    /// is not finite, `child` gets the bounds of `parent`, as the offsets cannot be
+    /// decoded in between.
+    fn quantize(parent: &AABB, child: &AABB) -> ([T; 3], [T; 3]) {
+        let mut min = [T::from_steps(0); 3];
+        let mut max = [T::from_steps(T::STEPS); 3];
+        for axis in 0..3 {
+            let (lower, upper) = (parent.min[axis], parent.max[axis]);
+            let size = upper - lower;
+            if !(size > 0.0 && size.is_finite()) {
+                continue;
+            }
+
+            // Start from the rounded offsets, and correct them where the decoded bound
+            // does not contain the exact bound due to the rounding of the floats.
+            let scale = T::STEPS as f32 / size;
+            let mut min_steps =
+                (((child.min[axis] - lower) * scale).floor().max(0.0) as u32).min(T::STEPS);
+            while min_steps > 0 && Self::decode(lower, upper, min_steps) > child.min[axis] {
+                min_steps -= 1;
+            }
+            let mut max_steps =
+                (((child.max[axis] - lower) * scale).ceil().max(0.0) as u32).min(T::STEPS);
+            while max_steps < T::STEPS && Self::decode(lower, upper, max_steps) < child.max[axis] {
+                max_steps += 1;
+            }
+
+            min[axis] = T::from_steps(min_steps);
+            max[axis] = T::from_steps(max_steps);
+        }
+        (min, max)
+    }
+
+    /// Returns the decoded [`AABB`] of the `child` (`0` for left, `1` for right) of this
+    /// node, whose own [`AABB`] is `aabb`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn child_aabb(&self, aabb: &AABB, child: usize) -> AABB {
+        let min = self.child_min[child];
+        let max = self.child_max[child];
+        let bound =
+            |axis: usize, steps: T| Self::decode(aabb.min[axis], aabb.max[axis], steps.steps());
+        AABB::with_bounds(
+            Point3::new(bound(0, min[0]), bound(1, min[1]), bound(2, min[2])),
+            Point3::new(bound(0, max[0]), bound(1, max[1]), bound(2, max[2])),
+        )
+    }
+}
+
+/// A [`BVH`] of [`CompressedNode`]s, which is created by [`BVH::flatten_compressed`]. The
+/// offset type `T` is `u8` by default.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::flatten_compressed`]: ../bvh/struct.BVH.html#method.flatten_compressed
+/// [`CompressedNode`]: struct.CompressedNode.html
+///
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressedBVH<T: QuantizedOffset = u8> {
+    /// The inner nodes. The root is the first node, unless the [`BVH`] is a single leaf.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub nodes: Vec<CompressedNode<T>>,
+
+    /// The exact [`AABB`] of the root. It is infinite if the root is a leaf.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub aabb: AABB,
+
+    /// The reference to the root, which is a leaf if the [`BVH`] has a single shape.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    root: u32,
+}
+
+impl BVH {
+    /// Compresses the [`BVH`] into a [`CompressedBVH`] with offsets of type `T`.
+    ///
+    /// # Panics
+    /// Panics if the [`BVH`] has `2^31` shapes or inner nodes, or more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let compressed = bvh.flatten_compressed::<u8>();
+    /// assert_eq!(compressed.nodes.len(), 999);
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(compressed.traverse(&ray, &shapes).len(), 1000);
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`CompressedBVH`]: ../compressed_bvh/struct.CompressedBVH.html
+    ///
+    pub fn flatten_compressed<T: QuantizedOffset>(&self) -> CompressedBVH<T> {
+        // The empty `AABB` of an empty `BVH` is never hit, so the root is never visited.
+        if self.nodes.is_empty() {
+            return CompressedBVH {
+                nodes: Vec::new(),
+                aabb: AABB::empty(),
+                root: 0,
+            };
+        }
+
+        let aabb = match self.nodes[0] {
+            BVHNode::Node {
+                ref child_l_aabb,
+                ref child_r_aabb,
+                ..
+            } => child_l_aabb.join(child_r_aabb),
+            BVHNode::Leaf { .. } => AABB::with_bounds(
+                Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+                Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            ),
+        };
+
+        let mut nodes = Vec::with_capacity(self.nodes.len() / 2);
+        let root = self.compress_subtree(0, &aabb, &mut nodes);
+        CompressedBVH { nodes, aabb, root }
+    }
+
+    /// Appends the [`CompressedNode`]s of the subtree of the node at `node_index` to
+    /// `nodes`, and returns the reference to the node. `aabb` is the decoded [`AABB`] of
+    /// the node, relative to which its children are quantized.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`CompressedNode`]: ../compressed_bvh/struct.CompressedNode.html
+    ///
+    fn compress_subtree<T: QuantizedOffset>(
+        &self,
+        node_index: usize,
+        aabb: &AABB,
+        nodes: &mut Vec<CompressedNode<T>>,
+    ) -> u32 {
+        match self.nodes[node_index] {
+            BVHNode::Node {
+                ref child_l_aabb,
+                child_l_index,
+                ref child_r_aabb,
+                child_r_index,
+                ..
+            } => {
+                let (l_min, l_max) = CompressedNode::<T>::quantize(aabb, child_l_aabb);
+                let (r_min, r_max) = CompressedNode::<T>::quantize(aabb, child_r_aabb);
+                let index = nodes.len();
+                assert!(index < LEAF_BIT as usize, "too many nodes to compress");
+                nodes.push(CompressedNode {
+                    child_min: [l_min, r_min],
+                    child_max: [l_max, r_max],
+                    children: [0, 0],
+                });
+
+                let decoded_l = nodes[index].child_aabb(aabb, 0);
+                let decoded_r = nodes[index].child_aabb(aabb, 1);
+                nodes[index].children = [
+                    self.compress_subtree(child_l_index as usize, &decoded_l, nodes),
+                    self.compress_subtree(child_r_index as usize, &decoded_r, nodes),
+                ];
+                index as u32
+            }
+            BVHNode::Leaf { shape_index, .. } => {
+                assert!(shape_index < LEAF_BIT, "too many shapes to compress");
+                shape_index | LEAF_BIT
+            }
+        }
+    }
+}
+
+impl<T: QuantizedOffset> MemoryUsage for CompressedBVH<T> {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.nodes.capacity() * std::mem::size_of::<CompressedNode<T>>()
+    }
+}
+
+impl<T: QuantizedOffset> BoundingHierarchy for CompressedBVH<T> {
+    /// A [`CompressedBVH`] is built from a regular [`BVH`] using the
+    /// [`flatten_compressed`] method.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`CompressedBVH`]: struct.CompressedBVH.html
+    /// [`flatten_compressed`]: ../bvh/struct.BVH.html#method.flatten_compressed
+    ///
+    fn build<Shape: BHShape>(shapes: &mut [Shape]) -> CompressedBVH<T> {
+        let bvh = BVH::build(shapes);
+        bvh.flatten_compressed()
+    }
+
+    /// Traverses a [`CompressedBVH`] with a stack of nodes and their decoded [`AABB`]s.
+    /// The shapes are returned in the same order as by [`BVH::traverse`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::traverse`]: ../bvh/struct.BVH.html#method.traverse
+    /// [`CompressedBVH`]: struct.CompressedBVH.html
+    ///
+    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`CompressedBVH`].
+    ///
+    /// [`CompressedBVH`]: struct.CompressedBVH.html
+    ///
+    fn pretty_print(&self) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let describe = |child: u32| {
+                if child & LEAF_BIT != 0 {
+                    format!("shape {}", child & !LEAF_BIT)
+                } else {
+                    format!("node {}", child)
+                }
+            };
+            println!(
+                "{}\t{}\t{}",
+                i,
+                describe(node.children[0]),
+                describe(node.children[1])
+            );
+        }
+    }
+}
+
+impl<T: QuantizedOffset> IndexedBoundingHierarchy for CompressedBVH<T> {
+    /// Traverses a [`CompressedBVH`] and returns the indices of the shapes whose decoded
+    /// [`AABB`]s were hit by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`CompressedBVH`]: struct.CompressedBVH.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        if self.root & LEAF_BIT != 0 {
+            hit_indices.push((self.root & !LEAF_BIT) as usize);
+            return hit_indices;
+        }
+        // The empty `AABB` of a `CompressedBVH` without shapes is not necessarily missed.
+        if self.nodes.is_empty() || !ray.intersects_aabb(&self.aabb) {
+            return hit_indices;
+        }
+
+        let mut stack = vec![(self.root, self.aabb)];
+        while let Some((child, aabb)) = stack.pop() {
+            if child & LEAF_BIT != 0 {
+                hit_indices.push((child & !LEAF_BIT) as usize);
+                continue;
+            }
+
+            let node = &self.nodes[child as usize];
+            // Push the right child first, so that the left child is visited first.
+            for i in (0..2).rev() {
+                let child_aabb = node.child_aabb(&aabb, i);
+                if ray.intersects_aabb(&child_aabb) {
+                    stack.push((node.children[i], child_aabb));
+                }
+            }
+        }
+
+        hit_indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::bounding_hierarchy::{BoundingHierarchy, IndexedBoundingHierarchy};
+    use crate::bvh::{BVHNode, BVH};
+    use crate::compressed_bvh::{CompressedBVH, CompressedNode, QuantizedOffset};
+    use crate::ray::Ray;
+    use crate::testbase::{
+        build_cubes_bvh, check_traverse_equals_bvh, create_n_cubes, create_ray, default_bounds,
+        traverse_some_bh,
+    };
+    use crate::{Point3, Vector3};
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `CompressedBVH` with 8 bit offsets.
+    fn test_traverse_compressed_bvh_u8() {
+        traverse_some_bh::<CompressedBVH<u8>>();
+    }
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `CompressedBVH` with 16 bit offsets.
+    fn test_traverse_compressed_bvh_u16() {
+        traverse_some_bh::<CompressedBVH<u16>>();
+    }
+
+    #[test]
+    /// Tests the sizes of `CompressedNode`s.
+    fn test_compressed_node_size() {
+        assert_eq!(std::mem::size_of::<CompressedNode<u8>>(), 20);
+        assert_eq!(std::mem::size_of::<CompressedNode<u16>>(), 32);
+    }
+
+    /// Tests whether the decoded `AABB`s of `compressed` contain the exact `AABB`s of the
+    /// `bvh`, and whether `compressed` finds all shapes which the `bvh` finds.
+    fn check_compressed<T: QuantizedOffset>(bvh: &BVH, compressed: &CompressedBVH<T>) {
+        // Walk both trees at once, with the decoded `AABB` of every compressed node.
+        let mut stack = vec![(0, 0, compressed.aabb)];
+        while let Some((node_index, compressed_index, aabb)) = stack.pop() {
+            if let BVHNode::Node {
+                child_l_aabb,
+                child_l_index,
+                child_r_aabb,
+                child_r_index,
+                ..
+            } = bvh.nodes[node_index]
+            {
+                let node = &compressed.nodes[compressed_index];
+                for (i, exact, index) in [
+                    (0, child_l_aabb, child_l_index),
+                    (1, child_r_aabb, child_r_index),
+                ] {
+                    let decoded = node.child_aabb(&aabb, i);
+                    assert!(decoded.contains_aabb(&exact));
+                    if let BVHNode::Node { .. } = bvh.nodes[index as usize] {
+                        stack.push((index as usize, node.children[i] as usize, decoded));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether compressed `AABB`s contain the exact `AABB`s, and whether a
+    /// `CompressedBVH` finds the same shapes as the `BVH`.
+    fn test_compressed_bvh_equals_bvh() {
+        let (triangles, bvh) = build_cubes_bvh();
+        let compressed_u8 = bvh.flatten_compressed::<u8>();
+        let compressed_u16 = bvh.flatten_compressed::<u16>();
+        check_compressed(&bvh, &compressed_u8);
+        check_compressed(&bvh, &compressed_u16);
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| {
+            compressed_u8.traverse(ray, &triangles)
+        });
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| {
+            compressed_u16.traverse(ray, &triangles)
+        });
+    }
+
+    #[test]
+    /// Tests whether quantized bounds are conservative for flat and tiny `AABB`s.
+    fn test_quantize_degenerate_aabbs() {
+        let parent = AABB::with_bounds(Point3::new(0.0, 0.0, 5.0), Point3::new(1.0e6, 1.0, 5.0));
+        let child = AABB::with_bounds(Point3::new(0.1, 0.3, 5.0), Point3::new(0.1, 0.7, 5.0));
+        let (min, max) = CompressedNode::<u8>::quantize(&parent, &child);
+        let node = CompressedNode {
+            child_min: [min, min],
+            child_max: [max, max],
+            children: [0, 0],
+        };
+        assert!(node.child_aabb(&parent, 0).contains_aabb(&child));
+    }
+
+    #[test]
+    /// Tests whether compressing a `BVH` without shapes yields a `CompressedBVH` which is
+    /// never hit.
+    fn test_flatten_compressed_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let compressed = bvh.flatten_compressed::<u8>();
+        assert!(compressed.nodes.is_empty());
+
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+        assert!(compressed.traverse(&ray, &triangles).is_empty());
+    }
+
+    #[test]
+    /// Tests whether children of a node with an infinite extent decode to the bounds of the
+    /// node instead of NaN, so that they are still hit.
+    fn test_quantize_infinite_parent() {
+        let infinite = AABB::with_bounds(
+            Point3::new(f32::NEG_INFINITY, -1.0, -1.0),
+            Point3::new(1.0, 1.0, 1.0),
+        );
+        let finite = AABB::with_bounds(Point3::new(2.0, -1.0, -1.0), Point3::new(3.0, 1.0, 1.0));
+        let bvh = BVH {
+            nodes: vec![
+                BVHNode::Node {
+                    parent_index: 0,
+                    depth: 0,
+                    child_l_aabb: infinite,
+                    child_l_index: 1,
+                    child_r_aabb: finite,
+                    child_r_index: 2,
+                },
+                BVHNode::Leaf {
+                    parent_index: 0,
+                    depth: 1,
+                    shape_index: 0,
+                },
+                BVHNode::Leaf {
+                    parent_index: 0,
+                    depth: 1,
+                    shape_index: 1,
+                },
+            ],
+        };
+        let compressed = bvh.flatten_compressed::<u16>();
+        check_compressed(&bvh, &compressed);
+        for i in 0..2 {
+            let decoded = compressed.nodes[0].child_aabb(&compressed.aabb, i);
+            assert!(!decoded.min.is_nan() && !decoded.max.is_nan());
+        }
+
+        let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(compressed.traverse_indices(&ray), vec![0, 1]);
+    }
+}
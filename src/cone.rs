@@ -0,0 +1,144 @@
+//! Cones with a limited range, like the light cone of a spotlight.
+
+use crate::aabb::{IntersectsAabb, AABB};
+use crate::{Point3, Vector3};
+
+/// A cone with its tip at `apex`, which opens along `direction` by `half_angle` and is cut
+/// off by a sphere of radius `range` around the `apex`.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cone {
+    /// The tip of the cone.
+    pub apex: Point3,
+
+    /// The normalized direction of the axis of the cone.
+    pub direction: Vector3,
+
+    /// The angle between the axis and the surface of the cone in radians.
+    /// Must be smaller than a right angle.
+    pub half_angle: f32,
+
+    /// The distance from the `apex` at which the cone ends.
+    pub range: f32,
+}
+
+impl Cone {
+    /// Creates a new [`Cone`]. `direction` will be normalized.
+    ///
+    /// [`Cone`]: struct.Cone.html
+    ///
+    pub fn new(apex: Point3, direction: Vector3, half_angle: f32, range: f32) -> Cone {
+        Cone {
+            apex,
+            direction: direction.normalize(),
+            half_angle,
+            range,
+        }
+    }
+
+    /// Returns true if the [`Point3`] is inside the [`Cone`].
+    ///
+    /// [`Cone`]: struct.Cone.html
+    /// [`Point3`]: glam::Vec3
+    ///
+    pub fn contains(&self, p: &Point3) -> bool {
+        let offset = *p - self.apex;
+        let distance = offset.length();
+        distance <= self.range && offset.dot(self.direction) >= distance * self.half_angle.cos()
+    }
+
+    /// Tests the intersection of the [`Cone`] with an [`AABB`]. The test is performed
+    /// against the bounding sphere of the [`AABB`], so it may report [`AABB`]s near the
+    /// surface of the [`Cone`] which do not actually intersect it, but it never misses one.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::cone::Cone;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// let cone = Cone::new(
+    ///     Point3::new(0.0, 0.0, 0.0),
+    ///     Vector3::new(1.0, 0.0, 0.0),
+    ///     std::f32::consts::FRAC_PI_4,
+    ///     10.0,
+    /// );
+    /// let inside = AABB::with_bounds(Point3::new(4.0, -1.0, -1.0), Point3::new(5.0, 1.0, 1.0));
+    /// let beside = AABB::with_bounds(Point3::new(1.0, 5.0, -1.0), Point3::new(2.0, 6.0, 1.0));
+    /// let behind = AABB::with_bounds(Point3::new(-5.0, -1.0, -1.0), Point3::new(-4.0, 1.0, 1.0));
+    /// let too_far = AABB::with_bounds(Point3::new(14.0, -1.0, -1.0), Point3::new(15.0, 1.0, 1.0));
+    ///
+    /// assert!(cone.intersects_aabb(&inside));
+    /// assert!(!cone.intersects_aabb(&beside));
+    /// assert!(!cone.intersects_aabb(&behind));
+    /// assert!(!cone.intersects_aabb(&too_far));
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Cone`]: struct.Cone.html
+    ///
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+
+        let radius = aabb.size().length() / 2.0;
+        let offset = aabb.center() - self.apex;
+        let distance_squared = offset.length_squared();
+        if distance_squared > (self.range + radius) * (self.range + radius) {
+            return false;
+        }
+
+        // The distance of the center from the axis, and along the axis.
+        let along_axis = offset.dot(self.direction);
+        let from_axis = (distance_squared - along_axis * along_axis).max(0.0).sqrt();
+
+        // The distance of the center from the surface of the cone, which is negative inside.
+        let (sin, cos) = self.half_angle.sin_cos();
+        let from_surface = cos * from_axis - sin * along_axis;
+        from_surface <= radius && along_axis >= -radius
+    }
+}
+
+impl IntersectsAabb for Cone {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        self.intersects_aabb(aabb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::cone::Cone;
+    use crate::testbase::next_point3;
+    use crate::Point3;
+
+    #[test]
+    /// Tests whether `intersects_aabb` never misses an `AABB` which contains a point
+    /// of the `Cone`.
+    fn test_cone_intersects_aabb() {
+        let bounds = AABB::with_bounds(
+            Point3::new(-10.0, -10.0, -10.0),
+            Point3::new(10.0, 10.0, 10.0),
+        );
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let apex = next_point3(&mut seed, &bounds);
+            let direction = next_point3(&mut seed, &bounds);
+            let half_angle = (next_point3(&mut seed, &bounds).x + 10.0) / 20.0 * 1.5;
+            let range = next_point3(&mut seed, &bounds).x + 10.0;
+            let cone = Cone::new(apex, direction, half_angle, range);
+            let aabb = AABB::empty()
+                .grow(&next_point3(&mut seed, &bounds))
+                .grow(&next_point3(&mut seed, &bounds));
+
+            for _ in 0..100 {
+                let point = next_point3(&mut seed, &aabb);
+                if cone.contains(&point) {
+                    assert!(cone.intersects_aabb(&aabb));
+                }
+            }
+            assert!(cone.contains(&apex));
+        }
+    }
+}
@@ -1,33 +1,48 @@
 //! This module exports methods to flatten the `BVH` and traverse it iteratively.
+//!
+//! Every flat layout implements [`BoundingHierarchy`], so it can be traversed on the CPU
+//! with [`BoundingHierarchy::traverse`] as well as on the GPU. The traversals of a
+//! [`FlatBVH`] and of a [`SkipBVH`] follow the stored indices and need no stack.
+//!
+//! [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+//! [`BoundingHierarchy::traverse`]: ../bounding_hierarchy/trait.BoundingHierarchy.html#tymethod.traverse
+//! [`FlatBVH`]: type.FlatBVH.html
+//! [`SkipBVH`]: type.SkipBVH.html
+//!
 
 use crate::aabb::{Bounded, AABB};
-use crate::bounding_hierarchy::{BHShape, BoundingHierarchy};
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
 use crate::bvh::{BVHNode, BVH};
 use crate::ray::Ray;
+use crate::utils::prefetch;
+use crate::Point3;
 
 /// A structure of a node of a flat [`BVH`]. The structure of the nodes allows for an
 /// iterative traversal approach without the necessity to maintain a stack or queue.
 ///
 /// [`BVH`]: ../bvh/struct.BVH.html
 ///
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
 pub struct FlatNode {
     /// The [`AABB`] of the [`BVH`] node. Prior to testing the [`AABB`] bounds,
-    /// the `entry_index` must be checked. In case the entry_index is [`u32::max_value()`],
+    /// the `entry_index` must be checked. In case the entry_index is [`u32::MAX`],
     /// the [`AABB`] is undefined.
     ///
     /// [`AABB`]: ../aabb/struct.AABB.html
     /// [`BVH`]: ../bvh/struct.BVH.html
-    /// [`u32::max_value()`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
     ///
     pub aabb: AABB,
 
     /// The index of the `FlatNode` to jump to, if the [`AABB`] test is positive.
-    /// If this value is [`u32::max_value()`] then the current node is a leaf node.
+    /// If this value is [`u32::MAX`] then the current node is a leaf node.
     /// Leaf nodes contain a shape index and an exit index. In leaf nodes the
     /// [`AABB`] is undefined.
     ///
     /// [`AABB`]: ../aabb/struct.AABB.html
-    /// [`u32::max_value()`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
     ///
     pub entry_index: u32,
 
@@ -41,6 +56,702 @@ pub struct FlatNode {
     pub shape_index: u32,
 }
 
+/// The memory layout of the nodes of a flat [`BVH`]. The traversal of a flat [`BVH`] is
+/// generic over its [`NodeLayout`], so it is monomorphized for every layout.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`NodeLayout`]: trait.NodeLayout.html
+///
+pub trait NodeLayout: Sized {
+    /// Creates a node. The arguments are the same as the ones fed to the `constructor`
+    /// of [`BVH::flatten_custom`].
+    ///
+    /// [`BVH::flatten_custom`]: ../bvh/struct.BVH.html#method.flatten_custom
+    ///
+    fn new(aabb: &AABB, entry_index: u32, exit_index: u32, shape_index: u32) -> Self;
+
+    /// The index of the node to jump to, if the [`AABB`] test is positive.
+    /// [`u32::MAX`] for leaf nodes.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    ///
+    fn entry_index(&self) -> u32;
+
+    /// The index of the node to jump to, if the [`AABB`] test is negative.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn exit_index(&self) -> u32;
+
+    /// The index of the shape in the shapes array. Only defined for leaf nodes.
+    fn shape_index(&self) -> u32;
+
+    /// Tests whether `ray` intersects the [`AABB`] of this node. Must never return `false`
+    /// if the ray intersects the exact [`AABB`] of the node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn intersects(&self, ray: &Ray) -> bool;
+}
+
+impl NodeLayout for FlatNode {
+    fn new(aabb: &AABB, entry_index: u32, exit_index: u32, shape_index: u32) -> FlatNode {
+        FlatNode {
+            aabb: *aabb,
+            entry_index,
+            exit_index,
+            shape_index,
+        }
+    }
+
+    fn entry_index(&self) -> u32 {
+        self.entry_index
+    }
+
+    fn exit_index(&self) -> u32 {
+        self.exit_index
+    }
+
+    fn shape_index(&self) -> u32 {
+        self.shape_index
+    }
+
+    fn intersects(&self, ray: &Ray) -> bool {
+        ray.intersects_aabb(&self.aabb)
+    }
+}
+
+/// A [`NodeLayout`] which stores its [`AABB`] with reduced precision. Each bound is rounded
+/// outwards to a 16 bit float with the range of an `f32` and an 8 bit mantissa, so the
+/// stored [`AABB`] always contains the exact one. This shrinks a node from 36 to 24 bytes.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`NodeLayout`]: trait.NodeLayout.html
+///
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuantizedFlatNode {
+    /// The lower bound of the [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    min: [u16; 3],
+
+    /// The upper bound of the [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    max: [u16; 3],
+
+    /// See [`FlatNode::entry_index`].
+    ///
+    /// [`FlatNode::entry_index`]: struct.FlatNode.html#structfield.entry_index
+    ///
+    entry_index: u32,
+
+    /// See [`FlatNode::exit_index`].
+    ///
+    /// [`FlatNode::exit_index`]: struct.FlatNode.html#structfield.exit_index
+    ///
+    exit_index: u32,
+
+    /// See [`FlatNode::shape_index`].
+    ///
+    /// [`FlatNode::shape_index`]: struct.FlatNode.html#structfield.shape_index
+    ///
+    shape_index: u32,
+}
+
+impl QuantizedFlatNode {
+    /// Truncates `value` to its upper 16 bits. If `round_up` is `true` the result is not
+    /// smaller than `value`, otherwise it is not greater than `value`.
+    fn quantize(value: f32, round_up: bool) -> u16 {
+        let bits = value.to_bits();
+        let truncated = bits >> 16;
+
+        // Truncation rounds towards zero. Increasing the magnitude rounds away from zero.
+        let towards_zero = (value >= 0.0) != round_up;
+        if towards_zero || truncated << 16 == bits {
+            truncated as u16
+        } else {
+            (truncated + 1) as u16
+        }
+    }
+
+    /// Restores the `f32` from a value created by [`QuantizedFlatNode::quantize`].
+    ///
+    /// [`QuantizedFlatNode::quantize`]: struct.QuantizedFlatNode.html#method.quantize
+    ///
+    fn dequantize(value: u16) -> f32 {
+        f32::from_bits((value as u32) << 16)
+    }
+
+    /// Returns the [`AABB`] of this node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn aabb(&self) -> AABB {
+        AABB::with_bounds(
+            Point3::new(
+                Self::dequantize(self.min[0]),
+                Self::dequantize(self.min[1]),
+                Self::dequantize(self.min[2]),
+            ),
+            Point3::new(
+                Self::dequantize(self.max[0]),
+                Self::dequantize(self.max[1]),
+                Self::dequantize(self.max[2]),
+            ),
+        )
+    }
+}
+
+impl NodeLayout for QuantizedFlatNode {
+    fn new(aabb: &AABB, entry_index: u32, exit_index: u32, shape_index: u32) -> Self {
+        QuantizedFlatNode {
+            min: [
+                Self::quantize(aabb.min.x, false),
+                Self::quantize(aabb.min.y, false),
+                Self::quantize(aabb.min.z, false),
+            ],
+            max: [
+                Self::quantize(aabb.max.x, true),
+                Self::quantize(aabb.max.y, true),
+                Self::quantize(aabb.max.z, true),
+            ],
+            entry_index,
+            exit_index,
+            shape_index,
+        }
+    }
+
+    fn entry_index(&self) -> u32 {
+        self.entry_index
+    }
+
+    fn exit_index(&self) -> u32 {
+        self.exit_index
+    }
+
+    fn shape_index(&self) -> u32 {
+        self.shape_index
+    }
+
+    fn intersects(&self, ray: &Ray) -> bool {
+        ray.intersects_aabb(&self.aabb())
+    }
+}
+
+/// A [`NodeLayout`] which can be uploaded to the GPU as it is. It is `#[repr(C)]` and has no
+/// implicit padding, so a slice of nodes can be viewed as bytes with [`GpuFlatNode::as_bytes`].
+///
+/// The layout takes 48 bytes and matches the following struct under the std140 and std430
+/// rules of GLSL, as well as in WGSL:
+///
+/// ```glsl
+/// struct Node {
+///     vec3 min;          // offset 0
+///     uint entry_index;  // offset 12
+///     vec3 max;          // offset 16
+///     uint exit_index;   // offset 28
+///     uint shape_index;  // offset 32, followed by 12 bytes of padding
+/// };
+/// ```
+///
+/// [`GpuFlatNode::as_bytes`]: struct.GpuFlatNode.html#method.as_bytes
+/// [`NodeLayout`]: trait.NodeLayout.html
+///
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct GpuFlatNode {
+    /// The lower bound of the [`AABB`]. Undefined for leaf nodes.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub min: [f32; 3],
+
+    /// See [`FlatNode::entry_index`].
+    ///
+    /// [`FlatNode::entry_index`]: struct.FlatNode.html#structfield.entry_index
+    ///
+    pub entry_index: u32,
+
+    /// The upper bound of the [`AABB`]. Undefined for leaf nodes.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub max: [f32; 3],
+
+    /// See [`FlatNode::exit_index`].
+    ///
+    /// [`FlatNode::exit_index`]: struct.FlatNode.html#structfield.exit_index
+    ///
+    pub exit_index: u32,
+
+    /// See [`FlatNode::shape_index`].
+    ///
+    /// [`FlatNode::shape_index`]: struct.FlatNode.html#structfield.shape_index
+    ///
+    pub shape_index: u32,
+
+    /// Pads the node to a multiple of 16 bytes, the alignment of `vec3` on the GPU.
+    padding: [u32; 3],
+}
+
+// The GPU struct is 48 bytes, and `as_bytes` relies on the absence of implicit padding.
+const _: () = assert!(std::mem::size_of::<GpuFlatNode>() == 48);
+
+impl GpuFlatNode {
+    /// Returns the bytes of `nodes`, ready to be copied into a GPU buffer.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::flat_bvh::{GpuFlatNode, NodeLayout};
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(1.0,2.0,3.0), Point3::new(4.0,5.0,6.0));
+    /// let nodes = [GpuFlatNode::new(&aabb, 1, 7, u32::MAX)];
+    /// let bytes = GpuFlatNode::as_bytes(&nodes);
+    ///
+    /// assert_eq!(bytes.len(), 48);
+    /// assert_eq!(bytes[12..16], 1u32.to_ne_bytes());
+    /// assert_eq!(bytes[16..20], 4.0f32.to_ne_bytes());
+    /// ```
+    ///
+    pub fn as_bytes(nodes: &[GpuFlatNode]) -> &[u8] {
+        // SAFETY: `GpuFlatNode` is `#[repr(C)]` and consists of 4 byte fields only, so it has
+        // no padding bytes, and `u8` has no alignment requirement.
+        unsafe {
+            std::slice::from_raw_parts(nodes.as_ptr() as *const u8, std::mem::size_of_val(nodes))
+        }
+    }
+}
+
+impl NodeLayout for GpuFlatNode {
+    fn new(aabb: &AABB, entry_index: u32, exit_index: u32, shape_index: u32) -> GpuFlatNode {
+        GpuFlatNode {
+            min: aabb.min.to_array(),
+            entry_index,
+            max: aabb.max.to_array(),
+            exit_index,
+            shape_index,
+            padding: [0; 3],
+        }
+    }
+
+    fn entry_index(&self) -> u32 {
+        self.entry_index
+    }
+
+    fn exit_index(&self) -> u32 {
+        self.exit_index
+    }
+
+    fn shape_index(&self) -> u32 {
+        self.shape_index
+    }
+
+    fn intersects(&self, ray: &Ray) -> bool {
+        ray.intersects_aabb(&AABB::with_bounds(self.min.into(), self.max.into()))
+    }
+}
+
+/// The reasons why [`FlatBVHView::new`] rejects a byte slice.
+///
+/// [`FlatBVHView::new`]: struct.FlatBVHView.html#method.new
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlatBVHViewError {
+    /// The length of the bytes is not a multiple of the size of a node, or zero.
+    Length(usize),
+
+    /// The node at the given index points to a node outside of the [`FlatBVH`], or to a
+    /// node before itself, which would make the traversal loop forever.
+    ///
+    /// [`FlatBVH`]: type.FlatBVH.html
+    ///
+    Index(usize),
+}
+
+impl std::fmt::Display for FlatBVHViewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            FlatBVHViewError::Length(length) => {
+                write!(f, "{} bytes are not a whole number of flat nodes", length)
+            }
+            FlatBVHViewError::Index(index) => {
+                write!(f, "the flat node at index {} has invalid indices", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FlatBVHViewError {}
+
+/// A flat [`BVH`] which is traversed right where its bytes are, e.g. in a memory-mapped
+/// file, without copying or deserializing them first.
+///
+/// The bytes are a sequence of [`GpuFlatNode`]s of [`FlatBVHView::NODE_SIZE`] bytes each,
+/// with all fields in little-endian byte order. This layout is written by
+/// [`BVH::flatten_to_bytes`], and on little-endian machines it is also the layout of
+/// [`GpuFlatNode::as_bytes`]. The bytes need no particular alignment.
+///
+/// # Examples
+/// ```
+/// use bvh::aabb::{AABB, Bounded};
+/// use bvh::bvh::BVH;
+/// use bvh::flat_bvh::FlatBVHView;
+/// use bvh::ray::Ray;
+/// use bvh::{Point3, Vector3};
+/// # use bvh::bounding_hierarchy::BHShape;
+/// # pub struct UnitBox {
+/// #     pub pos: Point3,
+/// #     node_index: usize,
+/// # }
+/// #
+/// # impl Bounded for UnitBox {
+/// #     fn aabb(&self) -> AABB {
+/// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+/// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+/// #         AABB::with_bounds(min, max)
+/// #     }
+/// # }
+/// #
+/// # impl BHShape for UnitBox {
+/// #     fn set_bh_node_index(&mut self, index: usize) {
+/// #         self.node_index = index;
+/// #     }
+/// #
+/// #     fn bh_node_index(&self) -> usize {
+/// #         self.node_index
+/// #     }
+/// # }
+/// #
+/// # fn create_bhshapes() -> Vec<UnitBox> {
+/// #     (0..100)
+/// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+/// #         .collect()
+/// # }
+///
+/// let mut shapes = create_bhshapes();
+/// let bvh = BVH::build(&mut shapes);
+/// let bytes = bvh.flatten_to_bytes();
+///
+/// // The bytes could as well come from a memory-mapped file.
+/// let view = FlatBVHView::new(&bytes).unwrap();
+/// assert_eq!(view.len(), bvh.flatten().len());
+///
+/// let ray = Ray::new(Point3::new(42.0, -10.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+/// assert_eq!(view.traverse(&ray, &shapes).len(), 1);
+/// ```
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::flatten_to_bytes`]: ../bvh/struct.BVH.html#method.flatten_to_bytes
+/// [`FlatBVHView::NODE_SIZE`]: struct.FlatBVHView.html#associatedconstant.NODE_SIZE
+/// [`GpuFlatNode`]: struct.GpuFlatNode.html
+/// [`GpuFlatNode::as_bytes`]: struct.GpuFlatNode.html#method.as_bytes
+///
+#[derive(Debug, Copy, Clone)]
+pub struct FlatBVHView<'a> {
+    /// The validated bytes of the nodes.
+    bytes: &'a [u8],
+}
+
+impl<'a> FlatBVHView<'a> {
+    /// The size of a node in bytes.
+    pub const NODE_SIZE: usize = std::mem::size_of::<GpuFlatNode>();
+
+    /// Creates a [`FlatBVHView`] of `bytes`, after checking that they are a whole number of
+    /// nodes, and that the traversal can neither leave the nodes nor loop forever. The shape
+    /// indices cannot be checked without the shapes, so a traversal panics if a leaf refers
+    /// to a shape which does not exist.
+    ///
+    /// # Errors
+    /// Returns [`FlatBVHViewError::Length`] if the length of `bytes` is not a positive
+    /// multiple of [`FlatBVHView::NODE_SIZE`], and [`FlatBVHViewError::Index`] for the first
+    /// node whose entry or exit index does not point to a later node or to the end.
+    ///
+    /// [`FlatBVHView`]: struct.FlatBVHView.html
+    /// [`FlatBVHView::NODE_SIZE`]: struct.FlatBVHView.html#associatedconstant.NODE_SIZE
+    /// [`FlatBVHViewError::Index`]: enum.FlatBVHViewError.html#variant.Index
+    /// [`FlatBVHViewError::Length`]: enum.FlatBVHViewError.html#variant.Length
+    ///
+    // `usize::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn new(bytes: &'a [u8]) -> Result<FlatBVHView<'a>, FlatBVHViewError> {
+        if bytes.is_empty() || bytes.len() % Self::NODE_SIZE != 0 {
+            return Err(FlatBVHViewError::Length(bytes.len()));
+        }
+
+        let view = FlatBVHView { bytes };
+        let len = view.len();
+        for index in 0..len {
+            let node = view.node(index);
+            let entry_is_valid = node.entry_index == u32::MAX
+                || (node.entry_index as usize > index && (node.entry_index as usize) < len);
+            let exit_is_valid = node.exit_index as usize > index && node.exit_index as usize <= len;
+            if !entry_is_valid || !exit_is_valid {
+                return Err(FlatBVHViewError::Index(index));
+            }
+        }
+        Ok(view)
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.bytes.len() / Self::NODE_SIZE
+    }
+
+    /// Returns true if there are no nodes, which is never the case for a valid view.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Decodes the node at `index`.
+    pub fn node(&self, index: usize) -> GpuFlatNode {
+        let bytes = &self.bytes[index * Self::NODE_SIZE..(index + 1) * Self::NODE_SIZE];
+        let word = |i: usize| {
+            [
+                bytes[4 * i],
+                bytes[4 * i + 1],
+                bytes[4 * i + 2],
+                bytes[4 * i + 3],
+            ]
+        };
+        let float = |i: usize| f32::from_le_bytes(word(i));
+        let uint = |i: usize| u32::from_le_bytes(word(i));
+        GpuFlatNode {
+            min: [float(0), float(1), float(2)],
+            entry_index: uint(3),
+            max: [float(4), float(5), float(6)],
+            exit_index: uint(7),
+            shape_index: uint(8),
+            padding: [0; 3],
+        }
+    }
+
+    /// Returns the shapes whose [`AABB`]s are hit by `ray`, like the traversal of a
+    /// [`FlatBVH`].
+    ///
+    /// # Panics
+    /// Panics if a leaf refers to a shape which is not in `shapes`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`FlatBVH`]: type.FlatBVH.html
+    ///
+    pub fn traverse<'b, T: Bounded>(&self, ray: &Ray, shapes: &'b [T]) -> Vec<&'b T> {
+        let mut hit_shapes = Vec::new();
+        self.traverse_with(ray, |shape_index| {
+            let shape = &shapes[shape_index];
+            if ray.intersects_aabb(&shape.aabb()) {
+                hit_shapes.push(shape);
+            }
+        });
+        hit_shapes
+    }
+
+    /// Returns the indices of the shapes whose leaves are reached by `ray`, like the
+    /// traversal of a [`FlatBVH`].
+    ///
+    /// [`FlatBVH`]: type.FlatBVH.html
+    ///
+    pub fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        self.traverse_with(ray, |shape_index| hit_indices.push(shape_index));
+        hit_indices
+    }
+
+    /// Calls `visit_leaf` with the shape index of every leaf which is reached by `ray`.
+    fn traverse_with<F: FnMut(usize)>(&self, ray: &Ray, mut visit_leaf: F) {
+        let mut index = 0;
+        while index < self.len() {
+            let node = self.node(index);
+            if node.entry_index == u32::MAX {
+                visit_leaf(node.shape_index as usize);
+                index = node.exit_index as usize;
+            } else if node.intersects(ray) {
+                index = node.entry_index as usize;
+            } else {
+                index = node.exit_index as usize;
+            }
+        }
+    }
+}
+
+impl BVH {
+    /// Flattens the [`BVH`] into the bytes of the nodes of a [`FlatBVHView`], which can be
+    /// written to a file and traversed from there without deserializing it.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`FlatBVHView`]: struct.FlatBVHView.html
+    ///
+    pub fn flatten_to_bytes(&self) -> Vec<u8> {
+        let nodes = self.flatten_layout::<GpuFlatNode>();
+        let mut bytes = Vec::with_capacity(nodes.len() * FlatBVHView::NODE_SIZE);
+        for node in &nodes {
+            for &value in node.min.iter() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&node.entry_index.to_le_bytes());
+            for &value in node.max.iter() {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&node.exit_index.to_le_bytes());
+            bytes.extend_from_slice(&node.shape_index.to_le_bytes());
+            bytes.extend_from_slice(&[0; 12]);
+        }
+        bytes
+    }
+}
+
+/// A node of a [`SkipBVH`]. Unlike a [`FlatBVH`], a [`SkipBVH`] has one node per node of
+/// the [`BVH`], in depth-first order. Every node stores its own [`AABB`], including leaves,
+/// and the index of the node after its subtree.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`FlatBVH`]: type.FlatBVH.html
+/// [`SkipBVH`]: type.SkipBVH.html
+///
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkipNode {
+    /// The [`AABB`] of the subtree of this node. For a [`BVH`] which consists of a single
+    /// leaf, the [`AABB`] of the root is infinite.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub aabb: AABB,
+
+    /// The index of the node to jump to, if the [`AABB`] test is negative or this node is a
+    /// leaf. If the [`AABB`] test of an inner node is positive, the traversal proceeds with
+    /// the next node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub skip_index: u32,
+
+    /// The index of the shape in the shapes array. [`u32::MAX`] for inner nodes.
+    ///
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    ///
+    pub shape_index: u32,
+}
+
+/// A node of a [`BreadthFirstBVH`]. The two children of an inner node are stored next to
+/// each other, the left child at `child_index` and the right child at `child_index + 1`.
+///
+/// [`BreadthFirstBVH`]: struct.BreadthFirstBVH.html
+///
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreadthFirstNode {
+    /// The [`AABB`] of the subtree of this node. Like for a [`SkipNode`], the [`AABB`] of
+    /// the root is infinite.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`SkipNode`]: struct.SkipNode.html
+    ///
+    pub aabb: AABB,
+
+    /// The index of the left child. [`u32::MAX`] for leaf nodes.
+    ///
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    ///
+    pub child_index: u32,
+
+    /// The index of the shape in the shapes array. [`u32::MAX`] for inner nodes.
+    ///
+    /// [`u32::MAX`]: https://doc.rust-lang.org/std/u32/constant.MAX.html
+    ///
+    pub shape_index: u32,
+}
+
+/// A flat [`BVH`] in breadth-first order, in which the nodes of each level of the tree are
+/// contiguous. It suits traversals which process the tree level by level, with all rays
+/// of a wavefront at once.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+///
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct BreadthFirstBVH {
+    /// The nodes in breadth-first order. The root is the first node.
+    pub nodes: Vec<BreadthFirstNode>,
+
+    /// The index of the first node of every level, followed by the number of nodes. The
+    /// nodes of level `i` are `nodes[levels[i]..levels[i + 1]]`.
+    pub levels: Vec<u32>,
+}
+
+/// A node of a flat [`BVH`] as it is passed to the `encoder` of [`BVH::flatten_with`].
+/// The indices refer to positions in the flat [`BVH`]. Unlike in a [`FlatNode`], the kind
+/// of the node is given by the variant instead of a sentinel value, so the `encoder` is
+/// free to choose its own sentinel values and widths of the indices.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::flatten_with`]: ../bvh/struct.BVH.html#method.flatten_with
+/// [`FlatNode`]: struct.FlatNode.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlatNodeDescriptor {
+    /// A node which guards a subtree with an [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Branch {
+        /// The [`AABB`] of the subtree.
+        ///
+        /// [`AABB`]: ../aabb/struct.AABB.html
+        ///
+        aabb: AABB,
+
+        /// The index of the node to jump to, if the [`AABB`] test is positive. This is
+        /// always the index of the node which follows this one.
+        ///
+        /// [`AABB`]: ../aabb/struct.AABB.html
+        ///
+        entry_index: u32,
+
+        /// The index of the node to jump to, if the [`AABB`] test is negative. This is the
+        /// index of the node after the subtree, which equals the number of nodes for the
+        /// last subtree.
+        ///
+        /// [`AABB`]: ../aabb/struct.AABB.html
+        ///
+        exit_index: u32,
+    },
+
+    /// A node which refers to a shape. Its [`AABB`] has already been tested by the
+    /// [`Branch`] in front of it, unless the leaf is the only node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Branch`]: #variant.Branch
+    ///
+    Leaf {
+        /// The index of the shape in the shapes array.
+        shape_index: u32,
+
+        /// The index of the node to continue with.
+        exit_index: u32,
+    },
+}
+
+/// Returns the [`AABB`] which contains everything. It is used for the root of flat
+/// [`BVH`]s whose nodes store their own [`AABB`], because the root has no stored [`AABB`].
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: ../bvh/struct.BVH.html
+///
+fn infinite_aabb() -> AABB {
+    AABB::with_bounds(
+        Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+    )
+}
+
 impl BVHNode {
     /// Creates a flat node from a `BVH` inner node and its `AABB`. Returns the next free index.
     /// TODO: change the algorithm which pushes `FlatNode`s to a vector to not use indices this
@@ -70,7 +781,7 @@ impl BVHNode {
             this_aabb,
             (next_free + 1) as u32,
             index_after_subtree as u32,
-            u32::max_value(),
+            u32::MAX,
         );
         vec[next_free] = navigator_node;
         index_after_subtree
@@ -81,77 +792,267 @@ impl BVHNode {
     ///
     /// [`BVH`]: ../bvh/struct.BVH.html
     ///
-    pub fn flatten_custom<F, FNodeType>(
-        &self,
-        nodes: &[BVHNode],
-        vec: &mut Vec<FNodeType>,
-        next_free: usize,
-        constructor: &F,
-    ) -> usize
+    pub fn flatten_custom<F, FNodeType>(
+        &self,
+        nodes: &[BVHNode],
+        vec: &mut Vec<FNodeType>,
+        next_free: usize,
+        constructor: &F,
+    ) -> usize
+    where
+        F: Fn(&AABB, u32, u32, u32) -> FNodeType,
+    {
+        match *self {
+            BVHNode::Node {
+                ref child_l_aabb,
+                child_l_index,
+                ref child_r_aabb,
+                child_r_index,
+                ..
+            } => {
+                let index_after_child_l = nodes[child_l_index as usize].create_flat_branch(
+                    nodes,
+                    child_l_aabb,
+                    vec,
+                    next_free,
+                    constructor,
+                );
+                nodes[child_r_index as usize].create_flat_branch(
+                    nodes,
+                    child_r_aabb,
+                    vec,
+                    index_after_child_l,
+                    constructor,
+                )
+            }
+            BVHNode::Leaf { shape_index, .. } => {
+                let mut next_shape = next_free;
+                next_shape += 1;
+                let leaf_node =
+                    constructor(&AABB::empty(), u32::MAX, next_shape as u32, shape_index);
+                vec.push(leaf_node);
+
+                next_shape
+            }
+        }
+    }
+}
+
+/// A flat [`BVH`]. Represented by a vector of [`FlatNode`]s. The [`FlatBVH`] is designed for use
+/// where a recursive traversal of a data structure is not possible, for example shader programs.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`FlatNode`]: struct.FlatNode.html
+/// [`FlatBVH`]: struct.FlatBVH.html
+///
+#[allow(clippy::upper_case_acronyms)]
+pub type FlatBVH = Vec<FlatNode>;
+
+/// A flat [`BVH`] with skip pointers. Represented by a vector of [`SkipNode`]s in depth-first
+/// order. Like a [`FlatBVH`] it is traversed without a stack, but it has fewer nodes.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`FlatBVH`]: type.FlatBVH.html
+/// [`SkipNode`]: struct.SkipNode.html
+///
+#[allow(clippy::upper_case_acronyms)]
+pub type SkipBVH = Vec<SkipNode>;
+
+impl BVH {
+    /// Flattens the [`BVH`] so that it can be traversed iteratively.
+    /// Constructs the flat nodes using the supplied function.
+    /// This function can be used, when the flat bvh nodes should be of some particular
+    /// non-default structure.
+    /// The `constructor` is fed the following arguments in this order:
+    ///
+    /// 1 - &AABB: The enclosing `AABB`
+    /// 2 - u32: The index of the nested node
+    /// 3 - u32: The exit index
+    /// 4 - u32: The shape index
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// use bvh::ray::Ray;
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub id: i32,
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl UnitBox {
+    /// #     pub fn new(id: i32, pos: Point3) -> UnitBox {
+    /// #         UnitBox {
+    /// #             id: id,
+    /// #             pos: pos,
+    /// #             node_index: 0,
+    /// #         }
+    /// #     }
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     let mut shapes = Vec::new();
+    /// #     for i in 0..1000 {
+    /// #         let position = Point3::new(i as f32, i as f32, i as f32);
+    /// #         shapes.push(UnitBox::new(i, position));
+    /// #     }
+    /// #     shapes
+    /// # }
+    ///
+    /// struct CustomStruct {
+    ///     aabb: AABB,
+    ///     entry_index: u32,
+    ///     exit_index: u32,
+    ///     shape_index: u32,
+    /// }
+    ///
+    /// let custom_constructor = |aabb: &AABB, entry, exit, shape_index| {
+    ///     CustomStruct {
+    ///         aabb: *aabb,
+    ///         entry_index: entry,
+    ///         exit_index: exit,
+    ///         shape_index: shape_index,
+    ///     }
+    /// };
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let custom_flat_bvh = bvh.flatten_custom(&custom_constructor);
+    /// ```
+    pub fn flatten_custom<F, FNodeType>(&self, constructor: &F) -> Vec<FNodeType>
+    where
+        F: Fn(&AABB, u32, u32, u32) -> FNodeType,
+    {
+        let mut vec = Vec::new();
+        if !self.nodes.is_empty() {
+            self.nodes[0].flatten_custom(&self.nodes, &mut vec, 0, constructor);
+        }
+        vec
+    }
+
+    /// Flattens the [`BVH`] so that it can be traversed iteratively, and encodes every
+    /// node with the `encoder`. The nodes are passed to the `encoder` in the order of the
+    /// flat [`BVH`], as [`FlatNodeDescriptor`]s.
+    ///
+    /// In contrast to [`BVH::flatten_custom`], the `encoder` decides how leaves are marked
+    /// and how wide the indices are, so the result can match the node layout of a shader.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::flat_bvh::FlatNodeDescriptor;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// /// The node of a shader, which marks leaves with a negative shape index.
+    /// struct GpuNode {
+    ///     min: [f32; 4],
+    ///     max: [f32; 4],
+    ///     exit_index: u16,
+    ///     shape_index: i16,
+    /// }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let gpu_nodes = bvh.flatten_with(|node| match *node {
+    ///     FlatNodeDescriptor::Branch { aabb, exit_index, .. } => GpuNode {
+    ///         min: aabb.min.extend(0.0).to_array(),
+    ///         max: aabb.max.extend(0.0).to_array(),
+    ///         exit_index: exit_index as u16,
+    ///         shape_index: -1,
+    ///     },
+    ///     FlatNodeDescriptor::Leaf { shape_index, exit_index } => GpuNode {
+    ///         min: [0.0; 4],
+    ///         max: [0.0; 4],
+    ///         exit_index: exit_index as u16,
+    ///         shape_index: shape_index as i16,
+    ///     },
+    /// });
+    ///
+    /// assert_eq!(gpu_nodes.len(), bvh.flatten().len());
+    /// assert_eq!(gpu_nodes.iter().filter(|node| node.shape_index >= 0).count(), 1000);
+    /// ```
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVH::flatten_custom`]: ../bvh/struct.BVH.html#method.flatten_custom
+    /// [`FlatNodeDescriptor`]: ../flat_bvh/enum.FlatNodeDescriptor.html
+    ///
+    pub fn flatten_with<F, N>(&self, encoder: F) -> Vec<N>
     where
-        F: Fn(&AABB, u32, u32, u32) -> FNodeType,
+        F: FnMut(&FlatNodeDescriptor) -> N,
     {
-        match *self {
-            BVHNode::Node {
-                ref child_l_aabb,
-                child_l_index,
-                ref child_r_aabb,
-                child_r_index,
-                ..
-            } => {
-                let index_after_child_l = nodes[child_l_index].create_flat_branch(
-                    nodes,
-                    child_l_aabb,
-                    vec,
-                    next_free,
-                    constructor,
-                );
-                nodes[child_r_index].create_flat_branch(
-                    nodes,
-                    child_r_aabb,
-                    vec,
-                    index_after_child_l,
-                    constructor,
-                )
-            }
-            BVHNode::Leaf { shape_index, .. } => {
-                let mut next_shape = next_free;
-                next_shape += 1;
-                let leaf_node = constructor(
-                    &AABB::empty(),
-                    u32::max_value(),
-                    next_shape as u32,
-                    shape_index as u32,
-                );
-                vec.push(leaf_node);
-
-                next_shape
+        let descriptors = self.flatten_custom(&|aabb, entry_index, exit_index, shape_index| {
+            if entry_index == u32::MAX {
+                FlatNodeDescriptor::Leaf {
+                    shape_index,
+                    exit_index,
+                }
+            } else {
+                FlatNodeDescriptor::Branch {
+                    aabb: *aabb,
+                    entry_index,
+                    exit_index,
+                }
             }
-        }
+        });
+        descriptors.iter().map(encoder).collect()
     }
-}
-
-/// A flat [`BVH`]. Represented by a vector of [`FlatNode`]s. The [`FlatBVH`] is designed for use
-/// where a recursive traversal of a data structure is not possible, for example shader programs.
-///
-/// [`BVH`]: ../bvh/struct.BVH.html
-/// [`FlatNode`]: struct.FlatNode.html
-/// [`FlatBVH`]: struct.FlatBVH.html
-///
-#[allow(clippy::upper_case_acronyms)]
-pub type FlatBVH = Vec<FlatNode>;
 
-impl BVH {
     /// Flattens the [`BVH`] so that it can be traversed iteratively.
-    /// Constructs the flat nodes using the supplied function.
-    /// This function can be used, when the flat bvh nodes should be of some particular
-    /// non-default structure.
-    /// The `constructor` is fed the following arguments in this order:
-    ///
-    /// 1 - &AABB: The enclosing `AABB`
-    /// 2 - u32: The index of the nested node
-    /// 3 - u32: The exit index
-    /// 4 - u32: The shape index
     ///
     /// [`BVH`]: ../bvh/struct.BVH.html
     ///
@@ -206,63 +1107,345 @@ impl BVH {
     /// #     shapes
     /// # }
     ///
-    /// struct CustomStruct {
-    ///     aabb: AABB,
-    ///     entry_index: u32,
-    ///     exit_index: u32,
-    ///     shape_index: u32,
-    /// }
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let flat_bvh = bvh.flatten();
+    /// ```
+    pub fn flatten(&self) -> FlatBVH {
+        self.flatten_layout()
+    }
+
+    /// Flattens the [`BVH`] into a [`SkipBVH`], which has one [`SkipNode`] per node of the
+    /// [`BVH`] in depth-first order. A traversal starts at index `0`. If the [`AABB`] of an
+    /// inner node is hit, it continues with the next node, otherwise and after every leaf
+    /// it continues at the `skip_index`. It ends when the index reaches the length of the
+    /// [`SkipBVH`].
     ///
-    /// let custom_constructor = |aabb: &AABB, entry, exit, shape_index| {
-    ///     CustomStruct {
-    ///         aabb: *aabb,
-    ///         entry_index: entry,
-    ///         exit_index: exit,
-    ///         shape_index: shape_index,
-    ///     }
-    /// };
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let skip_bvh = bvh.flatten_depth_first();
+    /// assert_eq!(skip_bvh.len(), bvh.nodes.len());
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(skip_bvh.traverse(&ray, &shapes).len(), 1000);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`SkipBVH`]: type.SkipBVH.html
+    /// [`SkipNode`]: struct.SkipNode.html
+    ///
+    pub fn flatten_depth_first(&self) -> SkipBVH {
+        let mut vec = Vec::with_capacity(self.nodes.len());
+        if !self.nodes.is_empty() {
+            self.flatten_depth_first_subtree(0, &infinite_aabb(), &mut vec);
+        }
+        vec
+    }
+
+    /// Appends the [`SkipNode`]s of the subtree of the node at `node_index`, whose [`AABB`]
+    /// is `aabb`, to `vec`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`SkipNode`]: struct.SkipNode.html
+    ///
+    fn flatten_depth_first_subtree(&self, node_index: usize, aabb: &AABB, vec: &mut SkipBVH) {
+        let index = vec.len();
+        match self.nodes[node_index] {
+            BVHNode::Node {
+                ref child_l_aabb,
+                child_l_index,
+                ref child_r_aabb,
+                child_r_index,
+                ..
+            } => {
+                vec.push(SkipNode {
+                    aabb: *aabb,
+                    skip_index: 0,
+                    shape_index: u32::MAX,
+                });
+                self.flatten_depth_first_subtree(child_l_index as usize, child_l_aabb, vec);
+                self.flatten_depth_first_subtree(child_r_index as usize, child_r_aabb, vec);
+                vec[index].skip_index = vec.len() as u32;
+            }
+            BVHNode::Leaf { shape_index, .. } => {
+                vec.push(SkipNode {
+                    aabb: *aabb,
+                    skip_index: index as u32 + 1,
+                    shape_index,
+                });
+            }
+        }
+    }
+
+    /// Flattens the [`BVH`] into a [`BreadthFirstBVH`], in which the nodes of each level
+    /// of the tree are contiguous.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let bfs_bvh = bvh.flatten_breadth_first();
+    /// assert_eq!(bfs_bvh.levels.first(), Some(&0));
+    /// assert_eq!(bfs_bvh.levels.last(), Some(&(bfs_bvh.nodes.len() as u32)));
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(bfs_bvh.traverse(&ray, &shapes).len(), 1000);
+    /// ```
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BreadthFirstBVH`]: ../flat_bvh/struct.BreadthFirstBVH.html
+    ///
+    pub fn flatten_breadth_first(&self) -> BreadthFirstBVH {
+        // The queue holds the index, `AABB` and depth of every node in breadth-first order,
+        // so the position of a node in the queue is its index in the flat `BVH`.
+        let mut queue = Vec::with_capacity(self.nodes.len());
+        if !self.nodes.is_empty() {
+            queue.push((0, infinite_aabb(), 0));
+        }
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        let mut levels = Vec::new();
+        while let Some(&(node_index, aabb, depth)) = queue.get(nodes.len()) {
+            if levels.len() == depth {
+                levels.push(nodes.len() as u32);
+            }
+            match self.nodes[node_index] {
+                BVHNode::Node {
+                    child_l_aabb,
+                    child_l_index,
+                    child_r_aabb,
+                    child_r_index,
+                    ..
+                } => {
+                    nodes.push(BreadthFirstNode {
+                        aabb,
+                        child_index: queue.len() as u32,
+                        shape_index: u32::MAX,
+                    });
+                    queue.push((child_l_index as usize, child_l_aabb, depth + 1));
+                    queue.push((child_r_index as usize, child_r_aabb, depth + 1));
+                }
+                BVHNode::Leaf { shape_index, .. } => {
+                    nodes.push(BreadthFirstNode {
+                        aabb,
+                        child_index: u32::MAX,
+                        shape_index,
+                    });
+                }
+            }
+        }
+        levels.push(nodes.len() as u32);
+
+        BreadthFirstBVH { nodes, levels }
+    }
+
+    /// Reconstructs a [`BVH`] from the [`FlatBVH`] created by [`BVH::flatten`]. The nodes
+    /// are numbered in depth-first order, like by [`BVH::build`], so the node indices which
+    /// the shapes received from [`BVH::build`] stay valid, unless the [`BVH`] was modified
+    /// before it was flattened.
+    ///
+    /// # Panics
+    /// Panics if `flat` is empty or not a valid [`FlatBVH`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bvh::BVH;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
     ///
     /// let mut shapes = create_bhshapes();
     /// let bvh = BVH::build(&mut shapes);
-    /// let custom_flat_bvh = bvh.flatten_custom(&custom_constructor);
+    /// let flat_bvh = bvh.flatten();
+    ///
+    /// let restored = BVH::from_flat(&flat_bvh);
+    /// assert_eq!(restored.nodes, bvh.nodes);
+    /// restored.assert_consistent(&shapes);
     /// ```
-    pub fn flatten_custom<F, FNodeType>(&self, constructor: &F) -> Vec<FNodeType>
-    where
-        F: Fn(&AABB, u32, u32, u32) -> FNodeType,
-    {
-        let mut vec = Vec::new();
-        self.nodes[0].flatten_custom(&self.nodes, &mut vec, 0, constructor);
-        vec
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVH::build`]: ../bvh/struct.BVH.html#method.build
+    /// [`BVH::flatten`]: ../bvh/struct.BVH.html#method.flatten
+    /// [`FlatBVH`]: type.FlatBVH.html
+    ///
+    pub fn from_flat(flat: &[FlatNode]) -> BVH {
+        let mut nodes = Vec::with_capacity(flat.len() * 2 / 3 + 1);
+        BVH::node_from_flat(flat, 0, 0, 0, &mut nodes);
+        BVH { nodes }
     }
 
-    /// Flattens the [`BVH`] so that it can be traversed iteratively.
+    /// Appends the [`BVHNode`]s of the subtree which starts at `flat_index` in `flat` to
+    /// `nodes`. Returns the index of the root of the subtree.
     ///
-    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVHNode`]: ../bvh/enum.BVHNode.html
+    ///
+    fn node_from_flat(
+        flat: &[FlatNode],
+        flat_index: usize,
+        parent_index: usize,
+        depth: u32,
+        nodes: &mut Vec<BVHNode>,
+    ) -> usize {
+        let node_index = nodes.len();
+        let left = &flat[flat_index];
+        if left.entry_index == u32::MAX {
+            nodes.push(BVHNode::Leaf {
+                parent_index: parent_index as u32,
+                depth,
+                shape_index: left.shape_index,
+            });
+            return node_index;
+        }
+
+        // The subtree of a node starts with the navigator node of its left child. The
+        // navigator node of its right child follows after the subtree of the left child.
+        let right = &flat[left.exit_index as usize];
+        nodes.push(BVHNode::create_dummy());
+        let child_l_index = BVH::node_from_flat(
+            flat,
+            left.entry_index as usize,
+            node_index,
+            depth + 1,
+            nodes,
+        );
+        let child_r_index = BVH::node_from_flat(
+            flat,
+            right.entry_index as usize,
+            node_index,
+            depth + 1,
+            nodes,
+        );
+        nodes[node_index] = BVHNode::Node {
+            parent_index: parent_index as u32,
+            depth,
+            child_l_index: child_l_index as u32,
+            child_l_aabb: left.aabb,
+            child_r_index: child_r_index as u32,
+            child_r_aabb: right.aabb,
+        };
+        node_index
+    }
+
+    /// Flattens the [`BVH`] into nodes of the given [`NodeLayout`].
     ///
     /// # Example
     ///
     /// ```
     /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
     /// use bvh::bvh::BVH;
-    /// use bvh::{Point3, Vector3};
+    /// use bvh::flat_bvh::QuantizedFlatNode;
     /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
     /// # use bvh::bounding_hierarchy::BHShape;
     /// # pub struct UnitBox {
-    /// #     pub id: i32,
     /// #     pub pos: Point3,
     /// #     node_index: usize,
     /// # }
     /// #
-    /// # impl UnitBox {
-    /// #     pub fn new(id: i32, pos: Point3) -> UnitBox {
-    /// #         UnitBox {
-    /// #             id: id,
-    /// #             pos: pos,
-    /// #             node_index: 0,
-    /// #         }
-    /// #     }
-    /// # }
-    /// #
     /// # impl Bounded for UnitBox {
     /// #     fn aabb(&self) -> AABB {
     /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
@@ -282,37 +1465,56 @@ impl BVH {
     /// # }
     /// #
     /// # fn create_bhshapes() -> Vec<UnitBox> {
-    /// #     let mut shapes = Vec::new();
-    /// #     for i in 0..1000 {
-    /// #         let position = Point3::new(i as f32, i as f32, i as f32);
-    /// #         shapes.push(UnitBox::new(i, position));
-    /// #     }
-    /// #     shapes
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
     /// # }
     ///
     /// let mut shapes = create_bhshapes();
     /// let bvh = BVH::build(&mut shapes);
-    /// let flat_bvh = bvh.flatten();
+    /// let flat_bvh = bvh.flatten_layout::<QuantizedFlatNode>();
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(flat_bvh.traverse(&ray, &shapes).len(), 1000);
     /// ```
-    pub fn flatten(&self) -> FlatBVH {
-        self.flatten_custom(&|aabb, entry, exit, shape| FlatNode {
-            aabb: *aabb,
-            entry_index: entry,
-            exit_index: exit,
-            shape_index: shape,
-        })
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`NodeLayout`]: trait.NodeLayout.html
+    ///
+    pub fn flatten_layout<N: NodeLayout>(&self) -> Vec<N> {
+        self.flatten_custom(&N::new)
+    }
+}
+
+impl<N: NodeLayout> MemoryUsage for Vec<N> {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity() * std::mem::size_of::<N>()
+    }
+}
+
+impl MemoryUsage for SkipBVH {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.capacity() * std::mem::size_of::<SkipNode>()
     }
 }
 
-impl BoundingHierarchy for FlatBVH {
+impl MemoryUsage for BreadthFirstBVH {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.nodes.capacity() * std::mem::size_of::<BreadthFirstNode>()
+            + self.levels.capacity() * std::mem::size_of::<u32>()
+    }
+}
+
+impl<N: NodeLayout> BoundingHierarchy for Vec<N> {
     /// A [`FlatBVH`] is built from a regular [`BVH`] using the [`flatten`] method.
     ///
     /// [`FlatBVH`]: struct.FlatBVH.html
     /// [`BVH`]: ../bvh/struct.BVH.html
     ///
-    fn build<T: BHShape>(shapes: &mut [T]) -> FlatBVH {
+    fn build<T: BHShape>(shapes: &mut [T]) -> Vec<N> {
         let bvh = BVH::build(shapes);
-        bvh.flatten()
+        bvh.flatten_layout()
     }
 
     /// Traverses a [`FlatBVH`] structure iteratively.
@@ -378,58 +1580,214 @@ impl BoundingHierarchy for FlatBVH {
     /// let flat_bvh = FlatBVH::build(&mut shapes);
     /// let hit_shapes = flat_bvh.traverse(&ray, &shapes);
     /// ```
-    fn traverse<'a, T: Bounded>(&'a self, ray: &Ray, shapes: &'a [T]) -> Vec<&T> {
-        let mut hit_shapes = Vec::new();
-        let mut index = 0;
+    fn traverse<'a, T: Bounded>(&'a self, ray: &Ray, shapes: &'a [T]) -> Vec<&'a T> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`FlatBVH`].
+    ///
+    /// [`FlatBVH`]: struct.FlatBVH.html
+    ///
+    fn pretty_print(&self) {
+        for (i, node) in self.iter().enumerate() {
+            println!(
+                "{}\tentry {}\texit {}\tshape {}",
+                i,
+                node.entry_index(),
+                node.exit_index(),
+                node.shape_index()
+            );
+        }
+    }
+}
 
-        // The traversal loop should terminate when `max_length` is set as the next node index.
+impl<N: NodeLayout> IndexedBoundingHierarchy for Vec<N> {
+    /// Traverses a [`FlatBVH`] structure iteratively and returns the indices of the shapes
+    /// whose [`AABB`]s were hit by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`FlatBVH`]: struct.FlatBVH.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        let mut index = 0;
         let max_length = self.len();
 
-        // Iterate while the node index is valid.
         while index < max_length {
             let node = &self[index];
 
-            if node.entry_index == u32::max_value() {
-                // If the entry_index is MAX_UINT32, then it's a leaf node.
-                let shape = &shapes[node.shape_index as usize];
-                if ray.intersects_aabb(&shape.aabb()) {
-                    hit_shapes.push(shape);
+            if node.entry_index() == u32::MAX {
+                // The `AABB` of a leaf has already been tested by the node which points to it.
+                hit_indices.push(node.shape_index() as usize);
+                index = node.exit_index() as usize;
+            } else {
+                prefetch(self, node.exit_index() as usize);
+                if node.intersects(ray) {
+                    index = node.entry_index() as usize;
+                } else {
+                    index = node.exit_index() as usize;
                 }
+            }
+        }
 
-                // Exit the current node.
-                index = node.exit_index as usize;
-            } else if ray.intersects_aabb(&node.aabb) {
-                // If entry_index is not MAX_UINT32 and the AABB test passes, then
-                // proceed to the node in entry_index (which goes down the bvh branch).
-                index = node.entry_index as usize;
+        hit_indices
+    }
+}
+
+impl BoundingHierarchy for SkipBVH {
+    /// A [`SkipBVH`] is built from a regular [`BVH`] using the [`flatten_depth_first`]
+    /// method.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`SkipBVH`]: type.SkipBVH.html
+    /// [`flatten_depth_first`]: ../bvh/struct.BVH.html#method.flatten_depth_first
+    ///
+    fn build<T: BHShape>(shapes: &mut [T]) -> SkipBVH {
+        let bvh = BVH::build(shapes);
+        bvh.flatten_depth_first()
+    }
+
+    /// Traverses a [`SkipBVH`] structure iteratively.
+    ///
+    /// [`SkipBVH`]: type.SkipBVH.html
+    ///
+    fn traverse<'a, T: Bounded>(&'a self, ray: &Ray, shapes: &'a [T]) -> Vec<&'a T> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`SkipBVH`].
+    ///
+    /// [`SkipBVH`]: type.SkipBVH.html
+    ///
+    fn pretty_print(&self) {
+        for (i, node) in self.iter().enumerate() {
+            println!(
+                "{}\tskip {}\tshape {}",
+                i, node.skip_index, node.shape_index
+            );
+        }
+    }
+}
+
+impl IndexedBoundingHierarchy for SkipBVH {
+    /// Traverses a [`SkipBVH`] structure iteratively and returns the indices of the shapes
+    /// whose [`AABB`]s were hit by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`SkipBVH`]: type.SkipBVH.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        let mut index = 0;
+        let max_length = self.len();
+
+        while index < max_length {
+            let node = &self[index];
+            prefetch(self, node.skip_index as usize);
+            if !ray.intersects_aabb(&node.aabb) {
+                index = node.skip_index as usize;
+            } else if node.shape_index == u32::MAX {
+                index += 1;
             } else {
-                // If entry_index is not MAX_UINT32 and the AABB test fails, then
-                // proceed to the node in exit_index (which defines the next untested partition).
-                index = node.exit_index as usize;
+                hit_indices.push(node.shape_index as usize);
+                index = node.skip_index as usize;
             }
         }
 
-        hit_shapes
+        hit_indices
     }
+}
 
-    /// Prints a textual representation of a [`FlatBVH`].
+impl BoundingHierarchy for BreadthFirstBVH {
+    /// A [`BreadthFirstBVH`] is built from a regular [`BVH`] using the
+    /// [`flatten_breadth_first`] method.
     ///
-    /// [`FlatBVH`]: struct.FlatBVH.html
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BreadthFirstBVH`]: struct.BreadthFirstBVH.html
+    /// [`flatten_breadth_first`]: ../bvh/struct.BVH.html#method.flatten_breadth_first
+    ///
+    fn build<T: BHShape>(shapes: &mut [T]) -> BreadthFirstBVH {
+        let bvh = BVH::build(shapes);
+        bvh.flatten_breadth_first()
+    }
+
+    /// Traverses a [`BreadthFirstBVH`] level by level. The shapes are returned in
+    /// breadth-first order.
+    ///
+    /// [`BreadthFirstBVH`]: struct.BreadthFirstBVH.html
+    ///
+    fn traverse<'a, T: Bounded>(&'a self, ray: &Ray, shapes: &'a [T]) -> Vec<&'a T> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`BreadthFirstBVH`].
+    ///
+    /// [`BreadthFirstBVH`]: struct.BreadthFirstBVH.html
     ///
     fn pretty_print(&self) {
-        for (i, node) in self.iter().enumerate() {
+        for (i, node) in self.nodes.iter().enumerate() {
             println!(
-                "{}\tentry {}\texit {}\tshape {}",
-                i, node.entry_index, node.exit_index, node.shape_index
+                "{}\tchild {}\tshape {}",
+                i, node.child_index, node.shape_index
             );
         }
     }
 }
 
+impl IndexedBoundingHierarchy for BreadthFirstBVH {
+    /// Traverses a [`BreadthFirstBVH`] level by level and returns the indices of the shapes
+    /// whose [`AABB`]s were hit by `ray`, in breadth-first order.
+    ///
+    /// The nodes of the current level which are hit by `ray` are kept in a list, and their
+    /// children make up the list of the next level.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BreadthFirstBVH`]: struct.BreadthFirstBVH.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        let mut level = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        let mut next_level = Vec::new();
+
+        while !level.is_empty() {
+            for &index in &level {
+                let node = &self.nodes[index];
+                if !ray.intersects_aabb(&node.aabb) {
+                    continue;
+                }
+                if node.child_index == u32::MAX {
+                    hit_indices.push(node.shape_index as usize);
+                } else {
+                    next_level.push(node.child_index as usize);
+                    next_level.push(node.child_index as usize + 1);
+                }
+            }
+            std::mem::swap(&mut level, &mut next_level);
+            next_level.clear();
+        }
+
+        hit_indices
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::flat_bvh::FlatBVH;
-    use crate::testbase::{build_some_bh, traverse_some_bh};
+    use crate::bounding_hierarchy::{
+        BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+    };
+    use crate::bvh::{BVHNode, BVH};
+    use crate::flat_bvh::{
+        BreadthFirstBVH, FlatBVH, FlatBVHView, FlatBVHViewError, FlatNode, FlatNodeDescriptor,
+        GpuFlatNode, QuantizedFlatNode, SkipBVH,
+    };
+    use crate::testbase::{
+        build_cubes_bvh, build_some_bh, check_traverse_equals_bvh, create_n_cubes, create_ray,
+        default_bounds, traverse_some_bh,
+    };
 
     #[test]
     /// Tests whether the building procedure succeeds in not failing.
@@ -443,6 +1801,337 @@ mod tests {
     fn test_traverse_flat_bvh() {
         traverse_some_bh::<FlatBVH>();
     }
+
+    #[test]
+    /// Tests whether the memory usage of a `BVH` and its flat forms covers their nodes,
+    /// and whether the flat forms are smaller than the `BVH`.
+    fn test_memory_usage() {
+        let (_, bvh) = build_some_bh::<BVH>();
+        let bvh_usage = bvh.memory_usage();
+        assert!(bvh_usage >= bvh.nodes.len() * std::mem::size_of::<BVHNode>());
+
+        let flat = bvh.flatten();
+        assert!(flat.memory_usage() >= flat.len() * std::mem::size_of::<FlatNode>());
+        assert!(flat.memory_usage() < bvh_usage);
+
+        let skip = bvh.flatten_depth_first();
+        assert!(skip.memory_usage() < bvh_usage);
+    }
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a flat BVH with `QuantizedFlatNode`s.
+    fn test_traverse_quantized_flat_bvh() {
+        traverse_some_bh::<Vec<QuantizedFlatNode>>();
+    }
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `SkipBVH`.
+    fn test_traverse_skip_bvh() {
+        traverse_some_bh::<SkipBVH>();
+    }
+
+    #[test]
+    /// Tests whether a `SkipBVH` has one node per `BVH` node, whose skip indices point
+    /// behind their subtrees, and whether it finds the same shapes as the `BVH`.
+    fn test_skip_bvh_equals_bvh() {
+        let (triangles, bvh) = build_cubes_bvh();
+        let skip_bvh = bvh.flatten_depth_first();
+
+        assert_eq!(skip_bvh.len(), bvh.nodes.len());
+        for (index, node) in skip_bvh.iter().enumerate() {
+            // A subtree of a binary tree has an odd number of nodes.
+            let subtree_size = node.skip_index as usize - index;
+            assert_eq!(subtree_size % 2, 1);
+            assert_eq!(subtree_size == 1, node.shape_index != u32::MAX);
+        }
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| skip_bvh.traverse(ray, &triangles));
+    }
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `BreadthFirstBVH`.
+    fn test_traverse_breadth_first_bvh() {
+        traverse_some_bh::<BreadthFirstBVH>();
+    }
+
+    #[test]
+    /// Tests whether the levels of a `BreadthFirstBVH` contain exactly the children of the
+    /// previous level, and whether it finds the same shapes as the `BVH`.
+    fn test_breadth_first_bvh_equals_bvh() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let bfs_bvh = bvh.flatten_breadth_first();
+
+        assert_eq!(bfs_bvh.nodes.len(), bvh.nodes.len());
+        assert_eq!(bfs_bvh.levels[..2], [0, 1]);
+        for level in 1..bfs_bvh.levels.len() - 1 {
+            let parents = bfs_bvh.levels[level - 1] as usize..bfs_bvh.levels[level] as usize;
+            let mut children: Vec<u32> = bfs_bvh.nodes[parents]
+                .iter()
+                .filter(|node| node.child_index != u32::MAX)
+                .flat_map(|node| vec![node.child_index, node.child_index + 1])
+                .collect();
+            children.sort_unstable();
+            let expected: Vec<u32> = (bfs_bvh.levels[level]..bfs_bvh.levels[level + 1]).collect();
+            assert_eq!(children, expected);
+        }
+
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let ray = create_ray(&mut seed, &bounds);
+            let mut expected: Vec<usize> = bvh
+                .traverse(&ray, &triangles)
+                .iter()
+                .map(|triangle| triangle.bh_node_index())
+                .collect();
+            let mut found: Vec<usize> = bfs_bvh
+                .traverse(&ray, &triangles)
+                .iter()
+                .map(|triangle| triangle.bh_node_index())
+                .collect();
+            expected.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(found, expected);
+        }
+    }
+
+    /// Tests whether the flat BVH `BH` returns the same shapes in the same order as the
+    /// `BVH` for random rays.
+    fn check_flat_traverse_equals_bvh<BH: BoundingHierarchy>() {
+        let (mut triangles, bvh) = build_cubes_bvh();
+        let flat_bvh = BH::build(&mut triangles);
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| flat_bvh.traverse(ray, &triangles));
+    }
+
+    #[test]
+    /// Tests whether the traversals of the depth-first flat layouts on the CPU return the
+    /// same shapes in the same order as the traversal of the `BVH`.
+    fn test_flat_traverse_equals_bvh() {
+        check_flat_traverse_equals_bvh::<FlatBVH>();
+        check_flat_traverse_equals_bvh::<Vec<QuantizedFlatNode>>();
+        check_flat_traverse_equals_bvh::<Vec<GpuFlatNode>>();
+        check_flat_traverse_equals_bvh::<SkipBVH>();
+    }
+
+    #[test]
+    /// Tests whether `from_flat` restores the nodes and `AABB`s of the flattened `BVH`.
+    fn test_from_flat_restores_bvh() {
+        let bounds = default_bounds();
+        for &n in &[1, 2, 100] {
+            let mut triangles = create_n_cubes(n, &bounds);
+            let bvh = BVH::build(&mut triangles);
+            let restored = BVH::from_flat(&bvh.flatten());
+
+            assert_eq!(restored.nodes, bvh.nodes);
+            for (a, b) in restored.nodes.iter().zip(bvh.nodes.iter()) {
+                if let (BVHNode::Node { .. }, BVHNode::Node { .. }) = (a, b) {
+                    let (a_l, b_l) = (a.child_l_aabb(), b.child_l_aabb());
+                    let (a_r, b_r) = (a.child_r_aabb(), b.child_r_aabb());
+                    assert_eq!((a_l.min, a_l.max), (b_l.min, b_l.max));
+                    assert_eq!((a_r.min, a_r.max), (b_r.min, b_r.max));
+                }
+            }
+            restored.assert_consistent(&triangles);
+        }
+    }
+
+    #[test]
+    /// Tests whether `flatten_with` describes the same nodes as `flatten`.
+    fn test_flatten_with_equals_flatten() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let flat = bvh.flatten();
+        let descriptors = bvh.flatten_with(|node| *node);
+
+        assert_eq!(flat.len(), descriptors.len());
+        for (node, descriptor) in flat.iter().zip(descriptors.iter()) {
+            match *descriptor {
+                FlatNodeDescriptor::Branch {
+                    aabb,
+                    entry_index,
+                    exit_index,
+                } => {
+                    assert_eq!((aabb.min, aabb.max), (node.aabb.min, node.aabb.max));
+                    assert_eq!(entry_index, node.entry_index);
+                    assert_eq!(exit_index, node.exit_index);
+                }
+                FlatNodeDescriptor::Leaf {
+                    shape_index,
+                    exit_index,
+                } => {
+                    assert_eq!(node.entry_index, u32::MAX);
+                    assert_eq!(shape_index, node.shape_index);
+                    assert_eq!(exit_index, node.exit_index);
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a flat BVH with `GpuFlatNode`s.
+    fn test_traverse_gpu_flat_bvh() {
+        traverse_some_bh::<Vec<GpuFlatNode>>();
+    }
+
+    #[test]
+    /// Tests whether the fields of a `GpuFlatNode` are at the offsets of the GPU layout.
+    fn test_gpu_flat_node_layout() {
+        use std::convert::TryInto;
+
+        let node = GpuFlatNode {
+            min: [1.0, 2.0, 3.0],
+            entry_index: 4,
+            max: [5.0, 6.0, 7.0],
+            exit_index: 8,
+            shape_index: 9,
+            padding: [0; 3],
+        };
+        let bytes = GpuFlatNode::as_bytes(std::slice::from_ref(&node));
+        let word =
+            |offset: usize| u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(std::mem::align_of::<GpuFlatNode>(), 4);
+        assert_eq!(bytes.len(), 48);
+        assert_eq!(f32::from_bits(word(0)), 1.0);
+        assert_eq!(f32::from_bits(word(8)), 3.0);
+        assert_eq!(word(12), 4);
+        assert_eq!(f32::from_bits(word(16)), 5.0);
+        assert_eq!(word(28), 8);
+        assert_eq!(word(32), 9);
+    }
+
+    #[test]
+    /// Tests whether quantized `AABB`s contain the exact `AABB`s.
+    fn test_quantized_flat_node_contains_aabb() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let exact = bvh.flatten();
+        let quantized = bvh.flatten_layout::<QuantizedFlatNode>();
+
+        assert_eq!(exact.len(), quantized.len());
+        for (exact, quantized) in exact.iter().zip(quantized.iter()) {
+            if exact.entry_index != u32::MAX {
+                assert!(quantized.aabb().approx_contains_aabb_eps(&exact.aabb, 0.0));
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether a `FlatBVHView` of the bytes of a `BVH` is traversed like its
+    /// `FlatBVH`, and whether invalid bytes are rejected.
+    fn test_flat_bvh_view() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let flat_bvh = bvh.flatten_layout::<GpuFlatNode>();
+        let bytes = bvh.flatten_to_bytes();
+        if cfg!(target_endian = "little") {
+            assert_eq!(bytes, GpuFlatNode::as_bytes(&flat_bvh));
+        }
+
+        // The view does not need aligned bytes.
+        let mut unaligned = vec![0];
+        unaligned.extend_from_slice(&bytes);
+        let view = FlatBVHView::new(&unaligned[1..]).unwrap();
+        assert_eq!(view.len(), flat_bvh.len());
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            assert_eq!(view.traverse_indices(&ray), flat_bvh.traverse_indices(&ray));
+            assert_eq!(
+                view.traverse(&ray, &triangles).len(),
+                flat_bvh.traverse(&ray, &triangles).len()
+            );
+        }
+
+        let size = FlatBVHView::NODE_SIZE;
+        assert_eq!(
+            FlatBVHView::new(&bytes[1..]).unwrap_err(),
+            FlatBVHViewError::Length(bytes.len() - 1)
+        );
+        assert_eq!(
+            FlatBVHView::new(&[]).unwrap_err(),
+            FlatBVHViewError::Length(0)
+        );
+        // A root whose exit index points back to itself.
+        let mut corrupt = bytes.clone();
+        corrupt[28..32].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(
+            FlatBVHView::new(&corrupt).unwrap_err(),
+            FlatBVHViewError::Index(0)
+        );
+        // A truncated view, whose last node points past the end.
+        let truncated = &bytes[..bytes.len() - size];
+        assert!(FlatBVHView::new(truncated).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serde_impls")]
+    /// Tests whether a `BVH`, its `FlatBVH` and an `AABB` survive a round trip through
+    /// CBOR, whose floats can be infinite, and whether the deserialized `FlatBVH` is
+    /// traversed like the original one.
+    fn test_serde_round_trip() {
+        use crate::aabb::AABB;
+
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(10, &bounds);
+        let bvh = BVH::build(&mut triangles);
+        let flat_bvh = bvh.flatten();
+
+        let bytes = serde_cbor::to_vec(&bvh).unwrap();
+        let deserialized: BVH = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(deserialized.nodes, bvh.nodes);
+
+        let bytes = serde_cbor::to_vec(&flat_bvh).unwrap();
+        let deserialized: FlatBVH = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(serde_cbor::to_vec(&deserialized).unwrap(), bytes);
+        let mut seed = 0;
+        for _ in 0..100 {
+            let ray = create_ray(&mut seed, &bounds);
+            assert_eq!(
+                deserialized.traverse(&ray, &triangles).len(),
+                flat_bvh.traverse(&ray, &triangles).len()
+            );
+        }
+
+        let bytes = serde_cbor::to_vec(&bounds).unwrap();
+        let deserialized: AABB = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(
+            (deserialized.min, deserialized.max),
+            (bounds.min, bounds.max)
+        );
+    }
+
+    #[test]
+    /// Tests whether flattening a `BVH` without shapes yields flat `BVH`s without nodes,
+    /// which can be traversed.
+    fn test_flatten_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+
+        let flat = bvh.flatten();
+        assert!(flat.is_empty());
+        assert!(flat.traverse(&ray, &triangles).is_empty());
+
+        let skip = bvh.flatten_depth_first();
+        assert!(skip.is_empty());
+        assert!(skip.traverse(&ray, &triangles).is_empty());
+
+        let breadth_first = bvh.flatten_breadth_first();
+        assert!(breadth_first.nodes.is_empty());
+        assert_eq!(breadth_first.levels, vec![0]);
+        assert!(breadth_first.traverse(&ray, &triangles).is_empty());
+    }
 }
 
 #[cfg(all(feature = "bench", test))]
@@ -0,0 +1,437 @@
+//! This module defines [`IntBVH`], a [`BVH`] over integer [`IntAABB`]s, for example of
+//! coordinates in millimeters.
+//!
+//! Building and querying an [`IntBVH`] only uses integer arithmetic, so the tree and the
+//! query results are bit-for-bit identical on every platform, which deterministic lockstep
+//! simulations require. The surface area heuristic is evaluated on exact half areas.
+//!
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`IntAABB`]: struct.IntAABB.html
+//! [`IntBVH`]: struct.IntBVH.html
+//!
+
+use crate::bounding_hierarchy::MemoryUsage;
+use glam::IVec3;
+
+/// An [`AABB`] with integer coordinates. Like an [`AABB`] it is closed, so it includes its
+/// bounds.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntAABB {
+    /// Minimum coordinates
+    pub min: IVec3,
+
+    /// Maximum coordinates
+    pub max: IVec3,
+}
+
+/// A trait implemented by things which can be bounded by an [`IntAABB`].
+///
+/// [`IntAABB`]: struct.IntAABB.html
+///
+pub trait IntBounded {
+    /// Returns the geometric bounds of this object in the form of an [`IntAABB`].
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    fn int_aabb(&self) -> IntAABB;
+}
+
+impl IntAABB {
+    /// Creates a new [`IntAABB`] with the given bounds.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn with_bounds(min: IVec3, max: IVec3) -> IntAABB {
+        IntAABB { min, max }
+    }
+
+    /// Creates a new empty [`IntAABB`].
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn empty() -> IntAABB {
+        IntAABB {
+            min: IVec3::splat(i32::MAX),
+            max: IVec3::splat(i32::MIN),
+        }
+    }
+
+    /// Returns true if the [`IntAABB`] is empty.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    /// Returns true if the point is inside the [`IntAABB`].
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn contains(&self, p: &IVec3) -> bool {
+        self.min.cmple(*p).all() && p.cmple(self.max).all()
+    }
+
+    /// Returns true if this [`IntAABB`] and `other` share at least one point.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn intersects_aabb(&self, other: &IntAABB) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Returns a new minimal [`IntAABB`] which contains both this [`IntAABB`] and `other`.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn join(&self, other: &IntAABB) -> IntAABB {
+        IntAABB::with_bounds(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Returns a new minimal [`IntAABB`] which contains both this [`IntAABB`] and the point
+    /// `other`.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn grow(&self, other: &IVec3) -> IntAABB {
+        IntAABB::with_bounds(self.min.min(*other), self.max.max(*other))
+    }
+
+    /// Returns twice the center of the [`IntAABB`], which is exact and does not overflow.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn double_center(&self) -> [i64; 3] {
+        let center = |axis: usize| self.min[axis] as i64 + self.max[axis] as i64;
+        [center(0), center(1), center(2)]
+    }
+
+    /// Returns half of the surface area of the [`IntAABB`], which is exact and does not
+    /// overflow. This is the cost metric used to build an [`IntBVH`].
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    /// [`IntBVH`]: struct.IntBVH.html
+    ///
+    pub fn half_area(&self) -> u128 {
+        if self.is_empty() {
+            return 0;
+        }
+        let size = |axis: usize| (self.max[axis] as i64 - self.min[axis] as i64) as u128;
+        let (x, y, z) = (size(0), size(1), size(2));
+        x * y + x * z + y * z
+    }
+}
+
+impl Default for IntAABB {
+    fn default() -> IntAABB {
+        IntAABB::empty()
+    }
+}
+
+impl IntBounded for IntAABB {
+    fn int_aabb(&self) -> IntAABB {
+        *self
+    }
+}
+
+impl IntBounded for IVec3 {
+    fn int_aabb(&self) -> IntAABB {
+        IntAABB::with_bounds(*self, *self)
+    }
+}
+
+/// A node of an [`IntBVH`].
+///
+/// [`IntBVH`]: struct.IntBVH.html
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntBVHNode {
+    /// Leaf node.
+    Leaf {
+        /// The shape contained in this leaf.
+        shape_index: u32,
+    },
+    /// Inner node.
+    Node {
+        /// Index of the left subtree's root node.
+        child_l_index: u32,
+
+        /// The convex hull of the shapes' `IntAABB`s in child_l.
+        child_l_aabb: IntAABB,
+
+        /// Index of the right subtree's root node.
+        child_r_index: u32,
+
+        /// The convex hull of the shapes' `IntAABB`s in child_r.
+        child_r_aabb: IntAABB,
+    },
+}
+
+/// A bounding volume hierarchy over [`IntAABB`]s. The root is the first node.
+///
+/// [`IntAABB`]: struct.IntAABB.html
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntBVH {
+    /// The list of nodes of the [`IntBVH`].
+    ///
+    /// [`IntBVH`]: struct.IntBVH.html
+    ///
+    pub nodes: Vec<IntBVHNode>,
+}
+
+impl IntBVH {
+    /// Creates a new [`IntBVH`] from the `shapes` slice. The same shapes always give the
+    /// same [`IntBVH`].
+    ///
+    /// # Panics
+    /// Panics if `shapes` is empty or has more than [`MAX_SHAPES`] shapes.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::int_bvh::{IntAABB, IntBVH};
+    /// use glam::IVec3;
+    ///
+    /// // Boxes of 1 m in millimeters.
+    /// let boxes: Vec<IntAABB> = (0..100)
+    ///     .map(|i| IntAABB::with_bounds(IVec3::new(i * 2000, 0, 0), IVec3::new(i * 2000 + 1000, 1000, 1000)))
+    ///     .collect();
+    /// let bvh = IntBVH::build(&boxes);
+    ///
+    /// let query = IntAABB::with_bounds(IVec3::new(1000, 0, 0), IVec3::new(4000, 0, 0));
+    /// assert_eq!(bvh.query(&query, &boxes).len(), 3);
+    /// ```
+    ///
+    /// [`IntBVH`]: struct.IntBVH.html
+    /// [`MAX_SHAPES`]: ../bvh/constant.MAX_SHAPES.html
+    ///
+    pub fn build<Shape: IntBounded>(shapes: &[Shape]) -> IntBVH {
+        assert!(!shapes.is_empty(), "cannot build an IntBVH without shapes");
+        assert!(
+            shapes.len() <= crate::bvh::MAX_SHAPES,
+            "cannot build an IntBVH of {} shapes, the maximum is {}",
+            shapes.len(),
+            crate::bvh::MAX_SHAPES
+        );
+        let aabbs: Vec<IntAABB> = shapes.iter().map(IntBounded::int_aabb).collect();
+        let mut indices: Vec<usize> = (0..shapes.len()).collect();
+        let mut nodes = Vec::with_capacity(shapes.len() * 2);
+        IntBVH::build_subtree(&aabbs, &mut indices, &mut nodes);
+        IntBVH { nodes }
+    }
+
+    /// Builds the subtree of the shapes at `indices` and returns the index of its root.
+    fn build_subtree(aabbs: &[IntAABB], indices: &mut [usize], nodes: &mut Vec<IntBVHNode>) -> u32 {
+        let node_index = nodes.len() as u32;
+        if indices.len() == 1 {
+            nodes.push(IntBVHNode::Leaf {
+                shape_index: indices[0] as u32,
+            });
+            return node_index;
+        }
+
+        // Replaced below, once the children are built.
+        nodes.push(IntBVHNode::Leaf { shape_index: 0 });
+
+        let split = IntBVH::find_split(aabbs, indices);
+        let (child_l_indices, child_r_indices) = indices.split_at_mut(split);
+        let joint_aabb = |indices: &[usize]| {
+            indices
+                .iter()
+                .fold(IntAABB::empty(), |aabb, &index| aabb.join(&aabbs[index]))
+        };
+        let child_l_aabb = joint_aabb(child_l_indices);
+        let child_r_aabb = joint_aabb(child_r_indices);
+        let child_l_index = IntBVH::build_subtree(aabbs, child_l_indices, nodes);
+        let child_r_index = IntBVH::build_subtree(aabbs, child_r_indices, nodes);
+        nodes[node_index as usize] = IntBVHNode::Node {
+            child_l_index,
+            child_l_aabb,
+            child_r_index,
+            child_r_aabb,
+        };
+        node_index
+    }
+
+    /// Sorts `indices` along the axis in which the centers of the shapes are spread the
+    /// most, and returns the number of shapes of the left child with the lowest surface area
+    /// heuristic cost. Ties are broken by the lower split, and equal centers by the shape
+    /// index, so that the result only depends on the shapes.
+    fn find_split(aabbs: &[IntAABB], indices: &mut [usize]) -> usize {
+        let centers: Vec<[i64; 3]> = indices
+            .iter()
+            .map(|&index| aabbs[index].double_center())
+            .collect();
+        let spread = |axis: usize| {
+            let min = centers.iter().map(|center| center[axis]).min().unwrap();
+            let max = centers.iter().map(|center| center[axis]).max().unwrap();
+            max - min
+        };
+        let axis = (0..3)
+            .max_by_key(|&axis| (spread(axis), -(axis as i64)))
+            .unwrap();
+        if spread(axis) == 0 {
+            // The shapes cannot be separated, split them in half.
+            indices.sort_unstable();
+            return indices.len() / 2;
+        }
+        indices.sort_unstable_by_key(|&index| (aabbs[index].double_center()[axis], index));
+
+        // The cost of a split is the sum of the half areas of the children, weighted by
+        // their numbers of shapes. `right_costs[i]` is the cost of the right child of the
+        // split before the `i`-th shape.
+        let mut right_costs = vec![0; indices.len()];
+        let mut right_aabb = IntAABB::empty();
+        for i in (1..indices.len()).rev() {
+            right_aabb = right_aabb.join(&aabbs[indices[i]]);
+            right_costs[i] = right_aabb.half_area() * (indices.len() - i) as u128;
+        }
+
+        let mut best = (u128::MAX, 1);
+        let mut left_aabb = IntAABB::empty();
+        for i in 1..indices.len() {
+            left_aabb = left_aabb.join(&aabbs[indices[i - 1]]);
+            let cost = left_aabb.half_area() * i as u128 + right_costs[i];
+            if cost < best.0 {
+                best = (cost, i);
+            }
+        }
+        best.1
+    }
+
+    /// Returns the subset of `shapes` whose [`IntAABB`]s intersect `query`, in the order of
+    /// a depth-first traversal.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn query<'a, Shape: IntBounded>(
+        &'a self,
+        query: &IntAABB,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        let mut found = Vec::new();
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            match self.nodes[node_index as usize] {
+                IntBVHNode::Node {
+                    child_l_index,
+                    ref child_l_aabb,
+                    child_r_index,
+                    ref child_r_aabb,
+                } => {
+                    // Push the right child first, so that the left child is visited first.
+                    if query.intersects_aabb(child_r_aabb) {
+                        stack.push(child_r_index);
+                    }
+                    if query.intersects_aabb(child_l_aabb) {
+                        stack.push(child_l_index);
+                    }
+                }
+                IntBVHNode::Leaf { shape_index } => {
+                    let shape = &shapes[shape_index as usize];
+                    if query.intersects_aabb(&shape.int_aabb()) {
+                        found.push(shape);
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Returns the subset of `shapes` whose [`IntAABB`]s contain `point`.
+    ///
+    /// [`IntAABB`]: struct.IntAABB.html
+    ///
+    pub fn query_point<'a, Shape: IntBounded>(
+        &'a self,
+        point: &IVec3,
+        shapes: &'a [Shape],
+    ) -> Vec<&'a Shape> {
+        self.query(&point.int_aabb(), shapes)
+    }
+}
+
+impl MemoryUsage for IntBVH {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.nodes.capacity() * std::mem::size_of::<IntBVHNode>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::int_bvh::{IntAABB, IntBVH};
+    use glam::IVec3;
+    use proptest::prelude::*;
+
+    /// Creates `n` pseudo-random `IntAABB`s spread over the whole range of `i32`.
+    fn create_int_aabbs(n: usize, seed: &mut u64) -> Vec<IntAABB> {
+        let mut next = || {
+            // A linear congruential generator, to stay independent of floating point.
+            *seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (*seed >> 32) as i32
+        };
+        (0..n)
+            .map(|_| {
+                let a = IVec3::new(next(), next(), next());
+                let b = IVec3::new(next(), next(), next());
+                IntAABB::with_bounds(a.min(b), a.max(b))
+            })
+            .collect()
+    }
+
+    #[test]
+    /// Tests whether building an `IntBVH` twice gives the same tree.
+    fn test_int_bvh_is_deterministic() {
+        let aabbs = create_int_aabbs(1000, &mut 0);
+        assert_eq!(IntBVH::build(&aabbs), IntBVH::build(&aabbs));
+    }
+
+    #[test]
+    /// Tests whether the half area of the largest `IntAABB` does not overflow.
+    fn test_half_area_of_full_range() {
+        let aabb = IntAABB::with_bounds(IVec3::splat(i32::MIN), IVec3::splat(i32::MAX));
+        assert_eq!(aabb.half_area(), 3 * (u32::MAX as u128).pow(2));
+    }
+
+    #[test]
+    /// Tests whether an `IntBVH` of shapes at the same position can be built and queried.
+    fn test_int_bvh_of_equal_shapes() {
+        let aabbs = vec![IntAABB::with_bounds(IVec3::ZERO, IVec3::ONE); 10];
+        let bvh = IntBVH::build(&aabbs);
+        assert_eq!(bvh.query_point(&IVec3::ONE, &aabbs).len(), 10);
+        assert!(bvh.query_point(&IVec3::splat(2), &aabbs).is_empty());
+    }
+
+    proptest! {
+        // Test whether an `IntBVH` finds the same shapes as a brute force search.
+        #[test]
+        fn test_int_bvh_query_equals_brute_force(seed: u64, n in 1..200usize) {
+            let mut seed = seed;
+            let aabbs = create_int_aabbs(n, &mut seed);
+            let bvh = IntBVH::build(&aabbs);
+            for query in create_int_aabbs(10, &mut seed) {
+                let mut found: Vec<*const IntAABB> = bvh
+                    .query(&query, &aabbs)
+                    .into_iter()
+                    .map(|aabb| aabb as *const IntAABB)
+                    .collect();
+                let mut expected: Vec<*const IntAABB> = aabbs
+                    .iter()
+                    .filter(|aabb| query.intersects_aabb(aabb))
+                    .map(|aabb| aabb as *const IntAABB)
+                    .collect();
+                found.sort();
+                expected.sort();
+                assert_eq!(found, expected);
+            }
+        }
+    }
+}
@@ -64,9 +64,21 @@
 //! let hit_sphere_aabbs = bvh.traverse(&ray, &spheres);
 //! ```
 //!
+//! ## Precision
+//!
+//! The [`AABB`](aabb::AABB)s, [`Ray`](ray::Ray)s and hierarchies of this crate use `f32`,
+//! because [`Point3`] and [`Vector3`] are the `glam` types [`glam::Vec3`]. Scenes which need
+//! double precision bound their shapes by an [`AABB64`](aabb64::AABB64), which is rounded
+//! outwards into an `f32` [`AABB`](aabb::AABB) for building, and traverse the hierarchy with
+//! a [`Ray64`](ray64::Ray64). The hierarchy then only costs some culling efficiency, while
+//! all intersections are computed in `f64`.
+//!
 //! ## Features
 //!
-//! - `serde_impls` (default **disabled**) - adds `Serialize` and `Deserialize` implementations for some types
+//! - `serde_impls` (default **disabled**) - adds `Serialize` and `Deserialize` implementations for all public data structures which own their data, like the [`BVH`](bvh::BVH), its flat layouts and the [`AABB`](aabb::AABB)
+//! - `rayon` (default **disabled**) - adds parallel batch traversal and refitting with [`rayon`](https://docs.rs/rayon)
+//! - `simd` (default **disabled**) - joins [`AABB`](aabb::AABB)s and tests them against rays with the SIMD type [`glam::Vec3A`]
+//! - `prefetch` (default **disabled**) - prefetches the node which is visited when a subtree is skipped during the traversal of a flat BVH, on x86 and x86-64
 //!
 
 #![deny(missing_docs)]
@@ -86,12 +98,26 @@ pub type Point3 = glam::Vec3;
 pub type Vector3 = glam::Vec3;
 
 pub mod aabb;
+pub mod aabb64;
 pub mod axis;
+pub mod benchmark;
 pub mod bounding_hierarchy;
 pub mod bvh;
+pub mod bvh4;
+pub mod bvh8;
+pub mod compressed_bvh;
+pub mod cone;
 pub mod flat_bvh;
+pub mod int_bvh;
+pub mod obb;
+pub mod packed_bvh;
+pub mod packet;
+pub mod plane;
 pub mod ray;
+pub mod ray64;
+pub mod shapes;
 mod utils;
+mod wide;
 
 #[cfg(test)]
 mod testbase;
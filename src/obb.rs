@@ -0,0 +1,198 @@
+//! Oriented Bounding Boxes.
+
+use crate::aabb::{Bounded, IntersectsAabb, AABB};
+use crate::{Point3, Vector3};
+use glam::{Mat3, Quat};
+
+/// An oriented bounding box, which is a box with arbitrary rotation.
+///
+/// [`OBB`]s are closed, so they include their bounds, just like [`AABB`]s.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`OBB`]: struct.OBB.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+#[allow(clippy::upper_case_acronyms)]
+pub struct OBB {
+    /// The center of the box.
+    pub center: Point3,
+
+    /// Half of the size of the box along each of its local axes.
+    pub half_extents: Vector3,
+
+    /// The rotation from the local axes of the box to the world axes.
+    pub rotation: Quat,
+}
+
+impl OBB {
+    /// Creates a new [`OBB`] from its `center`, its `half_extents` along its local axes and
+    /// the `rotation` of its local axes.
+    ///
+    /// [`OBB`]: struct.OBB.html
+    ///
+    pub fn new(center: Point3, half_extents: Vector3, rotation: Quat) -> OBB {
+        OBB {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    /// Returns the local axes of the [`OBB`] in world space.
+    ///
+    /// [`OBB`]: struct.OBB.html
+    ///
+    pub fn axes(&self) -> [Vector3; 3] {
+        let matrix = Mat3::from_quat(self.rotation);
+        [matrix.x_axis, matrix.y_axis, matrix.z_axis]
+    }
+
+    /// Returns true if the [`Point3`] is inside the [`OBB`].
+    ///
+    /// [`OBB`]: struct.OBB.html
+    /// [`Point3`]: glam::Vec3
+    ///
+    pub fn contains(&self, p: &Point3) -> bool {
+        let offset = *p - self.center;
+        self.axes()
+            .iter()
+            .enumerate()
+            .all(|(i, axis)| offset.dot(*axis).abs() <= self.half_extents[i])
+    }
+
+    /// Tests the intersection of the [`OBB`] with an [`AABB`] using the separating axis
+    /// theorem. Besides the face normals of both boxes, the cross products of their edges
+    /// are tested, so the test is exact.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{Bounded, AABB};
+    /// use bvh::obb::OBB;
+    /// use bvh::{Point3, Vector3};
+    /// use glam::Quat;
+    ///
+    /// // A long thin box along the diagonal of the xy-plane.
+    /// let obb = OBB::new(
+    ///     Point3::new(0.0, 0.0, 0.0),
+    ///     Vector3::new(10.0, 0.5, 0.5),
+    ///     Quat::from_rotation_z(std::f32::consts::FRAC_PI_4),
+    /// );
+    /// let on_diagonal = AABB::with_bounds(Point3::new(5.0, 5.0, -1.0), Point3::new(6.0, 6.0, 1.0));
+    /// let off_diagonal = AABB::with_bounds(Point3::new(5.0, -6.0, -1.0), Point3::new(6.0, -5.0, 1.0));
+    ///
+    /// assert!(obb.intersects_aabb(&on_diagonal));
+    /// assert!(!obb.intersects_aabb(&off_diagonal));
+    /// // The `AABB` of the `OBB` contains both.
+    /// assert!(obb.aabb().intersects(&off_diagonal));
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`OBB`]: struct.OBB.html
+    ///
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+
+        let aabb_half_extents = aabb.size() / 2.0;
+        let offset = self.center - aabb.center();
+        let axes = self.axes();
+
+        // Returns true if `axis` separates the boxes.
+        let separates = |axis: Vector3| {
+            let aabb_radius = aabb_half_extents.dot(axis.abs());
+            let obb_radius = (0..3)
+                .map(|i| self.half_extents[i] * axes[i].dot(axis).abs())
+                .sum::<f32>();
+            offset.dot(axis).abs() > aabb_radius + obb_radius
+        };
+
+        let world_axes = [Vector3::X, Vector3::Y, Vector3::Z];
+        if world_axes
+            .iter()
+            .chain(axes.iter())
+            .any(|&axis| separates(axis))
+        {
+            return false;
+        }
+
+        // Parallel edges give a zero cross product, which never separates the boxes.
+        !world_axes
+            .iter()
+            .any(|world_axis| axes.iter().any(|&axis| separates(world_axis.cross(axis))))
+    }
+}
+
+impl Bounded for OBB {
+    fn aabb(&self) -> AABB {
+        let axes = self.axes();
+        let half_size = axes[0].abs() * self.half_extents.x
+            + axes[1].abs() * self.half_extents.y
+            + axes[2].abs() * self.half_extents.z;
+        AABB::with_bounds(self.center - half_size, self.center + half_size)
+    }
+}
+
+impl IntersectsAabb for OBB {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        self.intersects_aabb(aabb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, AABB};
+    use crate::obb::OBB;
+    use crate::testbase::next_point3;
+    use crate::Point3;
+    use glam::Quat;
+
+    #[test]
+    /// Tests whether `intersects_aabb` agrees with points sampled from the `OBB`, and with
+    /// the `AABB` overlap test for unrotated boxes.
+    fn test_obb_intersects_aabb() {
+        let bounds = AABB::with_bounds(
+            Point3::new(-10.0, -10.0, -10.0),
+            Point3::new(10.0, 10.0, 10.0),
+        );
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let center = next_point3(&mut seed, &bounds);
+            let half_extents = (next_point3(&mut seed, &bounds) - bounds.min) / 4.0;
+            let axis = next_point3(&mut seed, &bounds).normalize();
+            let angle = next_point3(&mut seed, &bounds).x;
+            let obb = OBB::new(center, half_extents, Quat::from_axis_angle(axis, angle));
+            let aabb = AABB::empty()
+                .grow(&next_point3(&mut seed, &bounds))
+                .grow(&next_point3(&mut seed, &bounds));
+
+            let axes = obb.axes();
+            let steps = [-1.0, -0.5, 0.0, 0.5, 1.0];
+            for &x in &steps {
+                for &y in &steps {
+                    for &z in &steps {
+                        let point = center
+                            + axes[0] * half_extents.x * x
+                            + axes[1] * half_extents.y * y
+                            + axes[2] * half_extents.z * z;
+                        assert!(obb.contains(&point.lerp(center, 0.001)));
+                        if aabb.contains(&point) {
+                            assert!(obb.intersects_aabb(&aabb));
+                        }
+                    }
+                }
+            }
+
+            if obb.intersects_aabb(&aabb) {
+                assert!(obb.aabb().intersects(&aabb));
+            }
+
+            let unrotated = OBB::new(center, half_extents, Quat::IDENTITY);
+            assert_eq!(
+                unrotated.intersects_aabb(&aabb),
+                unrotated.aabb().intersects(&aabb)
+            );
+        }
+    }
+}
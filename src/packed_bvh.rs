@@ -0,0 +1,346 @@
+//! This module defines [`PackedBVH`], a [`BVH`] whose nodes are packed into one cache line
+//! each.
+//!
+//! A [`BVHNode`] stores its parent, its depth and `usize` indices next to the [`AABB`]s of
+//! its children, which are not needed by a traversal and make it span two cache lines. A
+//! [`PackedNode`] stores only the bounds of both children, their `u32` indices and a few
+//! flags, in 64 bytes aligned to 64 bytes, so visiting a node touches one cache line.
+//!
+//! [`BVHNode`] itself keeps its layout, since [`BVH::optimize`] relies on its parent and
+//! depth, and since its variants and fields are public. A node cannot shrink to 32 bytes
+//! either, as the bounds of two children take 48 bytes at `f32` precision. Nodes of
+//! 32 bytes are available with quantized bounds as [`CompressedNode`]s with 16 bit offsets.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`BVH::optimize`]: ../bvh/struct.BVH.html#method.optimize
+//! [`BVHNode`]: ../bvh/enum.BVHNode.html
+//! [`CompressedNode`]: ../compressed_bvh/struct.CompressedNode.html
+//! [`PackedBVH`]: struct.PackedBVH.html
+//! [`PackedNode`]: struct.PackedNode.html
+//!
+
+use crate::aabb::{Bounded, AABB};
+use crate::bounding_hierarchy::{
+    BHShape, BoundingHierarchy, IndexedBoundingHierarchy, MemoryUsage,
+};
+use crate::bvh::{BVHNode, BVH};
+use crate::ray::Ray;
+
+/// The flag of a [`PackedNode`] which is set if its left child is a leaf.
+///
+/// [`PackedNode`]: struct.PackedNode.html
+///
+pub const PACKED_LEFT_LEAF: u32 = 1;
+
+/// The flag of a [`PackedNode`] which is set if its right child is a leaf.
+///
+/// [`PackedNode`]: struct.PackedNode.html
+///
+pub const PACKED_RIGHT_LEAF: u32 = 2;
+
+/// An inner node of a [`PackedBVH`], which fits into one cache line.
+///
+/// [`PackedBVH`]: struct.PackedBVH.html
+///
+#[repr(C, align(64))]
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedNode {
+    /// The lower bounds of the left and the right child.
+    pub child_min: [[f32; 3]; 2],
+
+    /// The upper bounds of the left and the right child.
+    pub child_max: [[f32; 3]; 2],
+
+    /// The indices of the left and the right child. The index of a child is the index of
+    /// its shape if it is a leaf, and the index of its node otherwise.
+    pub children: [u32; 2],
+
+    /// The flags [`PACKED_LEFT_LEAF`] and [`PACKED_RIGHT_LEAF`] of the node.
+    ///
+    /// [`PACKED_LEFT_LEAF`]: constant.PACKED_LEFT_LEAF.html
+    /// [`PACKED_RIGHT_LEAF`]: constant.PACKED_RIGHT_LEAF.html
+    ///
+    pub flags: u32,
+}
+
+impl PackedNode {
+    /// Returns the [`AABB`] of the `child` (`0` for left, `1` for right) of this node.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn child_aabb(&self, child: usize) -> AABB {
+        AABB::with_bounds(self.child_min[child].into(), self.child_max[child].into())
+    }
+
+    /// Returns true if the `child` (`0` for left, `1` for right) of this node is a leaf.
+    pub fn is_leaf(&self, child: usize) -> bool {
+        self.flags & (PACKED_LEFT_LEAF << child) != 0
+    }
+}
+
+/// A [`BVH`] of [`PackedNode`]s, which is created by [`BVH::pack`].
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::pack`]: ../bvh/struct.BVH.html#method.pack
+/// [`PackedNode`]: struct.PackedNode.html
+///
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedBVH {
+    /// The inner nodes. The root is the first node, unless the [`BVH`] is a single leaf.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub nodes: Vec<PackedNode>,
+
+    /// The index of the shape of the root, if the [`BVH`] is a single leaf.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub root_shape: Option<u32>,
+}
+
+impl BVH {
+    /// Packs the [`BVH`] into a [`PackedBVH`]. The nodes keep their depth-first order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bvh::aabb::{AABB, Bounded};
+    /// use bvh::bounding_hierarchy::BoundingHierarchy;
+    /// use bvh::bvh::BVH;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3, Vector3};
+    /// # use bvh::bounding_hierarchy::BHShape;
+    /// # pub struct UnitBox {
+    /// #     pub pos: Point3,
+    /// #     node_index: usize,
+    /// # }
+    /// #
+    /// # impl Bounded for UnitBox {
+    /// #     fn aabb(&self) -> AABB {
+    /// #         let min = self.pos + Vector3::new(-0.5, -0.5, -0.5);
+    /// #         let max = self.pos + Vector3::new(0.5, 0.5, 0.5);
+    /// #         AABB::with_bounds(min, max)
+    /// #     }
+    /// # }
+    /// #
+    /// # impl BHShape for UnitBox {
+    /// #     fn set_bh_node_index(&mut self, index: usize) {
+    /// #         self.node_index = index;
+    /// #     }
+    /// #
+    /// #     fn bh_node_index(&self) -> usize {
+    /// #         self.node_index
+    /// #     }
+    /// # }
+    /// #
+    /// # fn create_bhshapes() -> Vec<UnitBox> {
+    /// #     (0..1000)
+    /// #         .map(|i| UnitBox { pos: Point3::new(i as f32, 0.0, 0.0), node_index: 0 })
+    /// #         .collect()
+    /// # }
+    ///
+    /// let mut shapes = create_bhshapes();
+    /// let bvh = BVH::build(&mut shapes);
+    /// let packed = bvh.pack();
+    /// assert_eq!(packed.nodes.len(), 999);
+    ///
+    /// let ray = Ray::new(Point3::new(-10.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+    /// assert_eq!(packed.traverse(&ray, &shapes).len(), 1000);
+    /// ```
+    ///
+    /// [`BVH`]: struct.BVH.html
+    /// [`PackedBVH`]: ../packed_bvh/struct.PackedBVH.html
+    ///
+    pub fn pack(&self) -> PackedBVH {
+        if self.nodes.is_empty() {
+            return PackedBVH {
+                nodes: Vec::new(),
+                root_shape: None,
+            };
+        }
+        if let BVHNode::Leaf { shape_index, .. } = self.nodes[0] {
+            return PackedBVH {
+                nodes: Vec::new(),
+                root_shape: Some(shape_index),
+            };
+        }
+
+        let mut nodes = Vec::with_capacity(self.nodes.len() / 2);
+        self.pack_subtree(0, &mut nodes);
+        PackedBVH {
+            nodes,
+            root_shape: None,
+        }
+    }
+
+    /// Appends the [`PackedNode`]s of the subtree of the inner node at `node_index` to
+    /// `nodes`, and returns the index of the packed node.
+    ///
+    /// [`PackedNode`]: ../packed_bvh/struct.PackedNode.html
+    ///
+    fn pack_subtree(&self, node_index: usize, nodes: &mut Vec<PackedNode>) -> u32 {
+        let index = nodes.len();
+        if let BVHNode::Node {
+            child_l_aabb,
+            child_l_index,
+            child_r_aabb,
+            child_r_index,
+            ..
+        } = self.nodes[node_index]
+        {
+            nodes.push(PackedNode {
+                child_min: [child_l_aabb.min.to_array(), child_r_aabb.min.to_array()],
+                child_max: [child_l_aabb.max.to_array(), child_r_aabb.max.to_array()],
+                children: [0, 0],
+                flags: 0,
+            });
+            for (child, &child_index) in [child_l_index, child_r_index].iter().enumerate() {
+                let packed_child = match self.nodes[child_index as usize] {
+                    BVHNode::Leaf { shape_index, .. } => {
+                        nodes[index].flags |= PACKED_LEFT_LEAF << child;
+                        shape_index
+                    }
+                    BVHNode::Node { .. } => self.pack_subtree(child_index as usize, nodes),
+                };
+                nodes[index].children[child] = packed_child;
+            }
+        }
+        index as u32
+    }
+}
+
+impl MemoryUsage for PackedBVH {
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(self) + self.nodes.capacity() * std::mem::size_of::<PackedNode>()
+    }
+}
+
+impl BoundingHierarchy for PackedBVH {
+    /// A [`PackedBVH`] is built from a regular [`BVH`] using the [`pack`] method.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`PackedBVH`]: struct.PackedBVH.html
+    /// [`pack`]: ../bvh/struct.BVH.html#method.pack
+    ///
+    fn build<Shape: BHShape>(shapes: &mut [Shape]) -> PackedBVH {
+        let bvh = BVH::build(shapes);
+        bvh.pack()
+    }
+
+    /// Traverses a [`PackedBVH`] with a stack. The shapes are returned in the same order as
+    /// by [`BVH::traverse`].
+    ///
+    /// [`BVH::traverse`]: ../bvh/struct.BVH.html#method.traverse
+    /// [`PackedBVH`]: struct.PackedBVH.html
+    ///
+    fn traverse<'a, Shape: Bounded>(&'a self, ray: &Ray, shapes: &'a [Shape]) -> Vec<&'a Shape> {
+        self.traverse_shapes(ray, shapes)
+    }
+
+    /// Prints a textual representation of a [`PackedBVH`].
+    ///
+    /// [`PackedBVH`]: struct.PackedBVH.html
+    ///
+    fn pretty_print(&self) {
+        for (i, node) in self.nodes.iter().enumerate() {
+            let describe = |child: usize| {
+                if node.is_leaf(child) {
+                    format!("shape {}", node.children[child])
+                } else {
+                    format!("node {}", node.children[child])
+                }
+            };
+            println!("{}\t{}\t{}", i, describe(0), describe(1));
+        }
+    }
+}
+
+impl IndexedBoundingHierarchy for PackedBVH {
+    /// Traverses a [`PackedBVH`] and returns the indices of the shapes whose [`AABB`]s were
+    /// hit by `ray`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`PackedBVH`]: struct.PackedBVH.html
+    ///
+    fn traverse_indices(&self, ray: &Ray) -> Vec<usize> {
+        let mut hit_indices = Vec::new();
+        if let Some(shape_index) = self.root_shape {
+            hit_indices.push(shape_index as usize);
+            return hit_indices;
+        }
+        if self.nodes.is_empty() {
+            return hit_indices;
+        }
+
+        // The stack holds nodes, and shapes whose `AABB`s were hit, as `(index, is_leaf)`.
+        let mut stack = vec![(0, false)];
+        while let Some((index, is_leaf)) = stack.pop() {
+            if is_leaf {
+                hit_indices.push(index as usize);
+                continue;
+            }
+
+            let node = &self.nodes[index as usize];
+            // Push the right child first, so that the left child is visited first.
+            for child in (0..2).rev() {
+                if ray.intersects_aabb(&node.child_aabb(child)) {
+                    stack.push((node.children[child], node.is_leaf(child)));
+                }
+            }
+        }
+
+        hit_indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bounding_hierarchy::BoundingHierarchy;
+    use crate::bvh::BVH;
+    use crate::packed_bvh::{PackedBVH, PackedNode};
+    use crate::testbase::{
+        build_cubes_bvh, check_traverse_equals_bvh, create_n_cubes, create_ray, default_bounds,
+        traverse_some_bh,
+    };
+
+    #[test]
+    /// Runs some primitive tests for intersections of a ray with a fixed scene given
+    /// as a `PackedBVH`.
+    fn test_traverse_packed_bvh() {
+        traverse_some_bh::<PackedBVH>();
+    }
+
+    #[test]
+    /// Tests whether a `PackedNode` fills exactly one cache line.
+    fn test_packed_node_size() {
+        assert_eq!(std::mem::size_of::<PackedNode>(), 64);
+        assert_eq!(std::mem::align_of::<PackedNode>(), 64);
+    }
+
+    #[test]
+    /// Tests whether a `PackedBVH` finds the same shapes in the same order as the `BVH`.
+    fn test_packed_bvh_equals_bvh() {
+        let (triangles, bvh) = build_cubes_bvh();
+        let packed = bvh.pack();
+        assert_eq!(packed.nodes.len(), triangles.len() - 1);
+
+        check_traverse_equals_bvh(&bvh, &triangles, |ray| packed.traverse(ray, &triangles));
+    }
+
+    #[test]
+    /// Tests whether packing a `BVH` without shapes yields a `PackedBVH` which is never hit.
+    fn test_pack_empty() {
+        let mut triangles = create_n_cubes(0, &default_bounds());
+        let bvh = BVH::build(&mut triangles);
+        let packed = bvh.pack();
+        assert!(packed.nodes.is_empty());
+
+        let mut seed = 0;
+        let ray = create_ray(&mut seed, &default_bounds());
+        assert!(packed.traverse(&ray, &triangles).is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! This module defines packets of [`Ray`]s, which are tested against [`AABB`]s together.
+//!
+//! The lanes of a packet are stored as arrays per component, so that the slab test
+//! of all lanes compiles to SIMD instructions.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`Ray`]: ../ray/struct.Ray.html
+//!
+
+use crate::aabb::AABB;
+use crate::ray::Ray;
+
+/// A packet of `N` [`Ray`]s. A set of lanes of the packet is given as a bitmask, in which
+/// bit `i` stands for lane `i`, so `N` must not be larger than `32`.
+///
+/// [`Ray`]: ../ray/struct.Ray.html
+///
+#[derive(Debug, Clone)]
+pub struct RayPacket<const N: usize> {
+    /// The x, y and z components of the origins of the rays.
+    origin: [[f32; N]; 3],
+
+    /// The x, y and z components of the inverse directions of the rays.
+    inv_direction: [[f32; N]; 3],
+
+    /// The start of the interval of each ray.
+    t_min: [f32; N],
+
+    /// The end of the interval of each ray.
+    t_max: [f32; N],
+}
+
+/// A [`RayPacket`] of 4 rays, which fits into one SSE register per component.
+///
+/// [`RayPacket`]: struct.RayPacket.html
+///
+pub type RayPacket4 = RayPacket<4>;
+
+/// A [`RayPacket`] of 8 rays, which fits into one AVX register per component.
+///
+/// [`RayPacket`]: struct.RayPacket.html
+///
+pub type RayPacket8 = RayPacket<8>;
+
+impl<const N: usize> RayPacket<N> {
+    /// The mask in which all lanes are set.
+    pub const ALL_LANES: u32 = if N == 32 { u32::MAX } else { (1 << N) - 1 };
+
+    /// Creates a new [`RayPacket`] from `N` rays.
+    ///
+    /// # Panics
+    /// Panics if `N` is larger than `32`.
+    ///
+    /// [`RayPacket`]: struct.RayPacket.html
+    ///
+    pub fn new(rays: [&Ray; N]) -> RayPacket<N> {
+        assert!(N <= 32, "a ray packet has at most 32 lanes");
+        let mut packet = RayPacket {
+            origin: [[0.0; N]; 3],
+            inv_direction: [[0.0; N]; 3],
+            t_min: [0.0; N],
+            t_max: [0.0; N],
+        };
+        for (lane, ray) in rays.iter().enumerate() {
+            for axis in 0..3 {
+                packet.origin[axis][lane] = ray.origin[axis];
+                packet.inv_direction[axis][lane] = 1.0 / ray.direction[axis];
+            }
+            packet.t_min[lane] = ray.t_min;
+            packet.t_max[lane] = ray.t_max;
+        }
+        packet
+    }
+
+    /// Tests the intersection of all lanes of the [`RayPacket`] with an [`AABB`], using
+    /// the branchless slab test. Returns the mask of the lanes whose rays intersect the
+    /// [`AABB`] inside of their interval, with the same boundaries as
+    /// [`Ray::intersects_aabb`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::packet::RayPacket4;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(0.0,0.0,0.0);
+    /// let hit = Ray::new(origin, Vector3::new(1.0,0.0,0.0));
+    /// let miss = Ray::new(origin, Vector3::new(-1.0,0.0,0.0));
+    /// let packet = RayPacket4::new([&hit, &miss, &miss, &hit]);
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(9.0,-1.0,-1.0), Point3::new(10.0,1.0,1.0));
+    /// assert_eq!(packet.intersects_aabb(&aabb), 0b1001);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray::intersects_aabb`]: ../ray/struct.Ray.html#method.intersects_aabb
+    /// [`RayPacket`]: struct.RayPacket.html
+    ///
+    pub fn intersects_aabb(&self, aabb: &AABB) -> u32 {
+        let mut entry = [f32::NEG_INFINITY; N];
+        let mut exit = [f32::INFINITY; N];
+
+        // See `Ray::intersects_aabb_branchless` for the order of `min` and `max`,
+        // which ignores the NaNs of rays lying exactly in a bounding plane.
+        for axis in 0..3 {
+            let origin = &self.origin[axis];
+            let inv_direction = &self.inv_direction[axis];
+            for lane in 0..N {
+                let t1 = (aabb.min[axis] - origin[lane]) * inv_direction[lane];
+                let t2 = (aabb.max[axis] - origin[lane]) * inv_direction[lane];
+                entry[lane] = t1.max(entry[lane]).min(t2.max(entry[lane]));
+                exit[lane] = t1.min(exit[lane]).max(t2.min(exit[lane]));
+            }
+        }
+
+        let mut mask = 0;
+        for lane in 0..N {
+            let hit = entry[lane] <= exit[lane]
+                && entry[lane] <= self.t_max[lane]
+                && exit[lane] > self.t_min[lane];
+            mask |= (hit as u32) << lane;
+        }
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::packet::{RayPacket, RayPacket4, RayPacket8};
+    use crate::ray::Ray;
+    use crate::testbase::{create_ray, default_bounds, next_point3};
+
+    /// Tests whether every lane of `packet` agrees with the single ray tests of `rays`.
+    fn check_lanes<const N: usize>(packet: &RayPacket<N>, rays: &[Ray], aabb: &AABB) {
+        let mask = packet.intersects_aabb(aabb);
+        for (lane, ray) in rays.iter().enumerate() {
+            assert_eq!(mask & (1 << lane) != 0, ray.intersects_aabb(aabb));
+        }
+        assert_eq!(mask & !RayPacket::<N>::ALL_LANES, 0);
+    }
+
+    #[test]
+    /// Tests whether `intersects_aabb` of a packet agrees with the tests of its rays.
+    fn test_packet_intersects_aabb() {
+        let bounds = default_bounds();
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let rays: Vec<Ray> = (0..8).map(|_| create_ray(&mut seed, &bounds)).collect();
+            let aabb = AABB::empty()
+                .grow(&next_point3(&mut seed, &bounds))
+                .grow(&next_point3(&mut seed, &bounds));
+
+            let packet4 = RayPacket4::new([&rays[0], &rays[1], &rays[2], &rays[3]]);
+            check_lanes(&packet4, &rays[..4], &aabb);
+
+            let packet8 = RayPacket8::new([
+                &rays[0], &rays[1], &rays[2], &rays[3], &rays[4], &rays[5], &rays[6], &rays[7],
+            ]);
+            check_lanes(&packet8, &rays, &aabb);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! This module defines a [`Plane`] and the classification of [`AABB`]s against it.
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`Plane`]: struct.Plane.html
+//!
+
+use crate::aabb::AABB;
+use crate::{Point3, Vector3};
+
+/// The side of a [`Plane`] on which an [`AABB`] lies.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`Plane`]: struct.Plane.html
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlaneSide {
+    /// The [`AABB`] lies completely on the side the normal points to.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Front,
+
+    /// The [`AABB`] lies completely on the side opposite to the normal.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Back,
+
+    /// The [`AABB`] intersects the plane.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Straddling,
+}
+
+/// A plane which contains all points `p` with `normal.dot(p) == distance`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    /// The normal of the plane. Points in front of the plane lie on this side.
+    pub normal: Vector3,
+
+    /// The signed distance of the plane from the origin along the `normal`, scaled by the
+    /// length of the `normal`.
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Creates a new [`Plane`] from its `normal` and its `distance` from the origin.
+    ///
+    /// [`Plane`]: struct.Plane.html
+    ///
+    pub fn new(normal: Vector3, distance: f32) -> Plane {
+        Plane { normal, distance }
+    }
+
+    /// Creates a new [`Plane`] through `point` with the given `normal`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::plane::Plane;
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// let plane = Plane::from_point_normal(Point3::new(0.0, 2.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+    ///
+    /// assert_eq!(plane.distance, 2.0);
+    /// assert_eq!(plane.signed_distance(&Point3::new(5.0, 3.0, 5.0)), 1.0);
+    /// ```
+    ///
+    /// [`Plane`]: struct.Plane.html
+    ///
+    pub fn from_point_normal(point: Point3, normal: Vector3) -> Plane {
+        Plane {
+            normal,
+            distance: normal.dot(point),
+        }
+    }
+
+    /// Returns the signed distance from the plane to `point`, scaled by the length of the
+    /// `normal`. The distance is positive in front of the plane.
+    pub fn signed_distance(&self, point: &Point3) -> f32 {
+        self.normal.dot(*point) - self.distance
+    }
+
+    /// Returns the [`PlaneSide`] on which `aabb` lies.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::plane::{Plane, PlaneSide};
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// let plane = Plane::new(Vector3::new(1.0, 0.0, 0.0), 0.0);
+    /// let front = AABB::with_bounds(Point3::new(1.0, -1.0, -1.0), Point3::new(2.0, 1.0, 1.0));
+    /// let back = AABB::with_bounds(Point3::new(-2.0, -1.0, -1.0), Point3::new(-1.0, 1.0, 1.0));
+    ///
+    /// assert_eq!(plane.classify_aabb(&front), PlaneSide::Front);
+    /// assert_eq!(plane.classify_aabb(&back), PlaneSide::Back);
+    /// assert_eq!(plane.classify_aabb(&front.join(&back)), PlaneSide::Straddling);
+    /// ```
+    ///
+    /// [`PlaneSide`]: enum.PlaneSide.html
+    ///
+    pub fn classify_aabb(&self, aabb: &AABB) -> PlaneSide {
+        let center_distance = self.signed_distance(&aabb.center());
+        let radius = self.normal.abs().dot(aabb.size() / 2.0);
+        if center_distance > radius {
+            PlaneSide::Front
+        } else if center_distance < -radius {
+            PlaneSide::Back
+        } else {
+            PlaneSide::Straddling
+        }
+    }
+
+    /// Returns the [`PlaneSide`] on which `aabb` lies with respect to the convex volume
+    /// bounded by `planes`, whose normals point to the inside. The result is
+    /// [`PlaneSide::Front`] if `aabb` lies in front of all `planes`, [`PlaneSide::Back`]
+    /// if it lies behind any of them, and [`PlaneSide::Straddling`] otherwise.
+    ///
+    /// [`PlaneSide::Straddling`] is conservative: an [`AABB`] near an edge of the volume
+    /// may straddle several planes without intersecting the volume.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::plane::{Plane, PlaneSide};
+    /// use bvh::{Point3, Vector3};
+    ///
+    /// // The slab between x = 0 and x = 10.
+    /// let planes = [
+    ///     Plane::new(Vector3::new(1.0, 0.0, 0.0), 0.0),
+    ///     Plane::new(Vector3::new(-1.0, 0.0, 0.0), -10.0),
+    /// ];
+    /// let inside = AABB::with_bounds(Point3::new(1.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0));
+    /// let outside = AABB::with_bounds(Point3::new(11.0, 0.0, 0.0), Point3::new(12.0, 1.0, 1.0));
+    ///
+    /// assert_eq!(Plane::classify_aabb_convex(&planes, &inside), PlaneSide::Front);
+    /// assert_eq!(Plane::classify_aabb_convex(&planes, &outside), PlaneSide::Back);
+    /// assert_eq!(
+    ///     Plane::classify_aabb_convex(&planes, &inside.join(&outside)),
+    ///     PlaneSide::Straddling
+    /// );
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`PlaneSide`]: enum.PlaneSide.html
+    /// [`PlaneSide::Back`]: enum.PlaneSide.html#variant.Back
+    /// [`PlaneSide::Front`]: enum.PlaneSide.html#variant.Front
+    /// [`PlaneSide::Straddling`]: enum.PlaneSide.html#variant.Straddling
+    ///
+    pub fn classify_aabb_convex(planes: &[Plane], aabb: &AABB) -> PlaneSide {
+        let mut side = PlaneSide::Front;
+        for plane in planes {
+            match plane.classify_aabb(aabb) {
+                PlaneSide::Front => {}
+                PlaneSide::Back => return PlaneSide::Back,
+                PlaneSide::Straddling => side = PlaneSide::Straddling,
+            }
+        }
+        side
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::AABB;
+    use crate::plane::{Plane, PlaneSide};
+    use crate::testbase::next_point3;
+    use crate::Point3;
+
+    #[test]
+    /// Tests whether the classification of an `AABB` agrees with the sides of its corners.
+    fn test_classify_aabb_agrees_with_corners() {
+        const TOLERANCE: f32 = 0.001;
+        let bounds = AABB::with_bounds(
+            Point3::new(-10.0, -10.0, -10.0),
+            Point3::new(10.0, 10.0, 10.0),
+        );
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let plane = Plane::from_point_normal(
+                next_point3(&mut seed, &bounds),
+                next_point3(&mut seed, &bounds),
+            );
+            let aabb = AABB::empty()
+                .grow(&next_point3(&mut seed, &bounds))
+                .grow(&next_point3(&mut seed, &bounds));
+
+            let distances: Vec<f32> = aabb
+                .corners()
+                .iter()
+                .map(|corner| plane.signed_distance(corner) / plane.normal.length())
+                .collect();
+
+            let expected = if distances.iter().all(|&d| d > TOLERANCE) {
+                PlaneSide::Front
+            } else if distances.iter().all(|&d| d < -TOLERANCE) {
+                PlaneSide::Back
+            } else if distances.iter().any(|&d| d > TOLERANCE)
+                && distances.iter().any(|&d| d < -TOLERANCE)
+            {
+                PlaneSide::Straddling
+            } else {
+                // Too close to the plane to tell.
+                continue;
+            };
+            assert_eq!(plane.classify_aabb(&aabb), expected);
+        }
+    }
+}
@@ -1,13 +1,14 @@
 //! This module defines a Ray structure and intersection algorithms
 //! for axis aligned bounding boxes and triangles.
 
-use crate::aabb::AABB;
+use crate::aabb::{IntersectsAabb, AABB, AABB4, AABB8};
 use crate::EPSILON;
 use crate::{Point3, Vector3};
-use std::f32::INFINITY;
+use glam::{Mat4, Vec3A, Vec4};
 
 /// A struct which defines a ray and some of its cached values.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ray {
     /// The ray origin.
     pub origin: Point3,
@@ -21,29 +22,39 @@ pub struct Ray {
     ///
     inv_direction: Vector3,
 
-    /// Sign of the X direction. 0 means positive, 1 means negative.
+    /// Sign of the X direction. 0 means positive, 1 means negative (including `-0.0`).
     /// Cached for use in [`AABB`] intersections.
     ///
     /// [`AABB`]: struct.AABB.html
     ///
     sign_x: usize,
 
-    /// Sign of the Y direction. 0 means positive, 1 means negative.
+    /// Sign of the Y direction. 0 means positive, 1 means negative (including `-0.0`).
     /// Cached for use in [`AABB`] intersections.
     ///
     /// [`AABB`]: struct.AABB.html
     ///
     sign_y: usize,
 
-    /// Sign of the Z direction. 0 means positive, 1 means negative.
+    /// Sign of the Z direction. 0 means positive, 1 means negative (including `-0.0`).
     /// Cached for use in [`AABB`] intersections.
     ///
     /// [`AABB`]: struct.AABB.html
     ///
     sign_z: usize,
+
+    /// The start of the parametric interval along the ray in which intersections are
+    /// reported. Defaults to `0.0`.
+    pub t_min: f32,
+
+    /// The end of the parametric interval along the ray in which intersections are
+    /// reported. Defaults to positive infinity.
+    pub t_max: f32,
 }
 
 /// A struct which is returned by the `intersects_triangle` method.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
 pub struct Intersection {
     /// Distance from the ray origin to the intersection point.
     pub distance: f32,
@@ -63,6 +74,75 @@ impl Intersection {
     }
 }
 
+/// The face of a triangle which is hit by a [`Ray`].
+///
+/// [`Ray`]: struct.Ray.html
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub enum Face {
+    /// The side from which the vertices of the triangle appear in counter-clockwise order.
+    Front,
+
+    /// The side from which the vertices of the triangle appear in clockwise order.
+    Back,
+}
+
+/// An implementation of [`Ray::intersects_aabb8`], which is chosen once for the CPU by
+/// [`AABB8Kernel::detect`], so that wide traversals do not detect CPU features per node.
+///
+/// [`AABB8Kernel::detect`]: enum.AABB8Kernel.html#method.detect
+/// [`Ray::intersects_aabb8`]: struct.Ray.html#method.intersects_aabb8
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) enum AABB8Kernel {
+    /// AVX intrinsics, if the CPU supports AVX.
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    Avx,
+    /// NEON intrinsics, which every AArch64 target with NEON supports.
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    Neon,
+    /// A loop over the eight [`AABB`]s, which the compiler may vectorize.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    Scalar,
+}
+
+impl AABB8Kernel {
+    /// Returns the fastest kernel which the CPU supports.
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    pub(crate) fn detect() -> AABB8Kernel {
+        AABB8Kernel::Neon
+    }
+
+    /// Returns the fastest kernel which the CPU supports.
+    #[cfg(not(all(target_arch = "aarch64", target_feature = "neon")))]
+    pub(crate) fn detect() -> AABB8Kernel {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx") {
+                return AABB8Kernel::Avx;
+            }
+        }
+        AABB8Kernel::Scalar
+    }
+}
+
+/// Shapes which can be intersected by a [`Ray`].
+///
+/// [`Ray`]: struct.Ray.html
+///
+pub trait Intersectable {
+    /// Returns the closest [`Intersection`] of `ray` with this shape in front of the ray
+    /// origin, or `None` if the ray misses the shape.
+    ///
+    /// [`Intersection`]: struct.Intersection.html
+    ///
+    fn intersect(&self, ray: &Ray) -> Option<Intersection>;
+}
+
 impl Ray {
     /// Creates a new [`Ray`] from an `origin` and a `direction`.
     /// `direction` will be normalized.
@@ -88,15 +168,155 @@ impl Ray {
             origin,
             direction,
             inv_direction: Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z),
-            sign_x: (direction.x < 0.0) as usize,
-            sign_y: (direction.y < 0.0) as usize,
-            sign_z: (direction.z < 0.0) as usize,
+            // The sign has to match the sign of the inverse direction, which is -inf for -0.0.
+            sign_x: direction.x.is_sign_negative() as usize,
+            sign_y: direction.y.is_sign_negative() as usize,
+            sign_z: direction.z.is_sign_negative() as usize,
+            t_min: 0.0,
+            t_max: f32::INFINITY,
+        }
+    }
+
+    /// Creates a new [`Ray`] from an `origin` and a `direction`, which only reports
+    /// intersections at distances between `t_min` and `t_max` from the `origin`.
+    /// `direction` will be normalized.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(0.0,0.0,0.0);
+    /// let direction = Vector3::new(1.0,0.0,0.0);
+    /// let aabb = AABB::with_bounds(Point3::new(50.0,-1.0,-1.0), Point3::new(51.0,1.0,1.0));
+    ///
+    /// assert!(Ray::with_interval(origin, direction, 1.0, 60.0).intersects_aabb(&aabb));
+    /// assert!(!Ray::with_interval(origin, direction, 1.0, 35.0).intersects_aabb(&aabb));
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn with_interval(origin: Point3, direction: Vector3, t_min: f32, t_max: f32) -> Ray {
+        Ray {
+            t_min,
+            t_max,
+            ..Ray::new(origin, direction)
         }
     }
 
+    /// Creates a new [`Ray`] from `origin` towards `target`, whose interval ends at
+    /// `target`. Returns the [`Ray`] together with the distance from `origin` to `target`,
+    /// or `None` if both points are equal, as the direction is undefined.
+    ///
+    /// This is the usual way to create shadow and visibility rays, which must not report
+    /// intersections behind the `target`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(1.0,0.0,0.0);
+    /// let target = Point3::new(1.0,0.0,5.0);
+    /// let (ray, distance) = Ray::between(origin, target).unwrap();
+    ///
+    /// assert_eq!(distance, 5.0);
+    /// assert_eq!(ray.t_max, distance);
+    /// assert_eq!(ray.direction, Vector3::new(0.0,0.0,1.0));
+    /// assert!(Ray::between(origin, origin).is_none());
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn between(origin: Point3, target: Point3) -> Option<(Ray, f32)> {
+        let direction = target - origin;
+        let distance = direction.length();
+        if distance > 0.0 {
+            Some((
+                Ray::with_interval(origin, direction, 0.0, distance),
+                distance,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the [`Ray`] transformed by the affine `transform`, for example into the
+    /// local space of an instance. The cached values are recomputed for the new direction.
+    ///
+    /// As the direction is normalized again, distances along the transformed [`Ray`] are
+    /// scaled by the length of `transform.transform_vector3(self.direction)`. The
+    /// interval is scaled accordingly, so that it covers the same points.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    /// use glam::Mat4;
+    ///
+    /// let ray = Ray::with_interval(Point3::new(0.0,0.0,0.0), Vector3::new(1.0,0.0,0.0), 0.0, 10.0);
+    /// let transform = Mat4::from_translation(Vector3::new(0.0,5.0,0.0))
+    ///     * Mat4::from_scale(Vector3::new(2.0,2.0,2.0));
+    /// let transformed = ray.transformed(&transform);
+    ///
+    /// assert_eq!(transformed.origin, Point3::new(0.0,5.0,0.0));
+    /// assert_eq!(transformed.direction, Vector3::new(1.0,0.0,0.0));
+    /// assert_eq!(transformed.t_max, 20.0);
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn transformed(&self, transform: &Mat4) -> Ray {
+        let direction = transform.transform_vector3(self.direction);
+        let scale = direction.length();
+        Ray::with_interval(
+            transform.transform_point3(self.origin),
+            direction,
+            self.t_min * scale,
+            self.t_max * scale,
+        )
+    }
+
+    /// Returns the octant of the direction of the [`Ray`] as a number from `0` to `7`.
+    /// Bit `0`, `1` and `2` are set if the x, y and z component of the direction is
+    /// negative, respectively (including `-0.0`).
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(0.0,0.0,0.0);
+    ///
+    /// assert_eq!(Ray::new(origin, Vector3::new(1.0,1.0,1.0)).octant(), 0);
+    /// assert_eq!(Ray::new(origin, Vector3::new(-1.0,1.0,1.0)).octant(), 1);
+    /// assert_eq!(Ray::new(origin, Vector3::new(1.0,1.0,-1.0)).octant(), 4);
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn octant(&self) -> usize {
+        self.sign_x | self.sign_y << 1 | self.sign_z << 2
+    }
+
+    /// Returns true if the direction of the [`Ray`] is negative along `axis`.
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub(crate) fn is_negative(&self, axis: usize) -> bool {
+        (self.octant() >> axis) & 1 == 1
+    }
+
     /// Tests the intersection of a [`Ray`] with an [`AABB`] using the optimized algorithm
     /// from [this paper](http://www.cs.utah.edu/~awilliam/box/box.pdf).
     ///
+    /// An [`AABB`] is hit if the [`Ray`] enters it no later than it exits it, enters it no
+    /// later than `t_max` and exits it after `t_min`. So zero-thickness [`AABB`]s and
+    /// [`AABB`]s which are entered exactly at `t_max` are hit, while [`AABB`]s which are
+    /// exited exactly at `t_min` are missed. All other [`AABB`] tests of [`Ray`],
+    /// [`AABB4`] and [`AABB8`] and of [`RayPacket`] follow the same convention.
+    ///
     /// # Examples
     /// ```
     /// use bvh::aabb::AABB;
@@ -114,13 +334,28 @@ impl Ray {
     /// assert!(ray.intersects_aabb(&aabb));
     /// ```
     ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`AABB4`]: ../aabb/struct.AABB4.html
+    /// [`AABB8`]: ../aabb/struct.AABB8.html
     /// [`Ray`]: struct.Ray.html
-    /// [`AABB`]: struct.AABB.html
+    /// [`RayPacket`]: ../packet/struct.RayPacket.html
     ///
     pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        if cfg!(feature = "simd") {
+            return self.intersects_aabb_simd(aabb);
+        }
+
         let mut ray_min = (aabb[self.sign_x].x - self.origin.x) * self.inv_direction.x;
         let mut ray_max = (aabb[1 - self.sign_x].x - self.origin.x) * self.inv_direction.x;
 
+        // A ray parallel to the x-axis which lies exactly in a bounding plane yields NaN,
+        // `0.0 * inf`. It is inside of the slab, so its interval must not be limited.
+        // NaNs of the other axes are ignored by the comparisons below.
+        if ray_min.is_nan() || ray_max.is_nan() {
+            ray_min = f32::NEG_INFINITY;
+            ray_max = f32::INFINITY;
+        }
+
         let y_min = (aabb[self.sign_y].y - self.origin.y) * self.inv_direction.y;
         let y_max = (aabb[1 - self.sign_y].y - self.origin.y) * self.inv_direction.y;
 
@@ -147,10 +382,9 @@ impl Ray {
             return false;
         }
 
-        // Only required for bounded intersection intervals.
-        // if z_min > ray_min {
-        // ray_min = z_min;
-        // }
+        if z_min > ray_min {
+            ray_min = z_min;
+        }
 
         if z_max < ray_max {
             ray_max = z_max;
@@ -158,7 +392,54 @@ impl Ray {
         // Using the following solution significantly decreases the performance
         // ray_max = ray_max.min(y_max);
 
-        ray_max > 0.0
+        ray_min <= self.t_max && ray_max > self.t_min
+    }
+
+    /// Computes the distances at which the [`Ray`] enters and exits the [`AABB`] using the
+    /// slab method. Returns `None` if the [`Ray`] misses the [`AABB`] or if the [`AABB`]
+    /// lies outside of the interval of the [`Ray`]. The entry distance is smaller than
+    /// `t_min` if the [`AABB`] is entered before the interval starts.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(0.0,0.0,0.0);
+    /// let direction = Vector3::new(1.0,0.0,0.0);
+    /// let ray = Ray::new(origin, direction);
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(10.0,-1.0,-1.0), Point3::new(20.0,1.0,1.0));
+    /// assert_eq!(ray.intersection_slab(&aabb), Some((10.0, 20.0)));
+    ///
+    /// // The origin lies inside of this `AABB`, so it is entered behind the origin.
+    /// let aabb = AABB::with_bounds(Point3::new(-5.0,-1.0,-1.0), Point3::new(5.0,1.0,1.0));
+    /// assert_eq!(ray.intersection_slab(&aabb), Some((-5.0, 5.0)));
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(-20.0,-1.0,-1.0), Point3::new(-10.0,1.0,1.0));
+    /// assert_eq!(ray.intersection_slab(&aabb), None);
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    /// [`AABB`]: struct.AABB.html
+    ///
+    pub fn intersection_slab(&self, aabb: &AABB) -> Option<(f32, f32)> {
+        let x_min = (aabb[self.sign_x].x - self.origin.x) * self.inv_direction.x;
+        let x_max = (aabb[1 - self.sign_x].x - self.origin.x) * self.inv_direction.x;
+        let y_min = (aabb[self.sign_y].y - self.origin.y) * self.inv_direction.y;
+        let y_max = (aabb[1 - self.sign_y].y - self.origin.y) * self.inv_direction.y;
+        let z_min = (aabb[self.sign_z].z - self.origin.z) * self.inv_direction.z;
+        let z_max = (aabb[1 - self.sign_z].z - self.origin.z) * self.inv_direction.z;
+
+        let ray_min = x_min.max(y_min).max(z_min);
+        let ray_max = x_max.min(y_max).min(z_max);
+
+        if ray_min <= ray_max && ray_min <= self.t_max && ray_max > self.t_min {
+            Some((ray_min, ray_max))
+        } else {
+            None
+        }
     }
 
     /// Naive implementation of a [`Ray`]/[`AABB`] intersection algorithm.
@@ -193,17 +474,24 @@ impl Ray {
         let hit_min_z = (aabb.min.z - self.origin.z) * self.inv_direction.z;
         let hit_max_z = (aabb.max.z - self.origin.z) * self.inv_direction.z;
 
-        let x_entry = hit_min_x.min(hit_max_x);
-        let y_entry = hit_min_y.min(hit_max_y);
-        let z_entry = hit_min_z.min(hit_max_z);
-        let x_exit = hit_min_x.max(hit_max_x);
-        let y_exit = hit_min_y.max(hit_max_y);
-        let z_exit = hit_min_z.max(hit_max_z);
+        // A ray parallel to an axis which lies exactly in a bounding plane yields NaN,
+        // `0.0 * inf`. It is inside of the slab, so its interval must not be limited.
+        let slab = |hit_min: f32, hit_max: f32| {
+            if hit_min.is_nan() || hit_max.is_nan() {
+                (f32::NEG_INFINITY, f32::INFINITY)
+            } else {
+                (hit_min.min(hit_max), hit_min.max(hit_max))
+            }
+        };
+        let (x_entry, x_exit) = slab(hit_min_x, hit_max_x);
+        let (y_entry, y_exit) = slab(hit_min_y, hit_max_y);
+        let (z_entry, z_exit) = slab(hit_min_z, hit_max_z);
 
         let latest_entry = x_entry.max(y_entry).max(z_entry);
         let earliest_exit = x_exit.min(y_exit).min(z_exit);
 
-        latest_entry < earliest_exit && earliest_exit > 0.0
+        // Zero-thickness `AABB`s are entered and exited at the same distance.
+        latest_entry <= earliest_exit && latest_entry <= self.t_max && earliest_exit > self.t_min
     }
 
     /// Implementation of the algorithm described [here]
@@ -230,35 +518,409 @@ impl Ray {
     /// [`AABB`]: struct.AABB.html
     ///
     pub fn intersects_aabb_branchless(&self, aabb: &AABB) -> bool {
+        // The slabs are clipped in the order proposed in [part 2]
+        // (https://tavianator.com/fast-branchless-raybounding-box-intersections-part-2-nans/).
+        // `f32::min` and `f32::max` ignore the NaN of a ray which lies exactly in a
+        // bounding plane, `0.0 * inf`, so such a slab does not limit the interval.
+        let mut tmin = f32::NEG_INFINITY;
+        let mut tmax = f32::INFINITY;
+
         let tx1 = (aabb.min.x - self.origin.x) * self.inv_direction.x;
         let tx2 = (aabb.max.x - self.origin.x) * self.inv_direction.x;
 
-        let mut tmin = tx1.min(tx2);
-        let mut tmax = tx1.max(tx2);
+        tmin = tx1.max(tmin).min(tx2.max(tmin));
+        tmax = tx1.min(tmax).max(tx2.min(tmax));
 
         let ty1 = (aabb.min.y - self.origin.y) * self.inv_direction.y;
         let ty2 = (aabb.max.y - self.origin.y) * self.inv_direction.y;
 
-        tmin = tmin.max(ty1.min(ty2));
-        tmax = tmax.min(ty1.max(ty2));
+        tmin = ty1.max(tmin).min(ty2.max(tmin));
+        tmax = ty1.min(tmax).max(ty2.min(tmax));
 
         let tz1 = (aabb.min.z - self.origin.z) * self.inv_direction.z;
         let tz2 = (aabb.max.z - self.origin.z) * self.inv_direction.z;
 
-        tmin = tmin.max(tz1.min(tz2));
-        tmax = tmax.min(tz1.max(tz2));
+        tmin = tz1.max(tmin).min(tz2.max(tmin));
+        tmax = tz1.min(tmax).max(tz2.min(tmax));
 
-        tmax >= tmin && tmax >= 0.0
+        tmax >= tmin && tmin <= self.t_max && tmax > self.t_min
+    }
+
+    /// Tests the intersection of a [`Ray`] with an [`AABB`] like [`Ray::intersects_aabb`],
+    /// but computes the three slabs at once with the SIMD type [`glam::Vec3A`]. `glam`
+    /// selects SSE2, NEON or `simd128` at compile time, and falls back to scalar code on
+    /// other targets. With the `simd` feature, [`Ray::intersects_aabb`] uses this method.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let origin = Point3::new(0.0,0.0,0.0);
+    /// let direction = Vector3::new(1.0,0.0,0.0);
+    /// let ray = Ray::new(origin, direction);
+    ///
+    /// let point1 = Point3::new(99.9,-1.0,-1.0);
+    /// let point2 = Point3::new(100.1,1.0,1.0);
+    /// let aabb = AABB::with_bounds(point1, point2);
+    ///
+    /// assert!(ray.intersects_aabb_simd(&aabb));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    /// [`Ray::intersects_aabb`]: struct.Ray.html#method.intersects_aabb
+    ///
+    pub fn intersects_aabb_simd(&self, aabb: &AABB) -> bool {
+        let origin = Vec3A::from(self.origin);
+        let inv_direction = Vec3A::from(self.inv_direction);
+        let t1 = (Vec3A::from(aabb.min) - origin) * inv_direction;
+        let t2 = (Vec3A::from(aabb.max) - origin) * inv_direction;
+
+        // As in `Ray::intersects_aabb_branchless`, the NaN of a ray which lies exactly in a
+        // bounding plane is the first operand of `min` and `max`, so it is ignored.
+        let entry = t1
+            .max(Vec3A::splat(f32::NEG_INFINITY))
+            .min(t2.max(Vec3A::splat(f32::NEG_INFINITY)));
+        let exit = t1
+            .min(Vec3A::splat(f32::INFINITY))
+            .max(t2.min(Vec3A::splat(f32::INFINITY)));
+
+        let latest_entry = entry.max_element();
+        let earliest_exit = exit.min_element();
+        latest_entry <= earliest_exit && latest_entry <= self.t_max && earliest_exit > self.t_min
+    }
+
+    /// Tests the intersection of a [`Ray`] with four [`AABB`]s at once, with one SIMD lane
+    /// per [`AABB`]. Returns a mask whose bit `i` is set if the [`AABB`] in slot `i` is hit,
+    /// and the distances at which the [`Ray`] enters the [`AABB`]s. The distance of a hit
+    /// [`AABB`] is before `t_min` if the [`Ray`] starts inside of it, and the distances of
+    /// missed [`AABB`]s are unspecified. Empty [`AABB`]s are never hit.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, AABB4};
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let ray = Ray::new(Point3::new(0.0,0.0,0.0), Vector3::new(1.0,0.0,0.0));
+    /// let aabb = |x: f32, y: f32| {
+    ///     AABB::with_bounds(Point3::new(x,y-1.0,-1.0), Point3::new(x+1.0,y+1.0,1.0))
+    /// };
+    /// let aabbs = AABB4::from([aabb(2.0,0.0), aabb(4.0,5.0), AABB::empty(), aabb(-3.0,0.0)]);
+    ///
+    /// let (mask, entry) = ray.intersects_aabb4(&aabbs);
+    /// assert_eq!(mask, 0b0001);
+    /// assert_eq!(entry.x, 2.0);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn intersects_aabb4(&self, aabbs: &AABB4) -> (u32, Vec4) {
+        let mut entry = Vec4::splat(f32::NEG_INFINITY);
+        let mut exit = Vec4::splat(f32::INFINITY);
+        let mut not_empty = Vec4::ZERO.cmpeq(Vec4::ZERO);
+        for axis in 0..3 {
+            let origin = Vec4::splat(self.origin[axis]);
+            let inv_direction = Vec4::splat(self.inv_direction[axis]);
+            let t1 = (aabbs.min[axis] - origin) * inv_direction;
+            let t2 = (aabbs.max[axis] - origin) * inv_direction;
+
+            // The same order of `min` and `max` as in `Ray::intersects_aabb_branchless`.
+            entry = t1.max(entry).min(t2.max(entry));
+            exit = t1.min(exit).max(t2.min(exit));
+            not_empty &= aabbs.min[axis].cmple(aabbs.max[axis]);
+        }
+
+        let hit = entry.cmple(exit)
+            & entry.cmple(Vec4::splat(self.t_max))
+            & exit.cmpgt(Vec4::splat(self.t_min))
+            & not_empty;
+        (hit.bitmask(), entry)
+    }
+
+    /// Tests the intersection of a [`Ray`] with eight [`AABB`]s at once. Returns a mask
+    /// whose bit `i` is set if the [`AABB`] in slot `i` is hit, and the distances at which
+    /// the [`Ray`] enters the [`AABB`]s, like [`intersects_aabb4`].
+    ///
+    /// On x86 and x86-64 the eight [`AABB`]s are tested with one AVX lane each if the CPU
+    /// supports AVX, which is detected at runtime. On AArch64 they are tested with two NEON
+    /// registers. Otherwise they are tested one by one in a loop, which the compiler may
+    /// vectorize for the target.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::{AABB, AABB8};
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let ray = Ray::new(Point3::new(0.0,0.0,0.0), Vector3::new(1.0,0.0,0.0));
+    /// let mut aabbs = AABB8::empty();
+    /// for slot in 0..8 {
+    ///     let y = slot as f32 - 1.0;
+    ///     aabbs.set(slot, &AABB::with_bounds(Point3::new(2.0,y,-1.0), Point3::new(3.0,y+1.0,1.0)));
+    /// }
+    ///
+    /// let (mask, entry) = ray.intersects_aabb8(&aabbs);
+    /// assert_eq!(mask, 0b0011);
+    /// assert_eq!(entry[0], 2.0);
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray`]: struct.Ray.html
+    /// [`intersects_aabb4`]: struct.Ray.html#method.intersects_aabb4
+    ///
+    pub fn intersects_aabb8(&self, aabbs: &AABB8) -> (u32, [f32; 8]) {
+        self.intersects_aabb8_with(aabbs, AABB8Kernel::detect())
+    }
+
+    /// Implements [`Ray::intersects_aabb8`] with the given `kernel`, which has to come from
+    /// [`AABB8Kernel::detect`] or be [`AABB8Kernel::Scalar`].
+    ///
+    /// [`AABB8Kernel::detect`]: enum.AABB8Kernel.html#method.detect
+    /// [`AABB8Kernel::Scalar`]: enum.AABB8Kernel.html#variant.Scalar
+    /// [`Ray::intersects_aabb8`]: struct.Ray.html#method.intersects_aabb8
+    ///
+    pub(crate) fn intersects_aabb8_with(
+        &self,
+        aabbs: &AABB8,
+        kernel: AABB8Kernel,
+    ) -> (u32, [f32; 8]) {
+        match kernel {
+            // SAFETY: `AABB8Kernel::detect` only returns `Avx` if the CPU supports AVX.
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            AABB8Kernel::Avx => unsafe { self.intersects_aabb8_avx(aabbs) },
+            // SAFETY: The target supports NEON, which was checked at compile time.
+            #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+            AABB8Kernel::Neon => unsafe { self.intersects_aabb8_neon(aabbs) },
+            AABB8Kernel::Scalar => self.intersects_aabb8_scalar(aabbs),
+        }
+    }
+
+    /// Implements [`Ray::intersects_aabb8`] with AVX intrinsics.
+    ///
+    /// # Safety
+    /// The CPU has to support AVX.
+    ///
+    /// [`Ray::intersects_aabb8`]: struct.Ray.html#method.intersects_aabb8
+    ///
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[target_feature(enable = "avx")]
+    unsafe fn intersects_aabb8_avx(&self, aabbs: &AABB8) -> (u32, [f32; 8]) {
+        #[cfg(target_arch = "x86")]
+        use std::arch::x86::*;
+        #[cfg(target_arch = "x86_64")]
+        use std::arch::x86_64::*;
+
+        let mut entry = _mm256_set1_ps(f32::NEG_INFINITY);
+        let mut exit = _mm256_set1_ps(f32::INFINITY);
+        let mut not_empty = _mm256_cmp_ps::<_CMP_EQ_OQ>(entry, entry);
+        for axis in 0..3 {
+            let origin = _mm256_set1_ps(self.origin[axis]);
+            let inv_direction = _mm256_set1_ps(self.inv_direction[axis]);
+            let min = _mm256_loadu_ps(aabbs.min[axis].as_ptr());
+            let max = _mm256_loadu_ps(aabbs.max[axis].as_ptr());
+            let t1 = _mm256_mul_ps(_mm256_sub_ps(min, origin), inv_direction);
+            let t2 = _mm256_mul_ps(_mm256_sub_ps(max, origin), inv_direction);
+
+            // `_mm256_max_ps` and `_mm256_min_ps` return their second operand if the first
+            // one is NaN, so NaN distances are ignored like in `intersects_aabb4`.
+            entry = _mm256_min_ps(_mm256_max_ps(t1, entry), _mm256_max_ps(t2, entry));
+            exit = _mm256_max_ps(_mm256_min_ps(t1, exit), _mm256_min_ps(t2, exit));
+            not_empty = _mm256_and_ps(not_empty, _mm256_cmp_ps::<_CMP_LE_OQ>(min, max));
+        }
+
+        let hit = _mm256_and_ps(
+            _mm256_and_ps(
+                _mm256_cmp_ps::<_CMP_LE_OQ>(entry, exit),
+                _mm256_cmp_ps::<_CMP_LE_OQ>(entry, _mm256_set1_ps(self.t_max)),
+            ),
+            _mm256_and_ps(
+                _mm256_cmp_ps::<_CMP_GT_OQ>(exit, _mm256_set1_ps(self.t_min)),
+                not_empty,
+            ),
+        );
+        let mut entries = [0.0; 8];
+        _mm256_storeu_ps(entries.as_mut_ptr(), entry);
+        (_mm256_movemask_ps(hit) as u32, entries)
+    }
+
+    /// Implements [`Ray::intersects_aabb8`] with NEON intrinsics, four [`AABB`]s at a time.
+    ///
+    /// # Safety
+    /// The CPU has to support NEON.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray::intersects_aabb8`]: struct.Ray.html#method.intersects_aabb8
+    ///
+    #[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+    #[target_feature(enable = "neon")]
+    unsafe fn intersects_aabb8_neon(&self, aabbs: &AABB8) -> (u32, [f32; 8]) {
+        use std::arch::aarch64::*;
+
+        let lane_bits: [u32; 4] = [1, 2, 4, 8];
+        let lane_bits = vld1q_u32(lane_bits.as_ptr());
+        let mut mask = 0;
+        let mut entries = [0.0; 8];
+        for half in 0..2 {
+            let offset = 4 * half;
+            let mut entry = vdupq_n_f32(f32::NEG_INFINITY);
+            let mut exit = vdupq_n_f32(f32::INFINITY);
+            let mut not_empty = vceqq_f32(entry, entry);
+            for axis in 0..3 {
+                let origin = vdupq_n_f32(self.origin[axis]);
+                let inv_direction = vdupq_n_f32(self.inv_direction[axis]);
+                let min = vld1q_f32(aabbs.min[axis][offset..].as_ptr());
+                let max = vld1q_f32(aabbs.max[axis][offset..].as_ptr());
+                let t1 = vmulq_f32(vsubq_f32(min, origin), inv_direction);
+                let t2 = vmulq_f32(vsubq_f32(max, origin), inv_direction);
+
+                // Unlike `vmaxq_f32` and `vminq_f32`, `vmaxnmq_f32` and `vminnmq_f32` return
+                // the other operand if one is NaN, so NaN distances are ignored like in
+                // `intersects_aabb4`.
+                entry = vminnmq_f32(vmaxnmq_f32(t1, entry), vmaxnmq_f32(t2, entry));
+                exit = vmaxnmq_f32(vminnmq_f32(t1, exit), vminnmq_f32(t2, exit));
+                not_empty = vandq_u32(not_empty, vcleq_f32(min, max));
+            }
+
+            let hit = vandq_u32(
+                vandq_u32(
+                    vcleq_f32(entry, exit),
+                    vcleq_f32(entry, vdupq_n_f32(self.t_max)),
+                ),
+                vandq_u32(vcgtq_f32(exit, vdupq_n_f32(self.t_min)), not_empty),
+            );
+            mask |= vaddvq_u32(vandq_u32(hit, lane_bits)) << offset;
+            vst1q_f32(entries[offset..].as_mut_ptr(), entry);
+        }
+        (mask, entries)
+    }
+
+    /// Implements [`Ray::intersects_aabb8`] without intrinsics.
+    ///
+    /// [`Ray::intersects_aabb8`]: struct.Ray.html#method.intersects_aabb8
+    ///
+    fn intersects_aabb8_scalar(&self, aabbs: &AABB8) -> (u32, [f32; 8]) {
+        let mut mask = 0;
+        let mut entries = [0.0; 8];
+        for (slot, entry_slot) in entries.iter_mut().enumerate() {
+            let mut entry = f32::NEG_INFINITY;
+            let mut exit = f32::INFINITY;
+            let mut not_empty = true;
+            for axis in 0..3 {
+                let min = aabbs.min[axis][slot];
+                let max = aabbs.max[axis][slot];
+                let t1 = (min - self.origin[axis]) * self.inv_direction[axis];
+                let t2 = (max - self.origin[axis]) * self.inv_direction[axis];
+                entry = t1.max(entry).min(t2.max(entry));
+                exit = t1.min(exit).max(t2.min(exit));
+                not_empty &= min <= max;
+            }
+            if entry <= exit && entry <= self.t_max && exit > self.t_min && not_empty {
+                mask |= 1 << slot;
+            }
+            *entry_slot = entry;
+        }
+        (mask, entries)
+    }
+
+    /// Tests the intersection of a [`Ray`] with a sphere given by its `center` and `radius`.
+    /// Returns the distance at which the ray first hits the surface of the sphere inside of
+    /// its interval, or `None` if it does not. If the ray starts inside of the sphere, this
+    /// is the distance at which it leaves the sphere.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let ray = Ray::new(Point3::new(0.0,0.0,0.0), Vector3::new(1.0,0.0,0.0));
+    ///
+    /// assert_eq!(ray.intersects_sphere(&Point3::new(10.0,0.0,0.0), 1.0), Some(9.0));
+    /// assert_eq!(ray.intersects_sphere(&Point3::new(0.0,0.0,0.0), 1.0), Some(1.0));
+    /// assert!(ray.intersects_sphere(&Point3::new(10.0,0.5,0.0), 1.0).is_some());
+    /// assert!(ray.intersects_sphere(&Point3::new(10.0,2.0,0.0), 1.0).is_none());
+    /// assert!(ray.intersects_sphere(&Point3::new(-10.0,0.0,0.0), 1.0).is_none());
+    /// ```
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn intersects_sphere(&self, center: &Point3, radius: f32) -> Option<f32> {
+        let origin_to_center = *center - self.origin;
+        let projection = origin_to_center.dot(self.direction);
+
+        // Squared distance from the center of the sphere to the line of the ray.
+        let distance_squared = origin_to_center.length_squared() - projection * projection;
+        let radius_squared = radius * radius;
+        if distance_squared > radius_squared {
+            return None;
+        }
+
+        // The ray is inside of the sphere between `projection - half_chord` and
+        // `projection + half_chord`.
+        let half_chord = (radius_squared - distance_squared).sqrt();
+        let entry = projection - half_chord;
+        let exit = projection + half_chord;
+        if entry >= self.t_min && entry <= self.t_max {
+            Some(entry)
+        } else if exit >= self.t_min && exit <= self.t_max {
+            Some(exit)
+        } else {
+            None
+        }
     }
 
     /// Implementation of the [Möller-Trumbore triangle/ray intersection algorithm]
     /// (https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm).
     /// Returns the distance to the intersection, as well as
     /// the u and v coordinates of the intersection.
-    /// The distance is set to +INFINITY if the ray does not intersect the triangle, or hits
-    /// it from behind.
-    #[allow(clippy::many_single_char_names)]
+    /// The distance is set to +INFINITY if the ray does not intersect the triangle, hits
+    /// it from behind, or hits it outside of the interval of the ray.
     pub fn intersects_triangle(&self, a: &Point3, b: &Point3, c: &Point3) -> Intersection {
+        match self.intersects_triangle_face(a, b, c, true) {
+            Some((intersection, _)) => intersection,
+            None => Intersection::new(f32::INFINITY, 0.0, 0.0),
+        }
+    }
+
+    /// Variant of [`Ray::intersects_triangle`], which also reports the [`Face`] of the
+    /// triangle that was hit. The front face is the one from which `a`, `b` and `c`
+    /// appear in counter-clockwise order.
+    /// Back faces are only hit if `cull_back_faces` is `false`. Otherwise they are
+    /// rejected right after the determinant is computed, before any other work is done.
+    /// Returns `None` if the ray does not hit the triangle inside of its interval.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::{Face, Ray};
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// let a = Point3::new(0.0,0.0,0.0);
+    /// let b = Point3::new(1.0,0.0,0.0);
+    /// let c = Point3::new(0.0,1.0,0.0);
+    /// let from_above = Ray::new(Point3::new(0.2,0.2,1.0), Vector3::new(0.0,0.0,-1.0));
+    /// let from_below = Ray::new(Point3::new(0.2,0.2,-1.0), Vector3::new(0.0,0.0,1.0));
+    ///
+    /// let (_, face) = from_above.intersects_triangle_face(&a, &b, &c, true).unwrap();
+    /// assert_eq!(face, Face::Front);
+    ///
+    /// assert!(from_below.intersects_triangle_face(&a, &b, &c, true).is_none());
+    /// let (_, face) = from_below.intersects_triangle_face(&a, &b, &c, false).unwrap();
+    /// assert_eq!(face, Face::Back);
+    /// ```
+    ///
+    /// [`Face`]: enum.Face.html
+    /// [`Ray::intersects_triangle`]: struct.Ray.html#method.intersects_triangle
+    ///
+    #[allow(clippy::many_single_char_names)]
+    pub fn intersects_triangle_face(
+        &self,
+        a: &Point3,
+        b: &Point3,
+        c: &Point3,
+        cull_back_faces: bool,
+    ) -> Option<(Intersection, Face)> {
         let a_to_b = *b - *a;
         let a_to_c = *c - *a;
 
@@ -272,12 +934,14 @@ impl Ray {
         // det = 0 => [dir, a_to_b, a_to_c] not linearly independant
         let det = a_to_b.dot(u_vec);
 
-        // Only testing positive bound, thus enabling backface culling
-        // If backface culling is not desired write:
-        // det < EPSILON && det > -EPSILON
-        if det < EPSILON {
-            return Intersection::new(INFINITY, 0.0, 0.0);
-        }
+        // A negative determinant means that the ray hits the back face
+        let face = if det >= EPSILON {
+            Face::Front
+        } else if det <= -EPSILON && !cull_back_faces {
+            Face::Back
+        } else {
+            return None;
+        };
 
         let inv_det = 1.0 / det;
 
@@ -289,7 +953,7 @@ impl Ray {
 
         // Test bounds: u < 0 || u > 1 => outside of triangle
         if !(0.0..=1.0).contains(&u) {
-            return Intersection::new(INFINITY, u, 0.0);
+            return None;
         }
 
         // Prepare to test v parameter
@@ -299,28 +963,223 @@ impl Ray {
         let v = self.direction.dot(v_vec) * inv_det;
         // The intersection lies outside of the triangle
         if v < 0.0 || u + v > 1.0 {
-            return Intersection::new(INFINITY, u, v);
+            return None;
         }
 
         let dist = a_to_c.dot(v_vec) * inv_det;
 
-        if dist > EPSILON {
+        if dist > EPSILON && dist >= self.t_min && dist <= self.t_max {
+            Some((Intersection::new(dist, u, v), face))
+        } else {
+            None
+        }
+    }
+
+    /// Implementation of the [watertight ray/triangle intersection algorithm]
+    /// (https://jcgt.org/published/0002/01/05/) by Woop, Benthin and Wald.
+    /// Returns the same [`Intersection`]s as [`Ray::intersects_triangle`], but never lets
+    /// a ray slip through an edge or vertex shared by two triangles.
+    ///
+    /// The triangle is sheared into the space of the ray, in which the ray starts at the
+    /// origin and points along the z-axis. Edge functions which evaluate to exactly zero
+    /// are recomputed in double precision, so the sign of an edge function is consistent
+    /// for both triangles which share the edge.
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::ray::Ray;
+    /// use bvh::{Point3,Vector3};
+    ///
+    /// // Two triangles which share the edge from `a` to `c`.
+    /// let a = Point3::new(0.0,0.0,0.0);
+    /// let b = Point3::new(1.0,0.0,0.0);
+    /// let c = Point3::new(1.0,1.0,0.0);
+    /// let d = Point3::new(0.0,1.0,0.0);
+    ///
+    /// // A ray which hits the shared edge exactly.
+    /// let ray = Ray::new(Point3::new(0.3,0.3,1.0), Vector3::new(0.0,0.0,-1.0));
+    /// let first = ray.intersects_triangle_watertight(&a, &b, &c);
+    /// let second = ray.intersects_triangle_watertight(&a, &c, &d);
+    ///
+    /// assert!(first.distance == 1.0 || second.distance == 1.0);
+    /// ```
+    ///
+    /// [`Intersection`]: struct.Intersection.html
+    /// [`Ray::intersects_triangle`]: struct.Ray.html#method.intersects_triangle
+    ///
+    pub fn intersects_triangle_watertight(
+        &self,
+        a: &Point3,
+        b: &Point3,
+        c: &Point3,
+    ) -> Intersection {
+        let miss = Intersection::new(f32::INFINITY, 0.0, 0.0);
+
+        // Permute the axes, such that the ray points along the z-axis. Swapping x and y
+        // for a negative direction preserves the winding of the triangle.
+        let abs_direction = self.direction.abs();
+        let kz = if abs_direction.x > abs_direction.y {
+            if abs_direction.x > abs_direction.z {
+                0
+            } else {
+                2
+            }
+        } else if abs_direction.y > abs_direction.z {
+            1
+        } else {
+            2
+        };
+        let mut kx = (kz + 1) % 3;
+        let mut ky = (kx + 1) % 3;
+        if self.direction[kz] < 0.0 {
+            std::mem::swap(&mut kx, &mut ky);
+        }
+
+        // The shear which maps the direction of the ray onto the z-axis.
+        let shear_x = self.direction[kx] / self.direction[kz];
+        let shear_y = self.direction[ky] / self.direction[kz];
+        let shear_z = 1.0 / self.direction[kz];
+
+        let a = *a - self.origin;
+        let b = *b - self.origin;
+        let c = *c - self.origin;
+        let a_x = a[kx] - shear_x * a[kz];
+        let a_y = a[ky] - shear_y * a[kz];
+        let b_x = b[kx] - shear_x * b[kz];
+        let b_y = b[ky] - shear_y * b[kz];
+        let c_x = c[kx] - shear_x * c[kz];
+        let c_y = c[ky] - shear_y * c[kz];
+
+        // The scaled barycentric coordinates of `a`, `b` and `c`.
+        let mut edge_a = c_x * b_y - c_y * b_x;
+        let mut edge_b = a_x * c_y - a_y * c_x;
+        let mut edge_c = b_x * a_y - b_y * a_x;
+
+        // Fall back to double precision if the ray hits an edge exactly.
+        if edge_a == 0.0 || edge_b == 0.0 || edge_c == 0.0 {
+            edge_a = (c_x as f64 * b_y as f64 - c_y as f64 * b_x as f64) as f32;
+            edge_b = (a_x as f64 * c_y as f64 - a_y as f64 * c_x as f64) as f32;
+            edge_c = (b_x as f64 * a_y as f64 - b_y as f64 * a_x as f64) as f32;
+        }
+
+        // Only front faces are hit, which have no negative edge functions.
+        if edge_a < 0.0 || edge_b < 0.0 || edge_c < 0.0 {
+            return miss;
+        }
+
+        let det = edge_a + edge_b + edge_c;
+        if det == 0.0 {
+            return miss;
+        }
+
+        let a_z = shear_z * a[kz];
+        let b_z = shear_z * b[kz];
+        let c_z = shear_z * c[kz];
+        let scaled_dist = edge_a * a_z + edge_b * b_z + edge_c * c_z;
+
+        let inv_det = 1.0 / det;
+        let dist = scaled_dist * inv_det;
+        let u = edge_b * inv_det;
+        let v = edge_c * inv_det;
+
+        if dist > EPSILON && dist >= self.t_min && dist <= self.t_max {
             Intersection::new(dist, u, v)
         } else {
-            Intersection::new(INFINITY, u, v)
+            Intersection::new(f32::INFINITY, u, v)
         }
     }
 }
 
+/// A finite line segment from `start` to `end`. Only intersections between the two
+/// end points are reported, unlike for a [`Ray`].
+///
+/// [`Ray`]: struct.Ray.html
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// The start point of the segment.
+    pub start: Point3,
+
+    /// The end point of the segment.
+    pub end: Point3,
+}
+
+impl Segment {
+    /// Creates a new [`Segment`] from `start` to `end`.
+    ///
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn new(start: Point3, end: Point3) -> Segment {
+        Segment { start, end }
+    }
+
+    /// Returns the length of the [`Segment`].
+    ///
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn length(&self) -> f32 {
+        (self.end - self.start).length()
+    }
+
+    /// Returns the [`Ray`] from `start` towards `end`, whose interval ends at `end`.
+    /// Returns `None` if `start` and `end` are equal, as the direction is undefined.
+    ///
+    /// [`Ray`]: struct.Ray.html
+    ///
+    pub fn to_ray(&self) -> Option<Ray> {
+        Ray::between(self.start, self.end).map(|(ray, _)| ray)
+    }
+
+    /// Tests the intersection of a [`Segment`] with an [`AABB`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::ray::Segment;
+    /// use bvh::Point3;
+    ///
+    /// let aabb = AABB::with_bounds(Point3::new(9.0,-1.0,-1.0), Point3::new(11.0,1.0,1.0));
+    /// let start = Point3::new(0.0,0.0,0.0);
+    ///
+    /// assert!(Segment::new(start, Point3::new(20.0,0.0,0.0)).intersects_aabb(&aabb));
+    /// assert!(!Segment::new(start, Point3::new(5.0,0.0,0.0)).intersects_aabb(&aabb));
+    /// ```
+    ///
+    /// [`AABB`]: struct.AABB.html
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        match self.to_ray() {
+            Some(ray) => ray.intersects_aabb(aabb),
+            None => aabb.contains(&self.start),
+        }
+    }
+}
+
+impl IntersectsAabb for Ray {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        self.intersects_aabb(aabb)
+    }
+}
+
+impl IntersectsAabb for Segment {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        self.intersects_aabb(aabb)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::cmp;
-    use std::f32::INFINITY;
 
-    use crate::aabb::AABB;
-    use crate::ray::Ray;
-    use crate::testbase::{tuple_to_point, tuplevec_small_strategy, TupleVec};
-    use crate::EPSILON;
+    use crate::aabb::{AABB, AABB4, AABB8};
+    use crate::packet::RayPacket4;
+    use crate::ray::{AABB8Kernel, Face, Ray, Segment};
+    use crate::ray64::Ray64;
+    use crate::testbase::{tuple_to_point, tuple_to_vector, tuplevec_small_strategy, TupleVec};
+    use crate::{Point3, Vector3, EPSILON};
+    use glam::{Mat4, Quat};
 
     use proptest::prelude::*;
 
@@ -351,6 +1210,19 @@ mod tests {
             assert!(ray.intersects_aabb(&aabb));
         }
 
+        // Test whether a `Ray` which points at the center of an `AABB` enters it
+        // before it reaches the center. Uses the slab algorithm.
+        #[test]
+        fn test_ray_points_at_aabb_center_slab(data in (tuplevec_small_strategy(),
+                                                        tuplevec_small_strategy(),
+                                                        tuplevec_small_strategy())) {
+            let (ray, aabb) = gen_ray_to_aabb(data);
+            let (entry, exit) = ray.intersection_slab(&aabb).unwrap();
+            let center_distance = (aabb.center() - ray.origin).length();
+            assert!(entry <= center_distance + EPSILON);
+            assert!(exit >= center_distance - EPSILON);
+        }
+
         // Test whether a `Ray` which points at the center of an `AABB` intersects it.
         // Uses the naive algorithm.
         #[test]
@@ -371,6 +1243,16 @@ mod tests {
             assert!(ray.intersects_aabb_branchless(&aabb));
         }
 
+        // Test whether a `Ray` which points at the center of an `AABB` intersects it.
+        // Uses the SIMD algorithm.
+        #[test]
+        fn test_ray_points_at_aabb_center_simd(data in (tuplevec_small_strategy(),
+                                                        tuplevec_small_strategy(),
+                                                        tuplevec_small_strategy())) {
+            let (ray, aabb) = gen_ray_to_aabb(data);
+            assert!(ray.intersects_aabb_simd(&aabb));
+        }
+
         // Test whether a `Ray` which points away from the center of an `AABB`
         // does not intersect it, unless its origin is inside the `AABB`.
         // Uses the optimized algorithm.
@@ -415,6 +1297,165 @@ mod tests {
             assert!(!ray.intersects_aabb_branchless(&aabb) || aabb.contains(&ray.origin));
         }
 
+        // Test whether a `Ray` which points away from the center of an `AABB`
+        // does not intersect it, unless its origin is inside the `AABB`.
+        // Uses the SIMD algorithm.
+        #[test]
+        fn test_ray_points_from_aabb_center_simd(data in (tuplevec_small_strategy(),
+                                                          tuplevec_small_strategy(),
+                                                          tuplevec_small_strategy())) {
+            let (mut ray, aabb) = gen_ray_to_aabb(data);
+            // Invert the ray direction
+            ray.direction = -ray.direction;
+            ray.inv_direction = -ray.inv_direction;
+            assert!(!ray.intersects_aabb_simd(&aabb) || aabb.contains(&ray.origin));
+        }
+
+        // Test whether a `Ray` which points at the center of an `AABB` does not
+        // intersect it, if its interval ends before the `AABB` is entered.
+        // Uses all algorithms.
+        #[test]
+        fn test_ray_interval_ends_before_aabb(data in (tuplevec_small_strategy(),
+                                                       tuplevec_small_strategy(),
+                                                       tuplevec_small_strategy())) {
+            let (mut ray, aabb) = gen_ray_to_aabb(data);
+            let (entry, _) = ray.intersection_slab(&aabb).unwrap();
+            prop_assume!(entry > 1.0);
+
+            ray.t_max = entry / 2.0;
+            assert!(!ray.intersects_aabb(&aabb));
+            assert!(!ray.intersects_aabb_naive(&aabb));
+            assert!(!ray.intersects_aabb_branchless(&aabb));
+            assert!(!ray.intersects_aabb_simd(&aabb));
+            assert!(ray.intersection_slab(&aabb).is_none());
+        }
+
+        // Test whether a `Segment` intersects an `AABB` exactly if its end points lie on
+        // both sides of the point at which the corresponding `Ray` enters the `AABB`.
+        #[test]
+        fn test_segment_ends_at_aabb_entry(data in (tuplevec_small_strategy(),
+                                                    tuplevec_small_strategy(),
+                                                    tuplevec_small_strategy()),
+                                           fraction in 0.0..2.0f32) {
+            let (ray, aabb) = gen_ray_to_aabb(data);
+            let (entry, _) = ray.intersection_slab(&aabb).unwrap();
+            prop_assume!(entry > 1.0);
+            prop_assume!((fraction - 1.0).abs() > 0.01);
+
+            let segment = Segment::new(ray.origin, ray.origin + ray.direction * entry * fraction);
+            assert_eq!(segment.intersects_aabb(&aabb), fraction > 1.0);
+        }
+
+        // Test whether a `Ray` which runs along a face of an `AABB` intersects it, although
+        // its origin lies in the plane of the face and `0.0 * inf` yields NaN.
+        // Uses all algorithms.
+        #[test]
+        fn test_ray_along_aabb_face(data in (tuplevec_small_strategy(),
+                                             tuplevec_small_strategy(),
+                                             tuplevec_small_strategy()),
+                                    face_axis in 0..3usize,
+                                    axis_offset in 1..3usize,
+                                    max_face: bool,
+                                    negative_zero: bool) {
+            let (_, aabb) = gen_ray_to_aabb(data);
+            let axis = (face_axis + axis_offset) % 3;
+            prop_assume!(aabb.size()[axis] > 0.0);
+
+            // Start outside of the `AABB` in the plane of the face, and run along `axis`.
+            let zero = if negative_zero { -0.0 } else { 0.0 };
+            let mut direction = Vector3::new(zero, zero, zero);
+            direction[axis] = 1.0;
+            let mut origin = aabb.center();
+            origin[face_axis] = if max_face { aabb.max[face_axis] } else { aabb.min[face_axis] };
+            origin[axis] = aabb.min[axis] - aabb.size()[axis] - 1.0;
+            let ray = Ray::new(origin, direction);
+
+            assert!(ray.intersects_aabb(&aabb));
+            assert!(ray.intersects_aabb_naive(&aabb));
+            assert!(ray.intersects_aabb_branchless(&aabb));
+            assert!(ray.intersects_aabb_simd(&aabb));
+            assert!(ray.intersection_slab(&aabb).is_some());
+        }
+
+        // Test whether a `Ray` which points at a zero-thickness `AABB` intersects it,
+        // regardless of the sign of zero direction components. Uses all algorithms.
+        #[test]
+        fn test_ray_hits_flat_aabb(data in (tuplevec_small_strategy(),
+                                            tuplevec_small_strategy(),
+                                            tuplevec_small_strategy()),
+                                   axis in 0..3usize,
+                                   negative_zero: bool) {
+            let (_, aabb) = gen_ray_to_aabb(data);
+
+            // Flatten the `AABB` along `axis`.
+            let mut aabb = aabb;
+            aabb.max[axis] = aabb.min[axis];
+
+            // Shoot a ray along `axis` from outside through the center of the `AABB`.
+            let zero = if negative_zero { -0.0 } else { 0.0 };
+            let mut direction = Vector3::new(zero, zero, zero);
+            direction[axis] = 1.0;
+            let distance = aabb.center().abs().max_element() + 1.0;
+            let origin = aabb.center() - direction * distance;
+            let ray = Ray::new(origin, direction);
+
+            assert!(ray.intersects_aabb(&aabb));
+            assert!(ray.intersects_aabb_naive(&aabb));
+            assert!(ray.intersects_aabb_branchless(&aabb));
+            assert!(ray.intersects_aabb_simd(&aabb));
+            assert!(ray.intersection_slab(&aabb).is_some());
+        }
+
+        // Test whether the four lanes of `intersects_aabb4` agree with `intersects_aabb`
+        // and `intersection_slab`.
+        #[test]
+        fn test_intersects_aabb4_agrees(data in (tuplevec_small_strategy(),
+                                                 tuplevec_small_strategy(),
+                                                 tuplevec_small_strategy()),
+                                        corners in prop::collection::vec(
+                                            (tuplevec_small_strategy(), tuplevec_small_strategy()),
+                                            4)) {
+            let (ray, _) = gen_ray_to_aabb(data);
+            let mut aabbs = [AABB::empty(); 4];
+            for (aabb, (a, b)) in aabbs.iter_mut().zip(corners.iter()) {
+                *aabb = AABB::empty().grow(&tuple_to_point(a)).grow(&tuple_to_point(b));
+            }
+
+            let (mask, entry) = ray.intersects_aabb4(&AABB4::from(aabbs));
+            for (slot, aabb) in aabbs.iter().enumerate() {
+                assert_eq!(mask & (1 << slot) != 0, ray.intersects_aabb(aabb));
+                if let Some((slab_entry, _)) = ray.intersection_slab(aabb) {
+                    assert_eq!(entry[slot], slab_entry);
+                }
+            }
+        }
+
+        // Test whether the eight lanes of `intersects_aabb8`, with and without intrinsics,
+        // agree with `intersects_aabb` and `intersection_slab`.
+        #[test]
+        fn test_intersects_aabb8_agrees(data in (tuplevec_small_strategy(),
+                                                 tuplevec_small_strategy(),
+                                                 tuplevec_small_strategy()),
+                                        corners in prop::collection::vec(
+                                            (tuplevec_small_strategy(), tuplevec_small_strategy()),
+                                            8)) {
+            let (ray, _) = gen_ray_to_aabb(data);
+            let mut aabbs = [AABB::empty(); 8];
+            for (aabb, (a, b)) in aabbs.iter_mut().zip(corners.iter()) {
+                *aabb = AABB::empty().grow(&tuple_to_point(a)).grow(&tuple_to_point(b));
+            }
+
+            let aabb8 = AABB8::from(aabbs);
+            let (mask, entry) = ray.intersects_aabb8(&aabb8);
+            assert_eq!((mask, entry), ray.intersects_aabb8_with(&aabb8, AABB8Kernel::Scalar));
+            for (slot, aabb) in aabbs.iter().enumerate() {
+                assert_eq!(mask & (1 << slot) != 0, ray.intersects_aabb(aabb));
+                if let Some((slab_entry, _)) = ray.intersection_slab(aabb) {
+                    assert_eq!(entry[slot], slab_entry);
+                }
+            }
+        }
+
         // Test whether a `Ray` which points at the center of a triangle
         // intersects it, unless it sees the back face, which is culled.
         #[test]
@@ -451,12 +1492,12 @@ mod tests {
             // Either the intersection is in the back side (including the triangle-plane)
             if on_back_side {
                 // Intersection must be INFINITY, u and v are undefined
-                assert!(intersects.distance == INFINITY);
+                assert!(intersects.distance == f32::INFINITY);
             } else {
                 // Or it is on the front side
                 // Either the intersection is inside the triangle, which it should be
                 // for all u, v such that u+v <= 1.0
-                let intersection_inside = (0.0..=1.0).contains(&uv_sum) && intersects.distance < INFINITY;
+                let intersection_inside = (0.0..=1.0).contains(&uv_sum) && intersects.distance < f32::INFINITY;
 
                 // Or the input data was close to the border
                 let close_to_border =
@@ -475,6 +1516,230 @@ mod tests {
                 assert!(intersection_inside || close_to_border);
             }
         }
+
+        // Test whether the points along a transformed `Ray` are the transformed points
+        // along the original `Ray`, at the scaled distances.
+        #[test]
+        fn test_ray_transformed(origin in tuplevec_small_strategy(),
+                                direction in tuplevec_small_strategy(),
+                                translation in tuplevec_small_strategy(),
+                                axis in tuplevec_small_strategy(),
+                                angle in -10.0..10.0f32,
+                                scale in 0.1..10.0f32,
+                                t in 0.0..1000.0f32) {
+            let direction = tuple_to_vector(&direction);
+            let axis = tuple_to_vector(&axis);
+            prop_assume!(direction.length() > 0.1 && axis.length() > 0.1);
+
+            let ray = Ray::with_interval(tuple_to_point(&origin), direction, 0.0, t);
+            let transform = Mat4::from_scale_rotation_translation(
+                Vector3::new(scale, scale, scale),
+                Quat::from_axis_angle(axis.normalize(), angle),
+                tuple_to_vector(&translation),
+            );
+            let transformed = ray.transformed(&transform);
+
+            let expected = transform.transform_point3(ray.origin + ray.direction * t);
+            let point = transformed.origin + transformed.direction * transformed.t_max;
+            let tolerance = 0.001 * expected.abs().max_element().max(1.0);
+            assert!((point - expected).abs().max_element() <= tolerance);
+            assert!((transformed.t_max - t * scale).abs() <= 0.001 * t.max(1.0) * scale);
+        }
+
+        // Test whether a `Ray` which points at the center of a sphere hits its surface at
+        // the expected distance, from outside and from inside of the sphere.
+        #[test]
+        fn test_ray_hits_sphere(center in tuplevec_small_strategy(),
+                                origin in tuplevec_small_strategy(),
+                                scale in 0.01..2.0f32) {
+            let center = tuple_to_point(&center);
+            let origin = tuple_to_point(&origin);
+            let center_distance = (center - origin).length();
+            prop_assume!(center_distance > 0.1);
+            prop_assume!((scale - 1.0).abs() > 0.01);
+
+            // Scale the radius with the distance, so that the sphere is not too small to hit.
+            let radius = center_distance * scale;
+
+            let ray = Ray::new(origin, center - origin);
+            let distance = ray.intersects_sphere(&center, radius).unwrap();
+            let expected = if center_distance > radius {
+                center_distance - radius
+            } else {
+                center_distance + radius
+            };
+            assert!((distance - expected).abs() <= 0.001 * center_distance.max(radius));
+
+            let away = Ray::new(origin, origin - center);
+            assert_eq!(away.intersects_sphere(&center, radius).is_some(), center_distance <= radius);
+        }
+
+        // Test whether a `Ray` hits the same point of a triangle from either side if back
+        // faces are not culled, and whether it reports the face which it hits.
+        #[test]
+        fn test_ray_hits_triangle_face(a in tuplevec_small_strategy(),
+                                       b in tuplevec_small_strategy(),
+                                       c in tuplevec_small_strategy(),
+                                       origin in tuplevec_small_strategy(),
+                                       u in 0.05..0.45f32,
+                                       v in 0.05..0.45f32) {
+            let triangle = (tuple_to_point(&a), tuple_to_point(&b), tuple_to_point(&c));
+            let normal = (triangle.1 - triangle.0).cross(triangle.2 - triangle.0);
+            let point_on_triangle = triangle.0 + u * (triangle.1 - triangle.0) + v * (triangle.2 - triangle.0);
+            let origin = tuple_to_point(&origin);
+            let ray = Ray::new(origin, point_on_triangle - origin);
+            let reversed = (triangle.0, triangle.2, triangle.1);
+
+            // Rays at grazing angles are too sensitive to the order of the vertices.
+            let unit_normal = (normal / normal.abs().max_element()).normalize();
+            prop_assume!(unit_normal.dot(ray.direction).abs() > 0.1);
+
+            let front = ray.intersects_triangle_face(&triangle.0, &triangle.1, &triangle.2, false);
+            let back = ray.intersects_triangle_face(&reversed.0, &reversed.1, &reversed.2, false);
+            prop_assume!(front.is_some() && back.is_some());
+            let (front, front_face) = front.unwrap();
+            let (back, back_face) = back.unwrap();
+
+            // Reversing the winding swaps the faces, but not the point which is hit.
+            assert_ne!(front_face, back_face);
+            assert_eq!(front_face == Face::Front, normal.dot(ray.direction) < 0.0);
+            assert!((front.distance - back.distance).abs() < 0.001 * front.distance.max(1.0));
+            assert!((front.u - back.v).abs() < 0.001 && (front.v - back.u).abs() < 0.001);
+
+            // Culling only rejects back faces.
+            let culled = ray.intersects_triangle_face(&triangle.0, &triangle.1, &triangle.2, true);
+            assert_eq!(culled.is_some(), front_face == Face::Front);
+        }
+
+        // Test whether the watertight algorithm agrees with the Möller-Trumbore algorithm
+        // for rays which do not hit the triangle close to its border.
+        #[test]
+        fn test_ray_hits_triangle_watertight(a in tuplevec_small_strategy(),
+                                             b in tuplevec_small_strategy(),
+                                             c in tuplevec_small_strategy(),
+                                             origin in tuplevec_small_strategy(),
+                                             u in 0.05..0.45f32,
+                                             v in 0.05..0.45f32) {
+            let triangle = (tuple_to_point(&a), tuple_to_point(&b), tuple_to_point(&c));
+            let u_vec = triangle.1 - triangle.0;
+            let v_vec = triangle.2 - triangle.0;
+
+            let point_on_triangle = triangle.0 + u * u_vec + v * v_vec;
+            let origin = tuple_to_point(&origin);
+            let ray = Ray::new(origin, point_on_triangle - origin);
+
+            let expected = ray.intersects_triangle(&triangle.0, &triangle.1, &triangle.2);
+            let intersection =
+                ray.intersects_triangle_watertight(&triangle.0, &triangle.1, &triangle.2);
+            prop_assume!(expected.distance.is_finite() && expected.distance > 0.1);
+
+            let tolerance = 0.01 * expected.distance.max(1.0);
+            assert!((intersection.distance - expected.distance).abs() < tolerance);
+            assert!((intersection.u - u).abs() < 0.01);
+            assert!((intersection.v - v).abs() < 0.01);
+        }
+
+        // Test whether a `Ray` which points at the shared edge of two triangles hits at
+        // least one of them. Uses the watertight algorithm.
+        #[test]
+        fn test_ray_hits_shared_edge_watertight(a in tuplevec_small_strategy(),
+                                                b in tuplevec_small_strategy(),
+                                                c in tuplevec_small_strategy(),
+                                                d in tuplevec_small_strategy(),
+                                                origin in tuplevec_small_strategy(),
+                                                t in 0.01..0.99f32) {
+            let (a, mut b, c, mut d) = (tuple_to_point(&a), tuple_to_point(&b), tuple_to_point(&c), tuple_to_point(&d));
+            let origin = tuple_to_point(&origin);
+
+            // Both triangles must face the origin. Swapping `b` and `d` flips both of them.
+            if (b - a).cross(c - a).dot(origin - a) < 0.0 {
+                std::mem::swap(&mut b, &mut d);
+            }
+            prop_assume!((b - a).cross(c - a).dot(origin - a) > EPSILON);
+            prop_assume!((c - a).cross(d - a).dot(origin - a) > EPSILON);
+
+            let point_on_edge = a.lerp(c, t);
+            prop_assume!((point_on_edge - origin).length() > 0.1);
+            let ray = Ray::new(origin, point_on_edge - origin);
+            let first = ray.intersects_triangle_watertight(&a, &b, &c);
+            let second = ray.intersects_triangle_watertight(&a, &c, &d);
+            assert!(first.distance.is_finite() || second.distance.is_finite());
+        }
+    }
+
+    #[test]
+    /// Tests whether all `AABB` tests agree on the boundaries of the slab test: zero-thickness
+    /// `AABB`s, `AABB`s entered exactly at `t_max` and rays in a bounding plane are hit,
+    /// while `AABB`s exited exactly at `t_min` are missed.
+    fn test_intersects_aabb_boundaries() {
+        let aabb = |min_x: f32, max_x: f32| {
+            AABB::with_bounds(Point3::new(min_x, -1.0, -1.0), Point3::new(max_x, 1.0, 1.0))
+        };
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let cases = [
+            // A zero-thickness `AABB`.
+            (Ray::new(origin, x), aabb(2.0, 2.0), true),
+            // Entered exactly at `t_max`.
+            (
+                Ray::with_interval(origin, x, 0.0, 2.0),
+                aabb(2.0, 3.0),
+                true,
+            ),
+            // Exited exactly at `t_min`.
+            (
+                Ray::with_interval(origin, x, 2.0, f32::INFINITY),
+                aabb(1.0, 2.0),
+                false,
+            ),
+            // Exited exactly at the origin.
+            (Ray::new(origin, x), aabb(-1.0, 0.0), false),
+            // Lying exactly in the bounding plane `y = 1`.
+            (
+                Ray::new(Point3::new(0.0, 1.0, 0.0), x),
+                aabb(2.0, 3.0),
+                true,
+            ),
+        ];
+
+        type Kernel = fn(&Ray, &AABB) -> bool;
+        let kernels: [(&str, Kernel); 10] = [
+            ("intersects_aabb", |ray, aabb| ray.intersects_aabb(aabb)),
+            ("intersection_slab", |ray, aabb| {
+                ray.intersection_slab(aabb).is_some()
+            }),
+            ("naive", |ray, aabb| ray.intersects_aabb_naive(aabb)),
+            ("branchless", |ray, aabb| {
+                ray.intersects_aabb_branchless(aabb)
+            }),
+            ("simd", |ray, aabb| ray.intersects_aabb_simd(aabb)),
+            ("aabb4", |ray, aabb| {
+                let aabbs = AABB4::from([*aabb, AABB::empty(), AABB::empty(), AABB::empty()]);
+                ray.intersects_aabb4(&aabbs).0 == 1
+            }),
+            ("aabb8", |ray, aabb| {
+                let mut aabbs = AABB8::empty();
+                aabbs.set(0, aabb);
+                ray.intersects_aabb8(&aabbs).0 == 1
+            }),
+            ("aabb8 scalar", |ray, aabb| {
+                let mut aabbs = AABB8::empty();
+                aabbs.set(0, aabb);
+                ray.intersects_aabb8_with(&aabbs, AABB8Kernel::Scalar).0 == 1
+            }),
+            ("packet", |ray, aabb| {
+                RayPacket4::new([ray, ray, ray, ray]).intersects_aabb(aabb) == 0b1111
+            }),
+            ("ray64", |ray, aabb| {
+                Ray64::from(ray).intersection_slab(aabb).is_some()
+            }),
+        ];
+
+        for (case, (ray, aabb, expected)) in cases.iter().enumerate() {
+            for (name, kernel) in kernels.iter() {
+                assert_eq!(kernel(ray, aabb), *expected, "{} in case {}", name, case);
+            }
+        }
     }
 }
 
@@ -544,4 +1809,19 @@ mod bench {
             }
         });
     }
+
+    /// Benchmark for the SIMD intersection algorithm.
+    #[bench]
+    fn bench_intersects_aabb_simd(b: &mut ::test::Bencher) {
+        let seed = [0; 32];
+        let mut rng = StdRng::from_seed(seed);
+
+        b.iter(|| {
+            let one_thousand = ::test::black_box(1000);
+            for _ in 0..one_thousand {
+                let (ray, aabb) = gen_random_ray_aabb(&mut rng);
+                ray.intersects_aabb_simd(&aabb);
+            }
+        });
+    }
 }
@@ -0,0 +1,295 @@
+//! This module defines a double precision variant of the [`Ray`] and its intersection
+//! algorithms, for scenes which are too large for the precision of `f32`.
+//!
+//! The [`AABB`]s of a [`BVH`] stay in single precision. They are widened exactly to `f64`
+//! for the slab test, so a [`Ray64`] can traverse any [`BVH`] through [`BVH::query`].
+//! Shapes with double precision coordinates are bounded by an [`AABB64`].
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`AABB64`]: ../aabb64/struct.AABB64.html
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`BVH::query`]: ../bvh/struct.BVH.html#method.query
+//! [`Ray`]: ../ray/struct.Ray.html
+//! [`Ray64`]: struct.Ray64.html
+//!
+
+use crate::aabb::{IntersectsAabb, AABB};
+use crate::aabb64::AABB64;
+use crate::ray::Ray;
+use crate::EPSILON;
+use glam::DVec3;
+
+/// Double precision point math type. Type alias for [`glam::DVec3`].
+pub type DPoint3 = DVec3;
+
+/// Double precision vector math type. Type alias for [`glam::DVec3`].
+pub type DVector3 = DVec3;
+
+/// A double precision [`Ray`].
+///
+/// [`Ray`]: ../ray/struct.Ray.html
+///
+#[derive(Debug)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ray64 {
+    /// The ray origin.
+    pub origin: DPoint3,
+
+    /// The ray direction.
+    pub direction: DVector3,
+
+    /// Inverse (1/x) ray direction. Cached for use in [`AABB`] intersections.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    inv_direction: DVector3,
+
+    /// The start of the parametric interval along the ray in which intersections are
+    /// reported. Defaults to `0.0`.
+    pub t_min: f64,
+
+    /// The end of the parametric interval along the ray in which intersections are
+    /// reported. Defaults to positive infinity.
+    pub t_max: f64,
+}
+
+/// A double precision [`Intersection`], which is returned by
+/// [`Ray64::intersects_triangle`].
+///
+/// [`Intersection`]: ../ray/struct.Intersection.html
+/// [`Ray64::intersects_triangle`]: struct.Ray64.html#method.intersects_triangle
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Intersection64 {
+    /// Distance from the ray origin to the intersection point.
+    pub distance: f64,
+
+    /// U coordinate of the intersection.
+    pub u: f64,
+
+    /// V coordinate of the intersection.
+    pub v: f64,
+}
+
+impl Ray64 {
+    /// Creates a new [`Ray64`] from an `origin` and a `direction`.
+    /// `direction` will be normalized.
+    ///
+    /// [`Ray64`]: struct.Ray64.html
+    ///
+    pub fn new(origin: DPoint3, direction: DVector3) -> Ray64 {
+        let direction = direction.normalize();
+        Ray64 {
+            origin,
+            direction,
+            inv_direction: DVector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z),
+            t_min: 0.0,
+            t_max: f64::INFINITY,
+        }
+    }
+
+    /// Creates a new [`Ray64`] from an `origin` and a `direction`, which only reports
+    /// intersections at distances between `t_min` and `t_max` from the `origin`.
+    /// `direction` will be normalized.
+    ///
+    /// [`Ray64`]: struct.Ray64.html
+    ///
+    pub fn with_interval(origin: DPoint3, direction: DVector3, t_min: f64, t_max: f64) -> Ray64 {
+        Ray64 {
+            t_min,
+            t_max,
+            ..Ray64::new(origin, direction)
+        }
+    }
+
+    /// Computes the distances at which the [`Ray64`] enters and exits the [`AABB`] using
+    /// the slab method, like [`Ray::intersection_slab`]. Returns `None` if the [`Ray64`]
+    /// misses the [`AABB`] or if the [`AABB`] lies outside of the interval of the [`Ray64`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bvh::aabb::AABB;
+    /// use bvh::ray64::{DPoint3, DVector3, Ray64};
+    /// use bvh::Point3;
+    ///
+    /// // Far away from the origin, `f32` cannot represent the origin of this ray exactly.
+    /// let ray = Ray64::new(DPoint3::new(1.0e9 + 0.25, 0.0, 0.0), DVector3::new(1.0, 0.0, 0.0));
+    /// let aabb = AABB::with_bounds(Point3::new(1.0e9, -1.0, -1.0), Point3::new(1.0e9 + 64.0, 1.0, 1.0));
+    ///
+    /// assert_eq!(ray.intersection_slab(&aabb), Some((-0.25, 63.75)));
+    /// ```
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray::intersection_slab`]: ../ray/struct.Ray.html#method.intersection_slab
+    /// [`Ray64`]: struct.Ray64.html
+    ///
+    pub fn intersection_slab(&self, aabb: &AABB) -> Option<(f64, f64)> {
+        self.intersection_slab64(&AABB64::from(*aabb))
+    }
+
+    /// Computes the distances at which the [`Ray64`] enters and exits the [`AABB64`], like
+    /// [`Ray64::intersection_slab`].
+    ///
+    /// [`AABB64`]: ../aabb64/struct.AABB64.html
+    /// [`Ray64`]: struct.Ray64.html
+    /// [`Ray64::intersection_slab`]: struct.Ray64.html#method.intersection_slab
+    ///
+    pub fn intersection_slab64(&self, aabb: &AABB64) -> Option<(f64, f64)> {
+        let min = aabb.min;
+        let max = aabb.max;
+        let mut ray_min = f64::NEG_INFINITY;
+        let mut ray_max = f64::INFINITY;
+
+        // See `Ray::intersects_aabb_branchless` for the order of `min` and `max`,
+        // which ignores the NaNs of rays lying exactly in a bounding plane.
+        for axis in 0..3 {
+            let t1 = (min[axis] - self.origin[axis]) * self.inv_direction[axis];
+            let t2 = (max[axis] - self.origin[axis]) * self.inv_direction[axis];
+            ray_min = t1.max(ray_min).min(t2.max(ray_min));
+            ray_max = t1.min(ray_max).max(t2.min(ray_max));
+        }
+
+        if ray_min <= ray_max && ray_min <= self.t_max && ray_max > self.t_min {
+            Some((ray_min, ray_max))
+        } else {
+            None
+        }
+    }
+
+    /// Tests the intersection of a [`Ray64`] with an [`AABB`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray64`]: struct.Ray64.html
+    ///
+    pub fn intersects_aabb(&self, aabb: &AABB) -> bool {
+        self.intersection_slab(aabb).is_some()
+    }
+
+    /// Tests the intersection of a [`Ray64`] with an [`AABB64`].
+    ///
+    /// [`AABB64`]: ../aabb64/struct.AABB64.html
+    /// [`Ray64`]: struct.Ray64.html
+    ///
+    pub fn intersects_aabb64(&self, aabb: &AABB64) -> bool {
+        self.intersection_slab64(aabb).is_some()
+    }
+
+    /// Double precision variant of the Möller-Trumbore algorithm in
+    /// [`Ray::intersects_triangle`]. Back faces are culled in the same way.
+    /// The distance is set to +INFINITY if the ray does not intersect the triangle, hits
+    /// it from behind, or hits it outside of the interval of the ray.
+    ///
+    /// [`Ray::intersects_triangle`]: ../ray/struct.Ray.html#method.intersects_triangle
+    ///
+    pub fn intersects_triangle(&self, a: &DPoint3, b: &DPoint3, c: &DPoint3) -> Intersection64 {
+        let miss = Intersection64 {
+            distance: f64::INFINITY,
+            u: 0.0,
+            v: 0.0,
+        };
+
+        let a_to_b = *b - *a;
+        let a_to_c = *c - *a;
+        let u_vec = self.direction.cross(a_to_c);
+
+        // Only front faces have a positive determinant.
+        let det = a_to_b.dot(u_vec);
+        if det < EPSILON as f64 {
+            return miss;
+        }
+
+        let inv_det = 1.0 / det;
+        let a_to_origin = self.origin - *a;
+        let u = a_to_origin.dot(u_vec) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return miss;
+        }
+
+        let v_vec = a_to_origin.cross(a_to_b);
+        let v = self.direction.dot(v_vec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return miss;
+        }
+
+        let distance = a_to_c.dot(v_vec) * inv_det;
+        if distance > EPSILON as f64 && distance >= self.t_min && distance <= self.t_max {
+            Intersection64 { distance, u, v }
+        } else {
+            miss
+        }
+    }
+}
+
+impl From<&Ray> for Ray64 {
+    fn from(ray: &Ray) -> Ray64 {
+        Ray64::with_interval(
+            ray.origin.as_dvec3(),
+            ray.direction.as_dvec3(),
+            ray.t_min as f64,
+            ray.t_max as f64,
+        )
+    }
+}
+
+impl IntersectsAabb for Ray64 {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        self.intersects_aabb(aabb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bvh::BVH;
+    use crate::ray64::{DPoint3, DVector3, Ray64};
+    use crate::testbase::{create_n_cubes, create_ray, default_bounds};
+
+    #[test]
+    /// Tests whether a `Ray64` finds the same shapes and intersections as the `Ray`
+    /// it was created from.
+    fn test_ray64_agrees_with_ray() {
+        let bounds = default_bounds();
+        let mut triangles = create_n_cubes(100, &bounds);
+        let bvh = BVH::build(&mut triangles);
+
+        let mut seed = 0;
+        for _ in 0..1000 {
+            let ray = create_ray(&mut seed, &bounds);
+            let ray64 = Ray64::from(&ray);
+
+            let expected = bvh.query(&ray, &triangles);
+            let found = bvh.query(&ray64, &triangles);
+            assert_eq!(found.len(), expected.len());
+
+            for triangle in &triangles {
+                let intersection = ray.intersects_triangle(&triangle.a, &triangle.b, &triangle.c);
+                let intersection64 = ray64.intersects_triangle(
+                    &triangle.a.as_dvec3(),
+                    &triangle.b.as_dvec3(),
+                    &triangle.c.as_dvec3(),
+                );
+                if intersection.distance.is_finite() && intersection64.distance.is_finite() {
+                    let difference = intersection.distance as f64 - intersection64.distance;
+                    assert!(difference.abs() < 0.001 * intersection64.distance.max(1.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    /// Tests whether a `Ray64` hits a triangle far away from the origin at the exact
+    /// distance, which `f32` cannot represent.
+    fn test_ray64_precision_far_from_origin() {
+        let offset = DVector3::new(1.0e7, -3.0e7, 2.0e7);
+        let a = offset + DVector3::new(0.0, 0.0, 0.0);
+        let b = offset + DVector3::new(1.0, 0.0, 0.0);
+        let c = offset + DVector3::new(0.0, 1.0, 0.0);
+        let origin: DPoint3 = offset + DVector3::new(0.2, 0.3, 0.0125);
+        let ray = Ray64::new(origin, DVector3::new(0.0, 0.0, -1.0));
+
+        let intersection = ray.intersects_triangle(&a, &b, &c);
+        assert!((intersection.distance - 0.0125).abs() < 1.0e-6);
+        assert!((intersection.u - 0.2).abs() < 1.0e-3);
+        assert!((intersection.v - 0.3).abs() < 1.0e-3);
+    }
+}
@@ -0,0 +1,1696 @@
+//! This module defines ready-made shapes which can be put into a [`BVH`] and intersected
+//! by [`Ray`]s.
+//!
+//! [`BVH`]: ../bvh/struct.BVH.html
+//! [`Ray`]: ../ray/struct.Ray.html
+//!
+
+use crate::aabb::{Bounded, IntersectsAabb, AABB};
+use crate::bounding_hierarchy::BHShape;
+use crate::bvh::{PointDistance, RefinableShape, BVH};
+use crate::ray::{self, Intersectable, Intersection, Ray};
+use crate::{Point3, Vector3, EPSILON};
+use glam::Mat4;
+
+/// A triangle given by its three vertices. Like [`Ray::intersects_triangle`], only its
+/// front face is hit, from which the vertices appear in counter-clockwise order.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::ray::Ray;
+/// use bvh::shapes::Triangle;
+/// use bvh::{Point3,Vector3};
+///
+/// let mut triangles: Vec<Triangle> = (0..10)
+///     .map(|i| {
+///         let x = i as f32;
+///         Triangle::new(
+///             Point3::new(x, 0.0, 0.0),
+///             Point3::new(x + 1.0, 0.0, 0.0),
+///             Point3::new(x, 1.0, 0.0),
+///         )
+///     })
+///     .collect();
+/// let bvh = BVH::build(&mut triangles);
+///
+/// let ray = Ray::new(Point3::new(3.25, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+/// let (triangle, intersection) = bvh.nearest_hit(&ray, &triangles).unwrap();
+/// assert_eq!(triangle.a, Point3::new(3.0, 0.0, 0.0));
+/// assert_eq!((intersection.distance, intersection.u, intersection.v), (1.0, 0.25, 0.25));
+/// ```
+///
+/// [`Ray::intersects_triangle`]: ../ray/struct.Ray.html#method.intersects_triangle
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Triangle {
+    /// The first vertex.
+    pub a: Point3,
+
+    /// The second vertex.
+    pub b: Point3,
+
+    /// The third vertex.
+    pub c: Point3,
+
+    /// The index of the node of this triangle in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl Triangle {
+    /// Creates a new [`Triangle`] from its vertices.
+    ///
+    /// [`Triangle`]: struct.Triangle.html
+    ///
+    pub fn new(a: Point3, b: Point3, c: Point3) -> Triangle {
+        Triangle {
+            a,
+            b,
+            c,
+            node_index: 0,
+        }
+    }
+
+    /// Returns the distance at which `ray` hits the front face of this [`Triangle`] and the
+    /// barycentric coordinates `u` and `v` of the hit, such that the point of the hit is
+    /// `a + u * (b - a) + v * (c - a)`, or `None` if the ray misses it. The test is
+    /// watertight, see [`Ray::intersects_triangle_watertight`].
+    ///
+    /// [`Ray::intersects_triangle_watertight`]: ../ray/struct.Ray.html#method.intersects_triangle_watertight
+    /// [`Triangle`]: struct.Triangle.html
+    ///
+    pub fn intersect_ray(&self, ray: &Ray) -> Option<(f32, f32, f32)> {
+        self.intersect(ray)
+            .map(|intersection| (intersection.distance, intersection.u, intersection.v))
+    }
+}
+
+impl Bounded for Triangle {
+    fn aabb(&self) -> AABB {
+        AABB::empty().grow(&self.a).grow(&self.b).grow(&self.c)
+    }
+}
+
+impl BHShape for Triangle {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl Intersectable for Triangle {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let intersection = ray.intersects_triangle_watertight(&self.a, &self.b, &self.c);
+        if intersection.distance < f32::INFINITY {
+            Some(intersection)
+        } else {
+            None
+        }
+    }
+}
+
+/// A sphere given by its center and radius.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::ray::Ray;
+/// use bvh::shapes::Sphere;
+/// use bvh::{Point3,Vector3};
+///
+/// let mut spheres: Vec<Sphere> = (0..10)
+///     .map(|i| Sphere::new(Point3::new(i as f32 * 3.0, 0.0, 0.0), 1.0))
+///     .collect();
+/// let bvh = BVH::build(&mut spheres);
+///
+/// let ray = Ray::new(Point3::new(6.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0));
+/// let (sphere, intersection) = bvh.nearest_hit(&ray, &spheres).unwrap();
+/// assert_eq!(sphere.center, Point3::new(6.0, 0.0, 0.0));
+/// assert_eq!(intersection.distance, 9.0);
+/// ```
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sphere {
+    /// The center of the sphere.
+    pub center: Point3,
+
+    /// The radius of the sphere.
+    pub radius: f32,
+
+    /// The index of the node of this sphere in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl Sphere {
+    /// Creates a new [`Sphere`] from its center and radius.
+    ///
+    /// [`Sphere`]: struct.Sphere.html
+    ///
+    pub fn new(center: Point3, radius: f32) -> Sphere {
+        Sphere {
+            center,
+            radius,
+            node_index: 0,
+        }
+    }
+}
+
+impl Bounded for Sphere {
+    fn aabb(&self) -> AABB {
+        let half_size = Vector3::splat(self.radius);
+        AABB::with_bounds(self.center - half_size, self.center + half_size)
+    }
+}
+
+impl BHShape for Sphere {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl Intersectable for Sphere {
+    /// Returns the [`Intersection`] of `ray` with the surface of this [`Sphere`], see
+    /// [`Ray::intersects_sphere`]. `u` and `v` are the longitude around the y-axis and the
+    /// latitude of the hit, both scaled to `[0, 1]`.
+    ///
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    /// [`Ray::intersects_sphere`]: ../ray/struct.Ray.html#method.intersects_sphere
+    /// [`Sphere`]: struct.Sphere.html
+    ///
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let distance = ray.intersects_sphere(&self.center, self.radius)?;
+        let normal = (ray.origin + ray.direction * distance - self.center) / self.radius;
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * std::f32::consts::PI);
+        let v = 0.5 + normal.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI;
+        Some(Intersection::new(distance, u, v))
+    }
+}
+
+/// A point, e.g. of a point cloud. Its [`AABB`] is padded by `epsilon`, as the [`AABB`] of
+/// a single point has no surface area, which the surface area heuristic of [`BVH::build`]
+/// cannot work with.
+///
+/// [`BVH::build`] splits a set of coincident points in half, so even many copies of the
+/// same point give a balanced [`BVH`] instead of a deep one.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::shapes::Point;
+/// use bvh::Point3;
+///
+/// let mut cloud: Vec<Point> = (0..1000)
+///     .map(|i| Point::new(Point3::new((i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32)))
+///     .collect();
+/// let bvh = BVH::build(&mut cloud);
+///
+/// let (nearest, distance) = bvh.nearest(&Point3::new(4.2, 5.1, 20.0), &cloud).unwrap();
+/// assert_eq!(nearest.position, Point3::new(4.0, 5.0, 9.0));
+/// assert!((distance - 11.0).abs() < 0.01);
+/// ```
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::build`]: ../bvh/struct.BVH.html#method.build
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Point {
+    /// The position of the point.
+    pub position: Point3,
+
+    /// The padding of the [`AABB`] of the point on every side.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub epsilon: f32,
+
+    /// The index of the node of this point in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl Point {
+    /// Creates a new [`Point`] at `position`, whose [`AABB`] is padded by [`EPSILON`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`EPSILON`]: ../constant.EPSILON.html
+    /// [`Point`]: struct.Point.html
+    ///
+    pub fn new(position: Point3) -> Point {
+        Point::with_epsilon(position, EPSILON)
+    }
+
+    /// Creates a new [`Point`] at `position`, whose [`AABB`] is padded by `epsilon`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Point`]: struct.Point.html
+    ///
+    pub fn with_epsilon(position: Point3, epsilon: f32) -> Point {
+        Point {
+            position,
+            epsilon,
+            node_index: 0,
+        }
+    }
+}
+
+impl Bounded for Point {
+    fn aabb(&self) -> AABB {
+        AABB::with_bounds(self.position, self.position).grown_by(self.epsilon)
+    }
+}
+
+impl BHShape for Point {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl PointDistance for Point {
+    fn distance_squared(&self, point: &Point3) -> f32 {
+        (self.position - *point).length_squared()
+    }
+}
+
+/// A line segment from `a` to `b`, e.g. a piece of a cable, a hair strand or a polyline.
+/// Unlike [`ray::Segment`], which queries a [`BVH`], a [`Segment`] is a shape which can be
+/// put into one.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::shapes::Segment;
+/// use bvh::Point3;
+///
+/// // A polyline along the x-axis.
+/// let mut polyline: Vec<Segment> = (0..100)
+///     .map(|i| Segment::new(Point3::new(i as f32, 0.0, 0.0), Point3::new(i as f32 + 1.0, 0.0, 0.0)))
+///     .collect();
+/// let bvh = BVH::build(&mut polyline);
+///
+/// let point = Point3::new(42.5, 3.0, 4.0);
+/// let (segment, distance) = bvh.nearest(&point, &polyline).unwrap();
+/// assert_eq!((segment.a.x, distance), (42.0, 5.0));
+/// ```
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`Segment`]: struct.Segment.html
+/// [`ray::Segment`]: ../ray/struct.Segment.html
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// The start point of the segment.
+    pub a: Point3,
+
+    /// The end point of the segment.
+    pub b: Point3,
+
+    /// The index of the node of this segment in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl Segment {
+    /// Creates a new [`Segment`] from `a` to `b`.
+    ///
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn new(a: Point3, b: Point3) -> Segment {
+        Segment {
+            a,
+            b,
+            node_index: 0,
+        }
+    }
+
+    /// Returns the point on this [`Segment`] which is closest to `point`.
+    ///
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn closest_point(&self, point: &Point3) -> Point3 {
+        self.a + (self.b - self.a) * closest_segment_parameter(self.a, self.b, *point)
+    }
+
+    /// Returns the closest points of this [`Segment`] and `other`, the first one on this
+    /// [`Segment`] and the second one on `other`.
+    ///
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn closest_points(&self, other: &Segment) -> (Point3, Point3) {
+        let (s, t) = closest_segment_parameters(self.a, self.b, other.a, other.b);
+        (
+            self.a + (self.b - self.a) * s,
+            other.a + (other.b - other.a) * t,
+        )
+    }
+
+    /// Returns the squared distance between this [`Segment`] and `other`. The squared
+    /// distance to a point is given by [`PointDistance`].
+    ///
+    /// [`PointDistance`]: ../bvh/trait.PointDistance.html
+    /// [`Segment`]: struct.Segment.html
+    ///
+    pub fn distance_squared_to_segment(&self, other: &Segment) -> f32 {
+        let (closest, other_closest) = self.closest_points(other);
+        (closest - other_closest).length_squared()
+    }
+}
+
+impl From<ray::Segment> for Segment {
+    fn from(segment: ray::Segment) -> Segment {
+        Segment::new(segment.start, segment.end)
+    }
+}
+
+impl Bounded for Segment {
+    /// Returns the [`AABB`] of this [`Segment`], padded by [`EPSILON`] relative to the
+    /// magnitude of its coordinates. Without the padding, the [`AABB`] of a segment along
+    /// a coordinate axis would have no surface area, and the surface area heuristic of
+    /// [`BVH::build`] could not tell the splits of such segments apart.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH::build`]: ../bvh/struct.BVH.html#method.build
+    /// [`EPSILON`]: ../constant.EPSILON.html
+    /// [`Segment`]: struct.Segment.html
+    ///
+    fn aabb(&self) -> AABB {
+        let magnitude = self.a.abs().max(self.b.abs()).max_element().max(1.0);
+        AABB::with_bounds(self.a.min(self.b), self.a.max(self.b)).grown_by(EPSILON * magnitude)
+    }
+}
+
+impl BHShape for Segment {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl PointDistance for Segment {
+    fn distance_squared(&self, point: &Point3) -> f32 {
+        (self.closest_point(point) - *point).length_squared()
+    }
+}
+
+impl IntersectsAabb for Segment {
+    fn intersects(&self, aabb: &AABB) -> bool {
+        ray::Segment::new(self.a, self.b).intersects_aabb(aabb)
+    }
+}
+
+impl RefinableShape for Segment {
+    /// The interval of the parameter `t` of the points `a + t * (b - a)` of the part.
+    type Part = (f32, f32);
+
+    fn whole(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
+
+    fn part_aabb(&self, &(start, end): &(f32, f32)) -> AABB {
+        let axis = self.b - self.a;
+        Segment::new(self.a + axis * start, self.a + axis * end).aabb()
+    }
+
+    /// Splits the part in half.
+    fn split(&self, &(start, end): &(f32, f32)) -> Vec<(f32, f32)> {
+        let middle = (start + end) / 2.0;
+        vec![(start, middle), (middle, end)]
+    }
+}
+
+/// A capsule, the set of all points within `radius` of the segment from `a` to `b`.
+/// Characters and many colliders are capsules.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::ray::{Intersectable, Ray};
+/// use bvh::shapes::{Capsule, Sphere};
+/// use bvh::{Point3,Vector3};
+///
+/// let mut spheres: Vec<Sphere> = (0..10)
+///     .map(|i| Sphere::new(Point3::new(i as f32 * 3.0, 0.0, 0.0), 1.0))
+///     .collect();
+/// let bvh = BVH::build(&mut spheres);
+///
+/// let capsule = Capsule::new(Point3::new(4.0, 0.0, 0.0), Point3::new(8.0, 0.0, 0.0), 0.5);
+/// let touched: Vec<&Sphere> = bvh
+///     .query(&capsule, &spheres)
+///     .into_iter()
+///     .filter(|sphere| capsule.intersects_sphere(sphere))
+///     .collect();
+/// assert_eq!(touched.len(), 3);
+///
+/// let ray = Ray::new(Point3::new(6.0, 0.0, 10.0), Vector3::new(0.0, 0.0, -1.0));
+/// assert_eq!(capsule.intersect(&ray).unwrap().distance, 9.5);
+/// ```
+///
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde_impls", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capsule {
+    /// The first end of the axis of the capsule.
+    pub a: Point3,
+
+    /// The second end of the axis of the capsule.
+    pub b: Point3,
+
+    /// The radius of the capsule.
+    pub radius: f32,
+
+    /// The index of the node of this capsule in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl Capsule {
+    /// Creates a new [`Capsule`] from the ends of its axis and its radius.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    pub fn new(a: Point3, b: Point3, radius: f32) -> Capsule {
+        Capsule {
+            a,
+            b,
+            radius,
+            node_index: 0,
+        }
+    }
+
+    /// Returns the point on the axis of this [`Capsule`] which is closest to `point`.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    pub fn closest_point(&self, point: &Point3) -> Point3 {
+        self.a + (self.b - self.a) * closest_segment_parameter(self.a, self.b, *point)
+    }
+
+    /// Returns true if this [`Capsule`] overlaps `sphere`.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        let radius = self.radius + sphere.radius;
+        (self.closest_point(&sphere.center) - sphere.center).length_squared() <= radius * radius
+    }
+
+    /// Returns true if this [`Capsule`] overlaps `other`, i.e. if their axes are at most
+    /// the sum of their radii apart.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    pub fn intersects_capsule(&self, other: &Capsule) -> bool {
+        let (s, t) = closest_segment_parameters(self.a, self.b, other.a, other.b);
+        let closest = self.a + (self.b - self.a) * s;
+        let other_closest = other.a + (other.b - other.a) * t;
+        let radius = self.radius + other.radius;
+        (closest - other_closest).length_squared() <= radius * radius
+    }
+
+    /// Returns the interval of the line of `ray` inside of this [`Capsule`], which is the
+    /// union of the intervals inside of the cylinder around the axis and the two spheres
+    /// at its ends, as the [`Capsule`] is convex.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    fn ray_interval(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let intervals = [
+            sphere_interval(ray, &self.a, self.radius),
+            sphere_interval(ray, &self.b, self.radius),
+            self.cylinder_interval(ray),
+        ];
+        intervals
+            .iter()
+            .flatten()
+            .fold(None, |interval, &(entry, exit)| match interval {
+                None => Some((entry, exit)),
+                Some((union_entry, union_exit)) => {
+                    Some((f32::min(entry, union_entry), f32::max(exit, union_exit)))
+                }
+            })
+    }
+
+    /// Returns the interval of the line of `ray` inside of the cylinder between the two
+    /// ends of the axis of this [`Capsule`].
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    fn cylinder_interval(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let axis = self.b - self.a;
+        let length_squared = axis.length_squared();
+        if length_squared <= f32::EPSILON {
+            return None;
+        }
+
+        // The parts of the direction and origin of the ray perpendicular to the axis.
+        let offset = ray.origin - self.a;
+        let direction = ray.direction - axis * (ray.direction.dot(axis) / length_squared);
+        let origin = offset - axis * (offset.dot(axis) / length_squared);
+        let a = direction.length_squared();
+        let b = origin.dot(direction);
+        let c = origin.length_squared() - self.radius * self.radius;
+        let (mut entry, mut exit) = if a <= f32::EPSILON {
+            // The ray is parallel to the axis, and either always or never inside.
+            if c > 0.0 {
+                return None;
+            }
+            (f32::NEG_INFINITY, f32::INFINITY)
+        } else {
+            let discriminant = b * b - a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let root = discriminant.sqrt();
+            ((-b - root) / a, (-b + root) / a)
+        };
+
+        // Clip the interval to the slab between the planes through the ends of the axis.
+        let along = ray.direction.dot(axis);
+        let start = offset.dot(axis);
+        if along == 0.0 {
+            if start < 0.0 || start > length_squared {
+                return None;
+            }
+        } else {
+            let t0 = -start / along;
+            let t1 = (length_squared - start) / along;
+            entry = entry.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+        }
+
+        if entry <= exit {
+            Some((entry, exit))
+        } else {
+            None
+        }
+    }
+}
+
+impl Bounded for Capsule {
+    fn aabb(&self) -> AABB {
+        let half_size = Vector3::splat(self.radius);
+        AABB::with_bounds(
+            self.a.min(self.b) - half_size,
+            self.a.max(self.b) + half_size,
+        )
+    }
+}
+
+impl BHShape for Capsule {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl Intersectable for Capsule {
+    /// Returns the [`Intersection`] of `ray` with the surface of this [`Capsule`]. Like
+    /// [`Ray::intersects_sphere`], a ray from the inside hits the surface where it leaves
+    /// the [`Capsule`]. `u` is the position of the hit along the axis, from `0` at `a` to
+    /// `1` at `b`, and `v` is always `0`.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    /// [`Ray::intersects_sphere`]: ../ray/struct.Ray.html#method.intersects_sphere
+    ///
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let (entry, exit) = self.ray_interval(ray)?;
+        let distance = if entry >= ray.t_min && entry <= ray.t_max {
+            entry
+        } else if exit >= ray.t_min && exit <= ray.t_max {
+            exit
+        } else {
+            return None;
+        };
+
+        let axis = self.b - self.a;
+        let length_squared = axis.length_squared();
+        let u = if length_squared <= f32::EPSILON {
+            0.0
+        } else {
+            let point = ray.origin + ray.direction * distance;
+            ((point - self.a).dot(axis) / length_squared).clamp(0.0, 1.0)
+        };
+        Some(Intersection::new(distance, u, 0.0))
+    }
+}
+
+impl IntersectsAabb for Capsule {
+    /// Returns true if the axis of this [`Capsule`] comes within its radius of `aabb`.
+    /// The distance of the points on the axis to `aabb` is convex, and its minimum is
+    /// approximated by a ternary search.
+    ///
+    /// [`Capsule`]: struct.Capsule.html
+    ///
+    fn intersects(&self, aabb: &AABB) -> bool {
+        if !self.aabb().intersects(aabb) {
+            return false;
+        }
+
+        let axis = self.b - self.a;
+        let distance_squared = |t: f32| aabb.min_distance_squared(&(self.a + axis * t));
+        let radius_squared = self.radius * self.radius;
+        let (mut low, mut high) = (0.0f32, 1.0f32);
+        for _ in 0..32 {
+            if distance_squared(low).min(distance_squared(high)) <= radius_squared {
+                return true;
+            }
+            let third = (high - low) / 3.0;
+            if distance_squared(low + third) < distance_squared(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        distance_squared((low + high) / 2.0) <= radius_squared
+    }
+}
+
+/// Returns the interval of the line of `ray` inside of the sphere at `center`.
+pub(crate) fn sphere_interval(ray: &Ray, center: &Point3, radius: f32) -> Option<(f32, f32)> {
+    let origin_to_center = *center - ray.origin;
+    let projection = origin_to_center.dot(ray.direction);
+    let distance_squared = origin_to_center.length_squared() - projection * projection;
+    let radius_squared = radius * radius;
+    if distance_squared > radius_squared {
+        return None;
+    }
+    let half_chord = (radius_squared - distance_squared).sqrt();
+    Some((projection - half_chord, projection + half_chord))
+}
+
+/// Returns the parameter `t` of the point `a + t * (b - a)` of the segment from `a` to `b`
+/// which is closest to `point`, in `[0, 1]`.
+fn closest_segment_parameter(a: Point3, b: Point3, point: Point3) -> f32 {
+    let axis = b - a;
+    let length_squared = axis.length_squared();
+    if length_squared <= f32::EPSILON {
+        return 0.0;
+    }
+    ((point - a).dot(axis) / length_squared).clamp(0.0, 1.0)
+}
+
+/// Returns the parameters `s` and `t` of the closest points `p1 + s * (q1 - p1)` and
+/// `p2 + t * (q2 - p2)` of the segments from `p1` to `q1` and from `p2` to `q2`, both
+/// in `[0, 1]`. See Ericson, Real-Time Collision Detection, 5.1.9.
+fn closest_segment_parameters(p1: Point3, q1: Point3, p2: Point3, q2: Point3) -> (f32, f32) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+    if a <= f32::EPSILON && e <= f32::EPSILON {
+        return (0.0, 0.0);
+    }
+    if a <= f32::EPSILON {
+        return (0.0, (f / e).clamp(0.0, 1.0));
+    }
+    let c = d1.dot(r);
+    if e <= f32::EPSILON {
+        return ((-c / a).clamp(0.0, 1.0), 0.0);
+    }
+
+    let b = d1.dot(d2);
+    let denominator = a * e - b * b;
+    // Parallel segments have no unique closest points, any `s` will do.
+    let s = if denominator > 0.0 {
+        ((b * f - c * e) / denominator).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let t = (b * s + f) / e;
+    if t < 0.0 {
+        ((-c / a).clamp(0.0, 1.0), 0.0)
+    } else if t > 1.0 {
+        (((b - c) / a).clamp(0.0, 1.0), 1.0)
+    } else {
+        (s, t)
+    }
+}
+
+/// A copy of a bottom-level [`BVH`] and its shapes, placed in the scene by an affine
+/// `transform`. Many [`Instance`]s of the same mesh share its [`BVH`] and shapes, and can
+/// be put into a top-level [`BVH`] themselves.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::ray::{Intersectable, Ray};
+/// use bvh::shapes::{Instance, Triangle};
+/// use bvh::{Point3,Vector3};
+/// use glam::Mat4;
+///
+/// let mut mesh = vec![Triangle::new(
+///     Point3::new(0.0, 0.0, 0.0),
+///     Point3::new(1.0, 0.0, 0.0),
+///     Point3::new(0.0, 1.0, 0.0),
+/// )];
+/// let mesh_bvh = BVH::build(&mut mesh);
+///
+/// let mut instances: Vec<Instance<Triangle>> = (0..100)
+///     .map(|i| {
+///         let transform = Mat4::from_translation(Vector3::new(i as f32 * 2.0, 0.0, 0.0));
+///         Instance::new(&mesh_bvh, &mesh, transform)
+///     })
+///     .collect();
+/// let scene = BVH::build(&mut instances);
+///
+/// let ray = Ray::new(Point3::new(10.25, 0.25, 1.0), Vector3::new(0.0, 0.0, -1.0));
+/// let (instance, intersection) = scene.nearest_hit(&ray, &instances).unwrap();
+/// assert_eq!(instance.transform().w_axis.x, 10.0);
+/// assert_eq!(intersection.distance, 1.0);
+/// ```
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`Instance`]: struct.Instance.html
+///
+#[derive(Debug, Clone)]
+pub struct Instance<'a, Shape> {
+    /// The [`BVH`] of the `shapes`.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub bvh: &'a BVH,
+
+    /// The shapes of the `bvh`, in its local space.
+    pub shapes: &'a [Shape],
+
+    /// The transform from the local space of the `bvh` to world space.
+    transform: Mat4,
+
+    /// The inverse of `transform`.
+    inverse: Mat4,
+
+    /// The world space [`AABB`] of the root of the `bvh`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    aabb: AABB,
+
+    /// The index of the node of this instance in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl<'a, Shape: BHShape> Instance<'a, Shape> {
+    /// Creates a new [`Instance`] of `bvh` and its `shapes`, which are placed in the scene
+    /// by the invertible affine `transform`.
+    ///
+    /// [`Instance`]: struct.Instance.html
+    ///
+    pub fn new(bvh: &'a BVH, shapes: &'a [Shape], transform: Mat4) -> Instance<'a, Shape> {
+        let mut instance = Instance {
+            bvh,
+            shapes,
+            transform,
+            inverse: transform.inverse(),
+            aabb: AABB::empty(),
+            node_index: 0,
+        };
+        instance.set_transform(transform);
+        instance
+    }
+
+    /// Returns the transform from the local space of the [`BVH`] to world space.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub fn transform(&self) -> &Mat4 {
+        &self.transform
+    }
+
+    /// Moves the [`Instance`] by replacing its `transform`. A top-level [`BVH`] of the
+    /// [`Instance`] has to be updated afterwards, e.g. with [`BVH::update_shape`].
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`BVH::update_shape`]: ../bvh/struct.BVH.html#method.update_shape
+    /// [`Instance`]: struct.Instance.html
+    ///
+    pub fn set_transform(&mut self, transform: Mat4) {
+        self.transform = transform;
+        self.inverse = transform.inverse();
+        self.aabb = match self.bvh.nodes.first() {
+            Some(root) => root.get_node_aabb(self.shapes).transformed(&transform),
+            None => AABB::empty(),
+        };
+    }
+
+    /// Returns the world space `ray` in the local space of the [`BVH`].
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub fn local_ray(&self, ray: &Ray) -> Ray {
+        ray.transformed(&self.inverse)
+    }
+
+    /// Traverses the [`BVH`] with the world space `ray`. Returns the shapes whose [`AABB`]s
+    /// are hit by the [`Ray`] in local space.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`Ray`]: ../ray/struct.Ray.html
+    ///
+    pub fn traverse(&self, ray: &Ray) -> Vec<&'a Shape> {
+        self.bvh.traverse(&self.local_ray(ray), self.shapes)
+    }
+}
+
+impl<'a, Shape: BHShape + Intersectable> Instance<'a, Shape> {
+    /// Returns the shape which is hit first by the world space `ray`, together with its
+    /// [`Intersection`], whose distance is measured along the world space `ray`.
+    ///
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn nearest_hit(&self, ray: &Ray) -> Option<(&'a Shape, Intersection)> {
+        let local_ray = self.local_ray(ray);
+        let scale = self.inverse.transform_vector3(ray.direction).length();
+        self.bvh
+            .nearest_hit(&local_ray, self.shapes)
+            .map(|(shape, intersection)| {
+                let distance = intersection.distance / scale;
+                (
+                    shape,
+                    Intersection::new(distance, intersection.u, intersection.v),
+                )
+            })
+    }
+}
+
+impl<'a, Shape> Bounded for Instance<'a, Shape> {
+    fn aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl<'a, Shape> BHShape for Instance<'a, Shape> {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl<'a, Shape: BHShape + Intersectable> Intersectable for Instance<'a, Shape> {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        self.nearest_hit(ray).map(|(_, intersection)| intersection)
+    }
+}
+
+/// A triangle mesh given by borrowed vertex and index buffers. Its triangles can be put
+/// into a [`BVH`] with [`BVH::build_for_mesh`] without copying the vertices. The shape
+/// indices of the [`BVH`] are the indices of the triangles.
+///
+/// # Examples
+/// ```
+/// use bvh::bvh::BVH;
+/// use bvh::ray::Ray;
+/// use bvh::shapes::TriMesh;
+/// use bvh::{Point3,Vector3};
+///
+/// let vertices = [
+///     Point3::new(0.0, 0.0, 0.0),
+///     Point3::new(1.0, 0.0, 0.0),
+///     Point3::new(1.0, 1.0, 0.0),
+///     Point3::new(0.0, 1.0, 0.0),
+/// ];
+/// let indices = [[0, 1, 2], [0, 2, 3]];
+/// let mesh = TriMesh::new(&vertices, &indices);
+/// let bvh = BVH::build_for_mesh(&mesh);
+///
+/// let ray = Ray::new(Point3::new(0.25, 0.75, 1.0), Vector3::new(0.0, 0.0, -1.0));
+/// let (triangle_index, intersection) = mesh.nearest_hit(&bvh, &ray).unwrap();
+/// assert_eq!(triangle_index, 1);
+/// assert_eq!(intersection.distance, 1.0);
+/// ```
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`BVH::build_for_mesh`]: ../bvh/struct.BVH.html#method.build_for_mesh
+///
+#[derive(Debug, Copy, Clone)]
+pub struct TriMesh<'a> {
+    /// The vertices of the mesh.
+    pub vertices: &'a [Point3],
+
+    /// The indices of the three vertices of each triangle, in counter-clockwise order
+    /// seen from its front face.
+    pub indices: &'a [[u32; 3]],
+}
+
+impl<'a> TriMesh<'a> {
+    /// Creates a new [`TriMesh`] from its `vertices` and the vertex `indices` of its
+    /// triangles.
+    ///
+    /// [`TriMesh`]: struct.TriMesh.html
+    ///
+    pub fn new(vertices: &'a [Point3], indices: &'a [[u32; 3]]) -> TriMesh<'a> {
+        TriMesh { vertices, indices }
+    }
+
+    /// Returns the number of triangles.
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns true if the mesh has no triangles.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the three vertices of the triangle at `index`.
+    pub fn triangle(&self, index: usize) -> [Point3; 3] {
+        let [a, b, c] = self.indices[index];
+        [
+            self.vertices[a as usize],
+            self.vertices[b as usize],
+            self.vertices[c as usize],
+        ]
+    }
+
+    /// Returns the [`AABB`] of the triangle at `index`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn triangle_aabb(&self, index: usize) -> AABB {
+        let [a, b, c] = self.triangle(index);
+        AABB::empty().grow(&a).grow(&b).grow(&c)
+    }
+
+    /// Returns the [`Intersection`] of `ray` with the triangle at `index` like
+    /// [`Triangle::intersect`], or `None` if the ray misses it.
+    ///
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    /// [`Triangle::intersect`]: struct.Triangle.html#method.intersect
+    ///
+    pub fn intersect_triangle(&self, index: usize, ray: &Ray) -> Option<Intersection> {
+        let [a, b, c] = self.triangle(index);
+        let intersection = ray.intersects_triangle_watertight(&a, &b, &c);
+        if intersection.distance < f32::INFINITY {
+            Some(intersection)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the index of the triangle which is hit first by `ray`, together with its
+    /// [`Intersection`], or `None` if no triangle is hit. `bvh` has to be built by
+    /// [`BVH::build_for_mesh`] from this mesh.
+    ///
+    /// [`BVH::build_for_mesh`]: ../bvh/struct.BVH.html#method.build_for_mesh
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn nearest_hit(&self, bvh: &BVH, ray: &Ray) -> Option<(usize, Intersection)> {
+        bvh.nearest_hit_with(ray, |index| self.intersect_triangle(index, ray))
+    }
+}
+
+impl<'a> Bounded for TriMesh<'a> {
+    fn aabb(&self) -> AABB {
+        (0..self.len()).map(|index| self.triangle_aabb(index)).sum()
+    }
+}
+
+/// A triangle of a [`TriMesh`] or a cell of a [`Heightfield`] while its [`BVH`] is built.
+///
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`Heightfield`]: struct.Heightfield.html
+/// [`TriMesh`]: struct.TriMesh.html
+///
+struct PrimitiveBounds {
+    /// The [`AABB`] of the primitive.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    aabb: AABB,
+
+    /// The index of the node of the primitive in the [`BVH`].
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    node_index: usize,
+}
+
+impl Bounded for PrimitiveBounds {
+    fn aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl BHShape for PrimitiveBounds {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl BVH {
+    /// Creates a new [`BVH`] over the triangles of `mesh`, whose shape indices are the
+    /// indices of the triangles. Only the [`AABB`]s of the triangles are stored while the
+    /// [`BVH`] is built, and the vertices are never copied.
+    ///
+    /// # Panics
+    /// Panics if the mesh has no triangles.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`BVH`]: struct.BVH.html
+    ///
+    pub fn build_for_mesh(mesh: &TriMesh) -> BVH {
+        let mut triangles: Vec<PrimitiveBounds> = (0..mesh.len())
+            .map(|index| PrimitiveBounds {
+                aabb: mesh.triangle_aabb(index),
+                node_index: 0,
+            })
+            .collect();
+        BVH::build(&mut triangles)
+    }
+}
+
+/// A terrain given by a borrowed grid of heights. Every cell between four neighbouring
+/// samples is split into two triangles, which are only computed when they are intersected.
+/// The [`Heightfield`] keeps a [`BVH`] over the [`AABB`]s of its cells, and is itself a
+/// shape which can be put into the [`BVH`] of a scene.
+///
+/// The sample in column `x` and row `z` is at
+/// `origin + scale * Vector3::new(x, heights[z * columns + x], z)`.
+///
+/// # Examples
+/// ```
+/// use bvh::ray::{Intersectable, Ray};
+/// use bvh::shapes::Heightfield;
+/// use bvh::{Point3,Vector3};
+///
+/// // A slope of 100 x 100 samples, rising by one unit per row.
+/// let heights: Vec<f32> = (0..100 * 100).map(|i| (i / 100) as f32).collect();
+/// let terrain = Heightfield::new(&heights, 100, Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0));
+/// assert_eq!(terrain.cell_count(), 99 * 99);
+///
+/// let ray = Ray::new(Point3::new(42.5, 100.0, 42.5), Vector3::new(0.0, -1.0, 0.0));
+/// let (cell, intersection) = terrain.nearest_hit(&ray).unwrap();
+/// assert_eq!(terrain.cell(cell), (42, 42));
+/// assert_eq!(intersection.distance, 57.5);
+/// assert_eq!(terrain.intersect(&ray).unwrap().distance, 57.5);
+/// ```
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH`]: ../bvh/struct.BVH.html
+/// [`Heightfield`]: struct.Heightfield.html
+///
+#[derive(Debug, Clone)]
+pub struct Heightfield<'a> {
+    /// The heights of the samples, row by row.
+    pub heights: &'a [f32],
+
+    /// The number of samples in each row.
+    pub columns: usize,
+
+    /// The position of the first sample at height `0`.
+    pub origin: Point3,
+
+    /// The distance between two samples along the x- and z-axis, and the factor of the
+    /// heights along the y-axis.
+    pub scale: Vector3,
+
+    /// The [`BVH`] of the cells.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    bvh: BVH,
+
+    /// The [`AABB`] of all cells.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    aabb: AABB,
+
+    /// The index of the node of this heightfield in a [`BoundingHierarchy`].
+    ///
+    /// [`BoundingHierarchy`]: ../bounding_hierarchy/trait.BoundingHierarchy.html
+    ///
+    node_index: usize,
+}
+
+impl<'a> Heightfield<'a> {
+    /// Creates a new [`Heightfield`] from the `heights` of rows of `columns` samples each,
+    /// and builds the [`BVH`] of its cells.
+    ///
+    /// # Panics
+    /// Panics if the `heights` are not a grid of at least two rows of `columns` samples,
+    /// or if `columns` is smaller than two.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    /// [`Heightfield`]: struct.Heightfield.html
+    ///
+    // `usize::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn new(
+        heights: &'a [f32],
+        columns: usize,
+        origin: Point3,
+        scale: Vector3,
+    ) -> Heightfield<'a> {
+        assert!(
+            columns >= 2 && heights.len() >= 2 * columns && heights.len() % columns == 0,
+            "{} heights are not a grid of rows of {} samples",
+            heights.len(),
+            columns
+        );
+        let mut heightfield = Heightfield {
+            heights,
+            columns,
+            origin,
+            scale,
+            bvh: BVH { nodes: Vec::new() },
+            aabb: AABB::empty(),
+            node_index: 0,
+        };
+        let mut cells: Vec<PrimitiveBounds> = (0..heightfield.cell_count())
+            .map(|index| PrimitiveBounds {
+                aabb: heightfield.cell_aabb(index),
+                node_index: 0,
+            })
+            .collect();
+        heightfield.aabb = cells.iter().map(Bounded::aabb).sum();
+        heightfield.bvh = BVH::build(&mut cells);
+        heightfield
+    }
+
+    /// Returns the number of rows of samples.
+    pub fn rows(&self) -> usize {
+        self.heights.len() / self.columns
+    }
+
+    /// Returns the number of cells, which is one less than the number of samples along
+    /// either axis.
+    pub fn cell_count(&self) -> usize {
+        (self.columns - 1) * (self.rows() - 1)
+    }
+
+    /// Returns the column and row of the first sample of the cell at `index`.
+    pub fn cell(&self, index: usize) -> (usize, usize) {
+        (index % (self.columns - 1), index / (self.columns - 1))
+    }
+
+    /// Returns the [`BVH`] of the cells, whose shape indices are the indices of the cells.
+    ///
+    /// [`BVH`]: ../bvh/struct.BVH.html
+    ///
+    pub fn bvh(&self) -> &BVH {
+        &self.bvh
+    }
+
+    /// Returns the position of the sample in column `x` and row `z`.
+    pub fn sample(&self, x: usize, z: usize) -> Point3 {
+        let height = self.heights[z * self.columns + x];
+        self.origin + self.scale * Vector3::new(x as f32, height, z as f32)
+    }
+
+    /// Returns the four samples of the cell at `index`, in counter-clockwise order seen
+    /// from above for a positive `scale`.
+    fn cell_samples(&self, index: usize) -> [Point3; 4] {
+        let (x, z) = self.cell(index);
+        [
+            self.sample(x, z),
+            self.sample(x, z + 1),
+            self.sample(x + 1, z + 1),
+            self.sample(x + 1, z),
+        ]
+    }
+
+    /// Returns the [`AABB`] of the cell at `index`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    pub fn cell_aabb(&self, index: usize) -> AABB {
+        let [a, b, c, d] = self.cell_samples(index);
+        AABB::empty().grow(&a).grow(&b).grow(&c).grow(&d)
+    }
+
+    /// Returns the [`Intersection`] of `ray` with the two triangles of the cell at `index`,
+    /// or `None` if the ray misses them. `u` and `v` are the position of the hit on the
+    /// whole [`Heightfield`] along the x- and z-axis, both scaled to `[0, 1]`.
+    ///
+    /// [`Heightfield`]: struct.Heightfield.html
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn intersect_cell(&self, index: usize, ray: &Ray) -> Option<Intersection> {
+        let [a, b, c, d] = self.cell_samples(index);
+        let distance = ray
+            .intersects_triangle_watertight(&a, &b, &c)
+            .distance
+            .min(ray.intersects_triangle_watertight(&a, &c, &d).distance);
+        if distance == f32::INFINITY {
+            return None;
+        }
+
+        let point = ray.origin + ray.direction * distance - self.origin;
+        let u = point.x / (self.scale.x * (self.columns - 1) as f32);
+        let v = point.z / (self.scale.z * (self.rows() - 1) as f32);
+        Some(Intersection::new(
+            distance,
+            u.clamp(0.0, 1.0),
+            v.clamp(0.0, 1.0),
+        ))
+    }
+
+    /// Returns the index of the cell which is hit first by `ray`, together with its
+    /// [`Intersection`], or `None` if no cell is hit.
+    ///
+    /// [`Intersection`]: ../ray/struct.Intersection.html
+    ///
+    pub fn nearest_hit(&self, ray: &Ray) -> Option<(usize, Intersection)> {
+        self.bvh
+            .nearest_hit_with(ray, |index| self.intersect_cell(index, ray))
+    }
+}
+
+impl<'a> Bounded for Heightfield<'a> {
+    fn aabb(&self) -> AABB {
+        self.aabb
+    }
+}
+
+impl<'a> BHShape for Heightfield<'a> {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.node_index = index;
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.node_index
+    }
+}
+
+impl<'a> Intersectable for Heightfield<'a> {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        self.nearest_hit(ray).map(|(_, intersection)| intersection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::aabb::{Bounded, IntersectsAabb, AABB};
+    use crate::bvh::PointDistance;
+    use crate::bvh::BVH;
+    use crate::ray::Intersectable;
+    use crate::ray::Ray;
+    use crate::shapes::{
+        Capsule, Heightfield, Instance, Point, Segment, Sphere, TriMesh, Triangle,
+    };
+    use crate::{Point3, Vector3};
+    use glam::{Mat4, Quat};
+
+    use proptest::prelude::*;
+
+    proptest! {
+        // Test whether a `Ray` which points at a point inside of a `Triangle` from its
+        // front side hits it at that point, and misses it from its back side.
+        #[test]
+        fn test_ray_hits_triangle(a in prop::array::uniform3(-10.0f32..10.0),
+                                  b in prop::array::uniform3(-10.0f32..10.0),
+                                  c in prop::array::uniform3(-10.0f32..10.0),
+                                  u in 0.1f32..0.4,
+                                  v in 0.1f32..0.4) {
+            let triangle = Triangle::new(a.into(), b.into(), c.into());
+            let normal = (triangle.b - triangle.a).cross(triangle.c - triangle.a);
+            prop_assume!(normal.length() > 1.0);
+            let normal = normal.normalize();
+            let point = triangle.a + u * (triangle.b - triangle.a) + v * (triangle.c - triangle.a);
+
+            let ray = Ray::new(point + normal * 10.0, -normal);
+            let (distance, hit_u, hit_v) = triangle.intersect_ray(&ray).unwrap();
+            assert!((distance - 10.0).abs() < 0.01);
+            assert!((hit_u - u).abs() < 0.01 && (hit_v - v).abs() < 0.01);
+            assert!(triangle.aabb().approx_contains_eps(&point, 0.01));
+
+            let ray = Ray::new(point - normal * 10.0, normal);
+            assert!(triangle.intersect_ray(&ray).is_none());
+        }
+    }
+
+    #[test]
+    /// Tests whether a `Ray` next to a `Triangle` misses it.
+    fn test_ray_misses_triangle() {
+        let triangle = Triangle::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(Point3::new(0.6, 0.6, 1.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(triangle.intersect_ray(&ray).is_none());
+    }
+
+    #[test]
+    /// Tests whether `Ray`s hit a `Sphere` on its surface, at the expected `u` and `v`.
+    fn test_ray_hits_sphere() {
+        let sphere = Sphere::new(Point3::new(1.0, 2.0, 3.0), 2.0);
+        let aabb = sphere.aabb();
+        assert_eq!(
+            (aabb.min, aabb.max),
+            (Point3::new(-1.0, 0.0, 1.0), Point3::new(3.0, 4.0, 5.0))
+        );
+
+        // A ray along the negative x-axis hits the sphere on the equator at longitude 0.5.
+        let ray = Ray::new(Point3::new(10.0, 2.0, 3.0), Vector3::new(-1.0, 0.0, 0.0));
+        let intersection = sphere.intersect(&ray).unwrap();
+        assert_eq!(intersection.distance, 7.0);
+        assert_eq!((intersection.u, intersection.v), (0.5, 0.5));
+
+        // A ray from the inside hits the sphere where it leaves it, at the north pole.
+        let ray = Ray::new(sphere.center, Vector3::new(0.0, 1.0, 0.0));
+        let intersection = sphere.intersect(&ray).unwrap();
+        assert_eq!(intersection.distance, 2.0);
+        assert_eq!(intersection.v, 1.0);
+
+        let ray = Ray::new(Point3::new(10.0, 4.5, 3.0), Vector3::new(-1.0, 0.0, 0.0));
+        assert!(sphere.intersect(&ray).is_none());
+    }
+
+    #[test]
+    /// Tests whether `BVH`s of `Point`s in a plane and of coincident `Point`s are balanced.
+    fn test_points_are_balanced() {
+        let mut plane: Vec<Point> = (0..1024)
+            .map(|i| Point::new(Point3::new((i % 32) as f32, (i / 32) as f32, 0.0)))
+            .collect();
+        let mut coincident = vec![Point::with_epsilon(Point3::new(1.0, 2.0, 3.0), 0.5); 1024];
+        assert_eq!(
+            (coincident[0].aabb().min, coincident[0].aabb().max),
+            (Point3::new(0.5, 1.5, 2.5), Point3::new(1.5, 2.5, 3.5))
+        );
+
+        for points in [&mut plane, &mut coincident] {
+            let bvh = BVH::build(points);
+            bvh.assert_consistent(points);
+            let depth = bvh.nodes.iter().map(|node| node.depth()).max().unwrap();
+            assert!(depth <= 20, "the BVH has a depth of {}", depth);
+        }
+    }
+
+    #[test]
+    /// Tests the closest points and distances of `Segment`s.
+    fn test_segment_distances() {
+        let segment = Segment::new(Point3::new(0.0, 0.0, 0.0), Point3::new(4.0, 0.0, 0.0));
+        assert_eq!(segment.distance_squared(&Point3::new(2.0, 3.0, 0.0)), 9.0);
+        assert_eq!(segment.distance_squared(&Point3::new(-3.0, 0.0, 4.0)), 25.0);
+        assert_eq!(
+            segment.closest_point(&Point3::new(7.0, 1.0, 0.0)),
+            Point3::new(4.0, 0.0, 0.0)
+        );
+
+        let skew = Segment::new(Point3::new(1.0, -1.0, 2.0), Point3::new(1.0, 1.0, 2.0));
+        assert_eq!(
+            segment.closest_points(&skew),
+            (Point3::new(1.0, 0.0, 0.0), Point3::new(1.0, 0.0, 2.0))
+        );
+        assert_eq!(segment.distance_squared_to_segment(&skew), 4.0);
+
+        let parallel = Segment::new(Point3::new(6.0, 1.0, 0.0), Point3::new(9.0, 1.0, 0.0));
+        assert_eq!(segment.distance_squared_to_segment(&parallel), 5.0);
+        let point = Segment::new(Point3::new(2.0, 0.0, 3.0), Point3::new(2.0, 0.0, 3.0));
+        assert_eq!(segment.distance_squared_to_segment(&point), 9.0);
+    }
+
+    #[test]
+    /// Tests whether a `BVH` of axis-aligned `Segment`s in a plane is balanced, which needs
+    /// the padding of their `AABB`s.
+    fn test_segments_in_plane() {
+        let mut segments: Vec<Segment> = (0..1024)
+            .map(|i| {
+                let (x, y) = ((i % 32) as f32, (i / 32) as f32);
+                Segment::new(Point3::new(x, y, 0.0), Point3::new(x + 1.0, y, 0.0))
+            })
+            .collect();
+        let aabb = segments[0].aabb();
+        assert!(aabb.surface_area() > 0.0);
+        assert!(aabb.contains(&segments[0].a) && aabb.contains(&segments[0].b));
+
+        let bvh = BVH::build(&mut segments);
+        bvh.assert_consistent(&segments);
+        let depth = bvh.nodes.iter().map(|node| node.depth()).max().unwrap();
+        assert!(depth <= 20, "the BVH has a depth of {}", depth);
+    }
+
+    #[test]
+    /// Tests whether `Ray`s hit a `Capsule` on its body and its caps, and miss it next to
+    /// the rounded edges of its `AABB`.
+    fn test_ray_hits_capsule() {
+        let capsule = Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 4.0, 0.0), 1.0);
+        let aabb = capsule.aabb();
+        assert_eq!(
+            (aabb.min, aabb.max),
+            (Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 5.0, 1.0))
+        );
+
+        // A ray towards the middle of the axis hits the body.
+        let ray = Ray::new(Point3::new(5.0, 2.0, 0.0), Vector3::new(-1.0, 0.0, 0.0));
+        let intersection = capsule.intersect(&ray).unwrap();
+        assert_eq!((intersection.distance, intersection.u), (4.0, 0.5));
+
+        // A ray along the axis hits the tip of the cap at `b`.
+        let ray = Ray::new(Point3::new(0.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let intersection = capsule.intersect(&ray).unwrap();
+        assert_eq!((intersection.distance, intersection.u), (5.0, 1.0));
+
+        // A ray from the inside hits the body where it leaves the capsule.
+        let ray = Ray::new(Point3::new(0.0, 2.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(capsule.intersect(&ray).unwrap().distance, 1.0);
+
+        // A ray through a corner of the `AABB` misses the cap.
+        let ray = Ray::new(Point3::new(5.0, 4.8, 0.8), Vector3::new(-1.0, 0.0, 0.0));
+        assert!(ray.intersects_aabb(&aabb));
+        assert!(capsule.intersect(&ray).is_none());
+    }
+
+    #[test]
+    /// Tests whether a `Capsule` overlaps `AABB`s, `Sphere`s and other `Capsule`s.
+    fn test_capsule_overlaps() {
+        let capsule = Capsule::new(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 4.0, 0.0), 1.0);
+        let near_body = AABB::with_bounds(Point3::new(0.5, 2.0, -0.1), Point3::new(2.0, 3.0, 0.1));
+        let near_cap = AABB::with_bounds(Point3::new(0.8, 4.8, -0.1), Point3::new(2.0, 6.0, 0.1));
+        assert!(capsule.intersects(&near_body));
+        assert!(capsule.aabb().intersects(&near_cap));
+        assert!(!IntersectsAabb::intersects(&capsule, &near_cap));
+
+        assert!(capsule.intersects_sphere(&Sphere::new(Point3::new(0.0, 6.4, 0.0), 1.5)));
+        assert!(!capsule.intersects_sphere(&Sphere::new(Point3::new(2.0, 5.0, 0.0), 1.0)));
+
+        let parallel =
+            |x: f32| Capsule::new(Point3::new(x, 1.0, 0.0), Point3::new(x, 7.0, 0.0), 1.0);
+        assert!(capsule.intersects_capsule(&parallel(1.5)));
+        assert!(!capsule.intersects_capsule(&parallel(2.5)));
+        let crossing = Capsule::new(Point3::new(-5.0, 2.0, 1.5), Point3::new(5.0, 2.0, 1.5), 0.6);
+        assert!(capsule.intersects_capsule(&crossing));
+        let above = Capsule::new(Point3::new(-5.0, 6.0, 1.5), Point3::new(5.0, 6.0, 1.5), 0.6);
+        assert!(!capsule.intersects_capsule(&above));
+    }
+
+    #[test]
+    /// Tests whether querying a `BVH` with a `Capsule` finds all shapes it overlaps.
+    fn test_capsule_query() {
+        let mut spheres: Vec<Sphere> = (0..1000)
+            .map(|i| {
+                let i = i as f32;
+                let center = Point3::new((i * 0.37).sin(), (i * 0.91).cos(), (i * 0.13).sin());
+                Sphere::new(center * 20.0, 0.5)
+            })
+            .collect();
+        let bvh = BVH::build(&mut spheres);
+        let capsule = Capsule::new(
+            Point3::new(-15.0, -5.0, 0.0),
+            Point3::new(10.0, 8.0, 3.0),
+            3.0,
+        );
+
+        let found = bvh.query(&capsule, &spheres);
+        let expected: Vec<&Sphere> = spheres
+            .iter()
+            .filter(|sphere| capsule.intersects_sphere(sphere))
+            .collect();
+        assert!(!expected.is_empty());
+        for sphere in expected {
+            assert!(found.iter().any(|s| std::ptr::eq(*s, sphere)));
+        }
+    }
+
+    #[test]
+    /// Tests whether an `Instance` is hit where the transformed copies of its shapes are
+    /// hit, and whether it is bounded by them.
+    fn test_instance_equals_transformed_shapes() {
+        let mut mesh: Vec<Triangle> = (0..10)
+            .map(|i| {
+                let z = i as f32;
+                Triangle::new(
+                    Point3::new(0.0, 0.0, z),
+                    Point3::new(1.0, 0.0, z),
+                    Point3::new(0.0, 1.0, z),
+                )
+            })
+            .collect();
+        let bvh = BVH::build(&mut mesh);
+        let transform = Mat4::from_scale_rotation_translation(
+            Vector3::new(2.0, 2.0, 2.0),
+            Quat::from_rotation_y(0.5),
+            Vector3::new(5.0, -3.0, 1.0),
+        );
+        let instance = Instance::new(&bvh, &mesh, transform);
+
+        let world: Vec<Triangle> = mesh
+            .iter()
+            .map(|triangle| {
+                Triangle::new(
+                    transform.transform_point3(triangle.a),
+                    transform.transform_point3(triangle.b),
+                    transform.transform_point3(triangle.c),
+                )
+            })
+            .collect();
+        for triangle in &world {
+            assert!(instance
+                .aabb()
+                .approx_contains_aabb_eps(&triangle.aabb(), 0.001));
+        }
+
+        let target = transform.transform_point3(Point3::new(0.25, 0.25, 4.0));
+        let origin = target + Vector3::new(1.0, 2.0, 30.0);
+        let ray = Ray::new(origin, target - origin);
+        let expected = world
+            .iter()
+            .filter_map(|triangle| triangle.intersect(&ray))
+            .map(|intersection| intersection.distance)
+            .fold(f32::INFINITY, f32::min);
+        let (shape, intersection) = instance.nearest_hit(&ray).unwrap();
+        assert!((intersection.distance - expected).abs() < 0.001);
+        assert!(instance
+            .traverse(&ray)
+            .iter()
+            .any(|s| std::ptr::eq(*s, shape)));
+    }
+
+    #[test]
+    /// Tests whether a `TriMesh` finds the same nearest hits as its triangles as `Triangle`s.
+    fn test_tri_mesh_equals_triangles() {
+        let vertices: Vec<Point3> = (0..100)
+            .map(|i| {
+                let i = i as f32;
+                Point3::new((i * 0.37).sin() * 10.0, (i * 0.91).cos() * 10.0, i * 0.1)
+            })
+            .collect();
+        let indices: Vec<[u32; 3]> = (0..98).map(|i| [i, i + 1, i + 2]).collect();
+        let mesh = TriMesh::new(&vertices, &indices);
+        let bvh = BVH::build_for_mesh(&mesh);
+        assert_eq!(bvh.nodes.len(), 2 * mesh.len() - 1);
+
+        let mut triangles: Vec<Triangle> = (0..mesh.len())
+            .map(|index| {
+                let [a, b, c] = mesh.triangle(index);
+                Triangle::new(a, b, c)
+            })
+            .collect();
+        let triangle_bvh = BVH::build(&mut triangles);
+        assert!(mesh
+            .aabb()
+            .relative_eq(&triangles.iter().map(Bounded::aabb).sum(), crate::EPSILON));
+
+        for i in 0..100 {
+            let angle = i as f32 * 0.1;
+            let origin = Point3::new(angle.cos() * 30.0, angle.sin() * 30.0, 5.0);
+            let ray = Ray::new(origin, -origin);
+            let expected = triangle_bvh.nearest_hit(&ray, &triangles);
+            let found = mesh.nearest_hit(&bvh, &ray);
+            assert_eq!(
+                found.map(|(_, hit)| hit.distance),
+                expected.map(|(_, hit)| hit.distance)
+            );
+        }
+    }
+
+    #[test]
+    /// Tests whether a `Heightfield` finds the same nearest hits as a `TriMesh` of its
+    /// triangles, and whether `Heightfield`s can be put into a `BVH`.
+    fn test_heightfield_equals_tri_mesh() {
+        let (columns, rows) = (30, 20);
+        let heights: Vec<f32> = (0..columns * rows)
+            .map(|i| ((i % columns) as f32 * 0.3).sin() + ((i / columns) as f32 * 0.7).cos())
+            .collect();
+        let origin = Point3::new(-10.0, 1.0, 5.0);
+        let scale = Vector3::new(0.5, 2.0, 0.75);
+        let heightfield = Heightfield::new(&heights, columns, origin, scale);
+        assert_eq!(heightfield.cell_count(), 29 * 19);
+
+        let vertices: Vec<Point3> = (0..columns * rows)
+            .map(|i| heightfield.sample(i % columns, i / columns))
+            .collect();
+        let indices: Vec<[u32; 3]> = (0..heightfield.cell_count())
+            .flat_map(|index| {
+                let (x, z) = heightfield.cell(index);
+                let sample = |x: usize, z: usize| (z * columns + x) as u32;
+                let (a, b) = (sample(x, z), sample(x, z + 1));
+                let (c, d) = (sample(x + 1, z + 1), sample(x + 1, z));
+                vec![[a, b, c], [a, c, d]]
+            })
+            .collect();
+        let mesh = TriMesh::new(&vertices, &indices);
+        let mesh_bvh = BVH::build_for_mesh(&mesh);
+        assert!(heightfield.aabb().relative_eq(&mesh.aabb(), crate::EPSILON));
+
+        for i in 0..100 {
+            let target = Point3::new(-10.0 + (i % 10) as f32 * 1.4, 0.0, 5.0 + (i / 10) as f32);
+            let origin = target + Vector3::new((i as f32).sin(), 10.0, (i as f32).cos());
+            let ray = Ray::new(origin, target - origin);
+            let expected = mesh.nearest_hit(&mesh_bvh, &ray);
+            let found = heightfield.nearest_hit(&ray);
+            assert_eq!(
+                found.map(|(index, hit)| (index, hit.distance)),
+                expected.map(|(index, hit)| (index / 2, hit.distance))
+            );
+        }
+
+        let shifted = origin + Vector3::new(20.0, 0.0, 0.0);
+        let mut terrains = vec![
+            heightfield.clone(),
+            Heightfield::new(&heights, columns, shifted, scale),
+        ];
+        let scene = BVH::build(&mut terrains);
+        let ray = Ray::new(Point3::new(12.0, 10.0, 10.0), Vector3::new(0.0, -1.0, 0.0));
+        let (terrain, intersection) = scene.nearest_hit(&ray, &terrains).unwrap();
+        assert_eq!(terrain.origin, shifted);
+        assert!(intersection.u > 0.0 && intersection.u < 1.0);
+    }
+}
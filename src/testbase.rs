@@ -14,8 +14,11 @@ use rand::seq::SliceRandom;
 use rand::SeedableRng;
 
 use crate::aabb::{Bounded, AABB};
-use crate::bounding_hierarchy::{BHShape, BoundingHierarchy};
-use crate::ray::Ray;
+use crate::benchmark::cube_triangles;
+pub(crate) use crate::benchmark::{default_bounds, next_point3};
+use crate::bounding_hierarchy::{BHShape, BoundingHierarchy, IndexedBoundingHierarchy};
+use crate::bvh::BVH;
+use crate::ray::{Intersectable, Intersection, Ray};
 
 /// A vector represented as a tuple
 pub type TupleVec = (f32, f32, f32);
@@ -107,7 +110,7 @@ pub fn build_some_bh<BH: BoundingHierarchy>() -> (Vec<UnitBox>, BH) {
 
 /// Given a ray, a bounding hierarchy, the complete list of shapes in the scene and a list of
 /// expected hits, verifies, whether the ray hits only the expected shapes.
-fn traverse_and_verify<BH: BoundingHierarchy>(
+fn traverse_and_verify<BH: IndexedBoundingHierarchy>(
     ray_origin: Point3,
     ray_direction: Vector3,
     all_shapes: &[UnitBox],
@@ -121,10 +124,16 @@ fn traverse_and_verify<BH: BoundingHierarchy>(
     for shape in hit_shapes {
         assert!(expected_shapes.contains(&shape.id));
     }
+
+    let hit_indices = bh.traverse_indices(&ray);
+    assert_eq!(expected_shapes.len(), hit_indices.len());
+    for index in hit_indices {
+        assert!(expected_shapes.contains(&all_shapes[index].id));
+    }
 }
 
 /// Perform some fixed intersection tests on BH structures.
-pub fn traverse_some_bh<BH: BoundingHierarchy>() {
+pub fn traverse_some_bh<BH: IndexedBoundingHierarchy>() {
     let (all_shapes, bh) = build_some_bh::<BH>();
 
     {
@@ -203,6 +212,17 @@ impl BHShape for Triangle {
     }
 }
 
+impl Intersectable for Triangle {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let intersection = ray.intersects_triangle(&self.a, &self.b, &self.c);
+        if intersection.distance < f32::INFINITY {
+            Some(intersection)
+        } else {
+            None
+        }
+    }
+}
+
 impl<I: FromPrimitive + Integer> FromRawVertex<I> for Triangle {
     fn process(
         vertices: Vec<(f32, f32, f32, f32)>,
@@ -249,121 +269,9 @@ impl<I: FromPrimitive + Integer> FromRawVertex<I> for Triangle {
 
 /// Creates a unit size cube centered at `pos` and pushes the triangles to `shapes`.
 fn push_cube(pos: Point3, shapes: &mut Vec<Triangle>) {
-    let top_front_right = pos + Vector3::new(0.5, 0.5, -0.5);
-    let top_back_right = pos + Vector3::new(0.5, 0.5, 0.5);
-    let top_back_left = pos + Vector3::new(-0.5, 0.5, 0.5);
-    let top_front_left = pos + Vector3::new(-0.5, 0.5, -0.5);
-    let bottom_front_right = pos + Vector3::new(0.5, -0.5, -0.5);
-    let bottom_back_right = pos + Vector3::new(0.5, -0.5, 0.5);
-    let bottom_back_left = pos + Vector3::new(-0.5, -0.5, 0.5);
-    let bottom_front_left = pos + Vector3::new(-0.5, -0.5, -0.5);
-
-    shapes.push(Triangle::new(
-        top_back_right,
-        top_front_right,
-        top_front_left,
-    ));
-    shapes.push(Triangle::new(top_front_left, top_back_left, top_back_right));
-    shapes.push(Triangle::new(
-        bottom_front_left,
-        bottom_front_right,
-        bottom_back_right,
-    ));
-    shapes.push(Triangle::new(
-        bottom_back_right,
-        bottom_back_left,
-        bottom_front_left,
-    ));
-    shapes.push(Triangle::new(
-        top_back_left,
-        top_front_left,
-        bottom_front_left,
-    ));
-    shapes.push(Triangle::new(
-        bottom_front_left,
-        bottom_back_left,
-        top_back_left,
-    ));
-    shapes.push(Triangle::new(
-        bottom_front_right,
-        top_front_right,
-        top_back_right,
-    ));
-    shapes.push(Triangle::new(
-        top_back_right,
-        bottom_back_right,
-        bottom_front_right,
-    ));
-    shapes.push(Triangle::new(
-        top_front_left,
-        top_front_right,
-        bottom_front_right,
-    ));
-    shapes.push(Triangle::new(
-        bottom_front_right,
-        bottom_front_left,
-        top_front_left,
-    ));
-    shapes.push(Triangle::new(
-        bottom_back_right,
-        top_back_right,
-        top_back_left,
-    ));
-    shapes.push(Triangle::new(
-        top_back_left,
-        bottom_back_left,
-        bottom_back_right,
-    ));
-}
-
-/// Implementation of splitmix64.
-/// For reference see: http://xoroshiro.di.unimi.it/splitmix64.c
-fn splitmix64(x: &mut u64) -> u64 {
-    *x = x.wrapping_add(0x9E3779B97F4A7C15u64);
-    let mut z = *x;
-    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9u64);
-    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EBu64);
-    z ^ (z >> 31)
-}
-
-/// Generates a new `i32` triple. Mutates the seed.
-pub fn next_point3_raw(seed: &mut u64) -> (i32, i32, i32) {
-    let u = splitmix64(seed);
-    let a = ((u >> 32) & 0xFFFFFFFF) as i64 - 0x80000000;
-    let b = (u & 0xFFFFFFFF) as i64 - 0x80000000;
-    let c = a ^ b.rotate_left(6);
-    (a as i32, b as i32, c as i32)
-}
-
-/// Generates a new `Point3`, which will lie inside the given `aabb`. Mutates the seed.
-pub fn next_point3(seed: &mut u64, aabb: &AABB) -> Point3 {
-    let (a, b, c) = next_point3_raw(seed);
-    use std::i32;
-    let float_vector = Vector3::new(
-        (a as f32 / i32::MAX as f32) + 1.0,
-        (b as f32 / i32::MAX as f32) + 1.0,
-        (c as f32 / i32::MAX as f32) + 1.0,
-    ) * 0.5;
-
-    assert!(float_vector.x >= 0.0 && float_vector.x <= 1.0);
-    assert!(float_vector.y >= 0.0 && float_vector.y <= 1.0);
-    assert!(float_vector.z >= 0.0 && float_vector.z <= 1.0);
-
-    let size = aabb.size();
-    let offset = Vector3::new(
-        float_vector.x * size.x,
-        float_vector.y * size.y,
-        float_vector.z * size.z,
-    );
-    aabb.min + offset
-}
-
-/// Returns an `AABB` which defines the default testing space bounds.
-pub fn default_bounds() -> AABB {
-    AABB::with_bounds(
-        Point3::new(-100_000.0, -100_000.0, -100_000.0),
-        Point3::new(100_000.0, 100_000.0, 100_000.0),
-    )
+    for [a, b, c] in cube_triangles(pos) {
+        shapes.push(Triangle::new(a, b, c));
+    }
 }
 
 /// Creates `n` deterministic random cubes. Returns the `Vec` of surface `Triangle`s.
@@ -376,6 +284,37 @@ pub fn create_n_cubes(n: usize, bounds: &AABB) -> Vec<Triangle> {
     vec
 }
 
+/// Creates 100 deterministic random cubes inside the `default_bounds` and builds a `BVH`
+/// over their `Triangle`s.
+pub fn build_cubes_bvh() -> (Vec<Triangle>, BVH) {
+    let mut triangles = create_n_cubes(100, &default_bounds());
+    let bvh = BVH::build(&mut triangles);
+    (triangles, bvh)
+}
+
+/// Asserts that `found` contains the same shapes in the same order as `expected`.
+pub fn assert_same_shapes<Shape>(found: &[&Shape], expected: &[&Shape]) {
+    assert_eq!(found.len(), expected.len());
+    for (a, b) in found.iter().zip(expected.iter()) {
+        assert!(std::ptr::eq(*a, *b));
+    }
+}
+
+/// Asserts for 1000 deterministic random rays inside the `default_bounds` that `traverse`
+/// returns the same shapes in the same order as `BVH::traverse` of the `bvh`.
+pub fn check_traverse_equals_bvh<'a, F>(bvh: &BVH, triangles: &'a [Triangle], mut traverse: F)
+where
+    F: FnMut(&Ray) -> Vec<&'a Triangle>,
+{
+    let bounds = default_bounds();
+    let mut seed = 0;
+    for _ in 0..1000 {
+        let ray = create_ray(&mut seed, &bounds);
+        let expected = bvh.traverse(&ray, triangles);
+        assert_same_shapes(&traverse(&ray), &expected);
+    }
+}
+
 /// Loads the sponza model.
 #[cfg(feature = "bench")]
 pub fn load_sponza_scene() -> (Vec<Triangle>, AABB) {
@@ -400,7 +339,7 @@ pub fn load_sponza_scene() -> (Vec<Triangle>, AABB) {
 /// offset of a shape. This is used to simulate a realistic scene.
 /// Returns a `HashSet` of indices of modified triangles.
 pub fn randomly_transform_scene(
-    triangles: &mut Vec<Triangle>,
+    triangles: &mut [Triangle],
     amount: usize,
     bounds: &AABB,
     max_offset_option: Option<f32>,
@@ -416,11 +355,7 @@ pub fn randomly_transform_scene(
     indices.shuffle(&mut rng);
     indices.truncate(amount);
 
-    let max_offset = if let Some(value) = max_offset_option {
-        value
-    } else {
-        f32::INFINITY
-    };
+    let max_offset = max_offset_option.unwrap_or(f32::INFINITY);
 
     for index in &indices {
         let aabb = triangles[*index].aabb();
@@ -449,7 +384,6 @@ pub fn randomly_transform_scene(
 /// Creates a `Ray` from the random `seed`. Mutates the `seed`.
 /// The Ray origin will be inside the `bounds` and point to some other point inside this
 /// `bounds`.
-#[cfg(feature = "bench")]
 pub fn create_ray(seed: &mut u64, bounds: &AABB) -> Ray {
     let origin = next_point3(seed, bounds);
     let direction = next_point3(seed, bounds);
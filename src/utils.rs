@@ -3,10 +3,9 @@
 use crate::aabb::AABB;
 use crate::bounding_hierarchy::BHShape;
 
-/// Concatenates the list of vectors into a single vector.
-/// Drains the elements from the source `vectors`.
-pub fn concatenate_vectors<T: Sized>(vectors: &mut [Vec<T>]) -> Vec<T> {
-    let mut result = Vec::new();
+/// Concatenates the list of vectors into a single vector, which is appended to `result`.
+/// Drains the elements from the source `vectors`, but keeps their capacity.
+pub fn concatenate_vectors<T: Sized>(vectors: &mut [Vec<T>], mut result: Vec<T>) -> Vec<T> {
     for vector in vectors.iter_mut() {
         result.append(vector);
     }
@@ -36,27 +35,53 @@ impl Bucket {
     /// Extend this `Bucket` by a shape with the given `AABB`.
     pub fn add_aabb(&mut self, aabb: &AABB) {
         self.size += 1;
-        self.aabb = self.aabb.join(aabb);
+        self.aabb += *aabb;
     }
 
     /// Join the contents of two `Bucket`s.
     pub fn join_bucket(a: Bucket, b: &Bucket) -> Bucket {
         Bucket {
             size: a.size + b.size,
-            aabb: a.aabb.join(&b.aabb),
+            aabb: a.aabb + b.aabb,
         }
     }
 }
 
 pub fn joint_aabb_of_shapes<Shape: BHShape>(indices: &[usize], shapes: &[Shape]) -> AABB {
-    let mut aabb = AABB::empty();
-    for index in indices {
-        let shape = &shapes[*index];
-        aabb.join_mut(&shape.aabb());
+    indices.iter().map(|index| shapes[*index].aabb()).sum()
+}
+
+/// Hints the CPU to load `slice[index]` into the cache, so that it is present when it is
+/// accessed later. Does nothing if `index` is out of bounds, or without the `prefetch`
+/// feature on x86 or x86-64 with SSE.
+#[cfg(all(
+    feature = "prefetch",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse"
+))]
+#[inline(always)]
+pub fn prefetch<T>(slice: &[T], index: usize) {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+    if let Some(element) = slice.get(index) {
+        // SAFETY: SSE is enabled for the target, and a prefetch never faults.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(element as *const T as *const i8) }
     }
-    aabb
 }
 
+/// Hints the CPU to load `slice[index]` into the cache. This target or configuration has
+/// no prefetch instruction, so it does nothing.
+#[cfg(not(all(
+    feature = "prefetch",
+    any(target_arch = "x86", target_arch = "x86_64"),
+    target_feature = "sse"
+)))]
+#[inline(always)]
+pub fn prefetch<T>(_slice: &[T], _index: usize) {}
+
 #[cfg(test)]
 mod tests {
     use crate::utils::concatenate_vectors;
@@ -66,7 +91,10 @@ mod tests {
     fn test_concatenate_empty() {
         let mut vectors: Vec<Vec<usize>> = vec![];
         let expected = vec![];
-        assert_eq!(concatenate_vectors(vectors.as_mut_slice()), expected);
+        assert_eq!(
+            concatenate_vectors(vectors.as_mut_slice(), Vec::new()),
+            expected
+        );
         let expected_remainder: Vec<Vec<usize>> = vec![];
         assert_eq!(vectors, expected_remainder);
     }
@@ -75,7 +103,7 @@ mod tests {
     /// Test if concatenating some `Vec`s yields the concatenation of the vectors.
     fn test_concatenate_vectors() {
         let mut vectors = vec![vec![1, 2, 3], vec![], vec![4, 5, 6], vec![7, 8], vec![9]];
-        let result = concatenate_vectors(vectors.as_mut_slice());
+        let result = concatenate_vectors(vectors.as_mut_slice(), Vec::new());
         let expected = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
         assert_eq!(result, expected);
         assert_eq!(vectors, vec![vec![], vec![], vec![], vec![], vec![]]);
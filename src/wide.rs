@@ -0,0 +1,239 @@
+//! This module implements the collapse and the traversal of the wide [`BVH4`] and [`BVH8`],
+//! which only differ in the number of children per node and in how their child [`AABB`]s
+//! are tested against a [`Ray`].
+//!
+//! [`AABB`]: ../aabb/struct.AABB.html
+//! [`BVH4`]: ../bvh4/struct.BVH4.html
+//! [`BVH8`]: ../bvh8/struct.BVH8.html
+//! [`Ray`]: ../ray/struct.Ray.html
+//!
+
+use crate::aabb::AABB;
+use crate::bvh::{BVHNode, BVH};
+use crate::ray::Ray;
+
+/// The bit of a child reference which marks a leaf. The remaining bits of the reference
+/// are the index of the shape for leaves, and the index of the node otherwise.
+pub(crate) const LEAF_BIT: u32 = 1 << 31;
+
+/// The child reference of an unused child slot.
+pub(crate) const EMPTY: u32 = u32::MAX;
+
+/// A node with up to [`WIDTH`] children, whose used child slots come first.
+///
+/// [`WIDTH`]: trait.WideNode.html#associatedconstant.WIDTH
+///
+pub(crate) trait WideNode: Sized {
+    /// The maximum number of children of a node.
+    const WIDTH: usize;
+
+    /// Selects how the child [`AABB`]s are tested against a [`Ray`]. It is chosen once per
+    /// traversal by [`detect_kernel`].
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    /// [`Ray`]: ../ray/struct.Ray.html
+    /// [`detect_kernel`]: trait.WideNode.html#tymethod.detect_kernel
+    ///
+    type Kernel: Copy;
+
+    /// Creates a node without children.
+    fn empty() -> Self;
+
+    /// Returns the child references. See [`LEAF_BIT`] and [`EMPTY`].
+    ///
+    /// [`EMPTY`]: constant.EMPTY.html
+    /// [`LEAF_BIT`]: constant.LEAF_BIT.html
+    ///
+    fn children(&self) -> &[u32];
+
+    /// Returns the [`AABB`] of the child in `slot`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    #[cfg(test)]
+    fn child_aabb(&self, slot: usize) -> AABB;
+
+    /// Stores the child reference `child` and its [`AABB`] in `slot`.
+    ///
+    /// [`AABB`]: ../aabb/struct.AABB.html
+    ///
+    fn set_child(&mut self, slot: usize, aabb: &AABB, child: u32);
+
+    /// Returns the fastest kernel which the CPU supports.
+    fn detect_kernel() -> Self::Kernel;
+
+    /// Returns a mask whose bit `i` is set if `ray` hits the child in slot `i`.
+    fn intersects(&self, ray: &Ray, kernel: Self::Kernel) -> u32;
+}
+
+/// Collapses `bvh` into wide nodes, and returns the nodes and the reference to the root.
+/// The root of a `bvh` without nodes is [`EMPTY`].
+///
+/// # Panics
+/// Panics if `bvh` has `2^31` shapes or nodes, or more.
+///
+/// [`EMPTY`]: constant.EMPTY.html
+///
+pub(crate) fn collapse<N: WideNode>(bvh: &BVH) -> (Vec<N>, u32) {
+    let mut nodes = Vec::new();
+    if bvh.nodes.is_empty() {
+        return (nodes, EMPTY);
+    }
+    let root = collapse_subtree(bvh, 0, &mut nodes);
+    (nodes, root)
+}
+
+/// Appends the wide nodes of the subtree of the node at `node_index` to `nodes`, and
+/// returns the reference to the node.
+fn collapse_subtree<N: WideNode>(bvh: &BVH, node_index: usize, nodes: &mut Vec<N>) -> u32 {
+    let slots = match bvh.nodes[node_index] {
+        BVHNode::Node { .. } => collapse_children(bvh, node_index, N::WIDTH),
+        BVHNode::Leaf { shape_index, .. } => {
+            assert!(shape_index < LEAF_BIT, "too many shapes");
+            return shape_index | LEAF_BIT;
+        }
+    };
+
+    let index = nodes.len();
+    assert!(index < LEAF_BIT as usize, "too many nodes");
+    nodes.push(N::empty());
+    for (slot, &(aabb, child_index)) in slots.iter().enumerate() {
+        let child = collapse_subtree(bvh, child_index as usize, nodes);
+        nodes[index].set_child(slot, &aabb, child);
+    }
+    index as u32
+}
+
+/// Returns the [`AABB`]s and node indices of up to `width` descendants of the inner node
+/// at `node_index`, which together contain all of its shapes. Starting with its two
+/// children, the inner child with the largest surface area is replaced by its own
+/// children, until there are `width` children or only leaves are left.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+fn collapse_children(bvh: &BVH, node_index: usize, width: usize) -> Vec<(AABB, u32)> {
+    let node = &bvh.nodes[node_index];
+    let mut slots = vec![
+        (node.child_l_aabb(), node.child_l() as u32),
+        (node.child_r_aabb(), node.child_r() as u32),
+    ];
+
+    // A NaN surface area counts as the largest one.
+    let area = |aabb: &AABB| {
+        let area = aabb.surface_area();
+        if area.is_nan() {
+            f32::INFINITY
+        } else {
+            area
+        }
+    };
+
+    // Open the inner child with the largest surface area, until the node is full.
+    while slots.len() < width {
+        let largest = slots
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, index))| matches!(bvh.nodes[*index as usize], BVHNode::Node { .. }))
+            .max_by(|(_, (a, _)), (_, (b, _))| area(a).total_cmp(&area(b)))
+            .map(|(slot, _)| slot);
+        let slot = match largest {
+            Some(slot) => slot,
+            None => break,
+        };
+        if let BVHNode::Node {
+            child_l_aabb,
+            child_l_index,
+            child_r_aabb,
+            child_r_index,
+            ..
+        } = bvh.nodes[slots[slot].1 as usize]
+        {
+            slots[slot] = (child_l_aabb, child_l_index);
+            slots.insert(slot + 1, (child_r_aabb, child_r_index));
+        }
+    }
+    slots
+}
+
+/// Traverses the wide `nodes` below `root` with a stack, and returns the indices of the
+/// shapes whose [`AABB`]s were hit by `ray`, in the same order as [`BVH::traverse`].
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+/// [`BVH::traverse`]: ../bvh/struct.BVH.html#method.traverse
+///
+pub(crate) fn traverse_indices<N: WideNode>(nodes: &[N], root: u32, ray: &Ray) -> Vec<usize> {
+    let mut hit_indices = Vec::new();
+    // The root of a tree without shapes is empty.
+    let mut stack = if root == EMPTY {
+        Vec::new()
+    } else {
+        vec![root]
+    };
+    let kernel = N::detect_kernel();
+    while let Some(child) = stack.pop() {
+        if child & LEAF_BIT != 0 {
+            hit_indices.push((child & !LEAF_BIT) as usize);
+            continue;
+        }
+
+        let node = &nodes[child as usize];
+        // The empty `AABB`s of unused slots are never hit.
+        let mask = node.intersects(ray, kernel);
+        // Push the last child first, so that the first child is visited first.
+        for slot in (0..N::WIDTH).rev() {
+            if mask & (1 << slot) != 0 {
+                stack.push(node.children()[slot]);
+            }
+        }
+    }
+
+    hit_indices
+}
+
+/// Prints the children of each of the wide `nodes` on one line.
+pub(crate) fn pretty_print<N: WideNode>(nodes: &[N]) {
+    for (i, node) in nodes.iter().enumerate() {
+        let children: Vec<String> = node
+            .children()
+            .iter()
+            .filter(|&&child| child != EMPTY)
+            .map(|&child| {
+                if child & LEAF_BIT != 0 {
+                    format!("shape {}", child & !LEAF_BIT)
+                } else {
+                    format!("node {}", child)
+                }
+            })
+            .collect();
+        println!("{}\t{}", i, children.join("\t"));
+    }
+}
+
+/// Asserts that every one of the wide `nodes` has at least two children, which come first
+/// and whose [`AABB`]s contain the [`AABB`]s of their own children, and that the leaves
+/// reference `shape_count` shapes.
+///
+/// [`AABB`]: ../aabb/struct.AABB.html
+///
+#[cfg(test)]
+pub(crate) fn assert_collapsed<N: WideNode>(nodes: &[N], shape_count: usize) {
+    let mut shapes = 0;
+    for node in nodes {
+        let children = node.children();
+        let used = children.iter().filter(|&&c| c != EMPTY).count();
+        assert!(used >= 2);
+        assert!(children[used..].iter().all(|&c| c == EMPTY));
+        for (slot, &child) in children[..used].iter().enumerate() {
+            if child & LEAF_BIT != 0 {
+                shapes += 1;
+            } else {
+                let grandchild = &nodes[child as usize];
+                for grandchild_slot in 0..N::WIDTH {
+                    let aabb = grandchild.child_aabb(grandchild_slot);
+                    assert!(node.child_aabb(slot).contains_aabb(&aabb));
+                }
+            }
+        }
+    }
+    assert_eq!(shapes, shape_count);
+}